@@ -1,13 +1,15 @@
 //! Streaming summarization engine used by the `distilld` CLI.
 //!
 //! The [`run`] function orchestrates reading batched input, rendering a prompt
-//! template, and streaming the summary from an Ollama instance.
+//! template with [`PromptHelper`], and streaming the summary from an Ollama
+//! instance.
 //!
 //! ```no_run
 //! use distilld::Config;
 //! use tokio::io::{stdin, stdout};
 //! # async fn example() -> anyhow::Result<()> {
 //! let cfg = Config {
+//!     name: "summary".into(),
 //!     continuous: false,
 //!     lines: 1,
 //!     prompt: "Summarize: {{current}}".into(),
@@ -16,31 +18,42 @@
 //!     history_depth: 1,
 //!     beat: 0,
 //!     trim_newlines: true,
+//!     options: Default::default(),
+//!     examples: Vec::new(),
+//!     prompter: Default::default(),
 //! };
 //! let ollama = ollama_rs::Ollama::try_new("http://localhost:11434")?;
 //! distilld::run(cfg, ollama, tokio::io::BufReader::new(stdin()), stdout()).await?;
 //! # Ok(()) }
 //! ```
 use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+use ollama_rs::generation::chat::{self, request::ChatMessageRequest};
 use ollama_rs::generation::completion::request::GenerationRequest;
-use ollama_rs::generation::completion::GenerationResponseStream;
-use ollama_rs::models::pull::PullModelStatus;
+use ollama_rs::generation::parameters::{FormatType, JsonStructure};
+use ollama_rs::models::ModelOptions;
 use ollama_rs::{error::OllamaError, Ollama};
+use psyche::llm::ollama::{OllamaModels, PullProgress};
+use psyche::llm::prompt::{PromptContext, PromptHelper};
+use psyche::llm::usage::{UsageRecord, UsageStats};
+use psyche::llm::{ChatMessage, ChatRole, GenerationOptions};
 use std::collections::VecDeque;
-use tera::{Context, Tera};
+use std::pin::Pin;
 use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt, BufReader};
-use tokio::time::{sleep, Duration};
-use tokio_stream::StreamExt;
-use tracing::{debug, trace, warn};
+use tokio::time::{sleep, Duration, Instant};
+use tokio_stream::{Stream, StreamExt};
+use tracing::{debug, trace};
 
 /// Configuration for [`run`].
 #[derive(Debug, Clone)]
 pub struct Config {
+    /// Wit name reported in usage records.
+    pub name: String,
     /// Run continuously, using each summary as the next `{{previous}}` value.
     pub continuous: bool,
     /// Number of lines per batch.
     pub lines: usize,
-    /// Prompt template with `{{previous}}` and `{{current}}` placeholders.
+    /// Prompt template with `{{previous}}` and `{{current}}` placeholders,
+    /// plus everything [`PromptHelper::render`] provides.
     pub prompt: String,
     /// Model name for the Ollama API.
     pub model: String,
@@ -55,10 +68,37 @@ pub struct Config {
     pub beat: u64,
     /// Trim newline tokens emitted by the LLM
     pub trim_newlines: bool,
+    /// Sampling options applied to every generation request.
+    pub options: GenerationOptions,
+    /// Few-shot example turns sent between the system prompt and the
+    /// rendered prompt. Empty for a plain completion.
+    pub examples: Vec<ChatMessage>,
+    /// Renders [`Config::prompt`] with the soul's identity and partials.
+    pub prompter: PromptHelper,
 }
 
+/// System prompt sent with every request.
+const SYSTEM_PROMPT: &str = "You summarize text.";
+
 /// Processes the input stream and writes summaries to the output stream.
-pub async fn run<R, W>(cfg: Config, ollama: Ollama, input: R, mut output: W) -> anyhow::Result<()>
+pub async fn run<R, W>(cfg: Config, ollama: Ollama, input: R, output: W) -> anyhow::Result<()>
+where
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    run_with_stats(cfg, ollama, input, output, &UsageStats::new()).await
+}
+
+/// Like [`run`], recording token usage and latency of every call in `stats`.
+///
+/// A summary of the totals is logged once the input is exhausted.
+pub async fn run_with_stats<R, W>(
+    cfg: Config,
+    ollama: Ollama,
+    input: R,
+    mut output: W,
+    stats: &UsageStats,
+) -> anyhow::Result<()>
 where
     R: AsyncBufRead + Unpin,
     W: AsyncWrite + Unpin,
@@ -80,7 +120,8 @@ where
             } else {
                 history.iter().cloned().collect::<Vec<_>>().join("\n\n")
             };
-            let summary = summarize_into(&ollama, &cfg, &previous, &current, &mut output).await?;
+            let summary =
+                summarize_into(&ollama, &cfg, stats, &previous, &current, &mut output).await?;
             if !summary.is_empty() {
                 output.write_all(cfg.terminal.as_bytes()).await?;
                 if cfg.continuous && cfg.history_depth > 0 {
@@ -103,7 +144,8 @@ where
         } else {
             history.iter().cloned().collect::<Vec<_>>().join("\n\n")
         };
-        let summary = summarize_into(&ollama, &cfg, &previous, &current, &mut output).await?;
+        let summary =
+            summarize_into(&ollama, &cfg, stats, &previous, &current, &mut output).await?;
         if !summary.is_empty() {
             output.write_all(cfg.terminal.as_bytes()).await?;
             if cfg.continuous && cfg.history_depth > 0 {
@@ -116,12 +158,14 @@ where
     }
 
     output.flush().await?;
+    stats.log_summary();
     Ok(())
 }
 
 async fn summarize_into<W>(
     ollama: &Ollama,
     cfg: &Config,
+    stats: &UsageStats,
     previous: &str,
     current: &str,
     output: &mut W,
@@ -129,30 +173,48 @@ async fn summarize_into<W>(
 where
     W: AsyncWrite + Unpin,
 {
-    let mut ctx = Context::new();
-    ctx.insert("previous", previous);
-    ctx.insert("current", current);
-    let rendered = Tera::one_off(&cfg.prompt, &ctx, true)?;
+    let ctx = PromptContext::new()
+        .var("previous", previous)
+        .var("current", current);
+    let rendered = cfg.prompter.render(&cfg.prompt, &ctx)?;
     trace!(prompt = %rendered, "llm prompt");
 
-    let req = GenerationRequest::new(cfg.model.clone(), rendered).system("You summarize text.");
-
-    let mut stream: GenerationResponseStream = match ollama.generate_stream(req.clone()).await {
+    let mut start = Instant::now();
+    let mut stream = match open_stream(ollama, cfg, &rendered).await? {
         Ok(s) => s,
         Err(e) => match e {
             OllamaError::Other(msg) if msg.contains("not found") && msg.contains("pull") => {
-                pull_with_progress(ollama, cfg.model.clone()).await?;
-                ollama.generate_stream(req).await?
+                pull_with_progress(ollama, &cfg.model).await?;
+                start = Instant::now();
+                open_stream(ollama, cfg, &rendered).await??
             }
             other => return Err(other.into()),
         },
     };
     let mut out = String::new();
+    let mut record = UsageRecord {
+        wit: cfg.name.clone(),
+        model: cfg.model.clone(),
+        host: ollama.uri(),
+        prompt_tokens: None,
+        completion_tokens: None,
+        time_to_first_token: None,
+        latency: Duration::ZERO,
+    };
     while let Some(chunk) = stream.next().await {
         match chunk {
-            Ok(responses) => {
-                for resp in responses {
-                    let mut text = resp.response;
+            Ok(pieces) => {
+                for piece in pieces {
+                    if piece.done {
+                        record.prompt_tokens = piece.prompt_tokens;
+                        record.completion_tokens = piece.completion_tokens;
+                    }
+                    if !piece.text.is_empty() {
+                        record
+                            .time_to_first_token
+                            .get_or_insert_with(|| start.elapsed());
+                    }
+                    let mut text = piece.text;
                     if let Some(t) = text.strip_prefix('\u{FEFF}') {
                         text = t.to_string();
                     }
@@ -166,6 +228,8 @@ where
             Err(_) => break,
         }
     }
+    record.latency = start.elapsed();
+    stats.record(record);
 
     let text_empty = out.trim().is_empty();
     let processed = if cfg.trim_newlines {
@@ -190,43 +254,172 @@ where
     }
 }
 
+/// One streamed chunk of a reply from either the generate or the chat API.
+struct Piece {
+    text: String,
+    done: bool,
+    prompt_tokens: Option<u32>,
+    completion_tokens: Option<u32>,
+}
+
+type PieceStream = Pin<Box<dyn Stream<Item = Result<Vec<Piece>, ()>> + Send>>;
+
+/// Start streaming a reply to `prompt`. Without [`Config::examples`] this is
+/// a plain generate call; with them the examples are sent as chat turns
+/// between the system prompt and `prompt`. The outer error is a bad option,
+/// the inner one the request failing.
+async fn open_stream(
+    ollama: &Ollama,
+    cfg: &Config,
+    prompt: &str,
+) -> anyhow::Result<Result<PieceStream, OllamaError>> {
+    let options = model_options(&cfg.options)?;
+    let format = format_type(&cfg.options)?;
+    if cfg.examples.is_empty() {
+        let mut req =
+            GenerationRequest::new(cfg.model.clone(), prompt.to_string()).system(SYSTEM_PROMPT);
+        if let Some(options) = options {
+            req = req.options(options);
+        }
+        if let Some(format) = format {
+            req = req.format(format);
+        }
+        return Ok(ollama.generate_stream(req).await.map(|s| {
+            Box::pin(s.map(|chunk| {
+                chunk.map_err(drop).map(|responses| {
+                    responses
+                        .into_iter()
+                        .map(|r| Piece {
+                            text: r.response,
+                            done: r.done,
+                            prompt_tokens: r.prompt_eval_count.map(|n| n as u32),
+                            completion_tokens: r.eval_count.map(|n| n as u32),
+                        })
+                        .collect()
+                })
+            })) as PieceStream
+        }));
+    }
+    let mut messages = vec![chat::ChatMessage::system(SYSTEM_PROMPT.to_string())];
+    messages.extend(cfg.examples.iter().map(|m| {
+        let role = match m.role {
+            ChatRole::System => chat::MessageRole::System,
+            ChatRole::User => chat::MessageRole::User,
+            ChatRole::Assistant => chat::MessageRole::Assistant,
+            ChatRole::Tool => chat::MessageRole::Tool,
+        };
+        chat::ChatMessage::new(role, m.content.clone())
+    }));
+    messages.push(chat::ChatMessage::user(prompt.to_string()));
+    let mut req = ChatMessageRequest::new(cfg.model.clone(), messages);
+    if let Some(options) = options {
+        req = req.options(options);
+    }
+    if let Some(format) = format {
+        req = req.format(format);
+    }
+    Ok(ollama.send_chat_messages_stream(req).await.map(|s| {
+        Box::pin(s.map(|chunk| {
+            chunk.map(|resp| {
+                let usage = resp.final_data.as_ref();
+                vec![Piece {
+                    text: resp.message.content,
+                    done: resp.done,
+                    prompt_tokens: usage.map(|u| u.prompt_eval_count as u32),
+                    completion_tokens: usage.map(|u| u.eval_count as u32),
+                }]
+            })
+        })) as PieceStream
+    }))
+}
+
+/// Translate shared [`GenerationOptions`] into Ollama model options.
+fn model_options(opts: &GenerationOptions) -> anyhow::Result<Option<ModelOptions>> {
+    if opts.temperature.is_none()
+        && opts.top_p.is_none()
+        && opts.max_tokens.is_none()
+        && opts.stop.is_empty()
+        && opts.seed.is_none()
+    {
+        return Ok(None);
+    }
+    let mut options = ModelOptions::default();
+    if let Some(t) = opts.temperature {
+        options = options.temperature(t);
+    }
+    if let Some(p) = opts.top_p {
+        options = options.top_p(p);
+    }
+    if let Some(n) = opts.max_tokens {
+        options = options.num_predict(i32::try_from(n)?);
+    }
+    if !opts.stop.is_empty() {
+        options = options.stop(opts.stop.clone());
+    }
+    if let Some(seed) = opts.seed {
+        options = options.seed(i32::try_from(seed)?);
+    }
+    Ok(Some(options))
+}
+
+/// Translate the `format` option into an Ollama format constraint.
+fn format_type(opts: &GenerationOptions) -> anyhow::Result<Option<FormatType>> {
+    Ok(match &opts.format {
+        None => None,
+        Some(serde_json::Value::String(f)) if f == "json" => Some(FormatType::Json),
+        Some(schema) => {
+            let schema = schemars::Schema::try_from(schema.clone())?;
+            Some(FormatType::StructuredJson(Box::new(
+                JsonStructure::new_for_schema(schema),
+            )))
+        }
+    })
+}
+
 fn output_token(token: &str) {
     trace!(token, "stream token");
 }
 
-async fn pull_with_progress(ollama: &Ollama, model: String) -> anyhow::Result<()> {
-    warn!(%model, "pulling missing model");
+/// Install `model` on the server behind `ollama` if it is missing, drawing a
+/// progress bar on stderr while it downloads.
+pub async fn ensure_model(ollama: &Ollama, model: &str) -> anyhow::Result<()> {
+    let admin = OllamaModels::new(ollama.url().as_str());
+    if admin.has(model).await? {
+        debug!(%model, "model present");
+        return Ok(());
+    }
+    pull_with_progress(ollama, model).await
+}
+
+async fn pull_with_progress(ollama: &Ollama, model: &str) -> anyhow::Result<()> {
     let pb = ProgressBar::new_spinner();
     pb.set_draw_target(ProgressDrawTarget::stderr());
     pb.set_style(ProgressStyle::with_template("{spinner} {msg}").unwrap());
     pb.enable_steady_tick(std::time::Duration::from_millis(100));
-    let mut stream = ollama.pull_model_stream(model, false).await?;
-    while let Some(status) = stream.next().await {
-        let status = status?;
-        trace!(status = ?status, "pull progress");
-        match status {
-            PullModelStatus {
-                message,
+    let result = OllamaModels::new(ollama.url().as_str())
+        .pull(model, |progress: &PullProgress| match progress {
+            PullProgress {
+                status,
                 total: Some(t),
                 completed: Some(c),
                 ..
             } => {
-                if pb.length().is_none() {
+                if pb.length() != Some(*t) {
                     pb.set_style(
                         ProgressStyle::with_template("{bar:40.cyan/blue} {pos}/{len} {msg}")
                             .unwrap(),
                     );
-                    pb.set_length(t);
+                    pb.set_length(*t);
                 }
-                pb.set_message(message);
-                pb.set_position(c);
+                pb.set_message(status.clone());
+                pb.set_position(*c);
             }
-            PullModelStatus { message, .. } => {
-                pb.set_message(message);
+            PullProgress { status, .. } => {
+                pb.set_message(status.clone());
                 pb.tick();
             }
-        }
-    }
+        })
+        .await;
     pb.finish_and_clear();
-    Ok(())
+    result
 }
@@ -1,7 +1,10 @@
 use clap::Parser;
 use daemon_common::maybe_daemonize;
-use distilld::{run, Config};
+use distilld::Config;
 use ollama_rs::Ollama;
+use psyche::llm::prompt::PromptHelper;
+use psyche::llm::usage::UsageStats;
+use psyche::llm::GenerationOptions;
 use std::path::PathBuf;
 use tokio::fs::File;
 use tokio::io::{stdin, stdout, BufReader};
@@ -13,6 +16,18 @@ const SIMPLE_PROMPT: &str = "These things happened: {{current}}\n\nMake a one se
 #[derive(Parser, Debug)]
 #[command(name = "distilld", version)]
 struct Cli {
+    /// Wit name reported in usage logs
+    #[arg(long, default_value = "distilld")]
+    name: String,
+
+    /// File to keep token usage totals in, rewritten after every call
+    #[arg(long)]
+    usage_file: Option<PathBuf>,
+
+    /// Soul identity file providing template fields and partials
+    #[arg(long)]
+    identity: Option<PathBuf>,
+
     /// Run continuously, reusing each summary as {{previous}}
     #[arg(short = 'c', long)]
     continuous: bool,
@@ -61,6 +76,34 @@ struct Cli {
     #[arg(long)]
     no_trim: bool,
 
+    /// Sampling temperature
+    #[arg(long)]
+    temperature: Option<f32>,
+
+    /// Nucleus sampling probability mass
+    #[arg(long)]
+    top_p: Option<f32>,
+
+    /// Maximum number of tokens to generate
+    #[arg(long)]
+    max_tokens: Option<u32>,
+
+    /// Stop sequence; may be given multiple times
+    #[arg(long)]
+    stop: Vec<String>,
+
+    /// Seed for reproducible sampling
+    #[arg(long)]
+    seed: Option<i64>,
+
+    /// Output format: `json` or a JSON schema
+    #[arg(long)]
+    format: Option<String>,
+
+    /// Few-shot example turns as a JSON array of `{role, content}` messages
+    #[arg(long)]
+    examples: Option<String>,
+
     /// Run diagnostics and exit
     #[arg(long)]
     test: bool,
@@ -90,6 +133,7 @@ async fn main() -> anyhow::Result<()> {
     });
 
     let cfg = Config {
+        name: cli.name.clone(),
         continuous: cli.continuous,
         lines: cli.lines,
         prompt,
@@ -98,9 +142,33 @@ async fn main() -> anyhow::Result<()> {
         history_depth: cli.history_depth,
         beat: cli.beat,
         trim_newlines: !cli.no_trim,
+        options: GenerationOptions {
+            temperature: cli.temperature,
+            top_p: cli.top_p,
+            max_tokens: cli.max_tokens,
+            stop: cli.stop,
+            seed: cli.seed,
+            format: cli
+                .format
+                .map(|f| serde_json::from_str(&f).unwrap_or(serde_json::Value::String(f))),
+            priority: None,
+            wit: Some(cli.name.clone()),
+        },
+        examples: match cli.examples {
+            Some(json) => serde_json::from_str(&json)?,
+            None => Vec::new(),
+        },
+        prompter: cli
+            .identity
+            .as_deref()
+            .map(PromptHelper::from_config)
+            .unwrap_or_default(),
     };
 
     let ollama = Ollama::try_new(&cli.llm_url)?;
+    if let Err(e) = distilld::ensure_model(&ollama, &cfg.model).await {
+        tracing::warn!(model = %cfg.model, error = %e, "could not verify model");
+    }
 
     let input: Box<dyn tokio::io::AsyncBufRead + Unpin> = if cli.input == PathBuf::from("-") {
         Box::new(BufReader::new(stdin()))
@@ -113,5 +181,9 @@ async fn main() -> anyhow::Result<()> {
         None => Box::new(stdout()),
     };
 
-    run(cfg, ollama, input, output).await
+    let stats = match cli.usage_file {
+        Some(path) => UsageStats::new().saving_to(path),
+        None => UsageStats::new(),
+    };
+    distilld::run_with_stats(cfg, ollama, input, output, &stats).await
 }
@@ -51,6 +51,7 @@ async fn pulls_missing_model() {
     let handle = tokio::spawn(server);
 
     let cfg = Config {
+        name: "test".into(),
         continuous: false,
         lines: 1,
         prompt: "Summarize: {{current}}".into(),
@@ -59,6 +60,9 @@ async fn pulls_missing_model() {
         history_depth: 1,
         beat: 0,
         trim_newlines: true,
+        options: Default::default(),
+        examples: Vec::new(),
+        prompter: Default::default(),
     };
     let ollama = Ollama::try_new(format!("http://{}", addr)).unwrap();
     let input = BufReader::new("hello".as_bytes());
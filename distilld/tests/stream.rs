@@ -20,8 +20,9 @@ async fn run_streams_output() {
         })
         .await;
 
-    let ollama = Ollama::try_new(&server.base_url()).unwrap();
+    let ollama = Ollama::try_new(server.base_url()).unwrap();
     let cfg = Config {
+        name: "test".into(),
         continuous: false,
         lines: 1,
         prompt: "Summarize: {{current}}".into(),
@@ -30,6 +31,9 @@ async fn run_streams_output() {
         history_depth: 1,
         beat: 0,
         trim_newlines: true,
+        options: Default::default(),
+        examples: Vec::new(),
+        prompter: Default::default(),
     };
     let input = BufReader::new("hi".as_bytes());
     let mut out = Vec::new();
@@ -54,8 +58,9 @@ async fn run_trims_newlines() {
         })
         .await;
 
-    let ollama = Ollama::try_new(&server.base_url()).unwrap();
+    let ollama = Ollama::try_new(server.base_url()).unwrap();
     let cfg = Config {
+        name: "test".into(),
         continuous: false,
         lines: 1,
         prompt: "Summarize: {{current}}".into(),
@@ -64,6 +69,9 @@ async fn run_trims_newlines() {
         history_depth: 1,
         beat: 0,
         trim_newlines: true,
+        options: Default::default(),
+        examples: Vec::new(),
+        prompter: Default::default(),
     };
     let input = BufReader::new("hi".as_bytes());
     let mut out = Vec::new();
@@ -88,8 +96,9 @@ async fn run_no_trim_includes_newlines() {
         })
         .await;
 
-    let ollama = Ollama::try_new(&server.base_url()).unwrap();
+    let ollama = Ollama::try_new(server.base_url()).unwrap();
     let cfg = Config {
+        name: "test".into(),
         continuous: false,
         lines: 1,
         prompt: "Summarize: {{current}}".into(),
@@ -98,6 +107,9 @@ async fn run_no_trim_includes_newlines() {
         history_depth: 1,
         beat: 0,
         trim_newlines: false,
+        options: Default::default(),
+        examples: Vec::new(),
+        prompter: Default::default(),
     };
     let input = BufReader::new("hi".as_bytes());
     let mut out = Vec::new();
@@ -118,8 +130,9 @@ async fn run_skips_empty_input_lines() {
         })
         .await;
 
-    let ollama = Ollama::try_new(&server.base_url()).unwrap();
+    let ollama = Ollama::try_new(server.base_url()).unwrap();
     let cfg = Config {
+        name: "test".into(),
         continuous: false,
         lines: 1,
         prompt: "Summarize: {{current}}".into(),
@@ -128,6 +141,9 @@ async fn run_skips_empty_input_lines() {
         history_depth: 1,
         beat: 0,
         trim_newlines: true,
+        options: Default::default(),
+        examples: Vec::new(),
+        prompter: Default::default(),
     };
     let input = BufReader::new("foo\n\nbar\n".as_bytes());
     let mut out = Vec::new();
@@ -154,8 +170,9 @@ async fn run_filters_empty_output_lines() {
         })
         .await;
 
-    let ollama = Ollama::try_new(&server.base_url()).unwrap();
+    let ollama = Ollama::try_new(server.base_url()).unwrap();
     let cfg = Config {
+        name: "test".into(),
         continuous: false,
         lines: 1,
         prompt: "Summarize: {{current}}".into(),
@@ -164,6 +181,9 @@ async fn run_filters_empty_output_lines() {
         history_depth: 1,
         beat: 0,
         trim_newlines: true,
+        options: Default::default(),
+        examples: Vec::new(),
+        prompter: Default::default(),
     };
     let input = BufReader::new("hi".as_bytes());
     let mut out = Vec::new();
@@ -189,8 +209,9 @@ async fn run_no_trim_preserves_blank_lines() {
         })
         .await;
 
-    let ollama = Ollama::try_new(&server.base_url()).unwrap();
+    let ollama = Ollama::try_new(server.base_url()).unwrap();
     let cfg = Config {
+        name: "test".into(),
         continuous: false,
         lines: 1,
         prompt: "Summarize: {{current}}".into(),
@@ -199,6 +220,9 @@ async fn run_no_trim_preserves_blank_lines() {
         history_depth: 1,
         beat: 0,
         trim_newlines: false,
+        options: Default::default(),
+        examples: Vec::new(),
+        prompter: Default::default(),
     };
     let input = BufReader::new("hi".as_bytes());
     let mut out = Vec::new();
@@ -222,8 +246,9 @@ async fn run_skips_empty_summaries() {
         })
         .await;
 
-    let ollama = Ollama::try_new(&server.base_url()).unwrap();
+    let ollama = Ollama::try_new(server.base_url()).unwrap();
     let cfg = Config {
+        name: "test".into(),
         continuous: false,
         lines: 1,
         prompt: "Summarize: {{current}}".into(),
@@ -232,6 +257,9 @@ async fn run_skips_empty_summaries() {
         history_depth: 1,
         beat: 0,
         trim_newlines: true,
+        options: Default::default(),
+        examples: Vec::new(),
+        prompter: Default::default(),
     };
     let input = BufReader::new("hi".as_bytes());
     let mut out = Vec::new();
@@ -255,8 +283,9 @@ async fn run_no_trim_skips_empty_summaries() {
         })
         .await;
 
-    let ollama = Ollama::try_new(&server.base_url()).unwrap();
+    let ollama = Ollama::try_new(server.base_url()).unwrap();
     let cfg = Config {
+        name: "test".into(),
         continuous: false,
         lines: 1,
         prompt: "Summarize: {{current}}".into(),
@@ -265,6 +294,9 @@ async fn run_no_trim_skips_empty_summaries() {
         history_depth: 1,
         beat: 0,
         trim_newlines: false,
+        options: Default::default(),
+        examples: Vec::new(),
+        prompter: Default::default(),
     };
     let input = BufReader::new("hi".as_bytes());
     let mut out = Vec::new();
@@ -272,3 +304,186 @@ async fn run_no_trim_skips_empty_summaries() {
     eprintln!("new out={:?}", std::str::from_utf8(&out).unwrap());
     assert!(std::str::from_utf8(&out).unwrap().is_empty());
 }
+
+#[tokio::test]
+async fn run_sends_generation_options() {
+    let server = MockServer::start_async().await;
+    let body = "{\"model\":\"llama3\",\"created_at\":\"now\",\"response\":\"{}\",\"done\":true}\n";
+    let mock = server
+        .mock_async(|when, then| {
+            when.method(Method::POST)
+                .path("/api/generate")
+                .body_contains("\"temperature\":0.0")
+                .body_contains("\"seed\":3")
+                .body_contains("\"format\":\"json\"");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(body);
+        })
+        .await;
+
+    let ollama = Ollama::try_new(server.base_url()).unwrap();
+    let cfg = Config {
+        name: "test".into(),
+        continuous: false,
+        lines: 1,
+        prompt: "Summarize: {{current}}".into(),
+        model: "llama3".into(),
+        terminal: "\n".into(),
+        history_depth: 1,
+        beat: 0,
+        trim_newlines: true,
+        options: psyche::llm::GenerationOptions {
+            temperature: Some(0.0),
+            seed: Some(3),
+            format: Some(serde_json::json!("json")),
+            ..Default::default()
+        },
+        examples: Vec::new(),
+        prompter: Default::default(),
+    };
+    let input = BufReader::new("hi".as_bytes());
+    let mut out = Vec::new();
+    run(cfg, ollama, input, &mut out).await.unwrap();
+    assert_eq!(std::str::from_utf8(&out).unwrap(), "{}\n");
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn run_sends_examples_as_chat_turns() {
+    let server = MockServer::start_async().await;
+    let body = concat!(
+        "{\"model\":\"llama3\",\"created_at\":\"now\",\"message\":{\"role\":\"assistant\",\"content\":\"Rain \"},\"done\":false}\n",
+        "{\"model\":\"llama3\",\"created_at\":\"now\",\"message\":{\"role\":\"assistant\",\"content\":\"fell.\"},\"done\":true,",
+        "\"total_duration\":1,\"load_duration\":1,\"prompt_eval_count\":9,\"prompt_eval_duration\":1,",
+        "\"eval_count\":2,\"eval_duration\":1}\n"
+    );
+    let mock = server
+        .mock_async(|when, then| {
+            when.method(Method::POST)
+                .path("/api/chat")
+                .body_contains("\"content\":\"Clouds gathered.\"")
+                .body_contains("\"content\":\"It grew dark.\"")
+                .body_contains("\"content\":\"Summarize: hi\"");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(body);
+        })
+        .await;
+
+    let ollama = Ollama::try_new(server.base_url()).unwrap();
+    let cfg = Config {
+        name: "few-shot".into(),
+        continuous: false,
+        lines: 1,
+        prompt: "Summarize: {{current}}".into(),
+        model: "llama3".into(),
+        terminal: "\n".into(),
+        history_depth: 1,
+        beat: 0,
+        trim_newlines: true,
+        options: Default::default(),
+        examples: vec![
+            psyche::llm::ChatMessage::user("Clouds gathered."),
+            psyche::llm::ChatMessage::assistant("It grew dark."),
+        ],
+        prompter: Default::default(),
+    };
+    let stats = psyche::llm::usage::UsageStats::new();
+    let input = BufReader::new("hi".as_bytes());
+    let mut out = Vec::new();
+    distilld::run_with_stats(cfg, ollama, input, &mut out, &stats)
+        .await
+        .unwrap();
+    assert_eq!(std::str::from_utf8(&out).unwrap(), "Rain fell.\n");
+    assert_eq!(stats.wit("few-shot").unwrap().prompt_tokens, 9);
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn run_records_token_usage() {
+    let server = MockServer::start_async().await;
+    let body = concat!(
+        "{\"model\":\"llama3\",\"created_at\":\"now\",\"response\":\"foo\",\"done\":false}\n",
+        "{\"model\":\"llama3\",\"created_at\":\"now\",\"response\":\"\",\"done\":true,",
+        "\"prompt_eval_count\":21,\"eval_count\":4}\n"
+    );
+    server
+        .mock_async(|when, then| {
+            when.method(Method::POST).path("/api/generate");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(body);
+        })
+        .await;
+
+    let ollama = Ollama::try_new(server.base_url()).unwrap();
+    let cfg = Config {
+        name: "episode".into(),
+        continuous: false,
+        lines: 1,
+        prompt: "Summarize: {{current}}".into(),
+        model: "llama3".into(),
+        terminal: "\n".into(),
+        history_depth: 1,
+        beat: 0,
+        trim_newlines: true,
+        options: Default::default(),
+        examples: Vec::new(),
+        prompter: Default::default(),
+    };
+    let stats = psyche::llm::usage::UsageStats::new();
+    let input = BufReader::new("a\nb".as_bytes());
+    let mut out = Vec::new();
+    distilld::run_with_stats(cfg, ollama, input, &mut out, &stats)
+        .await
+        .unwrap();
+    let totals = stats.wit("episode").unwrap();
+    assert_eq!(totals.calls, 2);
+    assert_eq!(totals.prompt_tokens, 42);
+    assert_eq!(totals.completion_tokens, 8);
+    assert_eq!(totals.first_token_calls, 2);
+}
+
+#[tokio::test]
+async fn run_renders_identity_into_prompt() {
+    let server = MockServer::start_async().await;
+    let mock = server
+        .mock_async(|when, then| {
+            when.method(Method::POST)
+                .path("/api/generate")
+                .body_contains("Layka heard: hi");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(
+                "{\"model\":\"llama3\",\"created_at\":\"now\",\"response\":\"ok\",\"done\":true}\n",
+            );
+        })
+        .await;
+
+    let ollama = Ollama::try_new(server.base_url()).unwrap();
+    let cfg = Config {
+        name: "test".into(),
+        continuous: false,
+        lines: 1,
+        prompt: "{{ name }} heard: {{ current }}".into(),
+        model: "llama3".into(),
+        terminal: "\n".into(),
+        history_depth: 1,
+        beat: 0,
+        trim_newlines: true,
+        options: Default::default(),
+        examples: Vec::new(),
+        prompter: psyche::llm::prompt::PromptHelper::default().with_identity(
+            psyche::llm::prompt::Identity {
+                name: "Layka".into(),
+                ..Default::default()
+            },
+        ),
+    };
+    let input = BufReader::new("hi".as_bytes());
+    let mut out = Vec::new();
+    run(cfg, ollama, input, &mut out).await.unwrap();
+    assert_eq!(std::str::from_utf8(&out).unwrap(), "ok\n");
+    mock.assert_async().await;
+}
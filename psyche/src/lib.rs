@@ -5,5 +5,7 @@
 pub mod llm;
 pub mod memory;
 pub mod models;
+pub mod schema;
+pub mod soul;
 pub mod utils;
 pub mod wit;
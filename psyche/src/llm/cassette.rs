@@ -0,0 +1,407 @@
+//! Record and replay LLM traffic through JSONL "cassettes".
+//!
+//! [`RecordingChat`] and [`RecordingEmbed`] wrap a real backend and append
+//! every prompt/response pair to a cassette file. [`ReplayChat`] and
+//! [`ReplayEmbed`] serve those responses back, keyed by a hash of the prompt,
+//! and return an error for any prompt that was never recorded.
+
+use super::{
+    ok_tokens, CanChat, CanEmbed, ChatEvent, ChatMessage, GenerationOptions, LlmProfile, ToolCall,
+    ToolDefinition, ToolResponse,
+};
+use async_stream::stream;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tokio_stream::{iter, Stream, StreamExt};
+use tracing::{debug, error, warn};
+
+/// A single recorded interaction.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum CassetteEntry {
+    /// A chat completion, stored token by token.
+    Chat {
+        key: String,
+        messages: Vec<ChatMessage>,
+        #[serde(default)]
+        options: GenerationOptions,
+        tokens: Vec<String>,
+    },
+    /// A tool-enabled chat call and the calls the model chose.
+    Tools {
+        key: String,
+        messages: Vec<ChatMessage>,
+        tools: Vec<ToolDefinition>,
+        #[serde(default)]
+        options: GenerationOptions,
+        #[serde(default)]
+        content: String,
+        tool_calls: Vec<ToolCall>,
+    },
+    /// An embedding request.
+    Embed {
+        key: String,
+        text: String,
+        embedding: Vec<f32>,
+    },
+}
+
+/// Cassette key for a chat request: a SHA-256 over the messages and options.
+///
+/// ```
+/// use psyche::llm::cassette::chat_key;
+/// use psyche::llm::{ChatMessage, GenerationOptions};
+/// let a = chat_key(&[ChatMessage::user("hi")], &GenerationOptions::default());
+/// let b = chat_key(&[ChatMessage::user("hi!")], &GenerationOptions::default());
+/// assert_eq!(a.len(), 64);
+/// assert_ne!(a, b);
+/// ```
+pub fn chat_key(messages: &[ChatMessage], options: &GenerationOptions) -> String {
+    let payload = serde_json::json!({"messages": messages, "options": options});
+    hex_digest(payload.to_string().as_bytes())
+}
+
+/// Cassette key for a tool-enabled chat request.
+pub fn tools_key(
+    messages: &[ChatMessage],
+    tools: &[ToolDefinition],
+    options: &GenerationOptions,
+) -> String {
+    let payload = serde_json::json!({"messages": messages, "tools": tools, "options": options});
+    hex_digest(payload.to_string().as_bytes())
+}
+
+/// Cassette key for an embedding request.
+pub fn embed_key(text: &str) -> String {
+    hex_digest(text.as_bytes())
+}
+
+fn hex_digest(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+async fn append(path: &Path, entry: &CassetteEntry) -> anyhow::Result<()> {
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await?;
+    file.write_all(line.as_bytes()).await?;
+    file.flush().await?;
+    Ok(())
+}
+
+fn load_entries(path: &Path) -> anyhow::Result<Vec<CassetteEntry>> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("cassette {}: {}", path.display(), e))?;
+    text.lines()
+        .filter(|l| !l.trim().is_empty())
+        .enumerate()
+        .map(|(n, l)| {
+            serde_json::from_str(l)
+                .map_err(|e| anyhow::anyhow!("cassette {} line {}: {}", path.display(), n + 1, e))
+        })
+        .collect()
+}
+
+/// Chat wrapper that records every completed response to a cassette.
+pub struct RecordingChat {
+    inner: Arc<dyn CanChat>,
+    path: PathBuf,
+}
+
+impl RecordingChat {
+    /// Record the traffic of `inner` into the cassette at `path`.
+    pub fn new(inner: Arc<dyn CanChat>, path: impl Into<PathBuf>) -> Self {
+        Self {
+            inner,
+            path: path.into(),
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl CanChat for RecordingChat {
+    async fn chat_stream(
+        &self,
+        profile: &LlmProfile,
+        system: &str,
+        user: &str,
+    ) -> anyhow::Result<Box<dyn Stream<Item = String> + Unpin>> {
+        self.chat_stream_with_options(profile, system, user, &GenerationOptions::default())
+            .await
+    }
+
+    async fn chat_stream_with_options(
+        &self,
+        profile: &LlmProfile,
+        system: &str,
+        user: &str,
+        options: &GenerationOptions,
+    ) -> anyhow::Result<Box<dyn Stream<Item = String> + Unpin>> {
+        let messages = [ChatMessage::system(system), ChatMessage::user(user)];
+        self.chat_messages_stream(profile, &messages, options).await
+    }
+
+    async fn chat_messages_stream(
+        &self,
+        profile: &LlmProfile,
+        messages: &[ChatMessage],
+        options: &GenerationOptions,
+    ) -> anyhow::Result<Box<dyn Stream<Item = String> + Unpin>> {
+        Ok(ok_tokens(
+            self.chat_events(profile, messages, options).await?,
+        ))
+    }
+
+    /// Forwards the events of the inner backend, recording the reply once it
+    /// completes. A reply cut short by an error is not recorded.
+    async fn chat_events(
+        &self,
+        profile: &LlmProfile,
+        messages: &[ChatMessage],
+        options: &GenerationOptions,
+    ) -> anyhow::Result<Box<dyn Stream<Item = anyhow::Result<ChatEvent>> + Unpin>> {
+        let mut inner = self.inner.chat_events(profile, messages, options).await?;
+        let path = self.path.clone();
+        let messages = messages.to_vec();
+        let options = options.clone();
+        let out = stream! {
+            let mut tokens = Vec::new();
+            let mut failed = false;
+            while let Some(event) = inner.next().await {
+                match &event {
+                    Ok(ChatEvent::Token(t)) => tokens.push(t.clone()),
+                    Ok(ChatEvent::Usage(_)) => {}
+                    Err(_) => failed = true,
+                }
+                yield event;
+            }
+            if failed {
+                warn!(target: "llm", path = %path.display(), "not recording failed chat stream");
+            } else {
+                let entry = CassetteEntry::Chat {
+                    key: chat_key(&messages, &options),
+                    messages,
+                    options,
+                    tokens,
+                };
+                if let Err(e) = append(&path, &entry).await {
+                    warn!(target: "llm", error = %e, path = %path.display(), "failed to record cassette");
+                }
+            }
+        };
+        Ok(Box::new(Box::pin(out)))
+    }
+
+    async fn health_check(&self) -> anyhow::Result<()> {
+        self.inner.health_check().await
+    }
+
+    async fn chat_with_tools(
+        &self,
+        profile: &LlmProfile,
+        messages: &[ChatMessage],
+        tools: &[ToolDefinition],
+        options: &GenerationOptions,
+    ) -> anyhow::Result<ToolResponse> {
+        let response = self
+            .inner
+            .chat_with_tools(profile, messages, tools, options)
+            .await?;
+        let entry = CassetteEntry::Tools {
+            key: tools_key(messages, tools, options),
+            messages: messages.to_vec(),
+            tools: tools.to_vec(),
+            options: options.clone(),
+            content: response.content.clone(),
+            tool_calls: response.tool_calls.clone(),
+        };
+        if let Err(e) = append(&self.path, &entry).await {
+            warn!(target: "llm", error = %e, path = %self.path.display(), "failed to record cassette");
+        }
+        Ok(response)
+    }
+}
+
+/// Embedding wrapper that records every vector to a cassette.
+pub struct RecordingEmbed {
+    inner: Box<dyn CanEmbed>,
+    path: PathBuf,
+}
+
+impl RecordingEmbed {
+    /// Record the embeddings of `inner` into the cassette at `path`.
+    pub fn new(inner: Box<dyn CanEmbed>, path: impl Into<PathBuf>) -> Self {
+        Self {
+            inner,
+            path: path.into(),
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl CanEmbed for RecordingEmbed {
+    async fn embed(&self, profile: &LlmProfile, text: &str) -> anyhow::Result<Vec<f32>> {
+        let embedding = self.inner.embed(profile, text).await?;
+        let entry = CassetteEntry::Embed {
+            key: embed_key(text),
+            text: text.to_string(),
+            embedding: embedding.clone(),
+        };
+        if let Err(e) = append(&self.path, &entry).await {
+            warn!(target: "llm", error = %e, path = %self.path.display(), "failed to record cassette");
+        }
+        Ok(embedding)
+    }
+}
+
+/// Chat backend that replays responses recorded by [`RecordingChat`].
+#[derive(Debug, Default)]
+pub struct ReplayChat {
+    responses: HashMap<String, Vec<String>>,
+    tool_responses: HashMap<String, ToolResponse>,
+}
+
+impl ReplayChat {
+    /// Load every chat entry from the cassette at `path`.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let mut responses = HashMap::new();
+        let mut tool_responses = HashMap::new();
+        for entry in load_entries(path)? {
+            match entry {
+                CassetteEntry::Chat { key, tokens, .. } => {
+                    responses.insert(key, tokens);
+                }
+                CassetteEntry::Tools {
+                    key,
+                    content,
+                    tool_calls,
+                    ..
+                } => {
+                    tool_responses.insert(
+                        key,
+                        ToolResponse {
+                            content,
+                            tool_calls,
+                        },
+                    );
+                }
+                CassetteEntry::Embed { .. } => {}
+            }
+        }
+        debug!(
+            target: "llm",
+            path = %path.display(),
+            entries = responses.len() + tool_responses.len(),
+            "loaded chat cassette"
+        );
+        Ok(Self {
+            responses,
+            tool_responses,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl CanChat for ReplayChat {
+    async fn chat_stream(
+        &self,
+        profile: &LlmProfile,
+        system: &str,
+        user: &str,
+    ) -> anyhow::Result<Box<dyn Stream<Item = String> + Unpin>> {
+        self.chat_stream_with_options(profile, system, user, &GenerationOptions::default())
+            .await
+    }
+
+    async fn chat_stream_with_options(
+        &self,
+        profile: &LlmProfile,
+        system: &str,
+        user: &str,
+        options: &GenerationOptions,
+    ) -> anyhow::Result<Box<dyn Stream<Item = String> + Unpin>> {
+        let messages = [ChatMessage::system(system), ChatMessage::user(user)];
+        self.chat_messages_stream(profile, &messages, options).await
+    }
+
+    async fn chat_messages_stream(
+        &self,
+        _profile: &LlmProfile,
+        messages: &[ChatMessage],
+        options: &GenerationOptions,
+    ) -> anyhow::Result<Box<dyn Stream<Item = String> + Unpin>> {
+        let key = chat_key(messages, options);
+        match self.responses.get(&key) {
+            Some(tokens) => Ok(Box::new(iter(tokens.clone()))),
+            None => {
+                let last = messages.last().map(|m| m.content.as_str()).unwrap_or("");
+                error!(target: "llm", %key, prompt = %last, "cassette miss");
+                anyhow::bail!("no cassette entry for chat prompt {}: {:?}", key, last)
+            }
+        }
+    }
+
+    async fn chat_with_tools(
+        &self,
+        _profile: &LlmProfile,
+        messages: &[ChatMessage],
+        tools: &[ToolDefinition],
+        options: &GenerationOptions,
+    ) -> anyhow::Result<ToolResponse> {
+        let key = tools_key(messages, tools, options);
+        match self.tool_responses.get(&key) {
+            Some(response) => Ok(response.clone()),
+            None => {
+                let last = messages.last().map(|m| m.content.as_str()).unwrap_or("");
+                error!(target: "llm", %key, prompt = %last, "cassette miss");
+                anyhow::bail!("no cassette entry for tool prompt {}: {:?}", key, last)
+            }
+        }
+    }
+}
+
+/// Embedding backend that replays vectors recorded by [`RecordingEmbed`].
+#[derive(Debug, Default)]
+pub struct ReplayEmbed {
+    vectors: HashMap<String, Vec<f32>>,
+}
+
+impl ReplayEmbed {
+    /// Load every embedding entry from the cassette at `path`.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let mut vectors = HashMap::new();
+        for entry in load_entries(path)? {
+            if let CassetteEntry::Embed { key, embedding, .. } = entry {
+                vectors.insert(key, embedding);
+            }
+        }
+        debug!(target: "llm", path = %path.display(), entries = vectors.len(), "loaded embedding cassette");
+        Ok(Self { vectors })
+    }
+}
+
+#[async_trait(?Send)]
+impl CanEmbed for ReplayEmbed {
+    async fn embed(&self, _profile: &LlmProfile, text: &str) -> anyhow::Result<Vec<f32>> {
+        let key = embed_key(text);
+        match self.vectors.get(&key) {
+            Some(v) => Ok(v.clone()),
+            None => {
+                error!(target: "llm", %key, %text, "cassette miss");
+                anyhow::bail!("no cassette entry for embedding {}: {:?}", key, text)
+            }
+        }
+    }
+}
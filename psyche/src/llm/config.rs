@@ -0,0 +1,621 @@
+//! Loading of `llm.toml`, which describes the available model providers.
+//!
+//! ```toml
+//! [[llm]]
+//! provider = "ollama"
+//! base_url = "http://localhost:11434"
+//! models = ["gemma3:27b"]
+//! capabilities = ["chat", "image"]
+//! first_token_timeout_secs = 30
+//!
+//! [[llm]]
+//! provider = "scripted"
+//! name = "script"
+//! rules = "rules.toml"
+//! models = ["script"]
+//! ```
+
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// Configuration for an individual LLM provider.
+#[derive(Debug, Deserialize, Clone)]
+pub struct LlmProviderConfig {
+    /// Provider name like `"ollama"` or `"openai"`.
+    pub provider: String,
+    /// Optional unique name used for selecting this provider.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Base URL for the service if applicable (e.g. Ollama). For OpenAI
+    /// compatible servers this excludes the `/v1` suffix.
+    #[serde(default)]
+    pub base_url: Option<String>,
+    /// API key for services like OpenAI.
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// Models offered by this provider. The `"lexical"` embedder needs none.
+    #[serde(default)]
+    pub models: Vec<String>,
+    /// Supported capabilities such as `"chat"` or `"embedding"`.
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+    /// Maximum concurrent requests allowed.
+    #[serde(default)]
+    pub concurrency: Option<usize>,
+    /// Seconds of waiting that raise a queued request's priority by one.
+    #[serde(default)]
+    pub aging_secs: Option<f64>,
+    /// JSONL cassette, relative to the config file. The `"replay"` provider
+    /// serves responses from it; any other provider records into it.
+    #[serde(default)]
+    pub cassette: Option<PathBuf>,
+    /// Rules file for the `"scripted"` provider, relative to the config
+    /// file.
+    #[serde(default)]
+    pub rules: Option<PathBuf>,
+    /// Vector size for the `"lexical"` embedder.
+    #[serde(default)]
+    pub dimensions: Option<usize>,
+    /// Pull missing Ollama models during [`prepare_models`].
+    #[serde(default = "default_pull_missing")]
+    pub pull_missing: bool,
+    /// Preload chat models during [`prepare_models`] and keep them loaded
+    /// this long, as an Ollama duration such as `"30m"` or `"-1"`.
+    #[serde(default)]
+    pub keep_alive: Option<String>,
+    /// Seconds allowed to connect to the provider and get its response
+    /// headers, and for health checks.
+    #[serde(default)]
+    pub connect_timeout_secs: Option<f64>,
+    /// Seconds allowed until the first token of a reply.
+    #[serde(default)]
+    pub first_token_timeout_secs: Option<f64>,
+    /// Seconds allowed for a whole reply.
+    #[serde(default)]
+    pub total_timeout_secs: Option<f64>,
+}
+
+fn default_pull_missing() -> bool {
+    true
+}
+
+impl LlmProviderConfig {
+    /// Whether this entry only serves embeddings and cannot chat.
+    fn embeds_only(&self) -> bool {
+        self.provider == "lexical"
+            || parse_capabilities(&self.capabilities)
+                .iter()
+                .all(|c| *c == super::LlmCapability::Embedding)
+    }
+
+    /// Deadlines configured for this provider.
+    pub fn timeouts(&self) -> super::deadline::Timeouts {
+        let secs = |s: Option<f64>| s.map(std::time::Duration::from_secs_f64);
+        super::deadline::Timeouts {
+            connect: secs(self.connect_timeout_secs),
+            first_token: secs(self.first_token_timeout_secs),
+            total: secs(self.total_timeout_secs),
+        }
+    }
+}
+
+/// Selects the provider and model that serve the embedding capability.
+///
+/// ```toml
+/// [embedding]
+/// llm = "gpu1"
+/// model = "nomic-embed-text"
+/// ```
+///
+/// Without an embedding server, a `provider = "lexical"` entry with
+/// `capabilities = ["embedding"]` embeds text offline; its vector size is
+/// set with `dimensions`.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct EmbeddingConfig {
+    /// Name of the `[[llm]]` entry to use. Defaults to the first entry.
+    #[serde(default)]
+    pub llm: Option<String>,
+    /// Embedding model. Defaults to the first model of the selected entry.
+    #[serde(default)]
+    pub model: Option<String>,
+}
+
+/// Selects the provider and model that serve image understanding.
+///
+/// ```toml
+/// [vision]
+/// llm = "gpu1"
+/// model = "gemma3:27b"
+/// ```
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct VisionConfig {
+    /// Name of the `[[llm]]` entry to use. Defaults to the first entry.
+    #[serde(default)]
+    pub llm: Option<String>,
+    /// Vision model. Defaults to the first model of the selected entry.
+    #[serde(default)]
+    pub model: Option<String>,
+}
+
+fn default_health_interval_secs() -> u64 {
+    30
+}
+
+/// Routing across the chat providers.
+///
+/// ```toml
+/// [pool]
+/// policy = "round_robin"
+/// health_interval_secs = 10
+/// ```
+#[derive(Debug, Deserialize, Clone)]
+pub struct PoolConfig {
+    /// One of `"priority"`, `"round_robin"` or `"least_loaded"`.
+    #[serde(default)]
+    pub policy: super::pool::RoutingPolicy,
+    /// Seconds between health checks of every provider.
+    #[serde(default = "default_health_interval_secs")]
+    pub health_interval_secs: u64,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self {
+            policy: Default::default(),
+            health_interval_secs: default_health_interval_secs(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct LlmConfigFile {
+    #[serde(rename = "llm")]
+    pub llms: Vec<LlmProviderConfig>,
+    #[serde(default)]
+    pub embedding: Option<EmbeddingConfig>,
+    #[serde(default)]
+    pub vision: Option<VisionConfig>,
+    #[serde(default)]
+    pub pool: Option<PoolConfig>,
+}
+
+fn provider_name(prov: &LlmProviderConfig, idx: usize) -> String {
+    prov.name
+        .clone()
+        .unwrap_or_else(|| format!("{}{}", prov.provider, idx))
+}
+
+/// Map capability names from the config file, defaulting to chat only.
+fn parse_capabilities(names: &[String]) -> Vec<super::LlmCapability> {
+    if names.is_empty() {
+        return vec![super::LlmCapability::Chat];
+    }
+    names
+        .iter()
+        .filter_map(|c| match c.as_str() {
+            "chat" => Some(super::LlmCapability::Chat),
+            "embedding" => Some(super::LlmCapability::Embedding),
+            "image" | "vision" => Some(super::LlmCapability::Image),
+            "tool_use" => Some(super::LlmCapability::ToolUse),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Resolve the cassette of `prov` against the config directory `base`.
+fn cassette_path(prov: &LlmProviderConfig, base: &Path) -> Option<PathBuf> {
+    prov.cassette.as_ref().map(|c| base.join(c))
+}
+
+/// Build the chat backend for `prov`, wrapping it for recording when a
+/// cassette is configured and bounding it by any configured timeouts.
+fn build_chat(
+    prov: &LlmProviderConfig,
+    model: String,
+    base: &Path,
+) -> anyhow::Result<Box<dyn super::CanChat>> {
+    let cassette = cassette_path(prov, base);
+    let chat: Box<dyn super::CanChat> = match prov.provider.as_str() {
+        "ollama" => Box::new(super::ollama::OllamaChat {
+            base_url: prov
+                .base_url
+                .clone()
+                .unwrap_or_else(|| "http://localhost:11434".into()),
+            model,
+        }),
+        "openai" => Box::new(super::openai::OpenAiChat {
+            base_url: prov
+                .base_url
+                .clone()
+                .unwrap_or_else(|| "https://api.openai.com".into()),
+            model,
+            api_key: prov.api_key.clone(),
+        }),
+        "mock" => Box::new(super::mock_chat::MockChat),
+        "lexical" => anyhow::bail!("lexical provider only embeds"),
+        "scripted" => {
+            let rules = prov
+                .rules
+                .as_ref()
+                .ok_or_else(|| anyhow::anyhow!("scripted provider requires rules"))?;
+            Box::new(super::scripted::ScriptedChat::load(&base.join(rules))?)
+        }
+        "replay" => {
+            let path =
+                cassette.ok_or_else(|| anyhow::anyhow!("replay provider requires a cassette"))?;
+            return Ok(Box::new(super::cassette::ReplayChat::load(&path)?));
+        }
+        other => anyhow::bail!("unsupported provider: {}", other),
+    };
+    let chat: Box<dyn super::CanChat> = match cassette {
+        Some(path) => Box::new(super::cassette::RecordingChat::new(
+            std::sync::Arc::from(chat),
+            path,
+        )),
+        None => chat,
+    };
+    let timeouts = prov.timeouts();
+    Ok(if timeouts.is_unbounded() {
+        chat
+    } else {
+        Box::new(super::deadline::DeadlineChat::new(
+            std::sync::Arc::from(chat),
+            timeouts,
+        ))
+    })
+}
+
+/// Directory that relative paths in the config file at `path` resolve against.
+fn config_dir(path: &Path) -> &Path {
+    path.parent().unwrap_or_else(|| Path::new("."))
+}
+
+/// Find the `[[llm]]` entry called `name`, or the first entry when `None`.
+fn named_provider<'a>(
+    cfg: &'a LlmConfigFile,
+    name: Option<&str>,
+) -> anyhow::Result<&'a LlmProviderConfig> {
+    match name {
+        Some(name) => cfg
+            .llms
+            .iter()
+            .enumerate()
+            .find(|(idx, p)| provider_name(p, *idx) == name)
+            .map(|(_, p)| p)
+            .ok_or_else(|| anyhow::anyhow!("unknown llm: {}", name)),
+        None => cfg
+            .llms
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("no llm entries")),
+    }
+}
+
+/// Build the embedder described by `cfg`.
+///
+/// An explicit `[embedding]` section wins. Otherwise the first provider
+/// advertising the `"embedding"` capability is used, falling back to
+/// [`MockEmbed`](super::mock_embed::MockEmbed) when none does.
+fn build_embed(cfg: &LlmConfigFile, base: &Path) -> anyhow::Result<Box<dyn super::CanEmbed>> {
+    let selected = match &cfg.embedding {
+        Some(emb) => Some((
+            named_provider(cfg, emb.llm.as_deref())
+                .map_err(|e| anyhow::anyhow!("embedding: {}", e))?,
+            emb.model.clone(),
+        )),
+        None => cfg
+            .llms
+            .iter()
+            .find(|p| p.capabilities.iter().any(|c| c == "embedding"))
+            .map(|p| (p, None)),
+    };
+    let Some((prov, model)) = selected else {
+        tracing::warn!("no embedding provider configured; using mock embeddings");
+        return Ok(Box::new(super::mock_embed::MockEmbed));
+    };
+    let model = model
+        .or_else(|| prov.models.first().cloned())
+        .or_else(|| (prov.provider == "lexical").then(|| "lexical".to_string()))
+        .ok_or_else(|| anyhow::anyhow!("no embedding model for provider"))?;
+    tracing::info!(provider = %prov.provider, %model, "using embedding model");
+    let cassette = cassette_path(prov, base);
+    let embed: Box<dyn super::CanEmbed> = match prov.provider.as_str() {
+        "ollama" => Box::new(super::ollama::OllamaEmbed {
+            base_url: prov
+                .base_url
+                .clone()
+                .unwrap_or_else(|| "http://localhost:11434".into()),
+            model,
+        }),
+        "openai" => Box::new(super::openai::OpenAiEmbed {
+            base_url: prov
+                .base_url
+                .clone()
+                .unwrap_or_else(|| "https://api.openai.com".into()),
+            model,
+            api_key: prov.api_key.clone(),
+        }),
+        "mock" => Box::new(super::mock_embed::MockEmbed),
+        "lexical" => Box::new(super::lexical::LexicalEmbed::new(
+            prov.dimensions
+                .unwrap_or(super::lexical::DEFAULT_DIMENSIONS),
+        )),
+        "replay" => {
+            let path =
+                cassette.ok_or_else(|| anyhow::anyhow!("replay provider requires a cassette"))?;
+            return Ok(Box::new(super::cassette::ReplayEmbed::load(&path)?));
+        }
+        other => anyhow::bail!("unsupported embedding provider: {}", other),
+    };
+    Ok(match cassette {
+        Some(path) => Box::new(super::cassette::RecordingEmbed::new(embed, path)),
+        None => embed,
+    })
+}
+
+/// Loads the embedder selected by the LLM configuration at `path`.
+pub async fn load_embedder(path: &Path) -> anyhow::Result<Box<dyn super::CanEmbed>> {
+    let text = tokio::fs::read_to_string(path).await?;
+    let cfg: LlmConfigFile = toml::from_str(&text)?;
+    build_embed(&cfg, config_dir(path))
+}
+
+/// Find the provider and model that serve image understanding.
+///
+/// An explicit `[vision]` section wins, otherwise the first provider
+/// advertising the `"image"` capability is used.
+fn vision_model(cfg: &LlmConfigFile) -> anyhow::Result<(&LlmProviderConfig, String)> {
+    let (prov, model) = match &cfg.vision {
+        Some(vis) => (
+            named_provider(cfg, vis.llm.as_deref())
+                .map_err(|e| anyhow::anyhow!("vision: {}", e))?,
+            vis.model.clone(),
+        ),
+        None => (
+            cfg.llms
+                .iter()
+                .find(|p| p.capabilities.iter().any(|c| c == "image" || c == "vision"))
+                .ok_or_else(|| anyhow::anyhow!("no vision provider configured"))?,
+            None,
+        ),
+    };
+    let model = model
+        .or_else(|| prov.models.first().cloned())
+        .ok_or_else(|| anyhow::anyhow!("no vision model for provider"))?;
+    Ok((prov, model))
+}
+
+/// Build the vision model described by `cfg`.
+///
+/// See [`vision_model`] for how the provider is chosen.
+fn build_vision(
+    cfg: &LlmConfigFile,
+) -> anyhow::Result<(std::sync::Arc<dyn super::CanSee>, super::LlmProfile)> {
+    let (prov, model) = vision_model(cfg)?;
+    tracing::info!(provider = %prov.provider, %model, "using vision model");
+    let profile = super::LlmProfile {
+        provider: prov.provider.clone(),
+        model: model.clone(),
+        capabilities: vec![super::LlmCapability::Image],
+    };
+    let vision: std::sync::Arc<dyn super::CanSee> = match prov.provider.as_str() {
+        "ollama" => std::sync::Arc::new(super::ollama::OllamaChat {
+            base_url: prov
+                .base_url
+                .clone()
+                .unwrap_or_else(|| "http://localhost:11434".into()),
+            model,
+        }),
+        "mock" => std::sync::Arc::new(super::mock_vision::MockVision::default()),
+        other => anyhow::bail!("unsupported vision provider: {}", other),
+    };
+    Ok((vision, profile))
+}
+
+/// Loads the vision model selected by the LLM configuration at `path`.
+pub async fn load_vision(
+    path: &Path,
+) -> anyhow::Result<(std::sync::Arc<dyn super::CanSee>, super::LlmProfile)> {
+    let text = tokio::fs::read_to_string(path).await?;
+    let cfg: LlmConfigFile = toml::from_str(&text)?;
+    build_vision(&cfg)
+}
+
+/// Check that every Ollama model named in the LLM configuration at `path`
+/// is installed, pulling missing ones unless `pull_missing = false`, and
+/// warm up chat models of entries with a `keep_alive`.
+///
+/// Problems with individual models are logged rather than returned, so
+/// that an unreachable server does not prevent startup; the pool's health
+/// checks take it from there.
+pub async fn prepare_models(path: &Path) -> anyhow::Result<()> {
+    let text = tokio::fs::read_to_string(path).await?;
+    let cfg: LlmConfigFile = toml::from_str(&text)?;
+
+    // (provider index, model, whether it generates text and can be warmed up)
+    let mut wanted: Vec<(usize, String, bool)> = Vec::new();
+    let mut want = |idx: usize, model: &str, generative: bool| match wanted
+        .iter_mut()
+        .find(|(i, m, _)| *i == idx && m == model)
+    {
+        Some(entry) => entry.2 &= generative,
+        None => wanted.push((idx, model.to_string(), generative)),
+    };
+    for (idx, prov) in cfg.llms.iter().enumerate() {
+        let caps = parse_capabilities(&prov.capabilities);
+        let generative = caps.contains(&super::LlmCapability::Chat)
+            || caps.contains(&super::LlmCapability::Image);
+        for model in &prov.models {
+            want(idx, model, generative);
+        }
+    }
+    let index_of = |name: Option<&str>| {
+        let prov = named_provider(&cfg, name).ok()?;
+        cfg.llms.iter().position(|p| std::ptr::eq(p, prov))
+    };
+    if let Some(EmbeddingConfig {
+        llm,
+        model: Some(model),
+    }) = &cfg.embedding
+    {
+        if let Some(idx) = index_of(llm.as_deref()) {
+            want(idx, model, false);
+        }
+    }
+    if let Some(VisionConfig {
+        llm,
+        model: Some(model),
+    }) = &cfg.vision
+    {
+        if let Some(idx) = index_of(llm.as_deref()) {
+            want(idx, model, true);
+        }
+    }
+
+    for (idx, model, generative) in wanted {
+        prepare_model(&cfg.llms[idx], &model, generative).await;
+    }
+    Ok(())
+}
+
+/// Like [`prepare_models`], but only for the vision model that
+/// [`load_vision`] would select.
+pub async fn prepare_vision_model(path: &Path) -> anyhow::Result<()> {
+    let text = tokio::fs::read_to_string(path).await?;
+    let cfg: LlmConfigFile = toml::from_str(&text)?;
+    let (prov, model) = vision_model(&cfg)?;
+    prepare_model(prov, &model, true).await;
+    Ok(())
+}
+
+/// Make `model` of `prov` available, logging rather than returning
+/// failures. Only Ollama models need preparing.
+async fn prepare_model(prov: &LlmProviderConfig, model: &str, generative: bool) {
+    if prov.provider != "ollama" {
+        return;
+    }
+    let admin = super::ollama::OllamaModels::new(
+        prov.base_url
+            .clone()
+            .unwrap_or_else(|| "http://localhost:11434".into()),
+    );
+    let ready = if prov.pull_missing {
+        admin
+            .ensure(model, super::ollama::log_progress(model))
+            .await
+    } else {
+        match admin.has(model).await {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(anyhow::anyhow!("model not installed")),
+            Err(e) => Err(e),
+        }
+    };
+    if let Err(e) = ready {
+        tracing::warn!(%model, base_url = %admin.base_url, error = %e, "model unavailable");
+        return;
+    }
+    if let (Some(keep_alive), true) = (&prov.keep_alive, generative) {
+        if let Err(e) = admin.warmup(model, Some(keep_alive)).await {
+            tracing::warn!(%model, error = %e, "model warmup failed");
+        }
+    }
+}
+
+/// Loads LLM configuration from `path`, returning a registry and profile
+/// using the first chat-capable provider and its first model. Entries that
+/// only embed are skipped.
+pub async fn load_first_llm(
+    path: &Path,
+) -> anyhow::Result<(super::LlmRegistry, super::LlmProfile)> {
+    use tokio::fs;
+
+    let text = fs::read_to_string(path).await?;
+    let cfg: LlmConfigFile = toml::from_str(&text)?;
+    let base = config_dir(path);
+    let embed = build_embed(&cfg, base)?;
+    let first = cfg
+        .llms
+        .into_iter()
+        .find(|p| !p.embeds_only())
+        .ok_or_else(|| anyhow::anyhow!("no chat llm entries"))?;
+    let model = first
+        .models
+        .first()
+        .cloned()
+        .ok_or_else(|| anyhow::anyhow!("no models for provider"))?;
+    let capabilities = parse_capabilities(&first.capabilities);
+    let profile = super::LlmProfile {
+        provider: first.provider.clone(),
+        model: model.clone(),
+        capabilities,
+    };
+    let registry = super::LlmRegistry {
+        chat: build_chat(&first, model, base)?,
+        embed,
+    };
+    Ok((registry, profile))
+}
+
+/// Loads all LLM providers from `path` returning initialized instances.
+pub async fn load_llms(path: &Path) -> anyhow::Result<Vec<super::LlmInstance>> {
+    use tokio::fs;
+    let text = fs::read_to_string(path).await?;
+    let cfg: LlmConfigFile = toml::from_str(&text)?;
+    build_llms(cfg.llms, config_dir(path))
+}
+
+/// Loads every chat-capable provider from `path` into an
+/// [`LlmPool`](super::pool::LlmPool), along with the `[pool]` settings.
+pub async fn load_pool(path: &Path) -> anyhow::Result<(super::pool::LlmPool, PoolConfig)> {
+    let text = tokio::fs::read_to_string(path).await?;
+    let cfg: LlmConfigFile = toml::from_str(&text)?;
+    let pool_cfg = cfg.pool.unwrap_or_default();
+    let instances: Vec<_> = build_llms(cfg.llms, config_dir(path))?
+        .into_iter()
+        .filter(|i| i.profile.capabilities.contains(&super::LlmCapability::Chat))
+        .collect();
+    if instances.is_empty() {
+        anyhow::bail!("no chat llm entries");
+    }
+    Ok((
+        super::pool::LlmPool::new(instances, pool_cfg.policy),
+        pool_cfg,
+    ))
+}
+
+fn build_llms(
+    llms: Vec<LlmProviderConfig>,
+    base: &Path,
+) -> anyhow::Result<Vec<super::LlmInstance>> {
+    let mut out = Vec::new();
+    for (idx, prov) in llms.into_iter().enumerate() {
+        if prov.provider == "lexical" {
+            // Embedding only; served by `build_embed`.
+            continue;
+        }
+        let model = prov
+            .models
+            .first()
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("no models for provider"))?;
+        let capabilities = parse_capabilities(&prov.capabilities);
+        let profile = std::sync::Arc::new(super::LlmProfile {
+            provider: prov.provider.clone(),
+            model: model.clone(),
+            capabilities,
+        });
+        let name = provider_name(&prov, idx);
+        let chat = std::sync::Arc::from(build_chat(&prov, model, base)?);
+        let concurrency = prov.concurrency.unwrap_or(1);
+        out.push(super::LlmInstance {
+            name,
+            chat,
+            profile,
+            scheduler: super::scheduler::PriorityScheduler::new(concurrency).with_aging(
+                prov.aging_secs
+                    .map(std::time::Duration::from_secs_f64)
+                    .unwrap_or(super::scheduler::DEFAULT_AGING),
+            ),
+        });
+    }
+    Ok(out)
+}
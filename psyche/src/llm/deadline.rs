@@ -0,0 +1,261 @@
+//! Timeouts and cancellation for LLM streams.
+//!
+//! [`DeadlineChat`] wraps a backend so that a hung connection cannot block a
+//! caller forever: establishing the stream, receiving the first token and
+//! finishing the reply can each be bounded by [`Timeouts`]. When a deadline
+//! passes the stream yields an error and ends, dropping the underlying
+//! request. A [`CancelHandle`] aborts every generation in flight, for
+//! shutdown or when the user barges in.
+
+use super::{
+    ok_tokens, CanChat, ChatEvent, ChatMessage, GenerationOptions, LlmProfile, ToolDefinition,
+    ToolResponse,
+};
+use async_stream::stream;
+use async_trait::async_trait;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::{timeout, timeout_at, Instant};
+use tokio_stream::{Stream, StreamExt};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, warn};
+
+type EventStream = Box<dyn Stream<Item = anyhow::Result<ChatEvent>> + Unpin>;
+
+/// Deadlines applied to a single chat request. `None` means unbounded.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Timeouts {
+    /// Time allowed for the backend to accept the request and start
+    /// streaming.
+    pub connect: Option<Duration>,
+    /// Time allowed from sending the request until the first token.
+    pub first_token: Option<Duration>,
+    /// Time allowed for the whole reply.
+    pub total: Option<Duration>,
+}
+
+impl Timeouts {
+    /// Whether no deadline is set.
+    pub fn is_unbounded(&self) -> bool {
+        self.connect.is_none() && self.first_token.is_none() && self.total.is_none()
+    }
+}
+
+/// Aborts in-flight generations.
+///
+/// Cloning is cheap and every clone controls the same generations.
+/// Cancelling only affects streams started before the call; later requests
+/// run normally.
+///
+/// ```
+/// use psyche::llm::deadline::CancelHandle;
+/// let handle = CancelHandle::new();
+/// let token = handle.token();
+/// handle.cancel();
+/// assert!(token.is_cancelled());
+/// assert!(!handle.token().is_cancelled());
+/// ```
+#[derive(Clone, Default)]
+pub struct CancelHandle {
+    current: Arc<Mutex<CancellationToken>>,
+}
+
+impl CancelHandle {
+    /// Create a handle with nothing to cancel yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Token watched by generations started now.
+    pub fn token(&self) -> CancellationToken {
+        self.current.lock().unwrap().clone()
+    }
+
+    /// Abort every generation started so far.
+    pub fn cancel(&self) {
+        let mut current = self.current.lock().unwrap();
+        current.cancel();
+        *current = CancellationToken::new();
+    }
+}
+
+/// Bound an event stream by the first-token and total deadlines of
+/// `timeouts`, measured from `started`, and end it when `cancel` fires.
+///
+/// A missed deadline yields a trailing `Err`; cancellation ends the stream
+/// quietly. Either way the inner stream is dropped right away so that any
+/// connection or permit it holds is released.
+pub fn with_deadlines(
+    mut inner: EventStream,
+    timeouts: Timeouts,
+    started: Instant,
+    cancel: CancellationToken,
+) -> EventStream {
+    let first_token = timeouts.first_token.map(|t| started + t);
+    let total = timeouts.total.map(|t| started + t);
+    let out = stream! {
+        let mut seen_token = false;
+        loop {
+            let deadline = match (first_token.filter(|_| !seen_token), total) {
+                (Some(f), Some(t)) if f < t => Some((f, "first token")),
+                (Some(f), None) => Some((f, "first token")),
+                (_, Some(t)) => Some((t, "total")),
+                (None, None) => None,
+            };
+            let next = async {
+                match deadline {
+                    Some((at, _)) => timeout_at(at, inner.next()).await.ok(),
+                    None => Some(inner.next().await),
+                }
+            };
+            tokio::select! {
+                _ = cancel.cancelled() => {
+                    debug!(target: "llm", "llm generation cancelled");
+                    break;
+                }
+                item = next => match item {
+                    Some(Some(event)) => {
+                        if matches!(event, Ok(ChatEvent::Token(_))) {
+                            seen_token = true;
+                        }
+                        yield event;
+                    }
+                    Some(None) => break,
+                    None => {
+                        let (_, which) = deadline.expect("timed out without a deadline");
+                        warn!(target: "llm", deadline = which, elapsed_ms = started.elapsed().as_millis() as u64, "llm stream timed out");
+                        yield Err(std::io::Error::new(
+                            std::io::ErrorKind::TimedOut,
+                            format!("llm {} timeout after {:?}", which, started.elapsed()),
+                        ).into());
+                        break;
+                    }
+                },
+            }
+        }
+    };
+    Box::new(Box::pin(out))
+}
+
+/// Chat wrapper enforcing [`Timeouts`] and honoring a [`CancelHandle`].
+pub struct DeadlineChat {
+    inner: Arc<dyn CanChat>,
+    timeouts: Timeouts,
+    cancel: CancelHandle,
+}
+
+impl DeadlineChat {
+    /// Bound every request made through `inner` by `timeouts`.
+    pub fn new(inner: Arc<dyn CanChat>, timeouts: Timeouts) -> Self {
+        Self {
+            inner,
+            timeouts,
+            cancel: CancelHandle::new(),
+        }
+    }
+
+    /// Use `cancel` instead of a private handle, so that one handle can
+    /// abort several backends.
+    pub fn with_cancel(mut self, cancel: CancelHandle) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// Handle that aborts generations running through this wrapper.
+    pub fn cancel_handle(&self) -> CancelHandle {
+        self.cancel.clone()
+    }
+}
+
+#[async_trait(?Send)]
+impl CanChat for DeadlineChat {
+    async fn chat_stream(
+        &self,
+        profile: &LlmProfile,
+        system: &str,
+        user: &str,
+    ) -> anyhow::Result<Box<dyn Stream<Item = String> + Unpin>> {
+        self.chat_stream_with_options(profile, system, user, &GenerationOptions::default())
+            .await
+    }
+
+    async fn chat_stream_with_options(
+        &self,
+        profile: &LlmProfile,
+        system: &str,
+        user: &str,
+        options: &GenerationOptions,
+    ) -> anyhow::Result<Box<dyn Stream<Item = String> + Unpin>> {
+        let messages = [ChatMessage::system(system), ChatMessage::user(user)];
+        self.chat_messages_stream(profile, &messages, options).await
+    }
+
+    async fn chat_messages_stream(
+        &self,
+        profile: &LlmProfile,
+        messages: &[ChatMessage],
+        options: &GenerationOptions,
+    ) -> anyhow::Result<Box<dyn Stream<Item = String> + Unpin>> {
+        let stream = self.chat_events(profile, messages, options).await?;
+        Ok(ok_tokens(stream))
+    }
+
+    async fn chat_events(
+        &self,
+        profile: &LlmProfile,
+        messages: &[ChatMessage],
+        options: &GenerationOptions,
+    ) -> anyhow::Result<EventStream> {
+        let started = Instant::now();
+        let cancel = self.cancel.token();
+        let connect = self.inner.chat_events(profile, messages, options);
+        let connect = async {
+            match self.timeouts.connect {
+                Some(limit) => timeout(limit, connect)
+                    .await
+                    .map_err(|_| anyhow::anyhow!("llm connect timeout after {:?}", limit))?,
+                None => connect.await,
+            }
+        };
+        let stream = tokio::select! {
+            _ = cancel.cancelled() => anyhow::bail!("llm generation cancelled"),
+            stream = connect => stream?,
+        };
+        Ok(with_deadlines(stream, self.timeouts, started, cancel))
+    }
+
+    async fn health_check(&self) -> anyhow::Result<()> {
+        let check = self.inner.health_check();
+        match self.timeouts.connect {
+            Some(limit) => timeout(limit, check)
+                .await
+                .map_err(|_| anyhow::anyhow!("health check timeout after {:?}", limit))?,
+            None => check.await,
+        }
+    }
+
+    async fn chat_with_tools(
+        &self,
+        profile: &LlmProfile,
+        messages: &[ChatMessage],
+        tools: &[ToolDefinition],
+        options: &GenerationOptions,
+    ) -> anyhow::Result<ToolResponse> {
+        let cancel = self.cancel.token();
+        let call = self
+            .inner
+            .chat_with_tools(profile, messages, tools, options);
+        let call = async {
+            match self.timeouts.total {
+                Some(limit) => timeout(limit, call)
+                    .await
+                    .map_err(|_| anyhow::anyhow!("llm total timeout after {:?}", limit))?,
+                None => call.await,
+            }
+        };
+        tokio::select! {
+            _ = cancel.cancelled() => anyhow::bail!("llm generation cancelled"),
+            resp = call => resp,
+        }
+    }
+}
@@ -0,0 +1,126 @@
+//! Offline lexical embeddings.
+//!
+//! [`LexicalEmbed`] turns text into a fixed-size vector by hashing words,
+//! word pairs and character trigrams into buckets, weighting each by a
+//! sublinear term frequency and normalizing the result. Texts that share
+//! vocabulary get a high cosine similarity, which is enough for recall on
+//! machines without an embedding server. The hash is stable, so vectors
+//! stored by one run stay comparable with those of the next.
+
+use super::{CanEmbed, LlmProfile};
+use async_trait::async_trait;
+use std::collections::HashMap;
+
+/// Vector size used when none is configured.
+pub const DEFAULT_DIMENSIONS: usize = 384;
+
+/// Words too common to say anything about a text.
+const STOPWORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "but", "by", "for", "from", "has", "have", "i",
+    "in", "is", "it", "its", "of", "on", "or", "so", "that", "the", "their", "there", "this", "to",
+    "was", "were", "with",
+];
+
+const WORD_WEIGHT: f32 = 1.0;
+const PAIR_WEIGHT: f32 = 0.5;
+const TRIGRAM_WEIGHT: f32 = 0.25;
+
+/// Hashed n-gram embedder needing no model or network.
+///
+/// ```
+/// use psyche::llm::lexical::LexicalEmbed;
+/// let embed = LexicalEmbed::new(64);
+/// let cat = embed.vector("the cat sat on the mat");
+/// let cats = embed.vector("a cat sitting on a mat");
+/// let rocket = embed.vector("rocket engines ignite");
+/// let dot = |a: &[f32], b: &[f32]| a.iter().zip(b).map(|(x, y)| x * y).sum::<f32>();
+/// assert_eq!(cat.len(), 64);
+/// assert!(dot(&cat, &cats) > dot(&cat, &rocket));
+/// ```
+#[derive(Debug, Clone)]
+pub struct LexicalEmbed {
+    dimensions: usize,
+}
+
+impl Default for LexicalEmbed {
+    fn default() -> Self {
+        Self::new(DEFAULT_DIMENSIONS)
+    }
+}
+
+/// 64-bit FNV-1a, chosen because it is stable across builds and platforms.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ u64::from(*b)).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+impl LexicalEmbed {
+    /// Embedder producing vectors of `dimensions` entries.
+    pub fn new(dimensions: usize) -> Self {
+        Self {
+            dimensions: dimensions.max(1),
+        }
+    }
+
+    /// Length of the vectors produced.
+    pub fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    /// Embed `text` synchronously. The result has unit length unless
+    /// `text` contains no words.
+    pub fn vector(&self, text: &str) -> Vec<f32> {
+        let words = words(text);
+        let content: Vec<&str> = words
+            .iter()
+            .map(String::as_str)
+            .filter(|w| !STOPWORDS.contains(w))
+            .collect();
+
+        let mut counts: HashMap<String, (f32, u32)> = HashMap::new();
+        let mut add = |feature: String, weight: f32| {
+            counts.entry(feature).or_insert((weight, 0)).1 += 1;
+        };
+        for word in &content {
+            add(format!("w:{word}"), WORD_WEIGHT);
+            let padded: Vec<char> = format!("<{word}>").chars().collect();
+            for gram in padded.windows(3) {
+                add(
+                    format!("c:{}", gram.iter().collect::<String>()),
+                    TRIGRAM_WEIGHT,
+                );
+            }
+        }
+        for pair in content.windows(2) {
+            add(format!("p:{} {}", pair[0], pair[1]), PAIR_WEIGHT);
+        }
+
+        let mut vector = vec![0.0f32; self.dimensions];
+        for (feature, (weight, count)) in counts {
+            let hash = fnv1a(feature.as_bytes());
+            let bucket = (hash % self.dimensions as u64) as usize;
+            let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+            vector[bucket] += sign * weight * (1.0 + (count as f32).ln());
+        }
+        let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+        if norm > 0.0 {
+            vector.iter_mut().for_each(|x| *x /= norm);
+        }
+        vector
+    }
+}
+
+#[async_trait(?Send)]
+impl CanEmbed for LexicalEmbed {
+    async fn embed(&self, _profile: &LlmProfile, text: &str) -> anyhow::Result<Vec<f32>> {
+        Ok(self.vector(text))
+    }
+}
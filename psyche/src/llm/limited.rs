@@ -1,33 +1,60 @@
-use super::{CanChat, LlmProfile};
+use super::scheduler::{PriorityScheduler, SchedulerPermit, DEFAULT_PRIORITY};
+use super::{
+    CanChat, ChatEvent, ChatMessage, GenerationOptions, LlmProfile, ToolDefinition, ToolResponse,
+};
 use async_trait::async_trait;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{Context, Poll};
-use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 use tokio_stream::Stream;
 
-/// Wrapper that limits concurrent access to an inner [`CanChat`].
+/// Wrapper that limits concurrent access to an inner [`CanChat`], admitting
+/// waiting requests by [`GenerationOptions::priority`].
 pub struct LimitedChat {
     inner: Arc<dyn CanChat>,
-    semaphore: Arc<Semaphore>,
+    scheduler: PriorityScheduler,
 }
 
 impl LimitedChat {
-    pub fn new(inner: Arc<dyn CanChat>, semaphore: Arc<Semaphore>) -> Self {
-        Self { inner, semaphore }
+    pub fn new(inner: Arc<dyn CanChat>, scheduler: PriorityScheduler) -> Self {
+        Self { inner, scheduler }
+    }
+
+    async fn admit(&self, options: &GenerationOptions) -> SchedulerPermit {
+        self.scheduler
+            .acquire(options.priority.unwrap_or(DEFAULT_PRIORITY))
+            .await
     }
 }
 
+/// Holds a permit until the wrapped stream ends or is dropped.
+///
+/// Releasing at the end rather than on drop means a stream cut short by a
+/// [`DeadlineChat`](super::deadline::DeadlineChat) frees its slot even if
+/// the caller keeps the stream around.
 struct PermitStream<S> {
     stream: S,
-    _permit: OwnedSemaphorePermit,
+    permit: Option<SchedulerPermit>,
+}
+
+impl<S> PermitStream<S> {
+    fn new(stream: S, permit: SchedulerPermit) -> Self {
+        Self {
+            stream,
+            permit: Some(permit),
+        }
+    }
 }
 
 impl<S: Stream + Unpin> Stream for PermitStream<S> {
     type Item = S::Item;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        Pin::new(&mut self.stream).poll_next(cx)
+        let poll = Pin::new(&mut self.stream).poll_next(cx);
+        if let Poll::Ready(None) = poll {
+            self.permit = None;
+        }
+        poll
     }
 }
 
@@ -39,11 +66,65 @@ impl CanChat for LimitedChat {
         system: &str,
         user: &str,
     ) -> anyhow::Result<Box<dyn Stream<Item = String> + Unpin>> {
-        let permit = self.semaphore.clone().acquire_owned().await?;
+        let permit = self.scheduler.acquire(DEFAULT_PRIORITY).await;
         let stream = self.inner.chat_stream(profile, system, user).await?;
-        Ok(Box::new(PermitStream {
-            stream,
-            _permit: permit,
-        }))
+        Ok(Box::new(PermitStream::new(stream, permit)))
+    }
+
+    async fn chat_stream_with_options(
+        &self,
+        profile: &LlmProfile,
+        system: &str,
+        user: &str,
+        options: &GenerationOptions,
+    ) -> anyhow::Result<Box<dyn Stream<Item = String> + Unpin>> {
+        let permit = self.admit(options).await;
+        let stream = self
+            .inner
+            .chat_stream_with_options(profile, system, user, options)
+            .await?;
+        Ok(Box::new(PermitStream::new(stream, permit)))
+    }
+
+    async fn chat_messages_stream(
+        &self,
+        profile: &LlmProfile,
+        messages: &[ChatMessage],
+        options: &GenerationOptions,
+    ) -> anyhow::Result<Box<dyn Stream<Item = String> + Unpin>> {
+        let permit = self.admit(options).await;
+        let stream = self
+            .inner
+            .chat_messages_stream(profile, messages, options)
+            .await?;
+        Ok(Box::new(PermitStream::new(stream, permit)))
+    }
+
+    async fn chat_events(
+        &self,
+        profile: &LlmProfile,
+        messages: &[ChatMessage],
+        options: &GenerationOptions,
+    ) -> anyhow::Result<Box<dyn Stream<Item = anyhow::Result<ChatEvent>> + Unpin>> {
+        let permit = self.admit(options).await;
+        let stream = self.inner.chat_events(profile, messages, options).await?;
+        Ok(Box::new(PermitStream::new(stream, permit)))
+    }
+
+    async fn health_check(&self) -> anyhow::Result<()> {
+        self.inner.health_check().await
+    }
+
+    async fn chat_with_tools(
+        &self,
+        profile: &LlmProfile,
+        messages: &[ChatMessage],
+        tools: &[ToolDefinition],
+        options: &GenerationOptions,
+    ) -> anyhow::Result<ToolResponse> {
+        let _permit = self.admit(options).await;
+        self.inner
+            .chat_with_tools(profile, messages, tools, options)
+            .await
     }
 }
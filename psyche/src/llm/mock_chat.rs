@@ -1,4 +1,4 @@
-use super::{CanChat, LlmProfile};
+use super::{CanChat, ChatMessage, GenerationOptions, LlmProfile};
 use async_trait::async_trait;
 use tokio_stream::{iter, Stream};
 use tracing::{debug, trace};
@@ -20,6 +20,18 @@ impl CanChat for MockChat {
         debug!(target: "llm", response = %resp, "MockChat full response");
         Ok(Box::new(iter([resp])))
     }
+
+    async fn chat_messages_stream(
+        &self,
+        _profile: &LlmProfile,
+        messages: &[ChatMessage],
+        _options: &GenerationOptions,
+    ) -> anyhow::Result<Box<dyn Stream<Item = String> + Unpin>> {
+        trace!(target: "llm", ?messages, "MockChat messages");
+        let resp = "mock response".to_string();
+        debug!(target: "llm", response = %resp, "MockChat full response");
+        Ok(Box::new(iter([resp])))
+    }
 }
 
 /// Mock chat that returns its configured name as the response.
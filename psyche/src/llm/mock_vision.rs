@@ -0,0 +1,33 @@
+use super::{CanSee, LlmProfile};
+use async_trait::async_trait;
+use tokio_stream::{iter, Stream};
+use tracing::{debug, trace};
+
+/// Mock vision model returning a fixed caption for any image.
+#[derive(Clone)]
+pub struct MockVision {
+    /// The text returned for every image.
+    pub caption: String,
+}
+
+impl Default for MockVision {
+    fn default() -> Self {
+        Self {
+            caption: "mock caption".into(),
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl CanSee for MockVision {
+    async fn see_stream(
+        &self,
+        _profile: &LlmProfile,
+        image: &[u8],
+        prompt: &str,
+    ) -> anyhow::Result<Box<dyn Stream<Item = String> + Unpin>> {
+        trace!(target: "llm", bytes = image.len(), prompt, "MockVision prompt");
+        debug!(target: "llm", response = %self.caption, "MockVision full response");
+        Ok(Box::new(iter([self.caption.clone()])))
+    }
+}
@@ -1,13 +1,24 @@
+pub mod cassette;
 pub mod chat;
+pub mod config;
+pub mod deadline;
 pub mod embed;
+pub mod lexical;
 pub mod limited;
 pub mod mock_chat;
 pub mod mock_embed;
+pub mod mock_vision;
 pub mod ollama;
+pub mod openai;
+pub mod pool;
 pub mod prompt;
+pub mod scheduler;
+pub mod scripted;
+pub mod usage;
 
 use async_trait::async_trait;
-use tokio_stream::Stream;
+use serde::{Deserialize, Serialize};
+use tokio_stream::{Stream, StreamExt};
 
 /// Supported capabilities for a language model backend.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -33,6 +44,228 @@ pub struct LlmProfile {
     pub capabilities: Vec<LlmCapability>,
 }
 
+/// Per-call sampling and output controls.
+///
+/// Every field is optional so that unset values fall back to the model's
+/// defaults.
+///
+/// ```
+/// use psyche::llm::GenerationOptions;
+/// let opts: GenerationOptions = serde_json::from_str(r#"{"temperature":0.0,"format":"json"}"#).unwrap();
+/// assert_eq!(opts.temperature, Some(0.0));
+/// assert_eq!(opts.format, Some(serde_json::json!("json")));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GenerationOptions {
+    /// Sampling temperature.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    /// Nucleus sampling probability mass.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    /// Maximum number of tokens to generate.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    /// Sequences that end generation when produced.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stop: Vec<String>,
+    /// Seed for reproducible sampling.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<i64>,
+    /// Output format constraint, following Ollama's `format` field: either
+    /// the string `"json"` or a JSON schema object.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<serde_json::Value>,
+    /// Scheduling priority of the request; lower values are served first
+    /// when an instance is busy. Never sent to the model.
+    #[serde(skip)]
+    pub priority: Option<usize>,
+    /// Name of the wit making the request, for backends that answer or
+    /// account per wit. Never sent to the model.
+    #[serde(skip)]
+    pub wit: Option<String>,
+}
+
+/// Speaker of a [`ChatMessage`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChatRole {
+    /// Instructions framing the conversation.
+    System,
+    /// Input from the user or the world.
+    User,
+    /// A previous model reply.
+    Assistant,
+    /// Output returned by a tool call.
+    Tool,
+}
+
+impl ChatRole {
+    /// Returns the role name used by chat APIs.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChatRole::System => "system",
+            ChatRole::User => "user",
+            ChatRole::Assistant => "assistant",
+            ChatRole::Tool => "tool",
+        }
+    }
+}
+
+/// A single role-tagged turn in a conversation.
+///
+/// ```
+/// use psyche::llm::{ChatMessage, ChatRole};
+/// let msg: ChatMessage = serde_json::from_str(r#"{"role":"assistant","content":"hi"}"#).unwrap();
+/// assert_eq!(msg, ChatMessage::assistant("hi"));
+/// assert_eq!(msg.role, ChatRole::Assistant);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChatMessage {
+    /// Who produced this turn.
+    pub role: ChatRole,
+    /// Text of the turn.
+    pub content: String,
+}
+
+impl ChatMessage {
+    /// Creates a message with the given role.
+    pub fn new(role: ChatRole, content: impl Into<String>) -> Self {
+        Self {
+            role,
+            content: content.into(),
+        }
+    }
+
+    /// Creates a system message.
+    pub fn system(content: impl Into<String>) -> Self {
+        Self::new(ChatRole::System, content)
+    }
+
+    /// Creates a user message.
+    pub fn user(content: impl Into<String>) -> Self {
+        Self::new(ChatRole::User, content)
+    }
+
+    /// Creates an assistant message.
+    pub fn assistant(content: impl Into<String>) -> Self {
+        Self::new(ChatRole::Assistant, content)
+    }
+
+    /// Creates a tool result message.
+    pub fn tool(content: impl Into<String>) -> Self {
+        Self::new(ChatRole::Tool, content)
+    }
+}
+
+/// Collapse a message history into a single system and user prompt.
+///
+/// System messages are joined into the system prompt. A lone user message is
+/// passed through unchanged; longer histories are rendered as a transcript
+/// with one `role: content` line per turn.
+///
+/// ```
+/// use psyche::llm::{flatten_messages, ChatMessage};
+/// let (system, user) = flatten_messages(&[
+///     ChatMessage::system("be brief"),
+///     ChatMessage::user("hi"),
+///     ChatMessage::assistant("hello"),
+///     ChatMessage::user("bye"),
+/// ]);
+/// assert_eq!(system, "be brief");
+/// assert_eq!(user, "user: hi\nassistant: hello\nuser: bye");
+/// ```
+pub fn flatten_messages(messages: &[ChatMessage]) -> (String, String) {
+    let system = messages
+        .iter()
+        .filter(|m| m.role == ChatRole::System)
+        .map(|m| m.content.as_str())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    let turns: Vec<_> = messages
+        .iter()
+        .filter(|m| m.role != ChatRole::System)
+        .collect();
+    let user = match turns.as_slice() {
+        [only] if only.role == ChatRole::User => only.content.clone(),
+        _ => turns
+            .iter()
+            .map(|m| format!("{}: {}", m.role.as_str(), m.content))
+            .collect::<Vec<_>>()
+            .join("\n"),
+    };
+    (system, user)
+}
+
+/// A function the model may call, described by a JSON schema.
+///
+/// ```
+/// use psyche::llm::ToolDefinition;
+/// let tool = ToolDefinition {
+///     name: "speak".into(),
+///     description: "Say something aloud".into(),
+///     parameters: serde_json::json!({
+///         "type": "object",
+///         "properties": {"text": {"type": "string"}},
+///         "required": ["text"]
+///     }),
+/// };
+/// assert_eq!(tool.parameters["required"][0], "text");
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolDefinition {
+    /// Name the model uses to invoke the tool.
+    pub name: String,
+    /// Human readable explanation of what the tool does.
+    #[serde(default)]
+    pub description: String,
+    /// JSON schema describing the tool arguments.
+    pub parameters: serde_json::Value,
+}
+
+/// A tool invocation requested by the model.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolCall {
+    /// Name of the tool to run.
+    pub name: String,
+    /// Arguments as a JSON object.
+    #[serde(default)]
+    pub arguments: serde_json::Value,
+}
+
+/// Result of a tool-enabled chat call.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ToolResponse {
+    /// Any plain text the model produced alongside its calls.
+    pub content: String,
+    /// Requested tool calls in the order given by the model.
+    pub tool_calls: Vec<ToolCall>,
+}
+
+/// Token counts reported by a backend for one completion.
+///
+/// Backends that do not report a figure leave it as `None`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TokenUsage {
+    /// Model that served the request.
+    pub model: Option<String>,
+    /// Name of the host or pool instance that served the request.
+    pub host: Option<String>,
+    /// Tokens consumed by the prompt.
+    pub prompt_tokens: Option<u32>,
+    /// Tokens generated in the reply.
+    pub completion_tokens: Option<u32>,
+}
+
+/// One item of a streamed chat completion.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChatEvent {
+    /// A piece of the reply text.
+    Token(String),
+    /// Usage figures, sent once after the last token when known.
+    Usage(TokenUsage),
+}
+
 /// Interface for models capable of chatting.
 #[async_trait(?Send)]
 pub trait CanChat {
@@ -43,6 +276,86 @@ pub trait CanChat {
         system: &str,
         user: &str,
     ) -> anyhow::Result<Box<dyn Stream<Item = String> + Unpin>>;
+
+    /// Streams a chat completion honoring the supplied [`GenerationOptions`].
+    ///
+    /// Backends without sampling controls ignore the options.
+    async fn chat_stream_with_options(
+        &self,
+        profile: &LlmProfile,
+        system: &str,
+        user: &str,
+        _options: &GenerationOptions,
+    ) -> anyhow::Result<Box<dyn Stream<Item = String> + Unpin>> {
+        self.chat_stream(profile, system, user).await
+    }
+
+    /// Streams a completion for a full role-tagged message history.
+    ///
+    /// Backends without native multi-turn support receive the history
+    /// collapsed by [`flatten_messages`].
+    async fn chat_messages_stream(
+        &self,
+        profile: &LlmProfile,
+        messages: &[ChatMessage],
+        options: &GenerationOptions,
+    ) -> anyhow::Result<Box<dyn Stream<Item = String> + Unpin>> {
+        let (system, user) = flatten_messages(messages);
+        self.chat_stream_with_options(profile, &system, &user, options)
+            .await
+    }
+
+    /// Like [`CanChat::chat_messages_stream`], but surfaces transport
+    /// failures as `Err` items instead of silently ending the stream and
+    /// reports [`TokenUsage`] after the last token.
+    ///
+    /// The default wraps every token from `chat_messages_stream` in `Ok`
+    /// and reports no usage.
+    async fn chat_events(
+        &self,
+        profile: &LlmProfile,
+        messages: &[ChatMessage],
+        options: &GenerationOptions,
+    ) -> anyhow::Result<Box<dyn Stream<Item = anyhow::Result<ChatEvent>> + Unpin>> {
+        let stream = self
+            .chat_messages_stream(profile, messages, options)
+            .await?;
+        Ok(Box::new(stream.map(|t| Ok(ChatEvent::Token(t)))))
+    }
+
+    /// Checks that the backend is reachable.
+    ///
+    /// Backends without a remote endpoint are always healthy.
+    async fn health_check(&self) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Offers `tools` to the model and returns the calls it chose to make.
+    ///
+    /// Only backends advertising [`LlmCapability::ToolUse`] implement this;
+    /// the default returns an error so callers can fall back to parsing
+    /// free text.
+    async fn chat_with_tools(
+        &self,
+        _profile: &LlmProfile,
+        _messages: &[ChatMessage],
+        _tools: &[ToolDefinition],
+        _options: &GenerationOptions,
+    ) -> anyhow::Result<ToolResponse> {
+        anyhow::bail!("tool use not supported")
+    }
+}
+
+/// Keep only the tokens of a chat event stream.
+///
+/// Errors are already logged by the backends that produce them.
+pub(crate) fn ok_tokens(
+    stream: Box<dyn Stream<Item = anyhow::Result<ChatEvent>> + Unpin>,
+) -> Box<dyn Stream<Item = String> + Unpin> {
+    Box::new(stream.filter_map(|event| match event {
+        Ok(ChatEvent::Token(t)) => Some(t),
+        _ => None,
+    }))
 }
 
 /// Interface for models capable of producing embeddings.
@@ -52,6 +365,18 @@ pub trait CanEmbed {
     async fn embed(&self, profile: &LlmProfile, text: &str) -> anyhow::Result<Vec<f32>>;
 }
 
+/// Interface for models that can describe images.
+#[async_trait(?Send)]
+pub trait CanSee {
+    /// Streams the model's response to `prompt` about the encoded `image`.
+    async fn see_stream(
+        &self,
+        profile: &LlmProfile,
+        image: &[u8],
+        prompt: &str,
+    ) -> anyhow::Result<Box<dyn Stream<Item = String> + Unpin>>;
+}
+
 /// Registry holding implementations for each capability.
 pub struct LlmRegistry {
     /// Chat implementation.
@@ -61,7 +386,6 @@ pub struct LlmRegistry {
 }
 
 use std::sync::Arc;
-use tokio::sync::Semaphore;
 
 /// Runtime handle to a specific LLM instance with concurrency limits.
 #[derive(Clone)]
@@ -72,6 +396,6 @@ pub struct LlmInstance {
     pub chat: Arc<dyn CanChat>,
     /// Profile describing the model.
     pub profile: Arc<LlmProfile>,
-    /// Semaphore limiting concurrent usage.
-    pub semaphore: Arc<Semaphore>,
+    /// Admits requests by priority, limiting concurrent usage.
+    pub scheduler: scheduler::PriorityScheduler,
 }
@@ -1,10 +1,14 @@
-use super::{CanChat, LlmProfile};
+use super::{
+    ok_tokens, CanChat, CanEmbed, CanSee, ChatEvent, ChatMessage, GenerationOptions, LlmProfile,
+    TokenUsage, ToolCall, ToolDefinition, ToolResponse,
+};
 use async_stream::stream;
 use async_trait::async_trait;
+use base64::{engine::general_purpose, Engine};
 use futures_util::StreamExt;
 use serde::Deserialize;
 use tokio_stream::Stream;
-use tracing::{debug, trace};
+use tracing::{debug, info, trace, warn};
 
 /// Chat client that calls an Ollama instance via HTTP.
 #[derive(Clone, Debug)]
@@ -18,8 +22,27 @@ pub struct OllamaChat {
 #[derive(Deserialize)]
 struct Chunk {
     message: Option<Message>,
-    #[allow(dead_code)]
     done: Option<bool>,
+    #[serde(default)]
+    error: Option<String>,
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    prompt_eval_count: Option<u32>,
+    #[serde(default)]
+    eval_count: Option<u32>,
+}
+
+impl Chunk {
+    /// Usage figures carried by the final `done` chunk.
+    fn usage(&self) -> TokenUsage {
+        TokenUsage {
+            model: self.model.clone(),
+            host: None,
+            prompt_tokens: self.prompt_eval_count,
+            completion_tokens: self.eval_count,
+        }
+    }
 }
 
 #[derive(Deserialize)]
@@ -27,50 +50,493 @@ struct Message {
     content: String,
 }
 
+#[derive(Deserialize)]
+struct ToolChatResponse {
+    message: ToolMessage,
+}
+
+#[derive(Deserialize)]
+struct ToolMessage {
+    #[serde(default)]
+    content: String,
+    #[serde(default)]
+    tool_calls: Vec<ToolCallEntry>,
+}
+
+#[derive(Deserialize)]
+struct ToolCallEntry {
+    function: ToolCall,
+}
+
+/// Apply [`GenerationOptions`] to an Ollama request body.
+fn apply_options(body: &mut serde_json::Value, options: &GenerationOptions) {
+    let mut opts = serde_json::Map::new();
+    if let Some(t) = options.temperature {
+        opts.insert("temperature".into(), t.into());
+    }
+    if let Some(p) = options.top_p {
+        opts.insert("top_p".into(), p.into());
+    }
+    if let Some(n) = options.max_tokens {
+        opts.insert("num_predict".into(), n.into());
+    }
+    if !options.stop.is_empty() {
+        opts.insert("stop".into(), options.stop.clone().into());
+    }
+    if let Some(seed) = options.seed {
+        opts.insert("seed".into(), seed.into());
+    }
+    if !opts.is_empty() {
+        body["options"] = serde_json::Value::Object(opts);
+    }
+    if let Some(format) = &options.format {
+        body["format"] = format.clone();
+    }
+}
+
+/// Turn a streaming `/api/chat` response into a stream of [`ChatEvent`]s.
+///
+/// The token counts of the final `done` chunk are reported as a trailing
+/// [`ChatEvent::Usage`]. A transport error, an `error` line, or the body
+/// ending before the final `done` chunk is reported as a trailing `Err` item.
+fn try_event_stream(
+    resp: reqwest::Response,
+) -> Box<dyn Stream<Item = anyhow::Result<ChatEvent>> + Unpin> {
+    let mut stream = resp.bytes_stream();
+    let out = stream! {
+        let mut full = String::new();
+        let mut pending = String::new();
+        let mut done = false;
+        'outer: while let Some(chunk) = stream.next().await {
+            let bytes = match chunk {
+                Ok(b) => b,
+                Err(e) => {
+                    debug!(target = "llm", error = %e, "stream error");
+                    yield Err(e.into());
+                    break;
+                }
+            };
+            pending.push_str(&String::from_utf8_lossy(&bytes));
+            let complete = match pending.rfind('\n') {
+                Some(pos) => pending.drain(..=pos).collect::<String>(),
+                None => continue,
+            };
+            for line in complete.lines() {
+                if line.trim().is_empty() { continue; }
+                if let Ok(c) = serde_json::from_str::<Chunk>(line) {
+                    if let Some(err) = c.error {
+                        debug!(target = "llm", error = %err, "Ollama error");
+                        yield Err(anyhow::anyhow!("ollama: {}", err));
+                        break 'outer;
+                    }
+                    let usage = c.usage();
+                    if let Some(msg) = c.message {
+                        trace!(target = "llm", token = %msg.content, "stream token");
+                        full.push_str(&msg.content);
+                        yield Ok(ChatEvent::Token(msg.content));
+                    }
+                    if c.done == Some(true) {
+                        done = true;
+                        yield Ok(ChatEvent::Usage(usage));
+                        break 'outer;
+                    }
+                }
+            }
+        }
+        if !done && !pending.trim().is_empty() {
+            if let Ok(c) = serde_json::from_str::<Chunk>(pending.trim()) {
+                let usage = c.usage();
+                if let Some(msg) = c.message {
+                    full.push_str(&msg.content);
+                    yield Ok(ChatEvent::Token(msg.content));
+                }
+                done = c.done == Some(true);
+                if done {
+                    yield Ok(ChatEvent::Usage(usage));
+                }
+            }
+        }
+        debug!(target = "llm", response = %full, "Ollama full response");
+        if !done {
+            yield Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                "ollama stream ended before completion",
+            ).into());
+        }
+    };
+    Box::new(Box::pin(out))
+}
+
 #[async_trait(?Send)]
 impl CanChat for OllamaChat {
     async fn chat_stream(
         &self,
-        _profile: &LlmProfile,
+        profile: &LlmProfile,
+        system: &str,
+        user: &str,
+    ) -> anyhow::Result<Box<dyn Stream<Item = String> + Unpin>> {
+        self.chat_stream_with_options(profile, system, user, &GenerationOptions::default())
+            .await
+    }
+
+    async fn chat_stream_with_options(
+        &self,
+        profile: &LlmProfile,
         system: &str,
         user: &str,
+        options: &GenerationOptions,
+    ) -> anyhow::Result<Box<dyn Stream<Item = String> + Unpin>> {
+        let messages = [ChatMessage::system(system), ChatMessage::user(user)];
+        self.chat_messages_stream(profile, &messages, options).await
+    }
+
+    async fn chat_messages_stream(
+        &self,
+        profile: &LlmProfile,
+        messages: &[ChatMessage],
+        options: &GenerationOptions,
     ) -> anyhow::Result<Box<dyn Stream<Item = String> + Unpin>> {
+        let stream = self.chat_events(profile, messages, options).await?;
+        Ok(ok_tokens(stream))
+    }
+
+    async fn chat_events(
+        &self,
+        _profile: &LlmProfile,
+        messages: &[ChatMessage],
+        options: &GenerationOptions,
+    ) -> anyhow::Result<Box<dyn Stream<Item = anyhow::Result<ChatEvent>> + Unpin>> {
         let url = format!("{}/api/chat", self.base_url.trim_end_matches('/'));
-        let body = serde_json::json!({
+        let mut body = serde_json::json!({
             "model": self.model,
-            "messages": [
-                {"role": "system", "content": system},
-                {"role": "user", "content": user}
-            ],
+            "messages": messages,
             "stream": true
         });
+        apply_options(&mut body, options);
         trace!(target = "llm", %url, body = %body, "Ollama prompt");
-        let resp = reqwest::Client::new().post(url).json(&body).send().await?;
+        let resp = reqwest::Client::new()
+            .post(url)
+            .json(&body)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(try_event_stream(resp))
+    }
+
+    async fn health_check(&self) -> anyhow::Result<()> {
+        let url = format!("{}/api/version", self.base_url.trim_end_matches('/'));
+        reqwest::Client::new()
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    async fn chat_with_tools(
+        &self,
+        _profile: &LlmProfile,
+        messages: &[ChatMessage],
+        tools: &[ToolDefinition],
+        options: &GenerationOptions,
+    ) -> anyhow::Result<ToolResponse> {
+        let url = format!("{}/api/chat", self.base_url.trim_end_matches('/'));
+        let tools: Vec<_> = tools
+            .iter()
+            .map(|t| serde_json::json!({"type": "function", "function": t}))
+            .collect();
+        let mut body = serde_json::json!({
+            "model": self.model,
+            "messages": messages,
+            "tools": tools,
+            "stream": false
+        });
+        apply_options(&mut body, options);
+        trace!(target = "llm", %url, body = %body, "Ollama tool prompt");
+        let resp: ToolChatResponse = reqwest::Client::new()
+            .post(url)
+            .json(&body)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let response = ToolResponse {
+            content: resp.message.content,
+            tool_calls: resp
+                .message
+                .tool_calls
+                .into_iter()
+                .map(|c| c.function)
+                .collect(),
+        };
+        debug!(target = "llm", ?response, "Ollama tool response");
+        Ok(response)
+    }
+}
+
+#[async_trait(?Send)]
+impl CanSee for OllamaChat {
+    async fn see_stream(
+        &self,
+        _profile: &LlmProfile,
+        image: &[u8],
+        prompt: &str,
+    ) -> anyhow::Result<Box<dyn Stream<Item = String> + Unpin>> {
+        let url = format!("{}/api/chat", self.base_url.trim_end_matches('/'));
+        let body = serde_json::json!({
+            "model": self.model,
+            "messages": [{
+                "role": "user",
+                "content": prompt,
+                "images": [general_purpose::STANDARD.encode(image)]
+            }],
+            "stream": true
+        });
+        trace!(target = "llm", %url, %prompt, bytes = image.len(), "Ollama vision prompt");
+        let resp = reqwest::Client::new()
+            .post(url)
+            .json(&body)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(ok_tokens(try_event_stream(resp)))
+    }
+}
+
+/// Embedding client that calls Ollama's `/api/embed` endpoint.
+#[derive(Clone, Debug)]
+pub struct OllamaEmbed {
+    /// Base URL for the Ollama server, e.g. `http://localhost:11434`.
+    pub base_url: String,
+    /// Embedding model name such as `nomic-embed-text`.
+    pub model: String,
+}
+
+#[derive(Deserialize)]
+struct EmbedResponse {
+    embeddings: Vec<Vec<f32>>,
+}
+
+#[async_trait(?Send)]
+impl CanEmbed for OllamaEmbed {
+    async fn embed(&self, _profile: &LlmProfile, text: &str) -> anyhow::Result<Vec<f32>> {
+        let url = format!("{}/api/embed", self.base_url.trim_end_matches('/'));
+        let body = serde_json::json!({
+            "model": self.model,
+            "input": text,
+        });
+        trace!(target = "llm", %url, model = %self.model, "Ollama embed");
+        let resp: EmbedResponse = reqwest::Client::new()
+            .post(url)
+            .json(&body)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let vector = resp
+            .embeddings
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("no embedding returned"))?;
+        debug!(target = "llm", dim = vector.len(), "Ollama embedding");
+        Ok(vector)
+    }
+}
+
+/// A model installed on an Ollama server.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct LocalModel {
+    /// Full name including the tag, e.g. `llama3:latest`.
+    pub name: String,
+    /// Size on disk in bytes.
+    #[serde(default)]
+    pub size: u64,
+    /// Content digest of the model.
+    #[serde(default)]
+    pub digest: String,
+}
+
+#[derive(Deserialize)]
+struct TagsResponse {
+    #[serde(default)]
+    models: Vec<LocalModel>,
+}
+
+/// One status line reported while pulling a model.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct PullProgress {
+    /// Human readable phase such as `pulling manifest` or `success`.
+    #[serde(default)]
+    pub status: String,
+    /// Total bytes of the layer being downloaded.
+    #[serde(default)]
+    pub total: Option<u64>,
+    /// Bytes of that layer downloaded so far.
+    #[serde(default)]
+    pub completed: Option<u64>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+/// Whether two model names refer to the same model, treating a missing tag
+/// as `latest`.
+///
+/// ```
+/// use psyche::llm::ollama::same_model;
+/// assert!(same_model("llama3", "llama3:latest"));
+/// assert!(!same_model("llama3:8b", "llama3:latest"));
+/// ```
+pub fn same_model(a: &str, b: &str) -> bool {
+    let tagged = |m: &str| {
+        if m.contains(':') {
+            m.to_string()
+        } else {
+            format!("{m}:latest")
+        }
+    };
+    tagged(a) == tagged(b)
+}
+
+/// Progress callback for [`OllamaModels::pull`] that logs every tenth of
+/// each layer.
+pub fn log_progress(model: &str) -> impl FnMut(&PullProgress) {
+    let model = model.to_string();
+    let mut last = (String::new(), None);
+    move |p: &PullProgress| {
+        let tenth = match (p.total, p.completed) {
+            (Some(t), Some(c)) if t > 0 => Some(c * 10 / t),
+            _ => None,
+        };
+        if (p.status.as_str(), tenth) != (last.0.as_str(), last.1) {
+            match tenth {
+                Some(t) => {
+                    info!(target: "llm", %model, status = %p.status, percent = t * 10, "pulling model")
+                }
+                None => info!(target: "llm", %model, status = %p.status, "pulling model"),
+            }
+            last = (p.status.clone(), tenth);
+        }
+    }
+}
+
+/// Administration of the models on an Ollama server: listing, pulling and
+/// preloading them.
+#[derive(Clone, Debug)]
+pub struct OllamaModels {
+    /// Base URL for the Ollama server, e.g. `http://localhost:11434`.
+    pub base_url: String,
+}
+
+impl OllamaModels {
+    /// Manage the models of the server at `base_url`.
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into(),
+        }
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url.trim_end_matches('/'), path)
+    }
+
+    /// Models installed on the server.
+    pub async fn list(&self) -> anyhow::Result<Vec<LocalModel>> {
+        let resp: TagsResponse = reqwest::Client::new()
+            .get(self.url("/api/tags"))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        debug!(target: "llm", count = resp.models.len(), "Ollama local models");
+        Ok(resp.models)
+    }
+
+    /// Whether `model` is installed.
+    pub async fn has(&self, model: &str) -> anyhow::Result<bool> {
+        Ok(self
+            .list()
+            .await?
+            .iter()
+            .any(|m| same_model(&m.name, model)))
+    }
+
+    /// Download `model`, reporting each status line to `on_progress`.
+    pub async fn pull(
+        &self,
+        model: &str,
+        mut on_progress: impl FnMut(&PullProgress),
+    ) -> anyhow::Result<()> {
+        warn!(target: "llm", %model, "pulling missing model");
+        let resp = reqwest::Client::new()
+            .post(self.url("/api/pull"))
+            .json(&serde_json::json!({"model": model, "stream": true}))
+            .send()
+            .await?
+            .error_for_status()?;
         let mut stream = resp.bytes_stream();
-        let out = stream! {
-            let mut full = String::new();
-            while let Some(chunk) = stream.next().await {
-                let bytes = match chunk {
-                    Ok(b) => b,
-                    Err(e) => {
-                        debug!(target = "llm", error = %e, "stream error");
-                        break;
-                    }
-                };
-                let text = String::from_utf8_lossy(&bytes);
-                for line in text.lines() {
-                    if line.trim().is_empty() { continue; }
-                    if let Ok(c) = serde_json::from_str::<Chunk>(line) {
-                        if let Some(msg) = c.message {
-                            trace!(target = "llm", token = %msg.content, "stream token");
-                            full.push_str(&msg.content);
-                            yield msg.content;
-                        }
-                    }
+        let mut pending = String::new();
+        let mut success = false;
+        while let Some(bytes) = stream.next().await {
+            pending.push_str(&String::from_utf8_lossy(&bytes?));
+            while let Some(pos) = pending.find('\n') {
+                let line: String = pending.drain(..=pos).collect();
+                if line.trim().is_empty() {
+                    continue;
                 }
+                let progress: PullProgress = serde_json::from_str(line.trim())?;
+                if let Some(err) = progress.error {
+                    anyhow::bail!("ollama pull {}: {}", model, err);
+                }
+                trace!(target: "llm", ?progress, "pull progress");
+                success |= progress.status == "success";
+                on_progress(&progress);
             }
-            debug!(target = "llm", response = %full, "Ollama full response");
-        };
-        Ok(Box::new(Box::pin(out)) as Box<dyn Stream<Item = String> + Unpin>)
+        }
+        if let Ok(progress) = serde_json::from_str::<PullProgress>(pending.trim()) {
+            if let Some(err) = progress.error {
+                anyhow::bail!("ollama pull {}: {}", model, err);
+            }
+            success |= progress.status == "success";
+            on_progress(&progress);
+        }
+        if !success {
+            anyhow::bail!("ollama pull {} ended without success", model);
+        }
+        info!(target: "llm", %model, "model pulled");
+        Ok(())
+    }
+
+    /// Pull `model` unless it is already installed.
+    pub async fn ensure(
+        &self,
+        model: &str,
+        on_progress: impl FnMut(&PullProgress),
+    ) -> anyhow::Result<()> {
+        if self.has(model).await? {
+            debug!(target: "llm", %model, "model present");
+            return Ok(());
+        }
+        self.pull(model, on_progress).await
+    }
+
+    /// Load a generative `model` into memory and keep it there for
+    /// `keep_alive` (an Ollama duration such as `"10m"` or `"-1"`), so that
+    /// the first real request does not pay the load time.
+    pub async fn warmup(&self, model: &str, keep_alive: Option<&str>) -> anyhow::Result<()> {
+        let mut body = serde_json::json!({"model": model});
+        if let Some(keep_alive) = keep_alive {
+            body["keep_alive"] = keep_alive.into();
+        }
+        reqwest::Client::new()
+            .post(self.url("/api/generate"))
+            .json(&body)
+            .send()
+            .await?
+            .error_for_status()?;
+        info!(target: "llm", %model, ?keep_alive, "model warmed up");
+        Ok(())
     }
 }
@@ -0,0 +1,291 @@
+use super::{
+    ok_tokens, CanChat, CanEmbed, ChatEvent, ChatMessage, GenerationOptions, LlmProfile, TokenUsage,
+};
+use async_stream::stream;
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use serde::Deserialize;
+use tokio_stream::Stream;
+use tracing::{debug, trace};
+
+/// Chat client for servers speaking the OpenAI `/v1/chat/completions` API.
+///
+/// This covers hosted OpenAI as well as compatible local servers such as
+/// llama.cpp, vLLM and LM Studio.
+#[derive(Clone, Debug)]
+pub struct OpenAiChat {
+    /// Base URL for the server without the `/v1` suffix, e.g.
+    /// `https://api.openai.com` or `http://localhost:8080`.
+    pub base_url: String,
+    /// Model name such as `gpt-4o` or whatever the local server exposes.
+    pub model: String,
+    /// Optional bearer token sent in the `Authorization` header.
+    pub api_key: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Chunk {
+    #[serde(default)]
+    choices: Vec<Choice>,
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    usage: Option<Usage>,
+}
+
+#[derive(Deserialize)]
+struct Usage {
+    #[serde(default)]
+    prompt_tokens: Option<u32>,
+    #[serde(default)]
+    completion_tokens: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct Choice {
+    #[serde(default)]
+    delta: Option<Delta>,
+}
+
+#[derive(Deserialize)]
+struct Delta {
+    #[serde(default)]
+    content: Option<String>,
+}
+
+/// Extract the content tokens and usage from a single server-sent event line.
+///
+/// Returns `None` once the `[DONE]` sentinel is seen.
+fn parse_sse_line(line: &str) -> Option<Vec<ChatEvent>> {
+    let data = match line.trim().strip_prefix("data:") {
+        Some(d) => d.trim(),
+        None => return Some(Vec::new()),
+    };
+    if data == "[DONE]" {
+        return None;
+    }
+    let Ok(chunk) = serde_json::from_str::<Chunk>(data) else {
+        return Some(Vec::new());
+    };
+    let mut events: Vec<ChatEvent> = chunk
+        .choices
+        .into_iter()
+        .filter_map(|ch| ch.delta.and_then(|d| d.content))
+        .filter(|t| !t.is_empty())
+        .map(ChatEvent::Token)
+        .collect();
+    if let Some(usage) = chunk.usage {
+        events.push(ChatEvent::Usage(TokenUsage {
+            model: chunk.model,
+            host: None,
+            prompt_tokens: usage.prompt_tokens,
+            completion_tokens: usage.completion_tokens,
+        }));
+    }
+    Some(events)
+}
+
+/// Apply [`GenerationOptions`] to an OpenAI request body.
+fn apply_options(body: &mut serde_json::Value, options: &GenerationOptions) {
+    if let Some(t) = options.temperature {
+        body["temperature"] = t.into();
+    }
+    if let Some(p) = options.top_p {
+        body["top_p"] = p.into();
+    }
+    if let Some(n) = options.max_tokens {
+        body["max_tokens"] = n.into();
+    }
+    if !options.stop.is_empty() {
+        body["stop"] = options.stop.clone().into();
+    }
+    if let Some(seed) = options.seed {
+        body["seed"] = seed.into();
+    }
+    match &options.format {
+        Some(serde_json::Value::String(f)) if f == "json" => {
+            body["response_format"] = serde_json::json!({"type": "json_object"});
+        }
+        Some(schema) => {
+            body["response_format"] = serde_json::json!({
+                "type": "json_schema",
+                "json_schema": {"name": "output", "schema": schema},
+            });
+        }
+        None => {}
+    }
+}
+
+#[async_trait(?Send)]
+impl CanChat for OpenAiChat {
+    async fn chat_stream(
+        &self,
+        profile: &LlmProfile,
+        system: &str,
+        user: &str,
+    ) -> anyhow::Result<Box<dyn Stream<Item = String> + Unpin>> {
+        self.chat_stream_with_options(profile, system, user, &GenerationOptions::default())
+            .await
+    }
+
+    async fn chat_stream_with_options(
+        &self,
+        profile: &LlmProfile,
+        system: &str,
+        user: &str,
+        options: &GenerationOptions,
+    ) -> anyhow::Result<Box<dyn Stream<Item = String> + Unpin>> {
+        let messages = [ChatMessage::system(system), ChatMessage::user(user)];
+        self.chat_messages_stream(profile, &messages, options).await
+    }
+
+    async fn chat_messages_stream(
+        &self,
+        profile: &LlmProfile,
+        messages: &[ChatMessage],
+        options: &GenerationOptions,
+    ) -> anyhow::Result<Box<dyn Stream<Item = String> + Unpin>> {
+        let stream = self.chat_events(profile, messages, options).await?;
+        Ok(ok_tokens(stream))
+    }
+
+    async fn chat_events(
+        &self,
+        _profile: &LlmProfile,
+        messages: &[ChatMessage],
+        options: &GenerationOptions,
+    ) -> anyhow::Result<Box<dyn Stream<Item = anyhow::Result<ChatEvent>> + Unpin>> {
+        let url = format!(
+            "{}/v1/chat/completions",
+            self.base_url.trim_end_matches('/')
+        );
+        let mut body = serde_json::json!({
+            "model": self.model,
+            "messages": messages,
+            "stream": true,
+            "stream_options": {"include_usage": true}
+        });
+        apply_options(&mut body, options);
+        trace!(target = "llm", %url, body = %body, "OpenAI prompt");
+        let mut req = reqwest::Client::new().post(url).json(&body);
+        if let Some(key) = &self.api_key {
+            req = req.bearer_auth(key);
+        }
+        let resp = req.send().await?.error_for_status()?;
+        let mut stream = resp.bytes_stream();
+        let out = stream! {
+            let mut full = String::new();
+            let mut pending = String::new();
+            let mut done = false;
+            'outer: while let Some(chunk) = stream.next().await {
+                let bytes = match chunk {
+                    Ok(b) => b,
+                    Err(e) => {
+                        debug!(target = "llm", error = %e, "stream error");
+                        yield Err(e.into());
+                        break;
+                    }
+                };
+                pending.push_str(&String::from_utf8_lossy(&bytes));
+                while let Some(pos) = pending.find('\n') {
+                    let line: String = pending.drain(..=pos).collect();
+                    match parse_sse_line(&line) {
+                        Some(events) => {
+                            for event in events {
+                                if let ChatEvent::Token(token) = &event {
+                                    trace!(target = "llm", token = %token, "stream token");
+                                    full.push_str(token);
+                                }
+                                yield Ok(event);
+                            }
+                        }
+                        None => {
+                            done = true;
+                            break 'outer;
+                        }
+                    }
+                }
+            }
+            if !done {
+                match parse_sse_line(&pending) {
+                    Some(events) => {
+                        for event in events {
+                            if let ChatEvent::Token(token) = &event {
+                                full.push_str(token);
+                            }
+                            yield Ok(event);
+                        }
+                    }
+                    None => done = true,
+                }
+            }
+            debug!(target = "llm", response = %full, "OpenAI full response");
+            if !done {
+                yield Err(std::io::Error::new(
+                    std::io::ErrorKind::UnexpectedEof,
+                    "openai stream ended before [DONE]",
+                ).into());
+            }
+        };
+        Ok(Box::new(Box::pin(out))
+            as Box<
+                dyn Stream<Item = anyhow::Result<ChatEvent>> + Unpin,
+            >)
+    }
+
+    async fn health_check(&self) -> anyhow::Result<()> {
+        let url = format!("{}/v1/models", self.base_url.trim_end_matches('/'));
+        let mut req = reqwest::Client::new().get(url);
+        if let Some(key) = &self.api_key {
+            req = req.bearer_auth(key);
+        }
+        req.send().await?.error_for_status()?;
+        Ok(())
+    }
+}
+
+/// Embedding client for the OpenAI `/v1/embeddings` endpoint.
+#[derive(Clone, Debug)]
+pub struct OpenAiEmbed {
+    /// Base URL for the server without the `/v1` suffix.
+    pub base_url: String,
+    /// Embedding model name such as `text-embedding-3-small`.
+    pub model: String,
+    /// Optional bearer token sent in the `Authorization` header.
+    pub api_key: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct EmbedResponse {
+    data: Vec<EmbedData>,
+}
+
+#[derive(Deserialize)]
+struct EmbedData {
+    embedding: Vec<f32>,
+}
+
+#[async_trait(?Send)]
+impl CanEmbed for OpenAiEmbed {
+    async fn embed(&self, _profile: &LlmProfile, text: &str) -> anyhow::Result<Vec<f32>> {
+        let url = format!("{}/v1/embeddings", self.base_url.trim_end_matches('/'));
+        let body = serde_json::json!({
+            "model": self.model,
+            "input": text,
+        });
+        trace!(target = "llm", %url, model = %self.model, "OpenAI embed");
+        let mut req = reqwest::Client::new().post(url).json(&body);
+        if let Some(key) = &self.api_key {
+            req = req.bearer_auth(key);
+        }
+        let resp: EmbedResponse = req.send().await?.error_for_status()?.json().await?;
+        let vector = resp
+            .data
+            .into_iter()
+            .next()
+            .map(|d| d.embedding)
+            .ok_or_else(|| anyhow::anyhow!("no embedding returned"))?;
+        debug!(target = "llm", dim = vector.len(), "OpenAI embedding");
+        Ok(vector)
+    }
+}
@@ -0,0 +1,343 @@
+use super::deadline::{with_deadlines, CancelHandle, Timeouts};
+use super::scheduler::{SchedulerPermit, DEFAULT_PRIORITY};
+use super::{
+    ok_tokens, CanChat, ChatEvent, ChatMessage, GenerationOptions, LlmInstance, LlmProfile,
+    ToolDefinition, ToolResponse,
+};
+use async_stream::stream;
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_stream::{Stream, StreamExt};
+use tracing::{debug, info, warn};
+
+/// How an [`LlmPool`] picks the instance for each request.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RoutingPolicy {
+    /// Always prefer the earliest healthy instance in configuration order.
+    Priority,
+    /// Rotate through healthy instances.
+    RoundRobin,
+    /// Prefer the healthy instance with the most free permits.
+    #[default]
+    LeastLoaded,
+}
+
+struct Member {
+    instance: LlmInstance,
+    healthy: AtomicBool,
+}
+
+impl Member {
+    fn set_healthy(&self, healthy: bool) {
+        if self.healthy.swap(healthy, Ordering::Relaxed) != healthy {
+            if healthy {
+                info!(target: "llm", llm = %self.instance.name, "llm instance healthy");
+            } else {
+                warn!(target: "llm", llm = %self.instance.name, "llm instance unhealthy");
+            }
+        }
+    }
+}
+
+type EventStream = Box<dyn Stream<Item = anyhow::Result<ChatEvent>> + Unpin>;
+
+/// A set of [`LlmInstance`]s serving the same role, with failover.
+///
+/// Each request is routed to a healthy instance according to the
+/// [`RoutingPolicy`] and holds one of that instance's scheduler permits while
+/// streaming; a busy instance admits waiting requests by
+/// [`GenerationOptions::priority`]. When a host fails to connect, or its
+/// stream fails before the first token, the next candidate is tried. Once
+/// tokens have reached the caller a failure ends the stream with the error,
+/// as another host would start the reply over. Only connection and transport
+/// errors mark a host unhealthy; an error reported by the model does not.
+/// Every stream can be aborted through [`LlmPool::cancel_handle`].
+#[derive(Clone)]
+pub struct LlmPool {
+    members: Arc<[Member]>,
+    policy: RoutingPolicy,
+    next: Arc<AtomicUsize>,
+    cancel: CancelHandle,
+}
+
+impl LlmPool {
+    /// Create a pool over `instances`, all initially assumed healthy.
+    pub fn new(instances: Vec<LlmInstance>, policy: RoutingPolicy) -> Self {
+        let members = instances
+            .into_iter()
+            .map(|instance| Member {
+                instance,
+                healthy: AtomicBool::new(true),
+            })
+            .collect();
+        Self {
+            members,
+            policy,
+            next: Arc::new(AtomicUsize::new(0)),
+            cancel: CancelHandle::new(),
+        }
+    }
+
+    /// Handle that aborts every generation in flight on this pool.
+    pub fn cancel_handle(&self) -> CancelHandle {
+        self.cancel.clone()
+    }
+
+    /// Instances in configuration order.
+    pub fn instances(&self) -> impl Iterator<Item = &LlmInstance> {
+        self.members.iter().map(|m| &m.instance)
+    }
+
+    /// Whether the named instance passed its last health check.
+    pub fn is_healthy(&self, name: &str) -> Option<bool> {
+        self.members
+            .iter()
+            .find(|m| m.instance.name == name)
+            .map(|m| m.healthy.load(Ordering::Relaxed))
+    }
+
+    /// Probe every instance once and record the result.
+    pub async fn check_health(&self) {
+        for member in self.members.iter() {
+            let result = member.instance.chat.health_check().await;
+            if let Err(e) = &result {
+                debug!(target: "llm", llm = %member.instance.name, error = %e, "health check failed");
+            }
+            member.set_healthy(result.is_ok());
+        }
+    }
+
+    /// Probe every instance each `interval`, forever.
+    pub async fn run_health_checks(&self, interval: Duration) {
+        loop {
+            self.check_health().await;
+            tokio::time::sleep(interval).await;
+        }
+    }
+
+    /// Log the queue depth and wait times of every instance.
+    pub fn log_queue_summary(&self) {
+        for member in self.members.iter() {
+            member.instance.scheduler.log_summary(&member.instance.name);
+        }
+    }
+
+    /// Log the queue summary each `interval`, forever.
+    pub async fn run_queue_summaries(&self, interval: Duration) {
+        loop {
+            tokio::time::sleep(interval).await;
+            self.log_queue_summary();
+        }
+    }
+
+    /// Candidate member indices for the next request, best first.
+    ///
+    /// Unhealthy instances are kept at the end so a request still has
+    /// somewhere to go when every health check is failing.
+    fn order(&self) -> Vec<usize> {
+        let (mut healthy, unhealthy): (Vec<usize>, Vec<usize>) =
+            (0..self.members.len()).partition(|&i| self.members[i].healthy.load(Ordering::Relaxed));
+        match self.policy {
+            RoutingPolicy::Priority => {}
+            RoutingPolicy::RoundRobin => {
+                if !healthy.is_empty() {
+                    let start = self.next.fetch_add(1, Ordering::Relaxed) % healthy.len();
+                    healthy.rotate_left(start);
+                }
+            }
+            RoutingPolicy::LeastLoaded => {
+                healthy.sort_by_key(|&i| {
+                    std::cmp::Reverse(self.members[i].instance.scheduler.available_permits())
+                });
+            }
+        }
+        healthy.extend(unhealthy);
+        healthy
+    }
+}
+
+/// Whether `e` came from the connection to a host rather than its model:
+/// an I/O or HTTP failure, a cut-off body or a missed deadline.
+fn is_transport_error(e: &anyhow::Error) -> bool {
+    e.chain()
+        .any(|cause| cause.is::<std::io::Error>() || cause.is::<reqwest::Error>())
+}
+
+/// Open a stream on the first candidate that accepts the request.
+///
+/// Returns the position within `candidates` that succeeded.
+async fn open_stream(
+    members: &[Member],
+    candidates: &[usize],
+    messages: &[ChatMessage],
+    options: &GenerationOptions,
+) -> anyhow::Result<(usize, EventStream, SchedulerPermit)> {
+    let mut last_err = anyhow::anyhow!("no llm instances available");
+    let priority = options.priority.unwrap_or(DEFAULT_PRIORITY);
+    for (pos, &idx) in candidates.iter().enumerate() {
+        let member = &members[idx];
+        let permit = member.instance.scheduler.acquire(priority).await;
+        debug!(target: "llm", llm = %member.instance.name, "routing request");
+        match member
+            .instance
+            .chat
+            .chat_events(&member.instance.profile, messages, options)
+            .await
+        {
+            Ok(stream) => return Ok((pos, stream, permit)),
+            Err(e) => {
+                warn!(target: "llm", llm = %member.instance.name, error = %e, "llm request failed");
+                member.set_healthy(false);
+                last_err = e;
+            }
+        }
+    }
+    Err(last_err)
+}
+
+#[async_trait(?Send)]
+impl CanChat for LlmPool {
+    async fn chat_stream(
+        &self,
+        profile: &LlmProfile,
+        system: &str,
+        user: &str,
+    ) -> anyhow::Result<Box<dyn Stream<Item = String> + Unpin>> {
+        self.chat_stream_with_options(profile, system, user, &GenerationOptions::default())
+            .await
+    }
+
+    async fn chat_stream_with_options(
+        &self,
+        profile: &LlmProfile,
+        system: &str,
+        user: &str,
+        options: &GenerationOptions,
+    ) -> anyhow::Result<Box<dyn Stream<Item = String> + Unpin>> {
+        let messages = [ChatMessage::system(system), ChatMessage::user(user)];
+        self.chat_messages_stream(profile, &messages, options).await
+    }
+
+    async fn chat_messages_stream(
+        &self,
+        profile: &LlmProfile,
+        messages: &[ChatMessage],
+        options: &GenerationOptions,
+    ) -> anyhow::Result<Box<dyn Stream<Item = String> + Unpin>> {
+        let stream = self.chat_events(profile, messages, options).await?;
+        Ok(ok_tokens(stream))
+    }
+
+    async fn chat_events(
+        &self,
+        _profile: &LlmProfile,
+        messages: &[ChatMessage],
+        options: &GenerationOptions,
+    ) -> anyhow::Result<EventStream> {
+        let started = tokio::time::Instant::now();
+        let order = self.order();
+        let (pos, stream, permit) = open_stream(&self.members, &order, messages, options).await?;
+        let members = self.members.clone();
+        let messages = messages.to_vec();
+        let options = options.clone();
+        let mut rest = order[pos + 1..].to_vec();
+        let mut current = Some((order[pos], stream, permit));
+        let out = stream! {
+            let mut forwarded = false;
+            while let Some((idx, mut stream, permit)) = current.take() {
+                let mut failed = None;
+                while let Some(item) = stream.next().await {
+                    match item {
+                        Ok(ChatEvent::Token(token)) => {
+                            forwarded = true;
+                            yield Ok(ChatEvent::Token(token));
+                        }
+                        Ok(ChatEvent::Usage(mut usage)) => {
+                            usage.host.get_or_insert_with(|| members[idx].instance.name.clone());
+                            yield Ok(ChatEvent::Usage(usage));
+                        }
+                        Err(e) => {
+                            warn!(target: "llm", llm = %members[idx].instance.name, error = %e, "llm stream failed");
+                            if is_transport_error(&e) {
+                                members[idx].set_healthy(false);
+                            }
+                            failed = Some(e);
+                            break;
+                        }
+                    }
+                }
+                drop(permit);
+                let Some(e) = failed else {
+                    break;
+                };
+                if forwarded || rest.is_empty() {
+                    yield Err(e);
+                    break;
+                }
+                match open_stream(&members, &rest, &messages, &options).await {
+                    Ok((pos, stream, permit)) => {
+                        let idx = rest[pos];
+                        rest.drain(..=pos);
+                        info!(target: "llm", llm = %members[idx].instance.name, "retrying request on another instance");
+                        current = Some((idx, stream, permit));
+                    }
+                    Err(e) => yield Err(e),
+                }
+            }
+        };
+        Ok(with_deadlines(
+            Box::new(Box::pin(out)),
+            Timeouts::default(),
+            started,
+            self.cancel.token(),
+        ))
+    }
+
+    async fn health_check(&self) -> anyhow::Result<()> {
+        self.check_health().await;
+        if self
+            .members
+            .iter()
+            .any(|m| m.healthy.load(Ordering::Relaxed))
+        {
+            Ok(())
+        } else {
+            anyhow::bail!("no healthy llm instances")
+        }
+    }
+
+    async fn chat_with_tools(
+        &self,
+        _profile: &LlmProfile,
+        messages: &[ChatMessage],
+        tools: &[ToolDefinition],
+        options: &GenerationOptions,
+    ) -> anyhow::Result<ToolResponse> {
+        let mut last_err = anyhow::anyhow!("no llm instances available");
+        for idx in self.order() {
+            let member = &self.members[idx];
+            let _permit = member
+                .instance
+                .scheduler
+                .acquire(options.priority.unwrap_or(DEFAULT_PRIORITY))
+                .await;
+            match member
+                .instance
+                .chat
+                .chat_with_tools(&member.instance.profile, messages, tools, options)
+                .await
+            {
+                Ok(resp) => return Ok(resp),
+                Err(e) => {
+                    debug!(target: "llm", llm = %member.instance.name, error = %e, "tool call failed");
+                    last_err = e;
+                }
+            }
+        }
+        Err(last_err)
+    }
+}
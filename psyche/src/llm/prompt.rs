@@ -1,14 +1,156 @@
+//! Prompt templating shared by every wit, `distilld` and the
+//! [`Memorizer`](crate::memory::Memorizer).
+//!
+//! Templates use [Tera](https://keats.github.io/tera/) syntax. Every render
+//! sees the identity fields `name`, `role` and `purpose` from
+//! `identity.toml`, the current time as `now`, the joined text of the input
+//! entries as `input`, the entries themselves (with timestamps) as
+//! `entries`, and recent memories as `memories`. Files in the soul's
+//! `prompts/` directory are available as partials through
+//! `{% include "file.txt" %}`.
+//!
+//! The legacy `{input}` placeholder is still understood.
+
+use crate::memory::Experience;
+use crate::models::MemoryEntry;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use std::path::Path;
+use tera::Tera;
+use tracing::{debug, warn};
+
+/// Directory next to `identity.toml` holding shared partials.
+pub const PARTIALS_DIR: &str = "prompts";
+
+/// Identity fields read from `identity.toml`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Identity {
+    /// Name the soul goes by.
+    #[serde(default)]
+    pub name: String,
+    /// What the soul is, e.g. "Autonomous space probe".
+    #[serde(default)]
+    pub role: Option<String>,
+    /// Why the soul exists.
+    #[serde(default)]
+    pub purpose: Option<String>,
+}
+
+/// Values available to a single render beyond the identity.
+///
+/// ```
+/// use psyche::llm::prompt::{Identity, PromptContext, PromptHelper};
+/// let helper = PromptHelper::default().with_identity(Identity {
+///     name: "Layka".into(),
+///     ..Default::default()
+/// });
+/// let ctx = PromptContext::new().var("current", "a bird sings");
+/// let text = helper.render("{{ name }} hears: {{ current }}", &ctx).unwrap();
+/// assert_eq!(text, "Layka hears: a bird sings");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct PromptContext {
+    entries: Vec<MemoryEntry>,
+    memories: Vec<Experience>,
+    now: Option<DateTime<Utc>>,
+    vars: tera::Context,
+}
+
+#[derive(Serialize)]
+struct EntryView<'a> {
+    id: String,
+    kind: &'a str,
+    when: String,
+    text: String,
+    how: &'a str,
+    what: &'a serde_json::Value,
+}
+
+#[derive(Serialize)]
+struct MemoryView<'a> {
+    when: String,
+    how: &'a str,
+    what: &'a serde_json::Value,
+    tags: &'a [String],
+}
+
+/// Text of an entry as shown to the model: its summary when present,
+/// otherwise its raw content.
+pub fn entry_text(entry: &MemoryEntry) -> String {
+    if !entry.how.is_empty() {
+        entry.how.clone()
+    } else {
+        entry
+            .what
+            .as_str()
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| entry.what.to_string())
+    }
+}
+
+/// Rewrite the legacy single-brace `{input}` placeholder into Tera syntax,
+/// leaving `{{input}}` untouched.
+fn upgrade_legacy(template: &str) -> String {
+    const LEGACY: &str = "{input}";
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(pos) = rest.find(LEGACY) {
+        let end = pos + LEGACY.len();
+        let braced = rest[..pos].ends_with('{') || rest[end..].starts_with('}');
+        out.push_str(&rest[..pos]);
+        out.push_str(if braced { LEGACY } else { "{{ input }}" });
+        rest = &rest[end..];
+    }
+    out.push_str(rest);
+    out
+}
+
+impl PromptContext {
+    /// An empty context rendered at the current time.
+    pub fn new() -> Self {
+        Self::default()
+    }
 
-/// Helper that reads a self description from `self.txt` next to a config file.
-/// When present, this text is used as the system prompt for LLM calls.
-#[derive(Default, Clone)]
+    /// Entries the prompt is about, exposed as `input` and `entries`.
+    pub fn input(mut self, entries: &[MemoryEntry]) -> Self {
+        self.entries = entries.to_vec();
+        self
+    }
+
+    /// Recent memories, exposed as `memories`.
+    pub fn memories(mut self, memories: &[Experience]) -> Self {
+        self.memories = memories.to_vec();
+        self
+    }
+
+    /// Render as if at `now` instead of the current time.
+    pub fn at(mut self, now: DateTime<Utc>) -> Self {
+        self.now = Some(now);
+        self
+    }
+
+    /// Set an extra variable such as `previous` or `current`.
+    pub fn var(mut self, key: &str, value: impl Serialize) -> Self {
+        self.vars.insert(key, &value);
+        self
+    }
+}
+
+/// Renders prompt templates for one soul.
+///
+/// Also carries the self description from `self.txt`, which is used as the
+/// system prompt for LLM calls.
+#[derive(Default, Clone, Debug)]
 pub struct PromptHelper {
     header: Option<String>,
+    identity: Identity,
+    partials: Tera,
 }
 
 impl PromptHelper {
-    /// Loads a [`PromptHelper`] using `config_path` to locate `self.txt`.
+    /// Loads a [`PromptHelper`] for the soul whose `identity.toml` is at
+    /// `config_path`, reading `self.txt` and the `prompts/` partials next
+    /// to it.
     ///
     /// ```
     /// use psyche::llm::prompt::PromptHelper;
@@ -18,13 +160,107 @@ impl PromptHelper {
     /// ```
     pub fn from_config(config_path: &Path) -> Self {
         let dir = config_path.parent().unwrap_or_else(|| Path::new("."));
-        let path = dir.join("self.txt");
-        let header = std::fs::read_to_string(path).ok();
-        Self { header }
+        let header = std::fs::read_to_string(dir.join("self.txt")).ok();
+        let identity = std::fs::read_to_string(config_path)
+            .ok()
+            .and_then(|text| match toml::from_str(&text) {
+                Ok(id) => Some(id),
+                Err(e) => {
+                    warn!(path = %config_path.display(), error = %e, "unreadable identity");
+                    None
+                }
+            })
+            .unwrap_or_default();
+        let mut helper = Self {
+            header,
+            identity,
+            partials: Tera::default(),
+        };
+        helper.load_partials(&dir.join(PARTIALS_DIR));
+        helper
+    }
+
+    /// Replace the identity fields exposed to templates.
+    pub fn with_identity(mut self, identity: Identity) -> Self {
+        self.identity = identity;
+        self
+    }
+
+    /// Register every file in `dir` as a partial named after the file.
+    fn load_partials(&mut self, dir: &Path) {
+        let Ok(files) = std::fs::read_dir(dir) else {
+            return;
+        };
+        for file in files.flatten() {
+            let path = file.path();
+            if !path.is_file() {
+                continue;
+            }
+            let name = file.file_name().to_string_lossy().into_owned();
+            match std::fs::read_to_string(&path) {
+                Ok(text) => match self.partials.add_raw_template(&name, &text) {
+                    Ok(()) => debug!(partial = %name, "loaded prompt partial"),
+                    Err(e) => warn!(partial = %name, error = %e, "invalid prompt partial"),
+                },
+                Err(e) => warn!(path = %path.display(), error = %e, "failed to read partial"),
+            }
+        }
     }
 
     /// Returns the loaded header, or an empty string if none was found.
     pub fn system(&self) -> &str {
         self.header.as_deref().unwrap_or("")
     }
+
+    /// Identity fields exposed to templates.
+    pub fn identity(&self) -> &Identity {
+        &self.identity
+    }
+
+    /// Render `template` with the identity and `ctx`.
+    pub fn render(&self, template: &str, ctx: &PromptContext) -> anyhow::Result<String> {
+        let mut vars = ctx.vars.clone();
+        vars.insert("name", &self.identity.name);
+        vars.insert("role", &self.identity.role);
+        vars.insert("purpose", &self.identity.purpose);
+        vars.insert("now", &ctx.now.unwrap_or_else(Utc::now).to_rfc3339());
+        if !ctx.entries.is_empty() || !vars.contains_key("input") {
+            let joined = ctx
+                .entries
+                .iter()
+                .map(entry_text)
+                .collect::<Vec<_>>()
+                .join("\n");
+            vars.insert("input", &joined);
+        }
+        let entries: Vec<_> = ctx
+            .entries
+            .iter()
+            .map(|e| EntryView {
+                id: e.id.to_string(),
+                kind: &e.kind,
+                when: e.when.to_rfc3339(),
+                text: entry_text(e),
+                how: &e.how,
+                what: &e.what,
+            })
+            .collect();
+        vars.insert("entries", &entries);
+        let memories: Vec<_> = ctx
+            .memories
+            .iter()
+            .map(|m| MemoryView {
+                when: m.when.to_rfc3339(),
+                how: &m.how,
+                what: &m.what,
+                tags: &m.tags,
+            })
+            .collect();
+        vars.insert("memories", &memories);
+
+        let template = upgrade_legacy(template);
+        let mut tera = self.partials.clone();
+        tera.autoescape_on(vec![]);
+        Ok(tera.render_str(&template, &vars)?)
+    }
 }
@@ -0,0 +1,243 @@
+//! Priority-aware admission to an LLM instance.
+//!
+//! A [`PriorityScheduler`] hands out a fixed number of permits. When none is
+//! free, waiting requests are served by priority (lower values first, as
+//! with wit priorities) rather than arrival order, so a reflexive wit is not
+//! stuck behind a long narrative one. To keep low-priority work from
+//! starving, a waiter's effective priority improves by one for every
+//! [`aging`](PriorityScheduler::with_aging) interval it has waited. Queue
+//! depth and wait times are tracked per priority class.
+//!
+//! Priorities only apply to in-process wits: requests made through the
+//! same [`LlmPool`](super::pool::LlmPool) or
+//! [`LimitedChat`](super::limited::LimitedChat). Wits run as `distilld`
+//! processes call their model server directly and have no priority. Wits
+//! that must preempt each other belong in one process, sharing its pool.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tokio::time::Instant;
+use tracing::{debug, info};
+
+/// Priority used when a request does not set one.
+pub const DEFAULT_PRIORITY: usize = 0;
+
+/// Waiting time that improves a request's priority by one.
+pub const DEFAULT_AGING: Duration = Duration::from_secs(2);
+
+/// Queue figures for one priority class.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct QueueStats {
+    /// Requests currently waiting.
+    pub depth: usize,
+    /// Permits granted so far.
+    pub granted: u64,
+    /// Total time granted requests spent waiting.
+    pub total_wait: Duration,
+    /// Longest wait of a granted request.
+    pub max_wait: Duration,
+}
+
+impl QueueStats {
+    /// Mean wait per granted request.
+    pub fn mean_wait(&self) -> Duration {
+        if self.granted == 0 {
+            Duration::ZERO
+        } else {
+            self.total_wait / self.granted as u32
+        }
+    }
+}
+
+struct Waiter {
+    priority: usize,
+    enqueued: Instant,
+    grant: oneshot::Sender<()>,
+}
+
+struct State {
+    available: usize,
+    waiters: Vec<Waiter>,
+    stats: BTreeMap<usize, QueueStats>,
+}
+
+impl State {
+    fn record_grant(&mut self, priority: usize, waited: Duration) {
+        let stats = self.stats.entry(priority).or_default();
+        stats.granted += 1;
+        stats.total_wait += waited;
+        stats.max_wait = stats.max_wait.max(waited);
+    }
+}
+
+/// Grants a limited number of concurrent permits by priority, with aging.
+///
+/// ```
+/// use psyche::llm::scheduler::PriorityScheduler;
+/// # tokio_test::block_on(async {
+/// let scheduler = PriorityScheduler::new(1);
+/// let permit = scheduler.acquire(3).await;
+/// assert_eq!(scheduler.available_permits(), 0);
+/// drop(permit);
+/// assert_eq!(scheduler.available_permits(), 1);
+/// assert_eq!(scheduler.stats()[&3].granted, 1);
+/// # });
+/// ```
+#[derive(Clone)]
+pub struct PriorityScheduler {
+    state: Arc<Mutex<State>>,
+    aging: Duration,
+}
+
+/// A granted slot; released when dropped.
+pub struct SchedulerPermit {
+    scheduler: PriorityScheduler,
+}
+
+impl Drop for SchedulerPermit {
+    fn drop(&mut self) {
+        self.scheduler.release();
+    }
+}
+
+/// Waiting registration that returns a permit granted too late, i.e. after
+/// the waiting request was dropped.
+struct Pending {
+    scheduler: PriorityScheduler,
+    grant: Option<oneshot::Receiver<()>>,
+}
+
+impl Drop for Pending {
+    fn drop(&mut self) {
+        if let Some(mut grant) = self.grant.take() {
+            grant.close();
+            if grant.try_recv().is_ok() {
+                self.scheduler.release();
+            }
+        }
+    }
+}
+
+impl PriorityScheduler {
+    /// Scheduler handing out `permits` concurrent slots.
+    pub fn new(permits: usize) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(State {
+                available: permits,
+                waiters: Vec::new(),
+                stats: BTreeMap::new(),
+            })),
+            aging: DEFAULT_AGING,
+        }
+    }
+
+    /// Improve a waiting request's priority by one every `aging`.
+    pub fn with_aging(mut self, aging: Duration) -> Self {
+        self.aging = aging;
+        self
+    }
+
+    /// Wait for a permit for a request of `priority`.
+    pub async fn acquire(&self, priority: usize) -> SchedulerPermit {
+        let rx = {
+            let mut state = self.state.lock().unwrap();
+            if state.available > 0 && state.waiters.iter().all(|w| w.grant.is_closed()) {
+                state.available -= 1;
+                state.record_grant(priority, Duration::ZERO);
+                return SchedulerPermit {
+                    scheduler: self.clone(),
+                };
+            }
+            let (tx, rx) = oneshot::channel();
+            state.waiters.push(Waiter {
+                priority,
+                enqueued: Instant::now(),
+                grant: tx,
+            });
+            debug!(target: "llm", priority, queued = state.waiters.len(), "llm request queued");
+            rx
+        };
+        let mut pending = Pending {
+            scheduler: self.clone(),
+            grant: Some(rx),
+        };
+        pending
+            .grant
+            .as_mut()
+            .expect("pending grant")
+            .await
+            .expect("scheduler dropped a waiter without granting it");
+        pending.grant = None;
+        SchedulerPermit {
+            scheduler: self.clone(),
+        }
+    }
+
+    /// Permits not currently held.
+    pub fn available_permits(&self) -> usize {
+        self.state.lock().unwrap().available
+    }
+
+    /// Queue figures per priority class.
+    pub fn stats(&self) -> BTreeMap<usize, QueueStats> {
+        let state = self.state.lock().unwrap();
+        let mut stats = state.stats.clone();
+        for waiter in state.waiters.iter().filter(|w| !w.grant.is_closed()) {
+            stats.entry(waiter.priority).or_default().depth += 1;
+        }
+        stats
+    }
+
+    /// Log the queue figures of every priority class, labelled `llm`.
+    pub fn log_summary(&self, llm: &str) {
+        for (priority, stats) in self.stats() {
+            info!(
+                target: "llm",
+                llm,
+                priority,
+                depth = stats.depth,
+                granted = stats.granted,
+                mean_wait_ms = stats.mean_wait().as_millis() as u64,
+                max_wait_ms = stats.max_wait.as_millis() as u64,
+                "llm queue summary"
+            );
+        }
+    }
+
+    fn effective_priority(&self, waiter: &Waiter, now: Instant) -> usize {
+        let waited = now.duration_since(waiter.enqueued);
+        let boost = if self.aging.is_zero() {
+            usize::MAX
+        } else {
+            (waited.as_nanos() / self.aging.as_nanos()) as usize
+        };
+        waiter.priority.saturating_sub(boost)
+    }
+
+    /// Return a permit and hand it to the most urgent live waiter.
+    fn release(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.available += 1;
+        let now = Instant::now();
+        state.waiters.retain(|w| !w.grant.is_closed());
+        while !state.waiters.is_empty() {
+            let next = state
+                .waiters
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, w)| (self.effective_priority(w, now), w.enqueued))
+                .map(|(i, _)| i)
+                .expect("non-empty queue");
+            let waiter = state.waiters.remove(next);
+            if waiter.grant.send(()).is_ok() {
+                state.available -= 1;
+                let waited = now.duration_since(waiter.enqueued);
+                state.record_grant(waiter.priority, waited);
+                debug!(target: "llm", priority = waiter.priority, waited_ms = waited.as_millis() as u64, "llm request admitted");
+                break;
+            }
+        }
+    }
+}
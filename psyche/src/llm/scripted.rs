@@ -0,0 +1,275 @@
+//! Rule-driven mock LLM for deterministic end-to-end tests.
+//!
+//! [`ScriptedChat`] answers from a TOML rules file instead of a model. The
+//! first rule whose pattern matches the last user message wins; rules
+//! naming a `wit` only apply to requests whose [`GenerationOptions::wit`]
+//! names it, or to a chat bound to that wit with [`ScriptedChat::for_wit`].
+//! Replies are streamed in chunks and may be
+//! delayed or cut short by an injected error.
+//!
+//! ```toml
+//! default = "I have nothing to say."
+//!
+//! [[rule]]
+//! wit = "quick"
+//! contains = "hello"
+//! response = "Hi there!"
+//! chunk_chars = 3
+//! delay_ms = 5
+//!
+//! [[rule]]
+//! regex = "(?i)weather in (\\w+)"
+//! response = "It is sunny in $1."
+//!
+//! [[rule]]
+//! contains = "crash"
+//! error = "model overloaded"
+//! ```
+//!
+//! Select it in `llm.toml` with `provider = "scripted"` and
+//! `rules = "rules.toml"`, resolved against the config directory.
+
+use super::{ok_tokens, CanChat, ChatEvent, ChatMessage, ChatRole, GenerationOptions, LlmProfile};
+use async_stream::stream;
+use async_trait::async_trait;
+use regex::Regex;
+use serde::Deserialize;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_stream::Stream;
+use tracing::{debug, trace};
+
+type EventStream = Box<dyn Stream<Item = anyhow::Result<ChatEvent>> + Unpin>;
+
+/// One scripted behaviour.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    /// Only match requests from this wit.
+    #[serde(default)]
+    pub wit: Option<String>,
+    /// Match when the prompt contains this text.
+    #[serde(default)]
+    pub contains: Option<String>,
+    /// Match when the prompt matches this regex. Captures may be used in
+    /// `response` as `$1` or `$name`.
+    #[serde(default, with = "serde_regex")]
+    pub regex: Option<Regex>,
+    /// Reply text.
+    #[serde(default)]
+    pub response: String,
+    /// Characters per streamed chunk. The whole reply is one chunk when
+    /// unset.
+    #[serde(default)]
+    pub chunk_chars: Option<usize>,
+    /// Pause before the first chunk.
+    #[serde(default)]
+    pub first_token_delay_ms: u64,
+    /// Pause between chunks.
+    #[serde(default)]
+    pub delay_ms: u64,
+    /// Fail with this message instead of (or after part of) the reply.
+    #[serde(default)]
+    pub error: Option<String>,
+    /// Number of chunks streamed before `error` is raised. Without it the
+    /// request itself fails.
+    #[serde(default)]
+    pub error_after_chunks: Option<usize>,
+}
+
+mod serde_regex {
+    use regex::Regex;
+    use serde::{Deserialize, Deserializer};
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Option<Regex>, D::Error> {
+        Option::<String>::deserialize(d)?
+            .map(|s| Regex::new(&s).map_err(serde::de::Error::custom))
+            .transpose()
+    }
+}
+
+impl Rule {
+    /// Whether this rule answers `prompt` sent by `wit`.
+    fn matches(&self, wit: Option<&str>, prompt: &str) -> bool {
+        if self.wit.is_some() && self.wit.as_deref() != wit {
+            return false;
+        }
+        if let Some(needle) = &self.contains {
+            if !prompt.contains(needle.as_str()) {
+                return false;
+            }
+        }
+        if let Some(re) = &self.regex {
+            if !re.is_match(prompt) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Reply to `prompt`, expanding regex captures.
+    fn reply(&self, prompt: &str) -> String {
+        match self.regex.as_ref().and_then(|re| re.captures(prompt)) {
+            Some(caps) => {
+                let mut out = String::new();
+                caps.expand(&self.response, &mut out);
+                out
+            }
+            None => self.response.clone(),
+        }
+    }
+
+    /// Split `reply` into streamed chunks.
+    fn chunks(&self, reply: &str) -> Vec<String> {
+        match self.chunk_chars {
+            Some(n) if n > 0 => reply
+                .chars()
+                .collect::<Vec<_>>()
+                .chunks(n)
+                .map(|c| c.iter().collect())
+                .collect(),
+            _ => vec![reply.to_string()],
+        }
+    }
+}
+
+/// Contents of a rules file.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rules {
+    /// Reply used when no rule matches. Unmatched requests fail without it.
+    #[serde(default)]
+    pub default: Option<String>,
+    /// Rules tried in order.
+    #[serde(default, rename = "rule")]
+    pub rules: Vec<Rule>,
+}
+
+/// Chat backend answering from [`Rules`].
+#[derive(Clone)]
+pub struct ScriptedChat {
+    rules: Arc<Rules>,
+    wit: Option<String>,
+}
+
+impl ScriptedChat {
+    /// Serve replies from `rules`.
+    pub fn new(rules: Rules) -> Self {
+        Self {
+            rules: Arc::new(rules),
+            wit: None,
+        }
+    }
+
+    /// Parse rules from TOML text.
+    ///
+    /// ```
+    /// use psyche::llm::scripted::ScriptedChat;
+    /// let chat = ScriptedChat::from_toml("[[rule]]\ncontains = \"hi\"\nresponse = \"hello\"\n");
+    /// assert!(chat.is_ok());
+    /// ```
+    pub fn from_toml(text: &str) -> anyhow::Result<Self> {
+        Ok(Self::new(toml::from_str(text)?))
+    }
+
+    /// Load rules from the TOML file at `path`.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("rules {}: {}", path.display(), e))?;
+        Self::from_toml(&text).map_err(|e| anyhow::anyhow!("rules {}: {}", path.display(), e))
+    }
+
+    /// A view of the same rules that also applies those naming `wit` to
+    /// requests that do not name their own.
+    pub fn for_wit(&self, wit: impl Into<String>) -> Self {
+        Self {
+            rules: self.rules.clone(),
+            wit: Some(wit.into()),
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl CanChat for ScriptedChat {
+    async fn chat_stream(
+        &self,
+        profile: &LlmProfile,
+        system: &str,
+        user: &str,
+    ) -> anyhow::Result<Box<dyn Stream<Item = String> + Unpin>> {
+        self.chat_stream_with_options(profile, system, user, &GenerationOptions::default())
+            .await
+    }
+
+    async fn chat_stream_with_options(
+        &self,
+        profile: &LlmProfile,
+        system: &str,
+        user: &str,
+        options: &GenerationOptions,
+    ) -> anyhow::Result<Box<dyn Stream<Item = String> + Unpin>> {
+        let messages = [ChatMessage::system(system), ChatMessage::user(user)];
+        self.chat_messages_stream(profile, &messages, options).await
+    }
+
+    async fn chat_messages_stream(
+        &self,
+        profile: &LlmProfile,
+        messages: &[ChatMessage],
+        options: &GenerationOptions,
+    ) -> anyhow::Result<Box<dyn Stream<Item = String> + Unpin>> {
+        let stream = self.chat_events(profile, messages, options).await?;
+        Ok(ok_tokens(stream))
+    }
+
+    async fn chat_events(
+        &self,
+        _profile: &LlmProfile,
+        messages: &[ChatMessage],
+        options: &GenerationOptions,
+    ) -> anyhow::Result<EventStream> {
+        let prompt = messages
+            .iter()
+            .rev()
+            .find(|m| m.role == ChatRole::User)
+            .map(|m| m.content.as_str())
+            .unwrap_or_default();
+        let wit = options.wit.as_deref().or(self.wit.as_deref());
+        trace!(target: "llm", ?wit, %prompt, "ScriptedChat prompt");
+        let rule = match self.rules.rules.iter().position(|r| r.matches(wit, prompt)) {
+            Some(idx) => {
+                debug!(target: "llm", rule = idx, "ScriptedChat rule matched");
+                self.rules.rules[idx].clone()
+            }
+            None => match &self.rules.default {
+                Some(default) => Rule {
+                    response: default.clone(),
+                    ..Default::default()
+                },
+                None => anyhow::bail!("no scripted rule matches prompt: {}", prompt),
+            },
+        };
+        if let (Some(error), None) = (&rule.error, rule.error_after_chunks) {
+            anyhow::bail!("{}", error);
+        }
+
+        let chunks = rule.chunks(&rule.reply(prompt));
+        let out = stream! {
+            tokio::time::sleep(Duration::from_millis(rule.first_token_delay_ms)).await;
+            for (i, chunk) in chunks.into_iter().enumerate() {
+                if rule.error_after_chunks == Some(i) {
+                    break;
+                }
+                if i > 0 {
+                    tokio::time::sleep(Duration::from_millis(rule.delay_ms)).await;
+                }
+                yield Ok(ChatEvent::Token(chunk));
+            }
+            if let Some(error) = rule.error {
+                yield Err(anyhow::anyhow!(error));
+            }
+        };
+        Ok(Box::new(Box::pin(out)))
+    }
+}
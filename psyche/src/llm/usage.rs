@@ -0,0 +1,425 @@
+//! Token usage and latency accounting for LLM calls.
+//!
+//! Every completed call produces a [`UsageRecord`]. [`UsageStats`] logs each
+//! record and keeps running [`UsageTotals`] per wit and per host, which can
+//! be queried at any time or logged as a periodic summary. Each process can
+//! also keep a [`UsageReport`] snapshot on disk so other processes can read
+//! its totals. [`MeteredChat`] wraps any [`CanChat`] backend and records its
+//! calls automatically.
+
+use super::{
+    ok_tokens, CanChat, ChatEvent, ChatMessage, GenerationOptions, LlmProfile, TokenUsage,
+    ToolDefinition, ToolResponse,
+};
+use async_stream::stream;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio_stream::{Stream, StreamExt};
+use tracing::{info, warn};
+
+/// Measurements for a single LLM call.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UsageRecord {
+    /// Name of the wit that made the call.
+    pub wit: String,
+    /// Model that served the call.
+    pub model: String,
+    /// Host or pool instance that served the call.
+    pub host: String,
+    /// Tokens consumed by the prompt, when reported.
+    pub prompt_tokens: Option<u32>,
+    /// Tokens generated in the reply, when reported.
+    pub completion_tokens: Option<u32>,
+    /// Time from sending the request to receiving the first token.
+    pub time_to_first_token: Option<Duration>,
+    /// Time from sending the request to the end of the reply.
+    pub latency: Duration,
+}
+
+impl UsageRecord {
+    /// Generated tokens per second after the first token arrived.
+    ///
+    /// ```
+    /// use psyche::llm::usage::UsageRecord;
+    /// use std::time::Duration;
+    /// let rec = UsageRecord {
+    ///     wit: "quick".into(),
+    ///     model: "llama3".into(),
+    ///     host: "local".into(),
+    ///     prompt_tokens: Some(12),
+    ///     completion_tokens: Some(20),
+    ///     time_to_first_token: Some(Duration::from_millis(500)),
+    ///     latency: Duration::from_millis(2500),
+    /// };
+    /// assert_eq!(rec.tokens_per_second(), Some(10.0));
+    /// ```
+    pub fn tokens_per_second(&self) -> Option<f64> {
+        let tokens = self.completion_tokens?;
+        let generating = self
+            .latency
+            .saturating_sub(self.time_to_first_token.unwrap_or_default());
+        (generating > Duration::ZERO).then(|| f64::from(tokens) / generating.as_secs_f64())
+    }
+
+    /// Emit this record as a structured log line.
+    pub fn log(&self) {
+        info!(
+            target: "llm",
+            wit = %self.wit,
+            model = %self.model,
+            host = %self.host,
+            prompt_tokens = ?self.prompt_tokens,
+            completion_tokens = ?self.completion_tokens,
+            ttft_ms = ?self.time_to_first_token.map(|d| d.as_millis()),
+            latency_ms = self.latency.as_millis() as u64,
+            tokens_per_sec = ?self.tokens_per_second(),
+            "llm usage"
+        );
+    }
+}
+
+/// Running totals over many [`UsageRecord`]s.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UsageTotals {
+    /// Number of calls recorded.
+    pub calls: u64,
+    /// Sum of reported prompt tokens.
+    pub prompt_tokens: u64,
+    /// Sum of reported completion tokens.
+    pub completion_tokens: u64,
+    /// Sum of all call latencies.
+    pub total_latency: Duration,
+    /// Sum of time-to-first-token over calls that produced a token.
+    pub total_time_to_first_token: Duration,
+    /// Number of calls that produced at least one token.
+    pub first_token_calls: u64,
+}
+
+impl UsageTotals {
+    /// Fold one record into the totals.
+    pub fn add(&mut self, record: &UsageRecord) {
+        self.calls += 1;
+        self.prompt_tokens += u64::from(record.prompt_tokens.unwrap_or(0));
+        self.completion_tokens += u64::from(record.completion_tokens.unwrap_or(0));
+        self.total_latency += record.latency;
+        if let Some(ttft) = record.time_to_first_token {
+            self.total_time_to_first_token += ttft;
+            self.first_token_calls += 1;
+        }
+    }
+
+    /// Fold totals kept elsewhere into these.
+    pub fn merge(&mut self, other: &UsageTotals) {
+        self.calls += other.calls;
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.total_latency += other.total_latency;
+        self.total_time_to_first_token += other.total_time_to_first_token;
+        self.first_token_calls += other.first_token_calls;
+    }
+
+    /// Mean latency per call.
+    pub fn mean_latency(&self) -> Option<Duration> {
+        (self.calls > 0).then(|| self.total_latency / self.calls as u32)
+    }
+
+    /// Mean time-to-first-token per call that produced a token.
+    pub fn mean_time_to_first_token(&self) -> Option<Duration> {
+        (self.first_token_calls > 0)
+            .then(|| self.total_time_to_first_token / self.first_token_calls as u32)
+    }
+
+    /// Completion tokens per second of total latency.
+    pub fn tokens_per_second(&self) -> Option<f64> {
+        let secs = self.total_latency.as_secs_f64();
+        (secs > 0.0).then(|| self.completion_tokens as f64 / secs)
+    }
+}
+
+/// Totals per wit and per host at one moment, as saved to disk.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UsageReport {
+    /// Totals keyed by wit name.
+    pub by_wit: BTreeMap<String, UsageTotals>,
+    /// Totals keyed by host name.
+    pub by_host: BTreeMap<String, UsageTotals>,
+}
+
+impl UsageReport {
+    /// Read a report saved by [`UsageStats::save`].
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    /// Fold the totals of `other`, e.g. from another process, into these.
+    pub fn merge(&mut self, other: &UsageReport) {
+        for (name, totals) in &other.by_wit {
+            self.by_wit.entry(name.clone()).or_default().merge(totals);
+        }
+        for (name, totals) in &other.by_host {
+            self.by_host.entry(name.clone()).or_default().merge(totals);
+        }
+    }
+}
+
+#[derive(Default)]
+struct Tables {
+    report: UsageReport,
+    snapshot: Option<PathBuf>,
+}
+
+/// Shared aggregator of [`UsageRecord`]s.
+///
+/// Cloning is cheap; every clone feeds the same totals.
+#[derive(Clone, Default)]
+pub struct UsageStats {
+    tables: Arc<Mutex<Tables>>,
+}
+
+impl UsageStats {
+    /// Create an empty aggregator.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Save the totals to `path` after every record, so other processes
+    /// can read them with [`UsageReport::load`].
+    pub fn saving_to(self, path: impl Into<PathBuf>) -> Self {
+        self.tables.lock().unwrap().snapshot = Some(path.into());
+        self
+    }
+
+    /// Log `record` and add it to the per-wit and per-host totals.
+    pub fn record(&self, record: UsageRecord) {
+        record.log();
+        let mut tables = self.tables.lock().unwrap();
+        tables
+            .report
+            .by_wit
+            .entry(record.wit.clone())
+            .or_default()
+            .add(&record);
+        tables
+            .report
+            .by_host
+            .entry(record.host.clone())
+            .or_default()
+            .add(&record);
+        if let Some(path) = &tables.snapshot {
+            if let Err(e) = write_report(path, &tables.report) {
+                warn!(target: "llm", error = %e, path = %path.display(), "failed to save usage");
+            }
+        }
+    }
+
+    /// Current totals per wit and per host.
+    pub fn report(&self) -> UsageReport {
+        self.tables.lock().unwrap().report.clone()
+    }
+
+    /// Write the current totals to `path`, replacing it atomically.
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        write_report(path, &self.report())
+    }
+
+    /// Totals for every wit, keyed by wit name.
+    pub fn by_wit(&self) -> BTreeMap<String, UsageTotals> {
+        self.tables.lock().unwrap().report.by_wit.clone()
+    }
+
+    /// Totals for every host, keyed by host name.
+    pub fn by_host(&self) -> BTreeMap<String, UsageTotals> {
+        self.tables.lock().unwrap().report.by_host.clone()
+    }
+
+    /// Totals for a single wit.
+    pub fn wit(&self, name: &str) -> Option<UsageTotals> {
+        self.tables.lock().unwrap().report.by_wit.get(name).cloned()
+    }
+
+    /// Totals for a single host.
+    pub fn host(&self, name: &str) -> Option<UsageTotals> {
+        self.tables
+            .lock()
+            .unwrap()
+            .report
+            .by_host
+            .get(name)
+            .cloned()
+    }
+
+    /// Log one summary line per wit and per host.
+    pub fn log_summary(&self) {
+        for (scope, totals) in [("wit", self.by_wit()), ("host", self.by_host())] {
+            for (name, t) in totals {
+                info!(
+                    target: "llm",
+                    scope,
+                    name = %name,
+                    calls = t.calls,
+                    prompt_tokens = t.prompt_tokens,
+                    completion_tokens = t.completion_tokens,
+                    mean_ttft_ms = ?t.mean_time_to_first_token().map(|d| d.as_millis()),
+                    mean_latency_ms = ?t.mean_latency().map(|d| d.as_millis()),
+                    tokens_per_sec = ?t.tokens_per_second(),
+                    "llm usage summary"
+                );
+            }
+        }
+    }
+
+    /// Log a summary each `interval`, forever.
+    pub async fn run_summaries(&self, interval: Duration) {
+        loop {
+            tokio::time::sleep(interval).await;
+            self.log_summary();
+        }
+    }
+}
+
+fn write_report(path: &Path, report: &UsageReport) -> anyhow::Result<()> {
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, serde_json::to_vec(report)?)?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// Chat wrapper that records a [`UsageRecord`] for every call.
+///
+/// Streams are measured from the request until they end. The model and host
+/// come from the backend's [`TokenUsage`] when it reports them, falling back
+/// to the call's [`LlmProfile`].
+pub struct MeteredChat {
+    inner: Arc<dyn CanChat>,
+    wit: String,
+    stats: UsageStats,
+}
+
+impl MeteredChat {
+    /// Record calls made by `wit` through `inner` into `stats`.
+    pub fn new(inner: Arc<dyn CanChat>, wit: impl Into<String>, stats: UsageStats) -> Self {
+        Self {
+            inner,
+            wit: wit.into(),
+            stats,
+        }
+    }
+
+    /// The same backend and totals, with calls attributed to `wit`. Give
+    /// every consumer its own so usage is kept per caller.
+    pub fn for_wit(&self, wit: impl Into<String>) -> Self {
+        Self::new(self.inner.clone(), wit, self.stats.clone())
+    }
+}
+
+/// Build a record, preferring figures reported by the backend over `profile`.
+fn usage_record(
+    wit: &str,
+    profile: &LlmProfile,
+    usage: TokenUsage,
+    time_to_first_token: Option<Duration>,
+    latency: Duration,
+) -> UsageRecord {
+    UsageRecord {
+        wit: wit.to_string(),
+        model: usage.model.unwrap_or_else(|| profile.model.clone()),
+        host: usage.host.unwrap_or_else(|| profile.provider.clone()),
+        prompt_tokens: usage.prompt_tokens,
+        completion_tokens: usage.completion_tokens,
+        time_to_first_token,
+        latency,
+    }
+}
+
+#[async_trait(?Send)]
+impl CanChat for MeteredChat {
+    async fn chat_stream(
+        &self,
+        profile: &LlmProfile,
+        system: &str,
+        user: &str,
+    ) -> anyhow::Result<Box<dyn Stream<Item = String> + Unpin>> {
+        self.chat_stream_with_options(profile, system, user, &GenerationOptions::default())
+            .await
+    }
+
+    async fn chat_stream_with_options(
+        &self,
+        profile: &LlmProfile,
+        system: &str,
+        user: &str,
+        options: &GenerationOptions,
+    ) -> anyhow::Result<Box<dyn Stream<Item = String> + Unpin>> {
+        let messages = [ChatMessage::system(system), ChatMessage::user(user)];
+        self.chat_messages_stream(profile, &messages, options).await
+    }
+
+    async fn chat_messages_stream(
+        &self,
+        profile: &LlmProfile,
+        messages: &[ChatMessage],
+        options: &GenerationOptions,
+    ) -> anyhow::Result<Box<dyn Stream<Item = String> + Unpin>> {
+        let stream = self.chat_events(profile, messages, options).await?;
+        Ok(ok_tokens(stream))
+    }
+
+    async fn chat_events(
+        &self,
+        profile: &LlmProfile,
+        messages: &[ChatMessage],
+        options: &GenerationOptions,
+    ) -> anyhow::Result<Box<dyn Stream<Item = anyhow::Result<ChatEvent>> + Unpin>> {
+        let start = Instant::now();
+        let mut inner = self.inner.chat_events(profile, messages, options).await?;
+        let wit = self.wit.clone();
+        let profile = profile.clone();
+        let stats = self.stats.clone();
+        let out = stream! {
+            let mut first_token = None;
+            let mut usage = TokenUsage::default();
+            while let Some(event) = inner.next().await {
+                match &event {
+                    Ok(ChatEvent::Token(_)) => {
+                        first_token.get_or_insert_with(|| start.elapsed());
+                    }
+                    Ok(ChatEvent::Usage(u)) => usage = u.clone(),
+                    Err(_) => {}
+                }
+                yield event;
+            }
+            stats.record(usage_record(&wit, &profile, usage, first_token, start.elapsed()));
+        };
+        Ok(Box::new(Box::pin(out)))
+    }
+
+    async fn health_check(&self) -> anyhow::Result<()> {
+        self.inner.health_check().await
+    }
+
+    async fn chat_with_tools(
+        &self,
+        profile: &LlmProfile,
+        messages: &[ChatMessage],
+        tools: &[ToolDefinition],
+        options: &GenerationOptions,
+    ) -> anyhow::Result<ToolResponse> {
+        let start = Instant::now();
+        let resp = self
+            .inner
+            .chat_with_tools(profile, messages, tools, options)
+            .await?;
+        self.stats.record(usage_record(
+            &self.wit,
+            profile,
+            TokenUsage::default(),
+            None,
+            start.elapsed(),
+        ));
+        Ok(resp)
+    }
+}
@@ -1,14 +1,47 @@
-use crate::llm::{prompt::PromptHelper, CanChat, CanEmbed, LlmProfile};
+use crate::llm::prompt::{PromptContext, PromptHelper};
+use crate::llm::{CanChat, CanEmbed, LlmProfile};
 use crate::utils::{first_sentence, parse_json_or_string};
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::HashMap;
 use tokio_stream::StreamExt;
 use tracing::{debug, info, trace};
 #[cfg(feature = "qdrant")]
 use uuid::Uuid;
 
+mod hnsw;
+pub use hnsw::{Edge, HnswBackend, SNAPSHOT_EVERY};
+mod dump;
+pub use dump::{ExperienceRecord, MemoryDump};
+mod consolidate;
+pub use consolidate::{
+    importance, plan, salience, similarity, summarize_run, Consolidation, Merge, Plan, Retention,
+    RetentionAction, Weights,
+};
+mod entity;
+pub use entity::{
+    about, mentioned_entities, record_entities, Entity, EntityKind, EntityMemories, MENTIONS,
+};
+mod linker;
+pub use linker::{asserted_causes, explicit_topics, Linker, Links, MAX_TOPICS, TOPIC_PROMPT};
+#[cfg(feature = "sqlite")]
+mod sqlite;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteBackend;
+
+/// Link types a backend may record between experiences, topics and
+/// entities.
+pub const EDGE_KINDS: &[&str] = &[
+    "SUMMARIZES",
+    "CALLED_TO_MIND",
+    "NEXT",
+    "CAUSES",
+    "REFERS_TO",
+    MENTIONS,
+];
+
 /// Single memory entry linking a key sentence with a full body.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Experience {
@@ -23,7 +56,194 @@ pub struct Experience {
     pub tags: Vec<String>,
 }
 
-#[cfg(feature = "neo4j")]
+/// Experience found by [`MemoryBackend::search`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchHit {
+    /// Backend id, usable with [`MemoryBackend::get`] and the link methods.
+    pub id: String,
+    /// Cosine similarity to the query vector; higher is closer.
+    pub score: f32,
+    /// The stored experience.
+    pub experience: Experience,
+}
+
+/// Restricts which experiences a search may return. The default admits
+/// everything.
+///
+/// ```
+/// use psyche::memory::{Experience, SearchFilter};
+/// let filter = SearchFilter {
+///     tags: vec!["instant".into(), "moment".into()],
+///     min_score: Some(0.5),
+///     ..Default::default()
+/// };
+/// let exp = Experience {
+///     how: "I see a cat.".into(),
+///     what: "cat".into(),
+///     when: chrono::Utc::now(),
+///     tags: vec!["instant".into()],
+/// };
+/// assert!(filter.matches(&exp));
+/// assert!(!filter.admits(0.2));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SearchFilter {
+    /// Only experiences carrying at least one of these tags; any tag when
+    /// empty.
+    pub tags: Vec<String>,
+    /// Only experiences from this moment on.
+    pub since: Option<DateTime<Utc>>,
+    /// Only experiences up to this moment.
+    pub until: Option<DateTime<Utc>>,
+    /// Only hits scoring at least this much.
+    pub min_score: Option<f32>,
+}
+
+impl SearchFilter {
+    /// Whether `exp` passes the tag and time restrictions.
+    pub fn matches(&self, exp: &Experience) -> bool {
+        (self.tags.is_empty() || exp.tags.iter().any(|t| self.tags.contains(t)))
+            && self.since.is_none_or(|since| exp.when >= since)
+            && self.until.is_none_or(|until| exp.when <= until)
+    }
+
+    /// Whether a hit with `score` is good enough.
+    pub fn admits(&self, score: f32) -> bool {
+        self.min_score.is_none_or(|min| score >= min)
+    }
+}
+
+/// Value bound to a `$name` placeholder of a [`GraphQuery`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum GraphParam {
+    /// Text such as an id, tag or timestamp.
+    Text(String),
+    /// List of strings, e.g. for `IN $ids`.
+    List(Vec<String>),
+    /// Integer such as a limit.
+    Int(i64),
+}
+
+impl From<&str> for GraphParam {
+    fn from(v: &str) -> Self {
+        GraphParam::Text(v.to_string())
+    }
+}
+
+impl From<String> for GraphParam {
+    fn from(v: String) -> Self {
+        GraphParam::Text(v)
+    }
+}
+
+impl From<Vec<String>> for GraphParam {
+    fn from(v: Vec<String>) -> Self {
+        GraphParam::List(v)
+    }
+}
+
+impl From<i64> for GraphParam {
+    fn from(v: i64) -> Self {
+        GraphParam::Int(v)
+    }
+}
+
+/// Columns every [`GraphQuery`] returns for `node`, mapped onto [`Experience`].
+const RETURN_NODE: &str =
+    "RETURN node.how AS how, node.what AS what, node.when AS when, node.tags AS tags";
+
+/// Cypher statement with bound parameters.
+///
+/// Values never become part of the query text; they are sent alongside it
+/// and referenced as `$name`, so quotes or Cypher in a sensation cannot
+/// change what the query does. Every query returns the `how`, `what`,
+/// `when` and `tags` columns of the matched experiences.
+///
+/// ```
+/// use psyche::memory::{GraphParam, GraphQuery};
+/// let q = GraphQuery::neighbors("a\"}) DETACH DELETE (e");
+/// assert!(!q.cypher.contains("DELETE"));
+/// assert_eq!(q.params["id"], GraphParam::Text("a\"}) DETACH DELETE (e".into()));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphQuery {
+    /// Query text containing `$name` placeholders.
+    pub cypher: String,
+    /// Values for the placeholders.
+    pub params: std::collections::BTreeMap<String, GraphParam>,
+}
+
+impl GraphQuery {
+    /// Query with text `cypher` and no parameters yet.
+    pub fn new(cypher: impl Into<String>) -> Self {
+        Self {
+            cypher: cypher.into(),
+            params: Default::default(),
+        }
+    }
+
+    /// Bind `value` to `$name`.
+    pub fn param(mut self, name: &str, value: impl Into<GraphParam>) -> Self {
+        self.params.insert(name.to_string(), value.into());
+        self
+    }
+
+    /// The experience `id` with its chronological neighbors, simple causal
+    /// links and up to two topically related experiences; at most seven.
+    pub fn neighbors(id: &str) -> Self {
+        Self::new(format!(
+            concat!(
+                "MATCH (e:Experience {{id: $id}})",
+                "\nOPTIONAL MATCH (prev:Experience)-[:NEXT]->(e)",
+                "\nOPTIONAL MATCH (e)-[:NEXT]->(next:Experience)",
+                "\nOPTIONAL MATCH (e)-[:CAUSES]->(caused:Experience)",
+                "\nOPTIONAL MATCH (causer:Experience)-[:CAUSES]->(e)",
+                "\nOPTIONAL MATCH (e)-[:REFERS_TO]->(topic)<-[:REFERS_TO]-(related:Experience)",
+                "\nWITH e, prev, next, caused, causer, collect(related)[0..2] AS topical",
+                "\nUNWIND [e, prev, next, caused, causer] + topical AS node",
+                "\nWITH DISTINCT node WHERE node IS NOT NULL",
+                "\n{ret}",
+                "\nLIMIT 7"
+            ),
+            ret = RETURN_NODE
+        ))
+        .param("id", id)
+    }
+
+    /// Experiences tagged `tag` that happened after `since`, oldest first.
+    pub fn tagged_since(tag: &str, since: DateTime<Utc>) -> Self {
+        Self::new(format!(
+            "MATCH (node:Experience) WHERE $tag IN node.tags AND node.when > $since\n{RETURN_NODE} ORDER BY node.when"
+        ))
+        .param("tag", tag)
+        .param("since", since.to_rfc3339())
+    }
+
+    /// The experience `id` followed by the summaries built on top of it,
+    /// nearest first, up to `depth` levels of `SUMMARIZES` links.
+    pub fn summary_chain(id: &str, depth: usize) -> Self {
+        Self::new(format!(
+            concat!(
+                "MATCH path = (node:Experience)-[:SUMMARIZES*0..{depth}]->(e:Experience {{id: $id}})",
+                "\nWITH node, min(length(path)) AS distance",
+                "\n{ret} ORDER BY distance, node.when"
+            ),
+            depth = depth,
+            ret = RETURN_NODE
+        ))
+        .param("id", id)
+    }
+
+    /// Experiences with a `MENTIONS` link to the entity `entity_id`,
+    /// oldest first.
+    pub fn mentioning(entity_id: &str) -> Self {
+        Self::new(format!(
+            "MATCH (node:Experience)-[:MENTIONS]->(entity {{id: $id}})\n{RETURN_NODE} ORDER BY node.when"
+        ))
+        .param("id", entity_id)
+    }
+}
+
 /// Fetch a compact contextual subgraph around a given [`Experience`] node.
 ///
 /// This retrieves the experience itself, its immediate chronological
@@ -34,45 +254,48 @@ pub struct Experience {
 ///
 /// ```
 /// use async_trait::async_trait;
-/// use psyche::memory::{context_subgraph, Experience, MemoryBackend};
+/// use psyche::memory::{context_subgraph, Experience, GraphQuery, MemoryBackend, SearchFilter, SearchHit};
 ///
 /// struct Dummy;
 ///
 /// #[async_trait(?Send)]
 /// impl MemoryBackend for Dummy {
 ///     async fn store(&self, _: &Experience, _: &[f32]) -> anyhow::Result<String> { Ok("1".into()) }
-///     async fn search(&self, _: &[f32], _: usize) -> anyhow::Result<Vec<Experience>> { Ok(vec![]) }
+///     async fn search(&self, _: &[f32], _: usize, _: &SearchFilter) -> anyhow::Result<Vec<SearchHit>> { Ok(vec![]) }
 ///     async fn get(&self, _: &str) -> anyhow::Result<Option<Experience>> { Ok(None) }
-///     async fn cypher_query(&self, _: &str) -> anyhow::Result<Vec<Experience>> { Ok(vec![]) }
+///     async fn graph_query(&self, _: &GraphQuery) -> anyhow::Result<Vec<Experience>> { Ok(vec![]) }
 /// }
 /// # tokio_test::block_on(async {
 /// let backend = Dummy;
 /// let _ = context_subgraph(&backend, "123").await;
 /// # });
 /// ```
-pub async fn context_subgraph<B: MemoryBackend + Sync>(
+pub async fn context_subgraph<B: MemoryBackend + Sync + ?Sized>(
     backend: &B,
     id: &str,
 ) -> anyhow::Result<Vec<Experience>> {
     debug!(id, "context_subgraph query");
-    let query = format!(
-        concat!(
-            "MATCH (e:Experience {{id: \"{id}\"}})",
-            "\nOPTIONAL MATCH (prev:Experience)-[:NEXT]->(e)",
-            "\nOPTIONAL MATCH (e)-[:NEXT]->(next:Experience)",
-            "\nOPTIONAL MATCH (e)-[:CAUSES]->(caused:Experience)",
-            "\nOPTIONAL MATCH (causer:Experience)-[:CAUSES]->(e)",
-            "\nOPTIONAL MATCH (e)-[:REFERS_TO]->(topic)<-[:REFERS_TO]-(related:Experience)",
-            "\nWITH e, prev, next, caused, causer, collect(related)[0..2] AS topical",
-            "\nUNWIND [e, prev, next, caused, causer] + topical AS node",
-            "\nWITH DISTINCT node WHERE node IS NOT NULL",
-            "\nRETURN node.how AS how, node.what AS what, node.when AS when, node.tags AS tags",
-            "\nLIMIT 7"
-        ),
-        id = id
-    );
-    trace!(%query, "context_subgraph cypher");
-    backend.cypher_query(&query).await
+    backend.neighbors(id).await
+}
+
+/// Experiences tagged `tag` stored after `since`, oldest first.
+pub async fn tagged_since<B: MemoryBackend + Sync + ?Sized>(
+    backend: &B,
+    tag: &str,
+    since: DateTime<Utc>,
+) -> anyhow::Result<Vec<Experience>> {
+    debug!(tag, %since, "tagged_since query");
+    backend.tagged_since(tag, since).await
+}
+
+/// The experience `id` and the summaries of it, up to `depth` levels.
+pub async fn summary_chain<B: MemoryBackend + Sync + ?Sized>(
+    backend: &B,
+    id: &str,
+    depth: usize,
+) -> anyhow::Result<Vec<Experience>> {
+    debug!(id, depth, "summary_chain query");
+    backend.summary_chain(id, depth).await
 }
 
 /// Result of persisting an experience.
@@ -88,8 +311,25 @@ pub struct StoredExperience {
 #[async_trait(?Send)]
 pub trait MemoryBackend {
     /// Store the given experience and embedding returning the backend id.
+    /// Backends that keep links also chain it with a `NEXT` edge to the
+    /// latest earlier experience of each of its tags, so every tag forms a
+    /// chronological stream.
     async fn store(&self, exp: &Experience, vector: &[f32]) -> anyhow::Result<String>;
 
+    /// Like [`store`](MemoryBackend::store), but under the caller's `id`, so
+    /// that links naming an id the caller already knows, such as that of a
+    /// [`MemoryEntry`](crate::models::MemoryEntry), reach the experience.
+    /// An id already stored is left as it is. Backends that assign their own
+    /// ids refuse.
+    async fn store_with_id(
+        &self,
+        _id: &str,
+        _exp: &Experience,
+        _vector: &[f32],
+    ) -> anyhow::Result<()> {
+        anyhow::bail!("this memory backend assigns its own ids")
+    }
+
     /// Link a summary experience to the original it summarizes.
     async fn link_summary(&self, _summary_id: &str, _original_id: &str) -> anyhow::Result<()> {
         Ok(())
@@ -100,17 +340,119 @@ pub trait MemoryBackend {
         Ok(())
     }
 
-    /// Find similar experiences using cosine similarity or a vector database.
-    async fn search(&self, vector: &[f32], top_k: usize) -> anyhow::Result<Vec<Experience>>;
+    /// Record that experience `cause` led to `effect`.
+    async fn link_causes(&self, _cause: &str, _effect: &str) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Link an experience to the topic it refers to.
+    async fn link_topic(&self, _id: &str, _topic: &str) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Store `entity`, merging it into a known entity of the same kind that
+    /// shares one of its names, and return the result with its id.
+    /// Backends without a graph keep nothing and return it unchanged.
+    async fn upsert_entity(&self, entity: &Entity) -> anyhow::Result<Entity> {
+        Ok(entity.clone())
+    }
+
+    /// Record that experience `id` mentions the entity `entity_id`.
+    async fn link_mention(&self, _id: &str, _entity_id: &str) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// The entity whose name or alias is `name`.
+    async fn entity(&self, _name: &str) -> anyhow::Result<Option<Entity>> {
+        Ok(None)
+    }
+
+    /// Experiences mentioning the entity `entity_id`, oldest first. Runs
+    /// [`GraphQuery::mentioning`] by default.
+    async fn mentioning(&self, entity_id: &str) -> anyhow::Result<Vec<Experience>> {
+        self.graph_query(&GraphQuery::mentioning(entity_id)).await
+    }
+
+    /// Find up to `top_k` experiences passing `filter`, most similar first.
+    async fn search(
+        &self,
+        vector: &[f32],
+        top_k: usize,
+        filter: &SearchFilter,
+    ) -> anyhow::Result<Vec<SearchHit>>;
 
     /// Retrieve a single experience by backend-defined identifier, if supported.
     async fn get(&self, id: &str) -> anyhow::Result<Option<Experience>>;
 
-    /// Execute a custom Cypher query against the graph store if available.
-    #[cfg(feature = "neo4j")]
-    async fn cypher_query(&self, query: &str) -> anyhow::Result<Vec<Experience>>;
+    /// Run a parameterized graph query, returning the matched experiences.
+    /// Backends without a graph store return nothing.
+    async fn graph_query(&self, _query: &GraphQuery) -> anyhow::Result<Vec<Experience>> {
+        Ok(Vec::new())
+    }
+
+    /// The experience `id` and its immediate neighbors. Runs
+    /// [`GraphQuery::neighbors`] unless the backend traverses links itself.
+    async fn neighbors(&self, id: &str) -> anyhow::Result<Vec<Experience>> {
+        let query = GraphQuery::neighbors(id);
+        trace!(cypher = %query.cypher, "neighbors cypher");
+        self.graph_query(&query).await
+    }
+
+    /// Experiences tagged `tag` after `since`, oldest first. Runs
+    /// [`GraphQuery::tagged_since`] by default.
+    async fn tagged_since(
+        &self,
+        tag: &str,
+        since: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<Experience>> {
+        self.graph_query(&GraphQuery::tagged_since(tag, since))
+            .await
+    }
+
+    /// The experience `id` and its summaries up to `depth` levels. Runs
+    /// [`GraphQuery::summary_chain`] by default.
+    async fn summary_chain(&self, id: &str, depth: usize) -> anyhow::Result<Vec<Experience>> {
+        self.graph_query(&GraphQuery::summary_chain(id, depth))
+            .await
+    }
+
+    /// How often each experience was called to mind: the number of
+    /// `CALLED_TO_MIND` links reaching it, by id. Backends without links
+    /// know of no recalls.
+    async fn recall_counts(&self) -> anyhow::Result<HashMap<String, usize>> {
+        Ok(HashMap::new())
+    }
+
+    /// Leave experience `id` out of searches but keep it and its links, so
+    /// the summaries of it still lead back to it. Backends that cannot
+    /// refuse.
+    async fn archive(&self, _id: &str) -> anyhow::Result<()> {
+        anyhow::bail!("this memory backend cannot archive")
+    }
+
+    /// Delete experience `id` with every link to or from it. Backends that
+    /// cannot refuse.
+    async fn forget(&self, _id: &str) -> anyhow::Result<()> {
+        anyhow::bail!("this memory backend cannot forget")
+    }
+
+    /// Everything stored, for backups and moving memory elsewhere.
+    /// Backends that cannot list their contents refuse.
+    async fn dump(&self) -> anyhow::Result<MemoryDump> {
+        anyhow::bail!("this memory backend cannot be dumped")
+    }
+
+    /// Load `dump` into this backend, which must be empty, keeping its ids
+    /// and links exactly; no `NEXT` edges are derived.
+    async fn restore(&self, _dump: &MemoryDump) -> anyhow::Result<()> {
+        anyhow::bail!("this memory backend cannot be restored")
+    }
 }
 
+/// Template used by [`Memorizer`] to summarize a new experience.
+pub const SUMMARY_PROMPT: &str =
+    "Summarize this as one emotionally descriptive sentence.\n\n{{ what }}";
+
 /// Captures experiences using a summarizer and embedder before persisting them
 /// via a [`MemoryBackend`].
 ///
@@ -148,7 +490,7 @@ pub struct Memorizer<'a, B> {
     pub profile: &'a LlmProfile,
     /// Backend that receives stored experiences.
     pub backend: B,
-    /// Helper for augmenting prompts with the self header.
+    /// Renders the summary prompt and supplies the self header.
     pub prompter: PromptHelper,
 }
 
@@ -172,10 +514,8 @@ where
             let chat = self
                 .chat
                 .ok_or_else(|| anyhow::anyhow!("no chat model configured"))?;
-            let prompt = format!(
-                "Summarize this as one emotionally descriptive sentence.\n\n{}",
-                what
-            );
+            let ctx = PromptContext::new().var("what", what);
+            let prompt = self.prompter.render(SUMMARY_PROMPT, &ctx)?;
             let system = self.prompter.system();
             trace!(target = "llm", system = %system, user = %prompt, "summary prompt");
             let mut stream = chat.chat_stream(self.profile, system, &prompt).await?;
@@ -237,15 +577,28 @@ impl MemoryBackend for InMemoryBackend {
         Ok(id.to_string())
     }
 
-    async fn search(&self, vector: &[f32], top_k: usize) -> anyhow::Result<Vec<Experience>> {
-        debug!(top_k, "in-memory search");
+    async fn search(
+        &self,
+        vector: &[f32],
+        top_k: usize,
+        filter: &SearchFilter,
+    ) -> anyhow::Result<Vec<SearchHit>> {
+        debug!(top_k, ?filter, "in-memory search");
         let data = self.data.lock().unwrap();
-        let mut scored: Vec<(f32, Experience)> = data
+        let mut hits: Vec<SearchHit> = data
             .iter()
-            .map(|(exp, v)| (cosine_similarity(vector, v), exp.clone()))
+            .enumerate()
+            .filter(|(_, (exp, _))| filter.matches(exp))
+            .map(|(id, (exp, v))| SearchHit {
+                id: id.to_string(),
+                score: cosine_similarity(vector, v),
+                experience: exp.clone(),
+            })
+            .filter(|hit| filter.admits(hit.score))
             .collect();
-        scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap());
-        Ok(scored.into_iter().take(top_k).map(|(_, e)| e).collect())
+        hits.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
+        hits.truncate(top_k);
+        Ok(hits)
     }
 
     async fn get(&self, id: &str) -> anyhow::Result<Option<Experience>> {
@@ -262,11 +615,6 @@ impl MemoryBackend for InMemoryBackend {
     async fn link_summary(&self, _summary_id: &str, _original_id: &str) -> anyhow::Result<()> {
         Ok(())
     }
-
-    #[cfg(feature = "neo4j")]
-    async fn cypher_query(&self, _query: &str) -> anyhow::Result<Vec<Experience>> {
-        Ok(Vec::new())
-    }
 }
 
 #[async_trait(?Send)]
@@ -279,9 +627,14 @@ impl MemoryBackend for &InMemoryBackend {
         Ok(id.to_string())
     }
 
-    async fn search(&self, vector: &[f32], top_k: usize) -> anyhow::Result<Vec<Experience>> {
+    async fn search(
+        &self,
+        vector: &[f32],
+        top_k: usize,
+        filter: &SearchFilter,
+    ) -> anyhow::Result<Vec<SearchHit>> {
         debug!(top_k, "in-memory search ref");
-        (**self).search(vector, top_k).await
+        (**self).search(vector, top_k, filter).await
     }
 
     async fn get(&self, id: &str) -> anyhow::Result<Option<Experience>> {
@@ -292,18 +645,16 @@ impl MemoryBackend for &InMemoryBackend {
     async fn link_summary(&self, _summary_id: &str, _original_id: &str) -> anyhow::Result<()> {
         Ok(())
     }
-
-    #[cfg(feature = "neo4j")]
-    async fn cypher_query(&self, query: &str) -> anyhow::Result<Vec<Experience>> {
-        (**self).cypher_query(query).await
-    }
 }
 
 #[cfg(feature = "qdrant")]
 mod qdrant_store {
     use super::*;
-    use qdrant_client::prelude::*;
-    use qdrant_client::qdrant::{CreateCollectionBuilder, Distance, VectorParamsBuilder};
+    use qdrant_client::qdrant::{
+        Condition, CreateCollectionBuilder, Distance, Filter, PointStruct, Range,
+        SearchPointsBuilder, UpsertPointsBuilder, VectorParamsBuilder,
+    };
+    use qdrant_client::Qdrant;
 
     #[async_trait(?Send)]
     pub trait CollectionMaker {
@@ -312,16 +663,17 @@ mod qdrant_store {
     }
 
     #[async_trait(?Send)]
-    impl CollectionMaker for QdrantClient {
+    impl CollectionMaker for Qdrant {
         async fn collection_exists(&self, name: &str) -> anyhow::Result<bool> {
             Ok(self.collection_exists(name).await?)
         }
 
         async fn create_memory_collection(&self, dim: u64) -> anyhow::Result<()> {
-            let req = CreateCollectionBuilder::new("memory")
-                .vectors_config(VectorParamsBuilder::new(dim, Distance::Cosine))
-                .build();
-            self.create_collection(&req).await?;
+            self.create_collection(
+                CreateCollectionBuilder::new("memory")
+                    .vectors_config(VectorParamsBuilder::new(dim, Distance::Cosine)),
+            )
+            .await?;
             Ok(())
         }
     }
@@ -334,102 +686,317 @@ mod qdrant_store {
         Ok(())
     }
 
+    /// Map a row with `how`, `what`, `when` and `tags` columns.
+    #[cfg(feature = "neo4j")]
+    fn row_to_experience(row: &neo4rs::Row) -> anyhow::Result<Experience> {
+        let how: String = row.get("how")?;
+        let what: serde_json::Value = row.get("what")?;
+        let when: String = row.get("when")?;
+        let tags: Vec<String> = row.get("tags")?;
+        let when = DateTime::parse_from_rfc3339(&when)?.with_timezone(&Utc);
+        Ok(Experience {
+            how,
+            what,
+            when,
+            tags,
+        })
+    }
+
+    /// The entity labelled `label`, or of any kind when `label` is empty,
+    /// whose name or an alias equals one of `names` ignoring case.
+    #[cfg(feature = "neo4j")]
+    async fn find_entity(
+        graph: &neo4rs::Graph,
+        names: Vec<String>,
+        label: &str,
+    ) -> anyhow::Result<Option<Entity>> {
+        use neo4rs::query;
+        let names: Vec<String> = names.iter().map(|n| n.to_lowercase()).collect();
+        let mut rows = graph
+            .execute(
+                query(concat!(
+                    "MATCH (n) WHERE (n:Person OR n:Place OR n:Object)",
+                    " AND ($label = '' OR $label IN labels(n))",
+                    " AND any(a IN [n.name] + coalesce(n.aliases, []) WHERE toLower(a) IN $names)",
+                    " RETURN n.id AS id, [l IN labels(n) WHERE l IN ['Person', 'Place', 'Object']][0] AS label,",
+                    " n.name AS name, coalesce(n.aliases, []) AS aliases LIMIT 1"
+                ))
+                .param("label", label)
+                .param("names", names),
+            )
+            .await?;
+        let Some(row) = rows.next().await? else {
+            return Ok(None);
+        };
+        Ok(Some(row_to_entity(&row)?))
+    }
+
+    /// Map a row with `id`, `label`, `name` and `aliases` columns.
+    #[cfg(feature = "neo4j")]
+    fn row_to_entity(row: &neo4rs::Row) -> anyhow::Result<Entity> {
+        let label: String = row.get("label")?;
+        Ok(Entity {
+            id: row.get("id")?,
+            kind: EntityKind::from_label(&label)
+                .ok_or_else(|| anyhow::anyhow!("unknown entity label {}", label))?,
+            name: row.get("name")?,
+            aliases: row.get("aliases")?,
+        })
+    }
+
+    /// Create or update the node of `entity`, keyed by its id.
+    #[cfg(feature = "neo4j")]
+    async fn write_entity(graph: &neo4rs::Graph, entity: &Entity) -> anyhow::Result<()> {
+        use neo4rs::query;
+        // labels cannot be parameters; this one comes from the enum
+        let cypher = format!(
+            "MERGE (n:{} {{id: $id}}) SET n.name = $name, n.aliases = $aliases",
+            entity.kind.label()
+        );
+        graph
+            .run(
+                query(&cypher)
+                    .param("id", entity.id.clone())
+                    .param("name", entity.name.clone())
+                    .param("aliases", entity.aliases.clone()),
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// `what` as it was before being stored as JSON text in a node.
+    #[cfg(feature = "neo4j")]
+    fn stored_what(what: serde_json::Value) -> serde_json::Value {
+        match what {
+            serde_json::Value::String(text) => {
+                serde_json::from_str(&text).unwrap_or(serde_json::Value::String(text))
+            }
+            other => other,
+        }
+    }
+
+    /// Qdrant payload for `exp`: its tags and its time in Unix seconds, the
+    /// fields [`payload_filter`] conditions refer to.
+    fn payload(
+        exp: &Experience,
+    ) -> std::collections::HashMap<String, qdrant_client::qdrant::Value> {
+        std::collections::HashMap::from([
+            ("tags".to_string(), exp.tags.clone().into()),
+            ("when".to_string(), exp.when.timestamp().into()),
+        ])
+    }
+
+    /// Qdrant filter applying the tag and time restrictions of `filter`, so
+    /// they are evaluated by the vector search itself. Points stored before
+    /// `tags` and `when` were kept in the payload lack them and pass, to be
+    /// checked against their experience once fetched.
+    pub fn payload_filter(filter: &SearchFilter) -> Option<Filter> {
+        let or_legacy =
+            |key: &str, cond: Condition| Filter::should([cond, Condition::is_empty(key)]).into();
+        let mut conditions = Vec::new();
+        if !filter.tags.is_empty() {
+            conditions.push(or_legacy(
+                "tags",
+                Condition::matches("tags", filter.tags.clone()),
+            ));
+        }
+        if filter.since.is_some() || filter.until.is_some() {
+            let range = Range {
+                gte: filter.since.map(|t| t.timestamp() as f64),
+                lte: filter.until.map(|t| t.timestamp() as f64),
+                ..Default::default()
+            };
+            conditions.push(or_legacy("when", Condition::range("when", range)));
+        }
+        (!conditions.is_empty()).then(|| Filter::must(conditions))
+    }
+
     /// Qdrant + Neo4j backend implementation.
     pub struct QdrantNeo4j {
-        pub qdrant: QdrantClient,
+        pub qdrant: Qdrant,
         #[cfg(feature = "neo4j")]
         pub graph: neo4rs::Graph,
     }
 
+    #[cfg(feature = "neo4j")]
+    impl QdrantNeo4j {
+        /// Create the `Experience` node for `exp` stored as `id`.
+        async fn create_node(&self, id: &str, exp: &Experience) -> anyhow::Result<()> {
+            use neo4rs::query;
+            self.graph
+                .run(
+                    query(
+                        "MERGE (e:Experience {id: $id}) ON CREATE SET e.how = $how, e.what = $what, e.when = $when, e.tags = $tags",
+                    )
+                    .param("id", id)
+                    .param("how", exp.how.clone())
+                    .param("what", exp.what.to_string())
+                    .param("when", exp.when.to_rfc3339())
+                    .param("tags", exp.tags.clone()),
+                )
+                .await?;
+            Ok(())
+        }
+
+        /// Every point of the `memory` collection with its vector.
+        async fn vectors(&self) -> anyhow::Result<std::collections::HashMap<String, Vec<f32>>> {
+            use qdrant_client::qdrant::{
+                point_id, vector_output, vectors_output, ScrollPointsBuilder,
+            };
+            let mut out = std::collections::HashMap::new();
+            if !self.qdrant.collection_exists("memory").await? {
+                return Ok(out);
+            }
+            let mut offset = None;
+            loop {
+                let mut request = ScrollPointsBuilder::new("memory")
+                    .limit(256)
+                    .with_vectors(true);
+                if let Some(offset) = offset {
+                    request = request.offset(offset);
+                }
+                let page = self.qdrant.scroll(request).await?;
+                for point in page.result {
+                    let id = match point.id.and_then(|id| id.point_id_options) {
+                        Some(point_id::PointIdOptions::Uuid(u)) => u,
+                        Some(point_id::PointIdOptions::Num(n)) => n.to_string(),
+                        None => continue,
+                    };
+                    let Some(vectors_output::VectorsOptions::Vector(vector)) =
+                        point.vectors.and_then(|v| v.vectors_options)
+                    else {
+                        continue;
+                    };
+                    let data = match vector.vector {
+                        Some(vector_output::Vector::Dense(dense)) => dense.data,
+                        _ => vector.data,
+                    };
+                    out.insert(id, data);
+                }
+                offset = page.next_page_offset;
+                if offset.is_none() {
+                    return Ok(out);
+                }
+            }
+        }
+    }
+
     #[async_trait(?Send)]
     impl MemoryBackend for QdrantNeo4j {
         async fn store(&self, exp: &Experience, vector: &[f32]) -> anyhow::Result<String> {
-            ensure_collection(&self.qdrant, vector.len() as u64).await?;
             let id = Uuid::new_v4().to_string();
+            self.store_with_id(&id, exp, vector).await?;
+            Ok(id)
+        }
+
+        async fn store_with_id(
+            &self,
+            id: &str,
+            exp: &Experience,
+            vector: &[f32],
+        ) -> anyhow::Result<()> {
+            #[cfg(feature = "neo4j")]
+            {
+                use neo4rs::query;
+                let mut rows = self
+                    .graph
+                    .execute(query("MATCH (e:Experience {id: $id}) RETURN e.id").param("id", id))
+                    .await?;
+                if rows.next().await?.is_some() {
+                    return Ok(());
+                }
+            }
+            ensure_collection(&self.qdrant, vector.len() as u64).await?;
             let points = vec![PointStruct::new(
-                id.clone(),
+                id.to_string(),
                 vector.to_vec(),
-                std::collections::HashMap::<String, qdrant_client::qdrant::Value>::new(),
+                payload(exp),
             )];
             self.qdrant
-                .upsert_points_blocking("memory", None, points, None)
+                .upsert_points(UpsertPointsBuilder::new("memory", points).wait(true))
                 .await?;
 
             #[cfg(feature = "neo4j")]
             {
                 use neo4rs::query;
+                self.create_node(id, exp).await?;
                 self.graph
                     .run(
-                        query(
-                            "CREATE (:Experience {id: $id, how: $how, what: $what, when: $when, tags: $tags})",
-                        )
-                        .param("id", id.clone())
-                        .param("how", exp.how.clone())
-                        .param("what", exp.what.to_string())
-                        .param("when", exp.when.to_rfc3339())
-                        .param("tags", exp.tags.clone()),
+                        query(concat!(
+                            "MATCH (e:Experience {id: $id}) UNWIND e.tags AS stream",
+                            " MATCH (prev:Experience) WHERE stream IN prev.tags AND prev.id <> e.id AND prev.when <= e.when",
+                            " WITH e, stream, prev ORDER BY prev.when DESC",
+                            " WITH e, stream, collect(prev)[0] AS last",
+                            " MERGE (last)-[:NEXT]->(e)"
+                        ))
+                        .param("id", id),
                     )
                     .await?;
             }
-            Ok(id)
+            Ok(())
         }
 
-        async fn search(&self, vector: &[f32], top_k: usize) -> anyhow::Result<Vec<Experience>> {
-            let request = SearchPoints {
-                collection_name: "memory".into(),
-                vector: vector.to_vec(),
-                filter: None,
-                limit: top_k as u64,
-                with_payload: None,
-                params: None,
-                score_threshold: None,
-                offset: None,
-                vector_name: None,
-                with_vectors: None,
-                read_consistency: None,
-                timeout: None,
-                shard_key_selector: None,
-                sparse_indices: None,
-            };
-            let search_result = self.qdrant.search_points(&request).await?;
+        async fn search(
+            &self,
+            vector: &[f32],
+            top_k: usize,
+            filter: &SearchFilter,
+        ) -> anyhow::Result<Vec<SearchHit>> {
+            let mut request = SearchPointsBuilder::new("memory", vector.to_vec(), top_k as u64)
+                .filter(Filter {
+                    must_not: vec![Condition::matches("archived", true)],
+                    ..payload_filter(filter).unwrap_or_default()
+                });
+            if let Some(min) = filter.min_score {
+                request = request.score_threshold(min);
+            }
+            let search_result = self.qdrant.search_points(request).await?;
             #[cfg(feature = "neo4j")]
             {
                 use neo4rs::query;
                 use qdrant_client::qdrant::point_id;
-                let ids: Vec<String> = search_result
+                let scored: Vec<(String, f32)> = search_result
                     .result
                     .iter()
-                    .filter_map(|pt| pt.id.as_ref())
-                    .filter_map(|p| match p.point_id_options.as_ref()? {
-                        point_id::PointIdOptions::Uuid(u) => Some(u.clone()),
-                        point_id::PointIdOptions::Num(n) => Some(n.to_string()),
+                    .filter_map(|pt| {
+                        let id = match pt.id.as_ref()?.point_id_options.as_ref()? {
+                            point_id::PointIdOptions::Uuid(u) => u.clone(),
+                            point_id::PointIdOptions::Num(n) => n.to_string(),
+                        };
+                        Some((id, pt.score))
                     })
                     .collect();
 
-                if ids.is_empty() {
+                if scored.is_empty() {
                     return Ok(Vec::new());
                 }
 
+                let ids: Vec<String> = scored.iter().map(|(id, _)| id.clone()).collect();
                 let mut rows = self
                     .graph
-                    .execute(query("MATCH (e:Experience) WHERE e.id IN $ids RETURN e.how AS how, e.what AS what, e.when AS when, e.tags AS tags")
+                    .execute(query("MATCH (e:Experience) WHERE e.id IN $ids RETURN e.id AS id, e.how AS how, e.what AS what, e.when AS when, e.tags AS tags")
                         .param("ids", ids))
                     .await?;
-                let mut out = Vec::new();
+                let mut found = std::collections::HashMap::new();
                 while let Ok(Some(row)) = rows.next().await {
-                    let how: String = row.get("how")?;
-                    let what: serde_json::Value = row.get("what")?;
-                    let when: String = row.get("when")?;
-                    let tags: Vec<String> = row.get("tags")?;
-                    let when = DateTime::parse_from_rfc3339(&when)?.with_timezone(&Utc);
-                    out.push(Experience {
-                        how,
-                        what,
-                        when,
-                        tags,
-                    });
+                    let id: String = row.get("id")?;
+                    found.insert(id, row_to_experience(&row)?);
                 }
-                return Ok(out);
+                return Ok(scored
+                    .into_iter()
+                    .filter_map(|(id, score)| {
+                        let experience = found.remove(&id)?;
+                        // legacy points got past the payload filter
+                        if !filter.matches(&experience) {
+                            return None;
+                        }
+                        Some(SearchHit {
+                            id,
+                            score,
+                            experience,
+                        })
+                    })
+                    .collect());
             }
 
             #[allow(unreachable_code)]
@@ -446,39 +1013,27 @@ mod qdrant_store {
                         .param("id", id))
                     .await?;
                 if let Ok(Some(row)) = rows.next().await {
-                    let how: String = row.get("how")?;
-                    let what: serde_json::Value = row.get("what")?;
-                    let when: String = row.get("when")?;
-                    let tags: Vec<String> = row.get("tags")?;
-                    let when = DateTime::parse_from_rfc3339(&when)?.with_timezone(&Utc);
-                    return Ok(Some(Experience {
-                        how,
-                        what,
-                        when,
-                        tags,
-                    }));
+                    return Ok(Some(row_to_experience(&row)?));
                 }
             }
             Ok(None)
         }
 
         #[cfg(feature = "neo4j")]
-        async fn cypher_query(&self, query_str: &str) -> anyhow::Result<Vec<Experience>> {
+        async fn graph_query(&self, graph_query: &GraphQuery) -> anyhow::Result<Vec<Experience>> {
             use neo4rs::query;
-            let mut rows = self.graph.execute(query(query_str)).await?;
+            let mut q = query(&graph_query.cypher);
+            for (name, value) in &graph_query.params {
+                q = match value {
+                    GraphParam::Text(v) => q.param(name, v.clone()),
+                    GraphParam::List(v) => q.param(name, v.clone()),
+                    GraphParam::Int(v) => q.param(name, *v),
+                };
+            }
+            let mut rows = self.graph.execute(q).await?;
             let mut out = Vec::new();
             while let Ok(Some(row)) = rows.next().await {
-                let how: String = row.get("how")?;
-                let what: serde_json::Value = row.get("what")?;
-                let when: String = row.get("when")?;
-                let tags: Vec<String> = row.get("tags")?;
-                let when = DateTime::parse_from_rfc3339(&when)?.with_timezone(&Utc);
-                out.push(Experience {
-                    how,
-                    what,
-                    when,
-                    tags,
-                });
+                out.push(row_to_experience(&row)?);
             }
             Ok(out)
         }
@@ -512,8 +1067,263 @@ mod qdrant_store {
             }
             Ok(())
         }
+
+        async fn link_causes(&self, cause: &str, effect: &str) -> anyhow::Result<()> {
+            #[cfg(feature = "neo4j")]
+            {
+                use neo4rs::query;
+                self.graph
+                    .run(
+                        query("MATCH (c:Experience {id: $cause}), (e:Experience {id: $effect}) MERGE (c)-[:CAUSES]->(e)")
+                            .param("cause", cause)
+                            .param("effect", effect),
+                    )
+                    .await?;
+            }
+            Ok(())
+        }
+
+        async fn link_topic(&self, id: &str, topic: &str) -> anyhow::Result<()> {
+            #[cfg(feature = "neo4j")]
+            {
+                use neo4rs::query;
+                self.graph
+                    .run(
+                        query("MATCH (e:Experience {id: $id}) MERGE (t:Topic {name: $topic}) MERGE (e)-[:REFERS_TO]->(t)")
+                            .param("id", id)
+                            .param("topic", topic),
+                    )
+                    .await?;
+            }
+            Ok(())
+        }
+
+        #[cfg(feature = "neo4j")]
+        async fn upsert_entity(&self, entity: &Entity) -> anyhow::Result<Entity> {
+            let names = entity.names().map(|n| n.trim().to_string()).collect();
+            let mut known: Vec<Entity> = find_entity(&self.graph, names, entity.kind.label())
+                .await?
+                .into_iter()
+                .collect();
+            let (i, changed) = super::entity::merge_into(&mut known, entity);
+            let stored = known.swap_remove(i);
+            if changed {
+                write_entity(&self.graph, &stored).await?;
+            }
+            Ok(stored)
+        }
+
+        async fn link_mention(&self, id: &str, entity_id: &str) -> anyhow::Result<()> {
+            #[cfg(feature = "neo4j")]
+            {
+                use neo4rs::query;
+                self.graph
+                    .run(
+                        query("MATCH (e:Experience {id: $id}), (n {id: $entity}) WHERE n:Person OR n:Place OR n:Object MERGE (e)-[:MENTIONS]->(n)")
+                            .param("id", id)
+                            .param("entity", entity_id),
+                    )
+                    .await?;
+            }
+            Ok(())
+        }
+
+        #[cfg(feature = "neo4j")]
+        async fn entity(&self, name: &str) -> anyhow::Result<Option<Entity>> {
+            find_entity(&self.graph, vec![name.trim().to_string()], "").await
+        }
+
+        #[cfg(feature = "neo4j")]
+        async fn recall_counts(&self) -> anyhow::Result<HashMap<String, usize>> {
+            use neo4rs::query;
+            let mut rows = self
+                .graph
+                .execute(query(
+                    "MATCH (:Experience)-[r:CALLED_TO_MIND]->(e:Experience) RETURN e.id AS id, count(r) AS recalls",
+                ))
+                .await?;
+            let mut counts = HashMap::new();
+            while let Some(row) = rows.next().await? {
+                let recalls: i64 = row.get("recalls")?;
+                counts.insert(row.get("id")?, recalls as usize);
+            }
+            Ok(counts)
+        }
+
+        #[cfg(feature = "neo4j")]
+        async fn archive(&self, id: &str) -> anyhow::Result<()> {
+            use neo4rs::query;
+            use qdrant_client::qdrant::{PointId, SetPayloadPointsBuilder};
+            use qdrant_client::Payload;
+            let mut rows = self
+                .graph
+                .execute(
+                    query("MATCH (e:Experience {id: $id}) SET e.archived = true RETURN e.id AS id")
+                        .param("id", id),
+                )
+                .await?;
+            if rows.next().await?.is_none() {
+                anyhow::bail!("unknown experience {}", id);
+            }
+            let payload = Payload::from([("archived", true.into())]);
+            self.qdrant
+                .set_payload(
+                    SetPayloadPointsBuilder::new("memory", payload)
+                        .points_selector(vec![PointId::from(id.to_string())])
+                        .wait(true),
+                )
+                .await?;
+            Ok(())
+        }
+
+        #[cfg(feature = "neo4j")]
+        async fn forget(&self, id: &str) -> anyhow::Result<()> {
+            use neo4rs::query;
+            use qdrant_client::qdrant::{DeletePointsBuilder, PointId};
+            let mut rows = self
+                .graph
+                .execute(
+                    query("MATCH (e:Experience {id: $id}) DETACH DELETE e RETURN count(*) AS gone")
+                        .param("id", id),
+                )
+                .await?;
+            let gone: i64 = match rows.next().await? {
+                Some(row) => row.get("gone")?,
+                None => 0,
+            };
+            if gone == 0 {
+                anyhow::bail!("unknown experience {}", id);
+            }
+            self.qdrant
+                .delete_points(
+                    DeletePointsBuilder::new("memory")
+                        .points(vec![PointId::from(id.to_string())])
+                        .wait(true),
+                )
+                .await?;
+            Ok(())
+        }
+
+        #[cfg(feature = "neo4j")]
+        async fn dump(&self) -> anyhow::Result<MemoryDump> {
+            use neo4rs::query;
+            let mut vectors = self.vectors().await?;
+            let mut dump = MemoryDump::default();
+            let mut rows = self
+                .graph
+                .execute(query("MATCH (e:Experience) RETURN e.id AS id, e.how AS how, e.what AS what, e.when AS when, e.tags AS tags, coalesce(e.archived, false) AS archived ORDER BY e.when"))
+                .await?;
+            while let Some(row) = rows.next().await? {
+                let id: String = row.get("id")?;
+                let Some(vector) = vectors.remove(&id) else {
+                    tracing::warn!(%id, "experience without a vector left out of dump");
+                    continue;
+                };
+                let mut experience = row_to_experience(&row)?;
+                experience.what = stored_what(experience.what);
+                dump.experiences.push(ExperienceRecord {
+                    id,
+                    experience,
+                    vector,
+                    archived: row.get("archived")?,
+                });
+            }
+            let mut rows = self
+                .graph
+                .execute(
+                    query("MATCH (a:Experience)-[r]->(b) WHERE type(r) IN $kinds RETURN type(r) AS kind, a.id AS from, coalesce(b.id, b.name) AS to")
+                        .param("kinds", EDGE_KINDS.iter().map(|k| k.to_string()).collect::<Vec<_>>()),
+                )
+                .await?;
+            while let Some(row) = rows.next().await? {
+                dump.edges.push(Edge {
+                    kind: row.get("kind")?,
+                    from: row.get("from")?,
+                    to: row.get("to")?,
+                });
+            }
+            let mut rows = self
+                .graph
+                .execute(query(concat!(
+                    "MATCH (n) WHERE n:Person OR n:Place OR n:Object",
+                    " RETURN n.id AS id, [l IN labels(n) WHERE l IN ['Person', 'Place', 'Object']][0] AS label,",
+                    " n.name AS name, coalesce(n.aliases, []) AS aliases"
+                )))
+                .await?;
+            while let Some(row) = rows.next().await? {
+                dump.entities.push(row_to_entity(&row)?);
+            }
+            Ok(dump)
+        }
+
+        #[cfg(feature = "neo4j")]
+        async fn restore(&self, dump: &MemoryDump) -> anyhow::Result<()> {
+            use neo4rs::query;
+            dump.check_dimensions()?;
+            let mut rows = self
+                .graph
+                .execute(query("MATCH (n) WHERE n:Experience OR n:Person OR n:Place OR n:Object RETURN count(n) AS stored"))
+                .await?;
+            let stored: i64 = match rows.next().await? {
+                Some(row) => row.get("stored")?,
+                None => 0,
+            };
+            if stored > 0 || !self.vectors().await?.is_empty() {
+                anyhow::bail!("cannot restore into a memory that is not empty");
+            }
+            if let Some(first) = dump.experiences.first() {
+                ensure_collection(&self.qdrant, first.vector.len() as u64).await?;
+            }
+            for chunk in dump.experiences.chunks(256) {
+                let points: Vec<PointStruct> = chunk
+                    .iter()
+                    .map(|r| {
+                        PointStruct::new(r.id.clone(), r.vector.clone(), payload(&r.experience))
+                    })
+                    .collect();
+                self.qdrant
+                    .upsert_points(UpsertPointsBuilder::new("memory", points).wait(true))
+                    .await?;
+                for record in chunk {
+                    self.create_node(&record.id, &record.experience).await?;
+                    if record.archived {
+                        self.archive(&record.id).await?;
+                    }
+                }
+            }
+            for entity in &dump.entities {
+                write_entity(&self.graph, entity).await?;
+            }
+            for edge in &dump.edges {
+                let (from, to) = (edge.from.as_str(), edge.to.as_str());
+                match edge.kind.as_str() {
+                    "SUMMARIZES" => self.link_summary(from, to).await?,
+                    "CALLED_TO_MIND" => self.link_called_to_mind(from, to).await?,
+                    "CAUSES" => self.link_causes(from, to).await?,
+                    "REFERS_TO" => self.link_topic(from, to).await?,
+                    MENTIONS => self.link_mention(from, to).await?,
+                    "NEXT" => {
+                        self.graph
+                            .run(
+                                query("MATCH (a:Experience {id: $from}), (b:Experience {id: $to}) MERGE (a)-[:NEXT]->(b)")
+                                    .param("from", from)
+                                    .param("to", to),
+                            )
+                            .await?
+                    }
+                    other => anyhow::bail!("unknown edge kind {}", other),
+                }
+            }
+            info!(
+                experiences = dump.experiences.len(),
+                edges = dump.edges.len(),
+                entities = dump.entities.len(),
+                "restored qdrant and neo4j memory"
+            );
+            Ok(())
+        }
     }
 }
 
 #[cfg(feature = "qdrant")]
-pub use qdrant_store::{ensure_collection, CollectionMaker, QdrantNeo4j};
+pub use qdrant_store::{ensure_collection, payload_filter, CollectionMaker, QdrantNeo4j};
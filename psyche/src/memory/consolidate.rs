@@ -0,0 +1,367 @@
+//! Consolidation: deciding what to keep, merge, summarize and forget.
+//!
+//! Each entry gets an importance in `[0, 1]` from three signals, weighted by
+//! [`Weights`]:
+//!
+//! - recall: how often it was called to mind, saturating quickly;
+//! - salience: an explicit `salience` in `what`, or a fixed score when the
+//!   entry speaks of a feeling;
+//! - recency: halving every [`Retention::half_life_hours`].
+//!
+//! [`plan`] only touches entries older than [`Retention::min_age_hours`].
+//! Among those, near-duplicates of the same kind collapse into their most
+//! important member. Runs of consecutive unimportant entries of one kind
+//! become a single summary whose `what` lists the originals, the shape
+//! [`crate::wit::link_sources`] uses for `SUMMARIZES`. The caller stores the
+//! summaries and archives or prunes the originals per [`RetentionAction`].
+
+use crate::models::MemoryEntry;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+/// Words that mark an entry as emotionally salient.
+const FEELING_WORDS: &[&str] = &[
+    "afraid",
+    "angry",
+    "anxious",
+    "ashamed",
+    "delighted",
+    "excited",
+    "feel",
+    "feels",
+    "felt",
+    "frightened",
+    "glad",
+    "grateful",
+    "happy",
+    "hate",
+    "hurt",
+    "joy",
+    "lonely",
+    "love",
+    "miss",
+    "proud",
+    "sad",
+    "scared",
+    "surprised",
+    "upset",
+    "worried",
+];
+
+/// Salience of an entry speaking of a feeling without an explicit score.
+const FEELING_SALIENCE: f32 = 0.7;
+
+/// How much each signal contributes to importance.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Weights {
+    /// Weight of recall frequency.
+    pub recall: f32,
+    /// Weight of emotional salience.
+    pub salience: f32,
+    /// Weight of recency.
+    pub recency: f32,
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Self {
+            recall: 0.5,
+            salience: 0.3,
+            recency: 0.2,
+        }
+    }
+}
+
+/// What happens to originals once they are merged or summarized.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RetentionAction {
+    /// Move them to an archive kept beside the live memory.
+    #[default]
+    Archive,
+    /// Delete them.
+    Prune,
+}
+
+/// Retention policy, the `[retention]` table of `policy.toml`.
+///
+/// ```
+/// use psyche::memory::{Retention, RetentionAction};
+/// let retention: Retention = toml::from_str("action = \"prune\"\nrun_length = 5").unwrap();
+/// assert_eq!(retention.action, RetentionAction::Prune);
+/// assert_eq!(retention.run_length, 5);
+/// assert_eq!(retention.kinds, ["instant", "situation"]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Retention {
+    /// Entry kinds that may be consolidated.
+    pub kinds: Vec<String>,
+    /// Entries younger than this are left alone.
+    pub min_age_hours: f64,
+    /// Entries at least this important are never summarized.
+    pub keep_importance: f32,
+    /// Word overlap from which two entries count as duplicates.
+    pub duplicate_similarity: f32,
+    /// Fewest consecutive unimportant entries worth a summary.
+    pub run_length: usize,
+    /// Most entries folded into one summary.
+    pub max_run: usize,
+    /// Age at which recency has halved.
+    pub half_life_hours: f64,
+    /// Fate of merged and summarized originals.
+    pub action: RetentionAction,
+    /// Consolidate this often. Only on request when absent.
+    pub interval_secs: Option<u64>,
+    /// Importance weights.
+    pub weights: Weights,
+}
+
+impl Default for Retention {
+    fn default() -> Self {
+        Self {
+            kinds: vec!["instant".into(), "situation".into()],
+            min_age_hours: 24.0,
+            keep_importance: 0.35,
+            duplicate_similarity: 0.85,
+            run_length: 3,
+            max_run: 20,
+            half_life_hours: 72.0,
+            action: RetentionAction::Archive,
+            interval_secs: None,
+            weights: Weights::default(),
+        }
+    }
+}
+
+/// Emotional salience of `entry` in `[0, 1]`.
+pub fn salience(entry: &MemoryEntry) -> f32 {
+    if let Some(s) = entry.what.get("salience").and_then(|v| v.as_f64()) {
+        return (s as f32).clamp(0.0, 1.0);
+    }
+    let emotional = entry
+        .how
+        .split(|c: char| !c.is_alphanumeric())
+        .any(|w| FEELING_WORDS.contains(&w.to_lowercase().as_str()));
+    if emotional {
+        FEELING_SALIENCE
+    } else {
+        0.0
+    }
+}
+
+/// Importance of `entry` in `[0, 1]` given how often it was `recalled`.
+pub fn importance(
+    entry: &MemoryEntry,
+    recalled: usize,
+    now: DateTime<Utc>,
+    retention: &Retention,
+) -> f32 {
+    let w = &retention.weights;
+    let total = w.recall + w.salience + w.recency;
+    if total <= 0.0 {
+        return 0.0;
+    }
+    let recall = 1.0 - 1.0 / (1.0 + recalled as f32);
+    let age_hours = (now - entry.when).num_seconds().max(0) as f64 / 3600.0;
+    let recency = 0.5f64.powf(age_hours / retention.half_life_hours.max(f64::EPSILON)) as f32;
+    (w.recall * recall + w.salience * salience(entry) + w.recency * recency) / total
+}
+
+/// Word overlap of two texts, from 0 (disjoint) to 1 (same words).
+///
+/// ```
+/// use psyche::memory::similarity;
+/// assert_eq!(similarity("The door creaked.", "the door CREAKED"), 1.0);
+/// assert_eq!(similarity("rain", "sun"), 0.0);
+/// ```
+pub fn similarity(a: &str, b: &str) -> f32 {
+    let words = |s: &str| -> HashSet<String> {
+        s.split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .map(str::to_lowercase)
+            .collect()
+    };
+    let (a, b) = (words(a), words(b));
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    a.intersection(&b).count() as f32 / a.union(&b).count() as f32
+}
+
+/// Near-duplicates collapsed into one entry.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Merge {
+    /// The most important member, kept.
+    pub keep: Uuid,
+    /// The others, archived or pruned.
+    pub drop: Vec<Uuid>,
+}
+
+/// Outcome of [`plan`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Plan {
+    /// Importance of every entry considered.
+    pub importance: HashMap<Uuid, f32>,
+    /// Near-duplicate groups.
+    pub merges: Vec<Merge>,
+    /// Summaries to store, each listing its originals in `what`.
+    pub summaries: Vec<MemoryEntry>,
+}
+
+impl Plan {
+    /// Ids of every entry leaving live memory.
+    pub fn removed(&self) -> HashSet<Uuid> {
+        let dropped = self.merges.iter().flat_map(|m| m.drop.iter().copied());
+        let summarized = self
+            .summaries
+            .iter()
+            .filter_map(|s| s.what.as_array())
+            .flatten()
+            .filter_map(|v| v.as_str().and_then(|s| s.parse().ok()));
+        dropped.chain(summarized).collect()
+    }
+}
+
+/// Summary standing in for `run`, which holds consecutive entries of one
+/// kind, oldest first. It keeps the most important sentence and the span.
+pub fn summarize_run(run: &[MemoryEntry], importance: &HashMap<Uuid, f32>) -> MemoryEntry {
+    let score = |e: &MemoryEntry| importance.get(&e.id).copied().unwrap_or(0.0);
+    let gist = run
+        .iter()
+        .max_by(|a, b| score(a).total_cmp(&score(b)))
+        .map(|e| e.how.trim_end_matches('.'))
+        .unwrap_or_default();
+    let (first, last) = (&run[0], &run[run.len() - 1]);
+    MemoryEntry {
+        id: Uuid::new_v4(),
+        kind: first.kind.clone(),
+        when: last.when,
+        what: serde_json::json!(run.iter().map(|e| e.id).collect::<Vec<_>>()),
+        how: format!(
+            "{}, among {} moments between {} and {}.",
+            gist,
+            run.len(),
+            first.when.format("%Y-%m-%d %H:%M"),
+            last.when.format("%Y-%m-%d %H:%M")
+        ),
+    }
+}
+
+/// Decide how to consolidate `entries`. `recalls` counts how often each
+/// entry was called to mind.
+pub fn plan(
+    entries: &[MemoryEntry],
+    recalls: &HashMap<Uuid, usize>,
+    retention: &Retention,
+    now: DateTime<Utc>,
+) -> Plan {
+    let cutoff = now - Duration::seconds((retention.min_age_hours * 3600.0) as i64);
+    let mut old: Vec<&MemoryEntry> = entries
+        .iter()
+        .filter(|e| e.when <= cutoff)
+        .filter(|e| {
+            let base = e.kind.split('/').next().unwrap_or(&e.kind);
+            retention.kinds.iter().any(|k| k == base || *k == e.kind)
+        })
+        .collect();
+    old.sort_by_key(|e| e.when);
+    let mut plan = Plan::default();
+    for e in &old {
+        let recalled = recalls.get(&e.id).copied().unwrap_or(0);
+        plan.importance
+            .insert(e.id, importance(e, recalled, now, retention));
+    }
+    let score = |e: &MemoryEntry| plan.importance[&e.id];
+
+    let mut dropped = HashSet::new();
+    for (i, e) in old.iter().enumerate() {
+        if dropped.contains(&e.id) {
+            continue;
+        }
+        let group: Vec<&MemoryEntry> = std::iter::once(*e)
+            .chain(old[i + 1..].iter().copied().filter(|o| {
+                o.kind == e.kind
+                    && !dropped.contains(&o.id)
+                    && similarity(&e.how, &o.how) >= retention.duplicate_similarity
+            }))
+            .collect();
+        if group.len() < 2 {
+            continue;
+        }
+        let keep = group
+            .iter()
+            .max_by(|a, b| score(a).total_cmp(&score(b)))
+            .map(|k| k.id)
+            .unwrap_or(e.id);
+        let drop: Vec<Uuid> = group
+            .iter()
+            .map(|g| g.id)
+            .filter(|id| *id != keep)
+            .collect();
+        dropped.extend(drop.iter().copied());
+        plan.merges.push(Merge { keep, drop });
+    }
+
+    let mut kinds: Vec<&str> = old.iter().map(|e| e.kind.as_str()).collect();
+    kinds.sort();
+    kinds.dedup();
+    for kind in kinds {
+        let mut run: Vec<MemoryEntry> = Vec::new();
+        let live = old
+            .iter()
+            .filter(|e| e.kind == kind && !dropped.contains(&e.id));
+        for e in live {
+            if score(e) < retention.keep_importance {
+                run.push((*e).clone());
+                if run.len() < retention.max_run.max(1) {
+                    continue;
+                }
+            }
+            if run.len() >= retention.run_length.max(2) {
+                plan.summaries.push(summarize_run(&run, &plan.importance));
+            }
+            run.clear();
+        }
+        if run.len() >= retention.run_length.max(2) {
+            plan.summaries.push(summarize_run(&run, &plan.importance));
+        }
+    }
+    plan
+}
+
+/// What a consolidation pass did.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Consolidation {
+    /// Entries old enough to be considered.
+    pub considered: usize,
+    /// Duplicates merged into another entry.
+    pub merged: usize,
+    /// Originals folded into summaries.
+    pub summarized: usize,
+    /// Ids of the new summaries.
+    pub summaries: Vec<Uuid>,
+    /// Whether originals were archived rather than pruned.
+    pub archived: bool,
+}
+
+impl Consolidation {
+    /// Report for applying `plan` under `retention`.
+    pub fn of(plan: &Plan, retention: &Retention) -> Self {
+        Self {
+            considered: plan.importance.len(),
+            merged: plan.merges.iter().map(|m| m.drop.len()).sum(),
+            summarized: plan
+                .summaries
+                .iter()
+                .filter_map(|s| s.what.as_array())
+                .map(Vec::len)
+                .sum(),
+            summaries: plan.summaries.iter().map(|s| s.id).collect(),
+            archived: retention.action == RetentionAction::Archive,
+        }
+    }
+}
@@ -0,0 +1,60 @@
+//! Whole-memory dumps for backup and moving between backends.
+//!
+//! A [`MemoryDump`] holds every experience with its id, its embedding and
+//! whether it is archived, every link and every entity. Restoring it keeps
+//! the ids, so links, entity mentions and entries referring to experiences
+//! by id stay valid, and the restored backend answers searches and graph
+//! queries as the original did.
+
+use super::{Edge, Entity, Experience};
+use serde::{Deserialize, Serialize};
+
+/// A stored experience as a backend knows it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExperienceRecord {
+    /// Backend id.
+    pub id: String,
+    /// The experience.
+    #[serde(with = "crate::models::versioned")]
+    pub experience: Experience,
+    /// Embedding used for vector search.
+    pub vector: Vec<f32>,
+    /// Whether consolidation archived it, leaving it out of searches.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub archived: bool,
+}
+
+/// Everything a memory backend holds, from [`super::MemoryBackend::dump`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MemoryDump {
+    /// Experiences in the order they were stored.
+    pub experiences: Vec<ExperienceRecord>,
+    /// Links between experiences, topics and entities.
+    pub edges: Vec<Edge>,
+    /// People, places and objects.
+    pub entities: Vec<Entity>,
+}
+
+impl MemoryDump {
+    /// Whether the dump holds nothing at all.
+    pub fn is_empty(&self) -> bool {
+        self.experiences.is_empty() && self.edges.is_empty() && self.entities.is_empty()
+    }
+
+    /// Fail unless every embedding has the same number of dimensions.
+    pub fn check_dimensions(&self) -> anyhow::Result<()> {
+        let Some(first) = self.experiences.first() else {
+            return Ok(());
+        };
+        let dim = first.vector.len();
+        if let Some(odd) = self.experiences.iter().find(|r| r.vector.len() != dim) {
+            anyhow::bail!(
+                "experience {} has {} dimensions, expected {}",
+                odd.id,
+                odd.vector.len(),
+                dim
+            );
+        }
+        Ok(())
+    }
+}
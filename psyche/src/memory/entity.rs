@@ -0,0 +1,209 @@
+//! People, places and objects remembered across experiences.
+//!
+//! An [`Entity`] is a graph node that experiences point at with `MENTIONS`.
+//! It answers to its name and to any of its aliases, compared without case,
+//! so "Alice", "alice" and "Ally" can all resolve to the same person once
+//! the aliases have been learned. Upserting an entity that shares a name
+//! with a known one of the same kind merges the two instead of creating a
+//! duplicate.
+//!
+//! The extraction wit ([`crate::wit::entity_extractor`]) produces entries
+//! whose `what` lists the entities it found and the entries mentioning
+//! them. [`record_entities`] turns such an entry into graph nodes and links.
+
+use super::{Experience, MemoryBackend};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::{debug, warn};
+
+/// Relation from an experience to an entity it mentions.
+pub const MENTIONS: &str = "MENTIONS";
+
+/// What an [`Entity`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntityKind {
+    /// Someone, stored with the `Person` label.
+    Person,
+    /// Somewhere, stored with the `Place` label.
+    Place,
+    /// Something else worth remembering, stored with the `Object` label.
+    Object,
+}
+
+impl EntityKind {
+    /// Graph label of entities of this kind.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Person => "Person",
+            Self::Place => "Place",
+            Self::Object => "Object",
+        }
+    }
+
+    /// Kind with the given graph label.
+    pub fn from_label(label: &str) -> Option<Self> {
+        [Self::Person, Self::Place, Self::Object]
+            .into_iter()
+            .find(|k| k.label() == label)
+    }
+}
+
+/// A named person, place or object.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Entity {
+    /// Backend id, empty until the entity has been stored.
+    #[serde(default)]
+    pub id: String,
+    /// What the entity is.
+    pub kind: EntityKind,
+    /// Canonical name.
+    pub name: String,
+    /// Other names it is known by.
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
+
+impl Entity {
+    /// Unstored entity without aliases.
+    pub fn new(kind: EntityKind, name: impl Into<String>) -> Self {
+        Self {
+            id: String::new(),
+            kind,
+            name: name.into(),
+            aliases: Vec::new(),
+        }
+    }
+
+    /// The name followed by the aliases.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.name.as_str()).chain(self.aliases.iter().map(String::as_str))
+    }
+
+    /// Whether `name` is the entity's name or one of its aliases.
+    ///
+    /// ```
+    /// use psyche::memory::{Entity, EntityKind};
+    /// let mut alice = Entity::new(EntityKind::Person, "Alice");
+    /// alice.aliases.push("Ally".into());
+    /// assert!(alice.answers_to(" ally"));
+    /// assert!(!alice.answers_to("Bob"));
+    /// ```
+    pub fn answers_to(&self, name: &str) -> bool {
+        let name = name.trim();
+        self.names().any(|n| n.eq_ignore_ascii_case(name))
+    }
+
+    /// Whether `other` is of the same kind and shares a name with this one.
+    pub fn same_as(&self, other: &Entity) -> bool {
+        self.kind == other.kind && other.names().any(|n| self.answers_to(n))
+    }
+
+    /// Learn the names of `other` as aliases. Returns whether any was new.
+    pub fn absorb(&mut self, other: &Entity) -> bool {
+        let mut changed = false;
+        for name in other.names() {
+            let name = name.trim();
+            if !name.is_empty() && !self.answers_to(name) {
+                self.aliases.push(name.to_string());
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+/// Merge `entity` into the matching entry of `known`, or add it with a new
+/// id. Returns the index of the stored entity and whether it changed.
+pub(crate) fn merge_into(known: &mut Vec<Entity>, entity: &Entity) -> (usize, bool) {
+    if let Some(i) = known.iter().position(|k| k.same_as(entity)) {
+        let changed = known[i].absorb(entity);
+        return (i, changed);
+    }
+    let mut new = Entity::new(entity.kind, entity.name.trim());
+    new.id = if entity.id.is_empty() {
+        uuid::Uuid::new_v4().to_string()
+    } else {
+        entity.id.clone()
+    };
+    new.absorb(entity);
+    known.push(new);
+    (known.len() - 1, true)
+}
+
+/// Entities and mentioning entry ids listed in the `what` of an entity
+/// entry: `{"entities": [...], "mentions": ["<id>", ...]}`. Malformed
+/// entities are skipped.
+pub fn mentioned_entities(what: &Value) -> (Vec<Entity>, Vec<String>) {
+    let entities = what
+        .get("entities")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|v| match serde_json::from_value::<Entity>(v.clone()) {
+            Ok(e) if !e.name.trim().is_empty() => Some(e),
+            Ok(_) => None,
+            Err(e) => {
+                warn!(error = %e, entity = %v, "skipping malformed entity");
+                None
+            }
+        })
+        .collect();
+    let mentions = what
+        .get("mentions")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|v| v.as_str().map(str::to_string))
+        .collect();
+    (entities, mentions)
+}
+
+/// Upsert the entities of an entity entry and link every mentioning
+/// experience to them, returning the stored entities.
+pub async fn record_entities<B: MemoryBackend + ?Sized>(
+    backend: &B,
+    what: &Value,
+) -> anyhow::Result<Vec<Entity>> {
+    let (entities, mentions) = mentioned_entities(what);
+    let mut stored = Vec::with_capacity(entities.len());
+    for entity in &entities {
+        let entity = backend.upsert_entity(entity).await?;
+        for id in &mentions {
+            backend.link_mention(id, &entity.id).await?;
+        }
+        stored.push(entity);
+    }
+    debug!(
+        entities = stored.len(),
+        mentions = mentions.len(),
+        "recorded entities"
+    );
+    Ok(stored)
+}
+
+/// An entity and the experiences mentioning it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EntityMemories {
+    /// The entity, with every alias known so far.
+    pub entity: Entity,
+    /// Experiences mentioning it, oldest first.
+    pub experiences: Vec<Experience>,
+}
+
+/// Everything remembered about the entity answering to `name`, or `None`
+/// when no entity does.
+pub async fn about<B: MemoryBackend + ?Sized>(
+    backend: &B,
+    name: &str,
+) -> anyhow::Result<Option<EntityMemories>> {
+    debug!(name, "about query");
+    let Some(entity) = backend.entity(name).await? else {
+        return Ok(None);
+    };
+    let experiences = backend.mentioning(&entity.id).await?;
+    Ok(Some(EntityMemories {
+        entity,
+        experiences,
+    }))
+}
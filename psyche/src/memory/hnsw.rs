@@ -0,0 +1,829 @@
+//! Embedded vector memory persisted to plain files.
+//!
+//! [`HnswBackend`] keeps experiences in a hierarchical navigable small world
+//! graph so searches visit a small fraction of the stored vectors. Everything
+//! lives in one directory:
+//!
+//! - `experiences.jsonl`: one record per stored experience, appended on store;
+//! - `edges.jsonl`: typed links such as `SUMMARIZES`, `CALLED_TO_MIND` and
+//!   the `NEXT` chain each stored experience extends;
+//! - `entities.jsonl`: people, places and objects, appended again whenever
+//!   one learns a new alias, so the last record of an id wins;
+//! - `retired.jsonl`: experiences archived or forgotten by consolidation.
+//!   Their records stay in the graph to route searches, but searches skip
+//!   them, and forgotten ones lose their links and cannot be read back;
+//! - `hnsw.json`: a snapshot of the graph, rewritten every
+//!   [`SNAPSHOT_EVERY`] stores.
+//!
+//! [`context_subgraph`](super::context_subgraph) follows the logged edges
+//! the way the graph backends do.
+//!
+//! On open the snapshot is loaded and any records stored after it are
+//! inserted again, so a crash loses no experiences. A missing or stale
+//! snapshot only costs a rebuild.
+
+use super::entity::merge_into;
+use super::{
+    Entity, Experience, ExperienceRecord, MemoryBackend, MemoryDump, SearchFilter, SearchHit,
+    MENTIONS,
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+/// Stores between two snapshots of the graph.
+pub const SNAPSHOT_EVERY: usize = 256;
+
+const EXPERIENCES: &str = "experiences.jsonl";
+const EDGES: &str = "edges.jsonl";
+const ENTITIES: &str = "entities.jsonl";
+const RETIRED: &str = "retired.jsonl";
+const SNAPSHOT: &str = "hnsw.json";
+
+/// Links kept per node above the bottom layer.
+const M: usize = 16;
+/// Links kept per node on the bottom layer.
+const M0: usize = 2 * M;
+/// Candidates considered while inserting.
+const EF_CONSTRUCTION: usize = 100;
+/// Minimum candidates considered while searching.
+const EF_SEARCH: usize = 64;
+
+/// Relation from a stored experience to another one, a topic or an entity.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Edge {
+    /// Relation name such as `SUMMARIZES`.
+    pub kind: String,
+    /// Id of the source experience.
+    pub from: String,
+    /// Id of the target experience or entity, or the topic name.
+    pub to: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Record {
+    id: String,
+    #[serde(with = "crate::models::versioned")]
+    experience: Experience,
+    vector: Vec<f32>,
+}
+
+/// Experience archived, or forgotten altogether.
+#[derive(Serialize, Deserialize)]
+struct Retired {
+    id: String,
+    #[serde(default)]
+    forgotten: bool,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Node {
+    /// Neighbors per layer, from the bottom layer up.
+    links: Vec<Vec<usize>>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct Graph {
+    nodes: Vec<Node>,
+    entry: Option<usize>,
+}
+
+/// Distance and node index ordered by distance.
+#[derive(Clone, Copy, PartialEq)]
+struct Scored(f32, usize);
+
+impl Eq for Scored {}
+
+impl PartialOrd for Scored {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Scored {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0).then(self.1.cmp(&other.1))
+    }
+}
+
+fn normalized(v: &[f32]) -> Vec<f32> {
+    let norm = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        v.iter().map(|x| x / norm).collect()
+    } else {
+        v.to_vec()
+    }
+}
+
+/// Layer of a new node, drawn from the exponential distribution HNSW needs.
+/// It is derived from the id so a rebuild yields the same graph, using only
+/// the low 62 bits of the UUID: the two above them are the fixed variant.
+/// Ids that are not UUIDs are hashed instead.
+fn level_for(id: &str) -> usize {
+    let bits = match Uuid::parse_str(id) {
+        Ok(uuid) => uuid.as_u128() as u64,
+        Err(_) => hash_id(id),
+    } & ((1 << 62) - 1);
+    let u = ((bits >> 9) as f64 + 0.5) / (1u64 << 53) as f64;
+    (-u.ln() / (M as f64).ln()).floor() as usize
+}
+
+/// FNV-1a hash of `id` with a final avalanche, stable across builds.
+fn hash_id(id: &str) -> u64 {
+    let mut h = id.bytes().fold(0xcbf29ce484222325u64, |h, b| {
+        (h ^ b as u64).wrapping_mul(0x100000001b3)
+    });
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xff51afd7ed558ccd);
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xc4ceb9fe1a85ec53);
+    h ^ (h >> 33)
+}
+
+struct Index {
+    records: Vec<Record>,
+    /// Unit-length copies of the stored vectors.
+    unit: Vec<Vec<f32>>,
+    ids: HashMap<String, usize>,
+    graph: Graph,
+    edges: Vec<Edge>,
+    entities: Vec<Entity>,
+    /// Ids left out of searches.
+    archived: HashSet<String>,
+    /// Ids of deleted experiences.
+    forgotten: HashSet<String>,
+    since_snapshot: usize,
+}
+
+impl Index {
+    fn distance(&self, query: &[f32], node: usize) -> f32 {
+        1.0 - query
+            .iter()
+            .zip(&self.unit[node])
+            .map(|(a, b)| a * b)
+            .sum::<f32>()
+    }
+
+    /// Best `ef` nodes of `layer` reachable from `entry`, closest first.
+    fn search_layer(&self, query: &[f32], entry: usize, ef: usize, layer: usize) -> Vec<Scored> {
+        let first = Scored(self.distance(query, entry), entry);
+        let mut visited = HashSet::from([entry]);
+        let mut candidates = BinaryHeap::from([Reverse(first)]);
+        let mut found = BinaryHeap::from([first]);
+        while let Some(Reverse(current)) = candidates.pop() {
+            if found.len() >= ef && current.0 > found.peek().map_or(f32::MAX, |s| s.0) {
+                break;
+            }
+            for &next in &self.graph.nodes[current.1].links[layer] {
+                if !visited.insert(next) {
+                    continue;
+                }
+                let scored = Scored(self.distance(query, next), next);
+                if found.len() < ef || scored.0 < found.peek().map_or(f32::MAX, |s| s.0) {
+                    candidates.push(Reverse(scored));
+                    found.push(scored);
+                    if found.len() > ef {
+                        found.pop();
+                    }
+                }
+            }
+        }
+        found.into_sorted_vec()
+    }
+
+    /// Descend greedily from the top layer to `layer`.
+    fn descend(&self, query: &[f32], mut entry: usize, from: usize, layer: usize) -> usize {
+        for l in (layer + 1..=from).rev() {
+            entry = self.search_layer(query, entry, 1, l)[0].1;
+        }
+        entry
+    }
+
+    /// Add node `idx`, whose record and unit vector are already present.
+    fn insert(&mut self, idx: usize) {
+        let level = level_for(&self.records[idx].id);
+        self.graph.nodes.push(Node {
+            links: vec![Vec::new(); level + 1],
+        });
+        let Some(entry) = self.graph.entry else {
+            self.graph.entry = Some(idx);
+            return;
+        };
+        let query = self.unit[idx].clone();
+        let top = self.graph.nodes[entry].links.len() - 1;
+        let mut nearest = self.descend(&query, entry, top, level);
+        for layer in (0..=level.min(top)).rev() {
+            let found = self.search_layer(&query, nearest, EF_CONSTRUCTION, layer);
+            nearest = found[0].1;
+            let max = if layer == 0 { M0 } else { M };
+            let chosen: Vec<usize> = found.iter().take(max).map(|s| s.1).collect();
+            for &other in &chosen {
+                self.graph.nodes[other].links[layer].push(idx);
+                self.prune(other, layer, max);
+            }
+            self.graph.nodes[idx].links[layer] = chosen;
+        }
+        if level > top {
+            self.graph.entry = Some(idx);
+        }
+    }
+
+    /// Keep only the `max` closest neighbors of `node` on `layer`.
+    fn prune(&mut self, node: usize, layer: usize, max: usize) {
+        if self.graph.nodes[node].links[layer].len() <= max {
+            return;
+        }
+        let base = self.unit[node].clone();
+        let mut links: Vec<Scored> = self.graph.nodes[node].links[layer]
+            .iter()
+            .map(|&n| Scored(self.distance(&base, n), n))
+            .collect();
+        links.sort();
+        links.truncate(max);
+        self.graph.nodes[node].links[layer] = links.into_iter().map(|s| s.1).collect();
+    }
+
+    /// Whether `id` is a stored experience that was not forgotten.
+    fn knows(&self, id: &str) -> bool {
+        self.ids.contains_key(id) && !self.forgotten.contains(id)
+    }
+
+    /// Append `edge` to the log in `dir` unless it is already known.
+    fn add_edge(&mut self, dir: &Path, edge: Edge) -> anyhow::Result<()> {
+        if self.edges.contains(&edge) {
+            return Ok(());
+        }
+        append_jsonl(&dir.join(EDGES), &edge)?;
+        self.edges.push(edge);
+        Ok(())
+    }
+
+    /// Ids linked to `id`, each with the rank [`GraphQuery::neighbors`]
+    /// gives it: chronological neighbors, then causes and effects, then up
+    /// to two experiences sharing a topic.
+    ///
+    /// [`GraphQuery::neighbors`]: super::GraphQuery::neighbors
+    fn linked<'a>(&'a self, id: &'a str) -> Vec<(usize, &'a str)> {
+        let edges = |kind: &'a str| self.edges.iter().filter(move |e| e.kind == kind);
+        let mut linked = vec![(0, id)];
+        linked.extend(
+            edges("NEXT")
+                .filter(|e| e.to == id)
+                .map(|e| (1, e.from.as_str())),
+        );
+        linked.extend(
+            edges("NEXT")
+                .filter(|e| e.from == id)
+                .map(|e| (2, e.to.as_str())),
+        );
+        linked.extend(
+            edges("CAUSES")
+                .filter(|e| e.from == id)
+                .map(|e| (3, e.to.as_str())),
+        );
+        linked.extend(
+            edges("CAUSES")
+                .filter(|e| e.to == id)
+                .map(|e| (4, e.from.as_str())),
+        );
+        let topical = edges("REFERS_TO")
+            .filter(|topic| topic.from == id)
+            .flat_map(|topic| {
+                edges("REFERS_TO")
+                    .filter(move |related| related.to == topic.to && related.from != id)
+            })
+            .take(2)
+            .map(|e| (5, e.from.as_str()));
+        linked.extend(topical);
+        linked
+    }
+
+    /// Latest stored experience tagged `tag` that is not newer than `when`.
+    fn stream_head(&self, tag: &str, when: DateTime<Utc>) -> Option<&Record> {
+        self.records
+            .iter()
+            .rev()
+            .filter(|r| !self.forgotten.contains(&r.id))
+            .find(|r| r.experience.when <= when && r.experience.tags.iter().any(|t| t == tag))
+    }
+
+    /// Write the graph to `dir` atomically.
+    fn save(&mut self, dir: &Path) -> anyhow::Result<()> {
+        let tmp = dir.join(format!("{SNAPSHOT}.tmp"));
+        std::fs::write(&tmp, serde_json::to_vec(&self.graph)?)?;
+        std::fs::rename(&tmp, dir.join(SNAPSHOT))?;
+        self.since_snapshot = 0;
+        debug!(nodes = self.graph.nodes.len(), "hnsw snapshot written");
+        Ok(())
+    }
+
+    /// Up to `top_k` hits passing `filter`, closest first. A selective
+    /// filter widens the search until enough hits pass or the whole graph
+    /// has been visited.
+    fn search(&self, vector: &[f32], top_k: usize, filter: &SearchFilter) -> Vec<SearchHit> {
+        let Some(entry) = self.graph.entry else {
+            return Vec::new();
+        };
+        let query = normalized(vector);
+        let top = self.graph.nodes[entry].links.len() - 1;
+        let nearest = self.descend(&query, entry, top, 0);
+        let mut ef = EF_SEARCH.max(top_k);
+        loop {
+            let found = self.search_layer(&query, nearest, ef, 0);
+            let exhausted = found.len() < ef || ef >= self.records.len();
+            let hits: Vec<SearchHit> = found
+                .into_iter()
+                .map(|s| (1.0 - s.0, &self.records[s.1]))
+                .filter(|(_, r)| !self.archived.contains(&r.id) && !self.forgotten.contains(&r.id))
+                .filter(|(score, r)| filter.admits(*score) && filter.matches(&r.experience))
+                .take(top_k)
+                .map(|(score, r)| SearchHit {
+                    id: r.id.clone(),
+                    score,
+                    experience: r.experience.clone(),
+                })
+                .collect();
+            if hits.len() >= top_k || exhausted {
+                return hits;
+            }
+            ef *= 4;
+        }
+    }
+}
+
+/// [`MemoryBackend`] with an embedded approximate nearest neighbor index.
+///
+/// ```
+/// use psyche::memory::{Experience, HnswBackend, MemoryBackend};
+/// # tokio_test::block_on(async {
+/// let dir = tempfile::tempdir().unwrap();
+/// let backend = HnswBackend::open(dir.path()).unwrap();
+/// let exp = Experience {
+///     how: "A bird sang.".into(),
+///     what: "tweet".into(),
+///     when: chrono::DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+///     tags: vec!["sensation/ear".into()],
+/// };
+/// let id = backend.store(&exp, &[1.0, 0.0]).await.unwrap();
+/// let hits = backend.search(&[0.9, 0.1], 1, &Default::default()).await.unwrap();
+/// assert_eq!((hits[0].id.as_str(), &hits[0].experience), (id.as_str(), &exp));
+/// drop(backend);
+/// let reopened = HnswBackend::open(dir.path()).unwrap();
+/// assert_eq!(reopened.get(&id).await.unwrap(), Some(exp));
+/// # });
+/// ```
+pub struct HnswBackend {
+    dir: PathBuf,
+    index: Mutex<Index>,
+}
+
+/// Read the records of a JSONL file. A torn final line, left by a crash
+/// mid-append, is cut off so the next append starts on a line of its own.
+fn read_jsonl<T: for<'de> Deserialize<'de>>(path: &Path) -> anyhow::Result<Vec<T>> {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut out = Vec::new();
+    let mut end = 0;
+    for (n, line) in text.split_inclusive('\n').enumerate() {
+        let start = end;
+        end += line.len();
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(line) {
+            Ok(item) => out.push(item),
+            Err(e) if end == text.len() => {
+                warn!(path = %path.display(), error = %e, "dropping truncated record");
+                std::fs::OpenOptions::new()
+                    .write(true)
+                    .open(path)?
+                    .set_len(start as u64)?;
+                return Ok(out);
+            }
+            Err(e) => anyhow::bail!("{}:{}: {}", path.display(), n + 1, e),
+        }
+    }
+    if !text.is_empty() && !text.ends_with('\n') {
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(path)?
+            .write_all(b"\n")?;
+    }
+    Ok(out)
+}
+
+fn append_jsonl<T: Serialize>(path: &Path, item: &T) -> anyhow::Result<()> {
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    let mut line = serde_json::to_vec(item)?;
+    line.push(b'\n');
+    file.write_all(&line)?;
+    Ok(())
+}
+
+impl HnswBackend {
+    /// Open the index stored in `dir`, creating the directory if needed.
+    pub fn open(dir: impl Into<PathBuf>) -> anyhow::Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+        let records: Vec<Record> = read_jsonl(&dir.join(EXPERIENCES))?;
+        let mut edges: Vec<Edge> = read_jsonl(&dir.join(EDGES))?;
+        let mut archived = HashSet::new();
+        let mut forgotten = HashSet::new();
+        for retired in read_jsonl::<Retired>(&dir.join(RETIRED))? {
+            if retired.forgotten {
+                archived.remove(&retired.id);
+                forgotten.insert(retired.id);
+            } else {
+                archived.insert(retired.id);
+            }
+        }
+        edges.retain(|e| !forgotten.contains(&e.from) && !forgotten.contains(&e.to));
+        let mut entities: Vec<Entity> = Vec::new();
+        for entity in read_jsonl::<Entity>(&dir.join(ENTITIES))? {
+            match entities.iter_mut().find(|e| e.id == entity.id) {
+                Some(known) => *known = entity,
+                None => entities.push(entity),
+            }
+        }
+        let unit = records.iter().map(|r| normalized(&r.vector)).collect();
+        let ids = records
+            .iter()
+            .enumerate()
+            .map(|(i, r)| (r.id.clone(), i))
+            .collect();
+        let graph = std::fs::read(dir.join(SNAPSHOT))
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<Graph>(&bytes).ok())
+            .filter(|g| g.nodes.len() <= records.len() && g.entry.is_some() != g.nodes.is_empty())
+            .unwrap_or_default();
+        let mut index = Index {
+            records,
+            unit,
+            ids,
+            graph,
+            edges,
+            entities,
+            archived,
+            forgotten,
+            since_snapshot: 0,
+        };
+        let indexed = index.graph.nodes.len();
+        for idx in indexed..index.records.len() {
+            index.insert(idx);
+        }
+        index.since_snapshot = index.records.len() - indexed;
+        info!(
+            dir = %dir.display(),
+            experiences = index.records.len(),
+            reindexed = index.since_snapshot,
+            "opened hnsw memory"
+        );
+        Ok(Self {
+            dir,
+            index: Mutex::new(index),
+        })
+    }
+
+    /// Number of stored experiences, archived ones included.
+    pub fn len(&self) -> usize {
+        let index = self.index.lock().unwrap();
+        index.records.len() - index.forgotten.len()
+    }
+
+    /// Whether nothing has been stored yet.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// All recorded links.
+    pub fn edges(&self) -> Vec<Edge> {
+        self.index.lock().unwrap().edges.clone()
+    }
+
+    /// Write the graph snapshot now instead of waiting for
+    /// [`SNAPSHOT_EVERY`] stores.
+    pub fn snapshot(&self) -> anyhow::Result<()> {
+        self.index.lock().unwrap().save(&self.dir)
+    }
+
+    /// All known people, places and objects.
+    pub fn entities(&self) -> Vec<Entity> {
+        self.index.lock().unwrap().entities.clone()
+    }
+
+    /// Record a `kind` link from experience `from` to `to`. `to` must be a
+    /// stored experience except for `REFERS_TO`, whose target is a topic,
+    /// and `MENTIONS`, whose target is an entity.
+    pub fn link(&self, kind: &str, from: &str, to: &str) -> anyhow::Result<()> {
+        let mut index = self.index.lock().unwrap();
+        if !index.knows(from) {
+            anyhow::bail!("unknown experience {}", from);
+        }
+        match kind {
+            "REFERS_TO" => {}
+            MENTIONS if index.entities.iter().any(|e| e.id == to) => {}
+            MENTIONS => anyhow::bail!("unknown entity {}", to),
+            _ if !index.knows(to) => anyhow::bail!("unknown experience {}", to),
+            _ => {}
+        }
+        index.add_edge(
+            &self.dir,
+            Edge {
+                kind: kind.to_string(),
+                from: from.to_string(),
+                to: to.to_string(),
+            },
+        )
+    }
+}
+
+#[async_trait(?Send)]
+impl MemoryBackend for HnswBackend {
+    async fn store(&self, exp: &Experience, vector: &[f32]) -> anyhow::Result<String> {
+        let id = Uuid::new_v4().to_string();
+        self.store_with_id(&id, exp, vector).await?;
+        Ok(id)
+    }
+
+    async fn store_with_id(
+        &self,
+        id: &str,
+        exp: &Experience,
+        vector: &[f32],
+    ) -> anyhow::Result<()> {
+        let mut index = self.index.lock().unwrap();
+        if index.ids.contains_key(id) {
+            return Ok(());
+        }
+        if let Some(first) = index.records.first() {
+            if first.vector.len() != vector.len() {
+                anyhow::bail!(
+                    "vector has {} dimensions, index has {}",
+                    vector.len(),
+                    first.vector.len()
+                );
+            }
+        }
+        let record = Record {
+            id: id.to_string(),
+            experience: exp.clone(),
+            vector: vector.to_vec(),
+        };
+        append_jsonl(&self.dir.join(EXPERIENCES), &record)?;
+        let previous: Vec<String> = exp
+            .tags
+            .iter()
+            .filter_map(|tag| index.stream_head(tag, exp.when))
+            .map(|r| r.id.clone())
+            .collect();
+        for prev in previous {
+            index.add_edge(
+                &self.dir,
+                Edge {
+                    kind: "NEXT".into(),
+                    from: prev,
+                    to: id.to_string(),
+                },
+            )?;
+        }
+        let idx = index.records.len();
+        index.ids.insert(id.to_string(), idx);
+        index.unit.push(normalized(vector));
+        index.records.push(record);
+        index.insert(idx);
+        index.since_snapshot += 1;
+        debug!(%id, how = %exp.how, "hnsw store");
+        if index.since_snapshot >= SNAPSHOT_EVERY {
+            if let Err(e) = index.save(&self.dir) {
+                warn!(error = %e, "hnsw snapshot failed");
+            }
+        }
+        Ok(())
+    }
+
+    async fn link_summary(&self, summary_id: &str, original_id: &str) -> anyhow::Result<()> {
+        self.link("SUMMARIZES", summary_id, original_id)
+    }
+
+    async fn link_called_to_mind(&self, from: &str, to: &str) -> anyhow::Result<()> {
+        self.link("CALLED_TO_MIND", from, to)
+    }
+
+    async fn link_causes(&self, cause: &str, effect: &str) -> anyhow::Result<()> {
+        self.link("CAUSES", cause, effect)
+    }
+
+    async fn link_topic(&self, id: &str, topic: &str) -> anyhow::Result<()> {
+        self.link("REFERS_TO", id, topic)
+    }
+
+    async fn upsert_entity(&self, entity: &Entity) -> anyhow::Result<Entity> {
+        let mut index = self.index.lock().unwrap();
+        let (i, changed) = merge_into(&mut index.entities, entity);
+        if changed {
+            append_jsonl(&self.dir.join(ENTITIES), &index.entities[i])?;
+        }
+        debug!(id = %index.entities[i].id, name = %index.entities[i].name, changed, "hnsw upsert entity");
+        Ok(index.entities[i].clone())
+    }
+
+    async fn link_mention(&self, id: &str, entity_id: &str) -> anyhow::Result<()> {
+        self.link(MENTIONS, id, entity_id)
+    }
+
+    async fn entity(&self, name: &str) -> anyhow::Result<Option<Entity>> {
+        let index = self.index.lock().unwrap();
+        Ok(index.entities.iter().find(|e| e.answers_to(name)).cloned())
+    }
+
+    async fn mentioning(&self, entity_id: &str) -> anyhow::Result<Vec<Experience>> {
+        let index = self.index.lock().unwrap();
+        let mut found: Vec<Experience> = index
+            .edges
+            .iter()
+            .filter(|e| e.kind == MENTIONS && e.to == entity_id)
+            .filter_map(|e| index.ids.get(&e.from))
+            .map(|&i| index.records[i].experience.clone())
+            .collect();
+        found.sort_by_key(|e| e.when);
+        Ok(found)
+    }
+
+    async fn neighbors(&self, id: &str) -> anyhow::Result<Vec<Experience>> {
+        let index = self.index.lock().unwrap();
+        if !index.knows(id) {
+            return Ok(Vec::new());
+        }
+        let mut found: Vec<(usize, &Record)> = Vec::new();
+        for (rank, linked) in index.linked(id) {
+            let Some(&i) = index.ids.get(linked) else {
+                continue;
+            };
+            if !found.iter().any(|(_, r)| r.id == linked) {
+                found.push((rank, &index.records[i]));
+            }
+        }
+        found.sort_by_key(|(rank, r)| (*rank, r.experience.when));
+        debug!(id, found = found.len(), "hnsw neighbors");
+        Ok(found
+            .into_iter()
+            .take(7)
+            .map(|(_, r)| r.experience.clone())
+            .collect())
+    }
+
+    async fn recall_counts(&self) -> anyhow::Result<HashMap<String, usize>> {
+        let index = self.index.lock().unwrap();
+        let mut counts = HashMap::new();
+        for edge in index.edges.iter().filter(|e| e.kind == "CALLED_TO_MIND") {
+            *counts.entry(edge.to.clone()).or_default() += 1;
+        }
+        Ok(counts)
+    }
+
+    async fn archive(&self, id: &str) -> anyhow::Result<()> {
+        let mut index = self.index.lock().unwrap();
+        if !index.knows(id) {
+            anyhow::bail!("unknown experience {}", id);
+        }
+        if index.archived.contains(id) {
+            return Ok(());
+        }
+        let retired = Retired {
+            id: id.to_string(),
+            forgotten: false,
+        };
+        append_jsonl(&self.dir.join(RETIRED), &retired)?;
+        index.archived.insert(retired.id);
+        debug!(id, "hnsw archive");
+        Ok(())
+    }
+
+    async fn forget(&self, id: &str) -> anyhow::Result<()> {
+        let mut index = self.index.lock().unwrap();
+        if !index.knows(id) {
+            anyhow::bail!("unknown experience {}", id);
+        }
+        let retired = Retired {
+            id: id.to_string(),
+            forgotten: true,
+        };
+        append_jsonl(&self.dir.join(RETIRED), &retired)?;
+        index.archived.remove(id);
+        index.edges.retain(|e| e.from != id && e.to != id);
+        index.forgotten.insert(retired.id);
+        debug!(id, "hnsw forget");
+        Ok(())
+    }
+
+    async fn dump(&self) -> anyhow::Result<MemoryDump> {
+        let index = self.index.lock().unwrap();
+        Ok(MemoryDump {
+            experiences: index
+                .records
+                .iter()
+                .filter(|r| !index.forgotten.contains(&r.id))
+                .map(|r| ExperienceRecord {
+                    id: r.id.clone(),
+                    experience: r.experience.clone(),
+                    vector: r.vector.clone(),
+                    archived: index.archived.contains(&r.id),
+                })
+                .collect(),
+            edges: index.edges.clone(),
+            entities: index.entities.clone(),
+        })
+    }
+
+    async fn restore(&self, dump: &MemoryDump) -> anyhow::Result<()> {
+        dump.check_dimensions()?;
+        let mut index = self.index.lock().unwrap();
+        if !index.records.is_empty() || !index.entities.is_empty() {
+            anyhow::bail!("cannot restore into a memory that is not empty");
+        }
+        // same ids in the same order rebuild the same graph
+        for r in &dump.experiences {
+            let record = Record {
+                id: r.id.clone(),
+                experience: r.experience.clone(),
+                vector: r.vector.clone(),
+            };
+            append_jsonl(&self.dir.join(EXPERIENCES), &record)?;
+            if r.archived {
+                let retired = Retired {
+                    id: r.id.clone(),
+                    forgotten: false,
+                };
+                append_jsonl(&self.dir.join(RETIRED), &retired)?;
+                index.archived.insert(retired.id);
+            }
+            let idx = index.records.len();
+            index.ids.insert(record.id.clone(), idx);
+            index.unit.push(normalized(&record.vector));
+            index.records.push(record);
+            index.insert(idx);
+        }
+        for entity in &dump.entities {
+            append_jsonl(&self.dir.join(ENTITIES), entity)?;
+            index.entities.push(entity.clone());
+        }
+        for edge in &dump.edges {
+            index.add_edge(&self.dir, edge.clone())?;
+        }
+        index.save(&self.dir)?;
+        info!(
+            experiences = dump.experiences.len(),
+            edges = dump.edges.len(),
+            entities = dump.entities.len(),
+            "restored hnsw memory"
+        );
+        Ok(())
+    }
+
+    async fn search(
+        &self,
+        vector: &[f32],
+        top_k: usize,
+        filter: &SearchFilter,
+    ) -> anyhow::Result<Vec<SearchHit>> {
+        debug!(top_k, ?filter, "hnsw search");
+        Ok(self.index.lock().unwrap().search(vector, top_k, filter))
+    }
+
+    async fn get(&self, id: &str) -> anyhow::Result<Option<Experience>> {
+        let index = self.index.lock().unwrap();
+        if index.forgotten.contains(id) {
+            return Ok(None);
+        }
+        Ok(index
+            .ids
+            .get(id)
+            .map(|&i| index.records[i].experience.clone()))
+    }
+}
+
+impl Drop for HnswBackend {
+    fn drop(&mut self) {
+        let Ok(index) = self.index.get_mut() else {
+            return;
+        };
+        if index.since_snapshot > 0 {
+            if let Err(e) = index.save(&self.dir) {
+                warn!(error = %e, "hnsw snapshot failed");
+            }
+        }
+    }
+}
@@ -0,0 +1,177 @@
+//! Topic and causal links for stored experiences.
+//!
+//! A [`Linker`] runs after an experience is stored. It connects the
+//! experience to `Topic`s through `REFERS_TO` and to its causes through
+//! `CAUSES`:
+//!
+//! - causes are asserted by the wit that produced the experience, as the id
+//!   or ids under `caused_by` in its `what` object;
+//! - topics are those listed under `topics` in `what` plus any `#hashtag`
+//!   in the text. With a chat model the linker also asks it for topics.
+
+use super::{Experience, MemoryBackend};
+use crate::llm::prompt::{PromptContext, PromptHelper};
+use crate::llm::{CanChat, LlmProfile};
+use serde_json::Value;
+use tokio_stream::StreamExt;
+use tracing::{debug, trace, warn};
+
+/// Template asking a chat model for the topics of an experience.
+pub const TOPIC_PROMPT: &str = "List at most {{ max }} topics this memory is about, as a JSON array of short lowercase noun phrases. Reply with the array only.\n\n{{ how }}\n\n{{ what }}";
+
+/// Most topics linked to one experience.
+pub const MAX_TOPICS: usize = 5;
+
+/// Links recorded for one experience.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Links {
+    /// Topics the experience refers to.
+    pub topics: Vec<String>,
+    /// Ids of the experiences that caused it.
+    pub causes: Vec<String>,
+}
+
+/// Ids under `caused_by` in `what`, given as a string or an array.
+///
+/// ```
+/// use psyche::memory::asserted_causes;
+/// let what = serde_json::json!({"text": "I flinched", "caused_by": ["a1", "b2"]});
+/// assert_eq!(asserted_causes(&what), ["a1", "b2"]);
+/// ```
+pub fn asserted_causes(what: &Value) -> Vec<String> {
+    match what.get("caused_by") {
+        Some(Value::String(id)) => vec![id.clone()],
+        Some(Value::Array(ids)) => ids
+            .iter()
+            .filter_map(|v| v.as_str().map(str::to_string))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Lowercase, trimmed topic, or `None` when nothing is left.
+fn normalize_topic(topic: &str) -> Option<String> {
+    let topic = topic
+        .trim()
+        .trim_start_matches('#')
+        .trim_matches(|c: char| !c.is_alphanumeric())
+        .to_lowercase();
+    (!topic.is_empty()).then_some(topic)
+}
+
+/// Topics named by the experience itself: `what.topics` and hashtags.
+///
+/// ```
+/// use psyche::memory::{explicit_topics, Experience};
+/// let exp = Experience {
+///     how: "Watering the #Garden before the #rain.".into(),
+///     what: serde_json::json!({"topics": ["Tomatoes"]}),
+///     when: chrono::Utc::now(),
+///     tags: vec![],
+/// };
+/// assert_eq!(explicit_topics(&exp), ["tomatoes", "garden", "rain"]);
+/// ```
+pub fn explicit_topics(exp: &Experience) -> Vec<String> {
+    let listed = exp
+        .what
+        .get("topics")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str);
+    let text = format!("{} {}", exp.how, exp.what);
+    let hashtags = text
+        .split_whitespace()
+        .filter(|w| w.starts_with('#') && w.len() > 1);
+    let mut topics = Vec::new();
+    for topic in listed.chain(hashtags).filter_map(normalize_topic) {
+        if !topics.contains(&topic) {
+            topics.push(topic);
+        }
+    }
+    topics
+}
+
+/// Extracts topics and causes of stored experiences and links them.
+pub struct Linker<'a, B: ?Sized> {
+    /// Optional chat model asked for topics.
+    pub chat: Option<&'a dyn CanChat>,
+    /// LLM profile for `chat`.
+    pub profile: &'a LlmProfile,
+    /// Backend receiving the links.
+    pub backend: &'a B,
+    /// Renders [`TOPIC_PROMPT`].
+    pub prompter: PromptHelper,
+}
+
+impl<B> Linker<'_, B>
+where
+    B: MemoryBackend + ?Sized,
+{
+    /// Link the experience stored as `id`, returning what was linked. A
+    /// failing chat model only costs its topics, and a cause that cannot be
+    /// linked, such as one never stored, only costs its own link.
+    pub async fn link(&self, id: &str, exp: &Experience) -> anyhow::Result<Links> {
+        let mut topics = explicit_topics(exp);
+        if let Some(chat) = self.chat {
+            match self.ask_topics(chat, exp).await {
+                Ok(asked) => {
+                    for topic in asked {
+                        if !topics.contains(&topic) {
+                            topics.push(topic);
+                        }
+                    }
+                }
+                Err(e) => warn!(error = %e, "topic extraction failed"),
+            }
+        }
+        topics.truncate(MAX_TOPICS);
+        for topic in &topics {
+            self.backend.link_topic(id, topic).await?;
+        }
+        let mut causes = Vec::new();
+        for cause in asserted_causes(&exp.what) {
+            match self.backend.link_causes(&cause, id).await {
+                Ok(()) => causes.push(cause),
+                Err(e) => warn!(id, %cause, error = %e, "cannot link cause"),
+            }
+        }
+        debug!(id, ?topics, ?causes, "linked experience");
+        Ok(Links { topics, causes })
+    }
+
+    async fn ask_topics(
+        &self,
+        chat: &dyn CanChat,
+        exp: &Experience,
+    ) -> anyhow::Result<Vec<String>> {
+        let what = match &exp.what {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        let ctx = PromptContext::new()
+            .var("max", MAX_TOPICS)
+            .var("how", &exp.how)
+            .var("what", what);
+        let prompt = self.prompter.render(TOPIC_PROMPT, &ctx)?;
+        trace!(target = "llm", user = %prompt, "topic prompt");
+        let mut stream = chat
+            .chat_stream(self.profile, self.prompter.system(), &prompt)
+            .await?;
+        let mut out = String::new();
+        while let Some(token) = stream.next().await {
+            out.push_str(&token);
+        }
+        debug!(target = "llm", response = %out, "topic response");
+        let reply = crate::schema::parse_reply(&out)
+            .ok_or_else(|| anyhow::anyhow!("topic reply is not JSON: {}", out))?;
+        let Value::Array(items) = reply else {
+            anyhow::bail!("topic reply is not an array: {}", reply);
+        };
+        Ok(items
+            .iter()
+            .filter_map(Value::as_str)
+            .filter_map(normalize_topic)
+            .collect())
+    }
+}
@@ -0,0 +1,697 @@
+//! Single-file relational memory.
+//!
+//! [`SqliteBackend`] keeps everything in one SQLite database so memory can be
+//! inspected with standard tools:
+//!
+//! - `experience`: id, `how`, `what` (JSON text), `when` (RFC 3339, UTC) and
+//!   the embedding as a little-endian `f32` blob;
+//! - `tag`: one row per experience tag;
+//! - `edge`: typed links, one of [`EDGE_KINDS`]. `REFERS_TO` targets are
+//!   topic names and `MENTIONS` targets are entity ids rather than
+//!   experiences, and `store` extends the `NEXT` chain of every tag;
+//! - `entity` and `entity_name`: people, places and objects with every name
+//!   they answer to, compared without case;
+//! - `archived`: experiences consolidation archived, which searches skip;
+//! - `experience_fts`: an FTS5 index over `how` and `what`, kept in sync by
+//!   triggers.
+//!
+//! Vector search scans every stored embedding, which is fine for the small
+//! deployments this backend is meant for. Graph helpers such as
+//! [`context_subgraph`](super::context_subgraph) run as SQL over `edge`.
+
+use super::{
+    cosine_similarity, Edge, Entity, EntityKind, Experience, ExperienceRecord, MemoryBackend,
+    MemoryDump, SearchFilter, SearchHit, EDGE_KINDS, MENTIONS,
+};
+use async_trait::async_trait;
+use chrono::{DateTime, SecondsFormat, Utc};
+use rusqlite::{params, Connection, OptionalExtension, ToSql};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+use tracing::{debug, info};
+use uuid::Uuid;
+
+const SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS experience (
+    id TEXT PRIMARY KEY,
+    how TEXT NOT NULL,
+    what TEXT NOT NULL,
+    "when" TEXT NOT NULL,
+    vector BLOB NOT NULL
+);
+CREATE INDEX IF NOT EXISTS experience_by_when ON experience("when");
+CREATE TABLE IF NOT EXISTS tag (
+    experience_id TEXT NOT NULL REFERENCES experience(id) ON DELETE CASCADE,
+    tag TEXT NOT NULL,
+    PRIMARY KEY (experience_id, tag)
+);
+CREATE INDEX IF NOT EXISTS tag_by_name ON tag(tag);
+CREATE TABLE IF NOT EXISTS edge (
+    kind TEXT NOT NULL,
+    source TEXT NOT NULL REFERENCES experience(id) ON DELETE CASCADE,
+    target TEXT NOT NULL,
+    PRIMARY KEY (kind, source, target)
+);
+CREATE INDEX IF NOT EXISTS edge_by_target ON edge(kind, target);
+CREATE TABLE IF NOT EXISTS entity (
+    id TEXT PRIMARY KEY,
+    kind TEXT NOT NULL,
+    name TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS entity_name (
+    entity_id TEXT NOT NULL REFERENCES entity(id) ON DELETE CASCADE,
+    name TEXT NOT NULL COLLATE NOCASE,
+    PRIMARY KEY (entity_id, name)
+);
+CREATE INDEX IF NOT EXISTS entity_name_by_name ON entity_name(name);
+CREATE TABLE IF NOT EXISTS archived (
+    experience_id TEXT PRIMARY KEY REFERENCES experience(id) ON DELETE CASCADE
+);
+CREATE VIRTUAL TABLE IF NOT EXISTS experience_fts
+    USING fts5(how, what, content='experience', content_rowid='rowid');
+CREATE TRIGGER IF NOT EXISTS experience_fts_insert AFTER INSERT ON experience BEGIN
+    INSERT INTO experience_fts(rowid, how, what) VALUES (new.rowid, new.how, new.what);
+END;
+CREATE TRIGGER IF NOT EXISTS experience_fts_delete AFTER DELETE ON experience BEGIN
+    INSERT INTO experience_fts(experience_fts, rowid, how, what)
+        VALUES ('delete', old.rowid, old.how, old.what);
+END;
+"#;
+
+/// Columns read by [`SqliteBackend::experiences`], from table alias `x`.
+const COLUMNS: &str = r#"x.id, x.how, x.what, x."when""#;
+
+fn timestamp(when: &DateTime<Utc>) -> String {
+    when.to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+/// How `kind` is spelled in the `entity` table.
+fn kind_name(kind: EntityKind) -> String {
+    kind.label().to_lowercase()
+}
+
+fn to_blob(vector: &[f32]) -> Vec<u8> {
+    vector.iter().flat_map(|x| x.to_le_bytes()).collect()
+}
+
+fn from_blob(blob: &[u8]) -> Vec<f32> {
+    blob.chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect()
+}
+
+/// FTS5 query matching every word of `text`, each quoted so punctuation
+/// and operators in it are taken literally.
+fn fts_query(text: &str) -> String {
+    text.split_whitespace()
+        .map(|w| format!("\"{}\"", w.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// [`MemoryBackend`] storing experiences, tags and links in SQLite.
+///
+/// ```
+/// use psyche::memory::{Experience, MemoryBackend, SqliteBackend};
+/// # tokio_test::block_on(async {
+/// let db = SqliteBackend::open_in_memory().unwrap();
+/// let exp = Experience {
+///     how: "The kettle whistled.".into(),
+///     what: "steam".into(),
+///     when: chrono::Utc::now(),
+///     tags: vec!["sensation/ear".into()],
+/// };
+/// db.store(&exp, &[0.0, 1.0]).await.unwrap();
+/// let hits = db.keyword_search("kettle", 5).unwrap();
+/// assert_eq!(hits[0].how, "The kettle whistled.");
+/// # });
+/// ```
+pub struct SqliteBackend {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteBackend {
+    /// Open or create the database file at `path`.
+    pub fn open(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        info!(path = %path.display(), "opening sqlite memory");
+        Self::init(Connection::open(path)?)
+    }
+
+    /// Database that lives only as long as the backend.
+    pub fn open_in_memory() -> anyhow::Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> anyhow::Result<Self> {
+        conn.pragma_update(None, "foreign_keys", true)?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Record a `kind` link from experience `from` to `to`. `kind` must be
+    /// one of [`EDGE_KINDS`]; `to` must be a stored experience except for
+    /// `REFERS_TO`, whose target is a topic, and `MENTIONS`, whose target
+    /// is an entity.
+    pub fn link(&self, kind: &str, from: &str, to: &str) -> anyhow::Result<()> {
+        if !EDGE_KINDS.contains(&kind) {
+            anyhow::bail!("unknown edge kind {}", kind);
+        }
+        let conn = self.conn.lock().unwrap();
+        let exists = |id: &str| -> rusqlite::Result<bool> {
+            conn.query_row("SELECT 1 FROM experience WHERE id = ?1", [id], |_| Ok(()))
+                .optional()
+                .map(|r| r.is_some())
+        };
+        let target_known = match kind {
+            "REFERS_TO" => true,
+            MENTIONS => conn
+                .query_row("SELECT 1 FROM entity WHERE id = ?1", [to], |_| Ok(()))
+                .optional()?
+                .is_some(),
+            _ => exists(to)?,
+        };
+        if !exists(from)? || !target_known {
+            anyhow::bail!("cannot link {} -[{}]-> {}: unknown node", from, kind, to);
+        }
+        conn.execute(
+            "INSERT OR IGNORE INTO edge (kind, source, target) VALUES (?1, ?2, ?3)",
+            params![kind, from, to],
+        )?;
+        debug!(kind, from, to, "sqlite link");
+        Ok(())
+    }
+
+    /// All recorded links, in insertion order.
+    pub fn edges(&self) -> anyhow::Result<Vec<Edge>> {
+        Self::load_edges(&self.conn.lock().unwrap())
+    }
+
+    fn load_edges(conn: &Connection) -> anyhow::Result<Vec<Edge>> {
+        let mut stmt = conn.prepare("SELECT kind, source, target FROM edge ORDER BY rowid")?;
+        let edges = stmt
+            .query_map([], |row| {
+                Ok(Edge {
+                    kind: row.get(0)?,
+                    from: row.get(1)?,
+                    to: row.get(2)?,
+                })
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(edges)
+    }
+
+    /// All known people, places and objects, in creation order.
+    pub fn entities(&self) -> anyhow::Result<Vec<Entity>> {
+        let conn = self.conn.lock().unwrap();
+        Self::load_entities(&conn, "SELECT id FROM entity ORDER BY rowid", &[])
+    }
+
+    /// Load the entities whose ids `sql` selects.
+    fn load_entities(
+        conn: &Connection,
+        sql: &str,
+        args: &[&dyn ToSql],
+    ) -> anyhow::Result<Vec<Entity>> {
+        let ids = conn
+            .prepare(sql)?
+            .query_map(args, |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        let mut entity = conn.prepare("SELECT kind, name FROM entity WHERE id = ?1")?;
+        let mut names = conn.prepare(
+            "SELECT n.name FROM entity_name n JOIN entity e ON e.id = n.entity_id \
+             WHERE n.entity_id = ?1 AND n.name <> e.name ORDER BY n.rowid",
+        )?;
+        let mut out = Vec::with_capacity(ids.len());
+        for id in ids {
+            let (kind, name): (String, String) =
+                entity.query_row([&id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            out.push(Entity {
+                kind: serde_json::from_value(serde_json::Value::String(kind))?,
+                name,
+                aliases: names
+                    .query_map([&id], |row| row.get(0))?
+                    .collect::<rusqlite::Result<_>>()?,
+                id,
+            });
+        }
+        Ok(out)
+    }
+
+    /// Experiences whose `how` or `what` contain every word of `text`, best
+    /// match first.
+    pub fn keyword_search(&self, text: &str, limit: usize) -> anyhow::Result<Vec<Experience>> {
+        let query = fts_query(text);
+        if query.is_empty() {
+            return Ok(Vec::new());
+        }
+        debug!(%query, limit, "sqlite keyword search");
+        let conn = self.conn.lock().unwrap();
+        Self::experiences(
+            &conn,
+            &format!(
+                "SELECT {COLUMNS} FROM experience_fts f JOIN experience x ON x.rowid = f.rowid \
+                 WHERE experience_fts MATCH ?1 \
+                 AND NOT EXISTS (SELECT 1 FROM archived a WHERE a.experience_id = x.id) \
+                 ORDER BY bm25(experience_fts) LIMIT ?2"
+            ),
+            &[&query, &(limit as i64)],
+        )
+    }
+
+    /// Run `sql`, which selects [`COLUMNS`], and load each row's tags.
+    fn experiences(
+        conn: &Connection,
+        sql: &str,
+        args: &[&dyn ToSql],
+    ) -> anyhow::Result<Vec<Experience>> {
+        let mut stmt = conn.prepare(sql)?;
+        let rows = stmt
+            .query_map(args, |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        let mut tags =
+            conn.prepare("SELECT tag FROM tag WHERE experience_id = ?1 ORDER BY rowid")?;
+        let mut out = Vec::with_capacity(rows.len());
+        for (id, how, what, when) in rows {
+            out.push(Experience {
+                how,
+                what: serde_json::from_str(&what)?,
+                when: DateTime::parse_from_rfc3339(&when)?.with_timezone(&Utc),
+                tags: tags
+                    .query_map([&id], |row| row.get(0))?
+                    .collect::<rusqlite::Result<_>>()?,
+            });
+        }
+        Ok(out)
+    }
+}
+
+#[async_trait(?Send)]
+impl MemoryBackend for SqliteBackend {
+    async fn store(&self, exp: &Experience, vector: &[f32]) -> anyhow::Result<String> {
+        let id = Uuid::new_v4().to_string();
+        self.store_with_id(&id, exp, vector).await?;
+        Ok(id)
+    }
+
+    async fn store_with_id(
+        &self,
+        id: &str,
+        exp: &Experience,
+        vector: &[f32],
+    ) -> anyhow::Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let known: bool = tx.query_row(
+            "SELECT EXISTS (SELECT 1 FROM experience WHERE id = ?1)",
+            params![id],
+            |row| row.get(0),
+        )?;
+        if known {
+            return Ok(());
+        }
+        tx.execute(
+            r#"INSERT INTO experience (id, how, what, "when", vector) VALUES (?1, ?2, ?3, ?4, ?5)"#,
+            params![
+                id,
+                exp.how,
+                exp.what.to_string(),
+                timestamp(&exp.when),
+                to_blob(vector)
+            ],
+        )?;
+        for tag in &exp.tags {
+            tx.execute(
+                r#"INSERT OR IGNORE INTO edge (kind, source, target)
+                SELECT 'NEXT', x.id, ?1 FROM experience x JOIN tag t ON t.experience_id = x.id
+                WHERE t.tag = ?2 AND x."when" <= ?3
+                ORDER BY x."when" DESC, x.rowid DESC LIMIT 1"#,
+                params![id, tag, timestamp(&exp.when)],
+            )?;
+            tx.execute(
+                "INSERT OR IGNORE INTO tag (experience_id, tag) VALUES (?1, ?2)",
+                params![id, tag],
+            )?;
+        }
+        tx.commit()?;
+        debug!(%id, how = %exp.how, "sqlite store");
+        Ok(())
+    }
+
+    async fn link_summary(&self, summary_id: &str, original_id: &str) -> anyhow::Result<()> {
+        self.link("SUMMARIZES", summary_id, original_id)
+    }
+
+    async fn link_called_to_mind(&self, from: &str, to: &str) -> anyhow::Result<()> {
+        self.link("CALLED_TO_MIND", from, to)
+    }
+
+    async fn link_causes(&self, cause: &str, effect: &str) -> anyhow::Result<()> {
+        self.link("CAUSES", cause, effect)
+    }
+
+    async fn link_topic(&self, id: &str, topic: &str) -> anyhow::Result<()> {
+        self.link("REFERS_TO", id, topic)
+    }
+
+    async fn upsert_entity(&self, entity: &Entity) -> anyhow::Result<Entity> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let names: Vec<&str> = entity.names().map(str::trim).collect();
+        let mut known = Self::load_entities(
+            &tx,
+            "SELECT e.id FROM entity e JOIN entity_name n ON n.entity_id = e.id \
+             WHERE e.kind = ?1 AND n.name IN (SELECT value FROM json_each(?2)) \
+             ORDER BY e.rowid LIMIT 1",
+            &[&kind_name(entity.kind), &serde_json::to_string(&names)?],
+        )?;
+        let (i, changed) = super::entity::merge_into(&mut known, entity);
+        let stored = known.swap_remove(i);
+        if changed {
+            tx.execute(
+                "INSERT OR IGNORE INTO entity (id, kind, name) VALUES (?1, ?2, ?3)",
+                params![stored.id, kind_name(stored.kind), stored.name],
+            )?;
+            for name in stored.names() {
+                tx.execute(
+                    "INSERT OR IGNORE INTO entity_name (entity_id, name) VALUES (?1, ?2)",
+                    params![stored.id, name],
+                )?;
+            }
+        }
+        tx.commit()?;
+        debug!(id = %stored.id, name = %stored.name, changed, "sqlite upsert entity");
+        Ok(stored)
+    }
+
+    async fn link_mention(&self, id: &str, entity_id: &str) -> anyhow::Result<()> {
+        self.link(MENTIONS, id, entity_id)
+    }
+
+    async fn entity(&self, name: &str) -> anyhow::Result<Option<Entity>> {
+        let conn = self.conn.lock().unwrap();
+        Ok(Self::load_entities(
+            &conn,
+            "SELECT e.id FROM entity e JOIN entity_name n ON n.entity_id = e.id \
+             WHERE n.name = ?1 ORDER BY e.rowid LIMIT 1",
+            &[&name.trim()],
+        )?
+        .pop())
+    }
+
+    async fn mentioning(&self, entity_id: &str) -> anyhow::Result<Vec<Experience>> {
+        let conn = self.conn.lock().unwrap();
+        Self::experiences(
+            &conn,
+            &format!(
+                r#"SELECT {COLUMNS} FROM edge JOIN experience x ON x.id = edge.source
+                WHERE edge.kind = 'MENTIONS' AND edge.target = ?1 ORDER BY x."when""#
+            ),
+            &[&entity_id],
+        )
+    }
+
+    async fn search(
+        &self,
+        vector: &[f32],
+        top_k: usize,
+        filter: &SearchFilter,
+    ) -> anyhow::Result<Vec<SearchHit>> {
+        debug!(top_k, ?filter, "sqlite search");
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            r#"SELECT x.id, x.vector FROM experience x
+            WHERE (?1 = '[]' OR EXISTS (
+                SELECT 1 FROM tag t
+                WHERE t.experience_id = x.id AND t.tag IN (SELECT value FROM json_each(?1))
+            ))
+            AND (?2 IS NULL OR x."when" >= ?2)
+            AND (?3 IS NULL OR x."when" <= ?3)
+            AND NOT EXISTS (SELECT 1 FROM archived a WHERE a.experience_id = x.id)"#,
+        )?;
+        let args = params![
+            serde_json::to_string(&filter.tags)?,
+            filter.since.as_ref().map(timestamp),
+            filter.until.as_ref().map(timestamp),
+        ];
+        let mut scored = stmt
+            .query_map(args, |row| {
+                let blob: Vec<u8> = row.get(1)?;
+                Ok((cosine_similarity(vector, &from_blob(&blob)), row.get(0)?))
+            })?
+            .filter(|r| r.as_ref().map_or(true, |(score, _)| filter.admits(*score)))
+            .collect::<rusqlite::Result<Vec<(f32, String)>>>()?;
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        let mut out = Vec::new();
+        for (score, id) in scored.into_iter().take(top_k) {
+            for experience in Self::experiences(
+                &conn,
+                &format!("SELECT {COLUMNS} FROM experience x WHERE x.id = ?1"),
+                &[&id],
+            )? {
+                out.push(SearchHit {
+                    id: id.clone(),
+                    score,
+                    experience,
+                });
+            }
+        }
+        Ok(out)
+    }
+
+    async fn get(&self, id: &str) -> anyhow::Result<Option<Experience>> {
+        let conn = self.conn.lock().unwrap();
+        Ok(Self::experiences(
+            &conn,
+            &format!("SELECT {COLUMNS} FROM experience x WHERE x.id = ?1"),
+            &[&id],
+        )?
+        .pop())
+    }
+
+    async fn neighbors(&self, id: &str) -> anyhow::Result<Vec<Experience>> {
+        debug!(id, "sqlite neighbors");
+        let conn = self.conn.lock().unwrap();
+        Self::experiences(
+            &conn,
+            &format!(
+                r#"WITH linked(id, rank) AS (
+                    SELECT ?1, 0
+                    UNION ALL SELECT source, 1 FROM edge WHERE kind = 'NEXT' AND target = ?1
+                    UNION ALL SELECT target, 2 FROM edge WHERE kind = 'NEXT' AND source = ?1
+                    UNION ALL SELECT target, 3 FROM edge WHERE kind = 'CAUSES' AND source = ?1
+                    UNION ALL SELECT source, 4 FROM edge WHERE kind = 'CAUSES' AND target = ?1
+                    UNION ALL SELECT * FROM (
+                        SELECT related.source, 5 FROM edge topic
+                        JOIN edge related ON related.kind = 'REFERS_TO'
+                            AND related.target = topic.target AND related.source <> ?1
+                        WHERE topic.kind = 'REFERS_TO' AND topic.source = ?1
+                        LIMIT 2
+                    )
+                )
+                SELECT {COLUMNS} FROM linked JOIN experience x ON x.id = linked.id
+                WHERE EXISTS (SELECT 1 FROM experience WHERE id = ?1)
+                GROUP BY x.id ORDER BY min(linked.rank), x."when" LIMIT 7"#
+            ),
+            &[&id],
+        )
+    }
+
+    async fn recall_counts(&self) -> anyhow::Result<HashMap<String, usize>> {
+        let conn = self.conn.lock().unwrap();
+        let counts = conn
+            .prepare(
+                "SELECT target, count(*) FROM edge WHERE kind = 'CALLED_TO_MIND' GROUP BY target",
+            )?
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)? as usize))
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(counts)
+    }
+
+    async fn archive(&self, id: &str) -> anyhow::Result<()> {
+        let conn = self.conn.lock().unwrap();
+        let archived = conn.execute(
+            "INSERT OR IGNORE INTO archived (experience_id) SELECT id FROM experience WHERE id = ?1",
+            [id],
+        )?;
+        if archived == 0
+            && conn
+                .query_row("SELECT 1 FROM experience WHERE id = ?1", [id], |_| Ok(()))
+                .optional()?
+                .is_none()
+        {
+            anyhow::bail!("unknown experience {}", id);
+        }
+        debug!(id, "sqlite archive");
+        Ok(())
+    }
+
+    async fn forget(&self, id: &str) -> anyhow::Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        // tags, outgoing links and the archive mark go with the experience
+        if tx.execute("DELETE FROM experience WHERE id = ?1", [id])? == 0 {
+            anyhow::bail!("unknown experience {}", id);
+        }
+        tx.execute("DELETE FROM edge WHERE target = ?1", [id])?;
+        tx.commit()?;
+        debug!(id, "sqlite forget");
+        Ok(())
+    }
+
+    async fn dump(&self) -> anyhow::Result<MemoryDump> {
+        let conn = self.conn.lock().unwrap();
+        let rows = conn
+            .prepare(
+                "SELECT id, vector, EXISTS (SELECT 1 FROM archived a WHERE a.experience_id = x.id) \
+                 FROM experience x ORDER BY rowid",
+            )?
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, Vec<u8>>(1)?,
+                    row.get::<_, bool>(2)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        let mut experiences = Vec::with_capacity(rows.len());
+        for (id, blob, archived) in rows {
+            let experience = Self::experiences(
+                &conn,
+                &format!("SELECT {COLUMNS} FROM experience x WHERE x.id = ?1"),
+                &[&id],
+            )?
+            .pop()
+            .ok_or_else(|| anyhow::anyhow!("experience {} vanished", id))?;
+            experiences.push(ExperienceRecord {
+                id,
+                experience,
+                vector: from_blob(&blob),
+                archived,
+            });
+        }
+        Ok(MemoryDump {
+            experiences,
+            edges: Self::load_edges(&conn)?,
+            entities: Self::load_entities(&conn, "SELECT id FROM entity ORDER BY rowid", &[])?,
+        })
+    }
+
+    async fn restore(&self, dump: &MemoryDump) -> anyhow::Result<()> {
+        dump.check_dimensions()?;
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let stored: i64 = tx.query_row(
+            "SELECT (SELECT count(*) FROM experience) + (SELECT count(*) FROM entity)",
+            [],
+            |row| row.get(0),
+        )?;
+        if stored > 0 {
+            anyhow::bail!("cannot restore into a memory that is not empty");
+        }
+        for record in &dump.experiences {
+            let exp = &record.experience;
+            tx.execute(
+                r#"INSERT INTO experience (id, how, what, "when", vector) VALUES (?1, ?2, ?3, ?4, ?5)"#,
+                params![
+                    record.id,
+                    exp.how,
+                    exp.what.to_string(),
+                    timestamp(&exp.when),
+                    to_blob(&record.vector)
+                ],
+            )?;
+            for tag in &exp.tags {
+                tx.execute(
+                    "INSERT OR IGNORE INTO tag (experience_id, tag) VALUES (?1, ?2)",
+                    params![record.id, tag],
+                )?;
+            }
+            if record.archived {
+                tx.execute(
+                    "INSERT INTO archived (experience_id) VALUES (?1)",
+                    [&record.id],
+                )?;
+            }
+        }
+        for entity in &dump.entities {
+            tx.execute(
+                "INSERT INTO entity (id, kind, name) VALUES (?1, ?2, ?3)",
+                params![entity.id, kind_name(entity.kind), entity.name],
+            )?;
+            for name in entity.names() {
+                tx.execute(
+                    "INSERT OR IGNORE INTO entity_name (entity_id, name) VALUES (?1, ?2)",
+                    params![entity.id, name],
+                )?;
+            }
+        }
+        for edge in &dump.edges {
+            if !EDGE_KINDS.contains(&edge.kind.as_str()) {
+                anyhow::bail!("unknown edge kind {}", edge.kind);
+            }
+            tx.execute(
+                "INSERT OR IGNORE INTO edge (kind, source, target) VALUES (?1, ?2, ?3)",
+                params![edge.kind, edge.from, edge.to],
+            )?;
+        }
+        tx.commit()?;
+        info!(
+            experiences = dump.experiences.len(),
+            edges = dump.edges.len(),
+            entities = dump.entities.len(),
+            "restored sqlite memory"
+        );
+        Ok(())
+    }
+
+    async fn tagged_since(
+        &self,
+        tag: &str,
+        since: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<Experience>> {
+        let conn = self.conn.lock().unwrap();
+        Self::experiences(
+            &conn,
+            &format!(
+                r#"SELECT {COLUMNS} FROM experience x JOIN tag t ON t.experience_id = x.id
+                WHERE t.tag = ?1 AND x."when" > ?2 ORDER BY x."when""#
+            ),
+            &[&tag, &timestamp(&since)],
+        )
+    }
+
+    async fn summary_chain(&self, id: &str, depth: usize) -> anyhow::Result<Vec<Experience>> {
+        let conn = self.conn.lock().unwrap();
+        Self::experiences(
+            &conn,
+            &format!(
+                r#"WITH RECURSIVE chain(id, distance) AS (
+                    SELECT id, 0 FROM experience WHERE id = ?1
+                    UNION SELECT edge.source, chain.distance + 1
+                    FROM edge JOIN chain ON edge.target = chain.id
+                    WHERE edge.kind = 'SUMMARIZES' AND chain.distance < ?2
+                )
+                SELECT {COLUMNS} FROM (SELECT id, min(distance) AS distance FROM chain GROUP BY id) c
+                JOIN experience x ON x.id = c.id ORDER BY c.distance, x."when""#
+            ),
+            &[&id, &(depth as i64)],
+        )
+    }
+}
@@ -1,3 +1,5 @@
+pub mod versioned;
+
 use serde::{Deserialize, Serialize};
 
 /// Raw input received by the system.
@@ -0,0 +1,351 @@
+//! Schema versions for records kept in JSONL files.
+//!
+//! Every [`Versioned`] record is written with a `version` field. Lines
+//! without one predate versioning and count as version 0. Reading a line
+//! runs the migrations from its version up to the current one, so old
+//! history keeps loading after a field changes. A line from a newer build
+//! or one that no migration can repair is an error, never silently dropped.
+//!
+//! To change a record, add a migration to the end of its
+//! [`Versioned::MIGRATIONS`]; the current version is their count.
+//!
+//! ```
+//! use psyche::models::versioned::{parse_line, to_line, Versioned};
+//! use psyche::models::MemoryEntry;
+//! let legacy = r#"{"id":"9a0c1f4e-8f53-4c4b-9d4e-7b7a4a3f1e2d","kind":"recall","when":"2024-05-01T12:00:00Z","what":[]}"#;
+//! let entry: MemoryEntry = parse_line(legacy).unwrap();
+//! assert_eq!(entry.when.timestamp(), 1_714_564_800);
+//! assert_eq!(entry.how, "");
+//! let line = to_line(&entry).unwrap();
+//! assert!(line.contains(&format!("\"version\":{}", MemoryEntry::current_version())));
+//! ```
+//!
+//! Fields holding a record inside another can use this module with
+//! `#[serde(with = "psyche::models::versioned")]`.
+
+use super::{MemoryEntry, Sensation};
+use crate::memory::Experience;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Value};
+
+/// Field carrying the schema version of a record.
+pub const VERSION_FIELD: &str = "version";
+
+/// Upgrade of a record's fields by one version.
+pub type Migration = fn(&mut Map<String, Value>) -> anyhow::Result<()>;
+
+/// A record type with a schema version.
+pub trait Versioned: Serialize + DeserializeOwned {
+    /// Name of the record type in reports.
+    const NAME: &'static str;
+
+    /// `MIGRATIONS[n]` upgrades a record from version `n` to `n + 1`.
+    const MIGRATIONS: &'static [Migration];
+
+    /// Version written by this build.
+    fn current_version() -> u32 {
+        Self::MIGRATIONS.len() as u32
+    }
+}
+
+/// Turn an RFC 3339 `when` into the Unix seconds records are written with.
+fn when_as_seconds(fields: &mut Map<String, Value>) -> anyhow::Result<()> {
+    if let Some(Value::String(when)) = fields.get("when") {
+        let when = chrono::DateTime::parse_from_rfc3339(when)?;
+        fields.insert("when".into(), when.timestamp().into());
+    }
+    Ok(())
+}
+
+/// Version 1 of [`MemoryEntry`]: `when` in Unix seconds, as `rememberd`
+/// once wrote recalls with RFC 3339 times, and `how` and `what` present.
+fn memory_entry_v1(fields: &mut Map<String, Value>) -> anyhow::Result<()> {
+    when_as_seconds(fields)?;
+    fields.entry("how").or_insert_with(|| "".into());
+    fields.entry("what").or_insert(Value::Null);
+    Ok(())
+}
+
+/// Version 1 of [`Sensation`] only adds the version stamp.
+fn sensation_v1(_: &mut Map<String, Value>) -> anyhow::Result<()> {
+    Ok(())
+}
+
+/// Version 1 of [`Experience`]: `when` in Unix seconds and `tags` present.
+fn experience_v1(fields: &mut Map<String, Value>) -> anyhow::Result<()> {
+    when_as_seconds(fields)?;
+    fields
+        .entry("tags")
+        .or_insert_with(|| Value::Array(Vec::new()));
+    Ok(())
+}
+
+impl Versioned for MemoryEntry {
+    const NAME: &'static str = "memory entry";
+    const MIGRATIONS: &'static [Migration] = &[memory_entry_v1];
+}
+
+impl Versioned for Sensation {
+    const NAME: &'static str = "sensation";
+    const MIGRATIONS: &'static [Migration] = &[sensation_v1];
+}
+
+impl Versioned for Experience {
+    const NAME: &'static str = "experience";
+    const MIGRATIONS: &'static [Migration] = &[experience_v1];
+}
+
+/// Why a line could not be read.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RecordError {
+    /// Not a JSON object.
+    Malformed(String),
+    /// Written by a newer build.
+    Newer {
+        /// Version of the line.
+        version: u32,
+        /// Newest version this build knows.
+        current: u32,
+    },
+    /// Migrating or decoding failed.
+    Invalid {
+        /// Version of the line.
+        version: u32,
+        /// What went wrong.
+        error: String,
+    },
+}
+
+impl std::fmt::Display for RecordError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Malformed(e) => write!(f, "malformed record: {e}"),
+            Self::Newer { version, current } => {
+                write!(f, "record version {version} is newer than {current}")
+            }
+            Self::Invalid { version, error } => {
+                write!(f, "invalid version {version} record: {error}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RecordError {}
+
+/// Schema version of `value`; 0 when it has none.
+pub fn version_of(value: &Value) -> Result<u32, RecordError> {
+    match value.get(VERSION_FIELD) {
+        None => Ok(0),
+        Some(v) => v
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| RecordError::Malformed(format!("bad version {v}"))),
+    }
+}
+
+/// `record` as JSON with the current version stamped on it.
+pub fn stamp<T: Versioned>(record: &T) -> anyhow::Result<Value> {
+    let mut value = serde_json::to_value(record)?;
+    let Value::Object(fields) = &mut value else {
+        anyhow::bail!("{} does not serialize to an object", T::NAME);
+    };
+    fields.insert(VERSION_FIELD.into(), T::current_version().into());
+    Ok(value)
+}
+
+/// `record` as one JSONL line, without the newline.
+pub fn to_line<T: Versioned>(record: &T) -> anyhow::Result<String> {
+    Ok(stamp(record)?.to_string())
+}
+
+/// Bring `value` to the current version of `T`, stamped.
+pub fn migrate<T: Versioned>(value: Value) -> Result<Value, RecordError> {
+    let version = version_of(&value)?;
+    let current = T::current_version();
+    if version > current {
+        return Err(RecordError::Newer { version, current });
+    }
+    let Value::Object(mut fields) = value else {
+        return Err(RecordError::Malformed(format!(
+            "{} is not an object",
+            T::NAME
+        )));
+    };
+    for migration in &T::MIGRATIONS[version as usize..] {
+        migration(&mut fields).map_err(|e| RecordError::Invalid {
+            version,
+            error: e.to_string(),
+        })?;
+    }
+    fields.insert(VERSION_FIELD.into(), current.into());
+    Ok(Value::Object(fields))
+}
+
+/// Read a record of any known version.
+pub fn upgrade<T: Versioned>(value: Value) -> Result<T, RecordError> {
+    let version = version_of(&value)?;
+    serde_json::from_value(migrate::<T>(value)?).map_err(|e| RecordError::Invalid {
+        version,
+        error: e.to_string(),
+    })
+}
+
+/// Read one JSONL line holding a record of any known version.
+pub fn parse_line<T: Versioned>(line: &str) -> Result<T, RecordError> {
+    let value = serde_json::from_str(line).map_err(|e| RecordError::Malformed(e.to_string()))?;
+    upgrade(value)
+}
+
+/// Serialize a nested record with its version, for `#[serde(with)]`.
+pub fn serialize<T: Versioned, S: Serializer>(
+    record: &T,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    stamp(record)
+        .map_err(serde::ser::Error::custom)?
+        .serialize(serializer)
+}
+
+/// Deserialize a nested record of any known version, for `#[serde(with)]`.
+pub fn deserialize<'de, T: Versioned, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<T, D::Error> {
+    upgrade(Value::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+}
+
+/// Record types found in `rememberd` JSONL files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordType {
+    /// [`Sensation`]s, in `sensation.jsonl`.
+    Sensation,
+    /// [`MemoryEntry`]s, in the files of every other kind.
+    MemoryEntry,
+    /// [`Experience`]s.
+    Experience,
+}
+
+impl RecordType {
+    /// Type of the entries stored under memory `kind`.
+    pub fn for_kind(kind: &str) -> Self {
+        if kind.split('/').next() == Some("sensation") {
+            Self::Sensation
+        } else {
+            Self::MemoryEntry
+        }
+    }
+
+    /// Version written by this build.
+    pub fn current_version(self) -> u32 {
+        match self {
+            Self::Sensation => Sensation::current_version(),
+            Self::MemoryEntry => MemoryEntry::current_version(),
+            Self::Experience => Experience::current_version(),
+        }
+    }
+
+    /// What `line` holds: its version if it reads as this type.
+    pub fn check(self, line: &str) -> Result<u32, RecordError> {
+        let value: Value =
+            serde_json::from_str(line).map_err(|e| RecordError::Malformed(e.to_string()))?;
+        let version = version_of(&value)?;
+        match self {
+            Self::Sensation => upgrade::<Sensation>(value).map(drop),
+            Self::MemoryEntry => upgrade::<MemoryEntry>(value).map(drop),
+            Self::Experience => upgrade::<Experience>(value).map(drop),
+        }?;
+        Ok(version)
+    }
+
+    /// `line` upgraded to the current version, keeping fields the record
+    /// type does not know.
+    pub fn upgrade_line(self, line: &str) -> Result<String, RecordError> {
+        self.check(line)?;
+        let value: Value =
+            serde_json::from_str(line).map_err(|e| RecordError::Malformed(e.to_string()))?;
+        let value = match self {
+            Self::Sensation => migrate::<Sensation>(value),
+            Self::MemoryEntry => migrate::<MemoryEntry>(value),
+            Self::Experience => migrate::<Experience>(value),
+        }?;
+        Ok(value.to_string())
+    }
+}
+
+/// A line that is not at the current version.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LineIssue {
+    /// Line number, from 1.
+    pub line: usize,
+    /// Version of a legacy line.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<u32>,
+    /// Why an unparseable line could not be read.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Outcome of checking the lines of one JSONL file.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LinesReport {
+    /// Non-empty lines.
+    pub lines: usize,
+    /// Lines at the current version.
+    pub current: usize,
+    /// Lines at an older version, readable through migrations.
+    pub legacy: Vec<LineIssue>,
+    /// Lines that cannot be read at all.
+    pub unparseable: Vec<LineIssue>,
+}
+
+impl LinesReport {
+    /// Whether every line is at the current version.
+    pub fn is_clean(&self) -> bool {
+        self.legacy.is_empty() && self.unparseable.is_empty()
+    }
+}
+
+/// Check every line of `text` as `record_type`.
+pub fn verify_lines(record_type: RecordType, text: &str) -> LinesReport {
+    let current = record_type.current_version();
+    let mut report = LinesReport::default();
+    for (n, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        report.lines += 1;
+        match record_type.check(line) {
+            Ok(version) if version == current => report.current += 1,
+            Ok(version) => report.legacy.push(LineIssue {
+                line: n + 1,
+                version: Some(version),
+                error: None,
+            }),
+            Err(e) => report.unparseable.push(LineIssue {
+                line: n + 1,
+                version: None,
+                error: Some(e.to_string()),
+            }),
+        }
+    }
+    report
+}
+
+/// Upgrade every line of `text` to the current version of `record_type`.
+/// Unparseable lines are kept unchanged and reported.
+pub fn migrate_lines(record_type: RecordType, text: &str) -> (String, LinesReport) {
+    let report = verify_lines(record_type, text);
+    let mut out = String::with_capacity(text.len());
+    for (n, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let legacy = report.legacy.iter().any(|l| l.line == n + 1);
+        match record_type.upgrade_line(line) {
+            Ok(upgraded) if legacy => out.push_str(&upgraded),
+            _ => out.push_str(line),
+        }
+        out.push('\n');
+    }
+    (out, report)
+}
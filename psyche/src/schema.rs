@@ -0,0 +1,236 @@
+//! Minimal JSON Schema validation for structured LLM output.
+//!
+//! Supports the subset of JSON Schema that models are usually constrained
+//! with: `type`, `enum`, `const`, `properties`, `required`,
+//! `additionalProperties`, `items`, `minItems`/`maxItems`,
+//! `minLength`/`maxLength` and `minimum`/`maximum`. Unknown keywords are
+//! ignored.
+
+use serde_json::Value;
+
+/// Check `value` against `schema`, returning every violation found.
+///
+/// Each error names the offending location as a JSON pointer.
+///
+/// ```
+/// use psyche::schema::validate;
+/// use serde_json::json;
+/// let schema = json!({
+///     "type": "object",
+///     "properties": {"urge": {"type": "string"}},
+///     "required": ["urge"]
+/// });
+/// assert!(validate(&schema, &json!({"urge": "say"})).is_ok());
+/// let errors = validate(&schema, &json!({"urge": 3})).unwrap_err();
+/// assert_eq!(errors, vec!["/urge: expected string, got number".to_string()]);
+/// ```
+pub fn validate(schema: &Value, value: &Value) -> Result<(), Vec<String>> {
+    let mut errors = Vec::new();
+    check(schema, value, "", &mut errors);
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Parse a model reply as JSON, tolerating Markdown code fences and
+/// surrounding prose.
+///
+/// ```
+/// use psyche::schema::parse_reply;
+/// use serde_json::json;
+/// assert_eq!(parse_reply("```json\n{\"a\": 1}\n```"), Some(json!({"a": 1})));
+/// assert_eq!(parse_reply("Sure: {\"a\": 1}"), Some(json!({"a": 1})));
+/// assert_eq!(parse_reply("no json here"), None);
+/// ```
+pub fn parse_reply(text: &str) -> Option<Value> {
+    let trimmed = text.trim();
+    if let Ok(v) = serde_json::from_str(trimmed) {
+        return Some(v);
+    }
+    let unfenced = trimmed
+        .strip_prefix("```json")
+        .or_else(|| trimmed.strip_prefix("```"))
+        .and_then(|t| t.strip_suffix("```"));
+    if let Some(Ok(v)) = unfenced.map(|t| serde_json::from_str(t.trim())) {
+        return Some(v);
+    }
+    for (open, close) in [('{', '}'), ('[', ']')] {
+        if let (Some(start), Some(end)) = (trimmed.find(open), trimmed.rfind(close)) {
+            if start < end {
+                if let Ok(v) = serde_json::from_str(&trimmed[start..=end]) {
+                    return Some(v);
+                }
+            }
+        }
+    }
+    None
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn matches_type(expected: &str, value: &Value) -> bool {
+    let actual = type_name(value);
+    expected == actual || (expected == "number" && actual == "integer")
+}
+
+fn check(schema: &Value, value: &Value, path: &str, errors: &mut Vec<String>) {
+    let Some(schema) = schema.as_object() else {
+        if schema == &Value::Bool(false) {
+            errors.push(format!("{}: not allowed", display(path)));
+        }
+        return;
+    };
+
+    if let Some(expected) = schema.get("type") {
+        let allowed: Vec<&str> = match expected {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(ts) => ts.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !allowed.is_empty() && !allowed.iter().any(|t| matches_type(t, value)) {
+            errors.push(format!(
+                "{}: expected {}, got {}",
+                display(path),
+                allowed.join(" or "),
+                match type_name(value) {
+                    "integer" => "number",
+                    other => other,
+                }
+            ));
+            return;
+        }
+    }
+    if let Some(Value::Array(options)) = schema.get("enum") {
+        if !options.contains(value) {
+            errors.push(format!(
+                "{}: expected one of {}",
+                display(path),
+                Value::Array(options.clone())
+            ));
+        }
+    }
+    if let Some(expected) = schema.get("const") {
+        if expected != value {
+            errors.push(format!("{}: expected {}", display(path), expected));
+        }
+    }
+
+    match value {
+        Value::Object(map) => {
+            if let Some(Value::Array(required)) = schema.get("required") {
+                for key in required.iter().filter_map(Value::as_str) {
+                    if !map.contains_key(key) {
+                        errors.push(format!("{}: missing property {:?}", display(path), key));
+                    }
+                }
+            }
+            let properties = schema.get("properties").and_then(Value::as_object);
+            for (key, item) in map {
+                let child = format!("{}/{}", path, key);
+                match properties.and_then(|p| p.get(key)) {
+                    Some(sub) => check(sub, item, &child, errors),
+                    None => match schema.get("additionalProperties") {
+                        Some(Value::Bool(false)) => {
+                            errors.push(format!("{}: unexpected property", child));
+                        }
+                        Some(sub @ Value::Object(_)) => check(sub, item, &child, errors),
+                        _ => {}
+                    },
+                }
+            }
+        }
+        Value::Array(items) => {
+            bound(
+                schema,
+                "minItems",
+                "maxItems",
+                items.len(),
+                "items",
+                path,
+                errors,
+            );
+            if let Some(sub) = schema.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    check(sub, item, &format!("{}/{}", path, i), errors);
+                }
+            }
+        }
+        Value::String(s) => {
+            let len = s.chars().count();
+            bound(
+                schema,
+                "minLength",
+                "maxLength",
+                len,
+                "characters",
+                path,
+                errors,
+            );
+        }
+        Value::Number(n) => {
+            let n = n.as_f64().unwrap_or_default();
+            if let Some(min) = schema.get("minimum").and_then(Value::as_f64) {
+                if n < min {
+                    errors.push(format!("{}: must be at least {}", display(path), min));
+                }
+            }
+            if let Some(max) = schema.get("maximum").and_then(Value::as_f64) {
+                if n > max {
+                    errors.push(format!("{}: must be at most {}", display(path), max));
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn bound(
+    schema: &serde_json::Map<String, Value>,
+    min_key: &str,
+    max_key: &str,
+    len: usize,
+    unit: &str,
+    path: &str,
+    errors: &mut Vec<String>,
+) {
+    if let Some(min) = schema.get(min_key).and_then(Value::as_u64) {
+        if (len as u64) < min {
+            errors.push(format!(
+                "{}: needs at least {} {}",
+                display(path),
+                min,
+                unit
+            ));
+        }
+    }
+    if let Some(max) = schema.get(max_key).and_then(Value::as_u64) {
+        if (len as u64) > max {
+            errors.push(format!(
+                "{}: allows at most {} {}",
+                display(path),
+                max,
+                unit
+            ));
+        }
+    }
+}
+
+fn display(path: &str) -> &str {
+    if path.is_empty() {
+        "/"
+    } else {
+        path
+    }
+}
@@ -0,0 +1,377 @@
+//! Soul archives: one file holding everything that makes an instance itself.
+//!
+//! An archive is JSON Lines. The first line is a header naming the
+//! [`ARCHIVE_FORMAT`], its version and how many records of each type follow,
+//! so a reader can tell what it holds and notice a truncated copy. Each
+//! further line is one [`Record`]:
+//!
+//! - `file`: a configuration file of the soul, such as `identity.toml`,
+//!   `self.txt` or `config/llm.toml`, by path relative to the soul;
+//! - `entry`: one line of a `memory/<kind>.jsonl` file kept by `rememberd`,
+//!   or of `memory/archive/<kind>.jsonl` when consolidation archived it.
+//!   `face` entries carry their embeddings, and `rememberd` indexes them
+//!   into Qdrant again when it starts;
+//! - `experience`, `edge` and `entity`: the [`MemoryDump`] of the memory
+//!   backend, with ids and embeddings as stored.
+//!
+//! ```
+//! use psyche::memory::{Experience, HnswBackend, MemoryBackend};
+//! use psyche::soul::SoulArchive;
+//! # tokio_test::block_on(async {
+//! let soul = tempfile::tempdir().unwrap();
+//! std::fs::write(soul.path().join("identity.toml"), "name = \"Layka\"").unwrap();
+//! let backend = HnswBackend::open(soul.path().join("memory/index")).unwrap();
+//! let exp = Experience {
+//!     how: "A bird sang.".into(),
+//!     what: "tweet".into(),
+//!     when: chrono::DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+//!     tags: vec!["sensation/ear".into()],
+//! };
+//! let id = backend.store(&exp, &[1.0, 0.0]).await.unwrap();
+//!
+//! let mut bytes = Vec::new();
+//! SoulArchive::collect(soul.path(), Some(&backend)).await.unwrap().write(&mut bytes).unwrap();
+//!
+//! let copy = tempfile::tempdir().unwrap();
+//! let restored = HnswBackend::open(copy.path().join("memory/index")).unwrap();
+//! SoulArchive::read(&bytes[..]).unwrap().restore(copy.path(), Some(&restored)).await.unwrap();
+//! assert_eq!(restored.get(&id).await.unwrap(), Some(exp));
+//! assert!(copy.path().join("identity.toml").exists());
+//! # });
+//! ```
+
+use crate::memory::{Edge, Entity, ExperienceRecord, MemoryBackend, MemoryDump};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::{BufRead, Write};
+use std::path::{Component, Path};
+use tracing::{debug, info};
+
+/// Name in the header of every soul archive.
+pub const ARCHIVE_FORMAT: &str = "psyche-soul";
+
+/// Archive version written by this build; older versions can be read.
+pub const ARCHIVE_VERSION: u32 = 1;
+
+/// Configuration files archived when present, relative to the soul.
+pub const SOUL_FILES: &[&str] = &[
+    "identity.toml",
+    "self.txt",
+    "config/llm.toml",
+    "memory/policy.toml",
+];
+
+/// Directory of prompt partials, archived as a whole.
+const PROMPTS: &str = "prompts";
+
+/// How many records of each type an archive holds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Contents {
+    /// Configuration files.
+    pub files: usize,
+    /// `rememberd` entries, live and archived.
+    pub entries: usize,
+    /// Experiences of the memory backend.
+    pub experiences: usize,
+    /// Links of the memory backend.
+    pub edges: usize,
+    /// Entities of the memory backend.
+    pub entities: usize,
+}
+
+/// First line of an archive.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Header {
+    /// Always [`ARCHIVE_FORMAT`].
+    pub format: String,
+    /// Version of the layout.
+    pub version: u32,
+    /// When the archive was written.
+    pub created: chrono::DateTime<chrono::Utc>,
+    /// What follows the header.
+    pub contents: Contents,
+}
+
+/// A configuration file of the soul.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SoulFile {
+    /// Path relative to the soul, with `/` separators.
+    pub path: String,
+    /// File contents.
+    pub text: String,
+}
+
+/// One line of a `rememberd` JSONL file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+    /// Memory kind, the file name without `.jsonl`.
+    pub kind: String,
+    /// Whether the line was moved to `memory/archive/` by consolidation.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub archived: bool,
+    /// The entry as stored.
+    pub value: Value,
+}
+
+/// Line of an archive after the header.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Record {
+    /// A configuration file.
+    File(SoulFile),
+    /// A `rememberd` entry.
+    Entry(Entry),
+    /// An experience of the memory backend.
+    Experience(ExperienceRecord),
+    /// A link of the memory backend.
+    Edge(Edge),
+    /// An entity of the memory backend.
+    Entity(Entity),
+}
+
+/// Everything exported from one soul.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SoulArchive {
+    /// Configuration files.
+    pub files: Vec<SoulFile>,
+    /// `rememberd` entries in file order.
+    pub entries: Vec<Entry>,
+    /// Contents of the memory backend.
+    pub memory: MemoryDump,
+}
+
+impl SoulArchive {
+    /// Gather the configuration files and `rememberd` entries under `soul`
+    /// and dump `backend`, if any.
+    pub async fn collect<B: MemoryBackend + ?Sized>(
+        soul: &Path,
+        backend: Option<&B>,
+    ) -> anyhow::Result<Self> {
+        let mut archive = Self::default();
+        for path in SOUL_FILES {
+            if let Some(text) = read_optional(&soul.join(path))? {
+                archive.files.push(SoulFile {
+                    path: path.to_string(),
+                    text,
+                });
+            }
+        }
+        for name in sorted_files(&soul.join(PROMPTS), None)? {
+            let path = format!("{PROMPTS}/{name}");
+            if let Some(text) = read_optional(&soul.join(&path))? {
+                archive.files.push(SoulFile { path, text });
+            }
+        }
+        let memory = soul.join("memory");
+        for (dir, archived) in [(memory.clone(), false), (memory.join("archive"), true)] {
+            for name in sorted_files(&dir, Some("jsonl"))? {
+                let kind = name.trim_end_matches(".jsonl").to_string();
+                let text = std::fs::read_to_string(dir.join(&name))?;
+                for line in text.lines().filter(|l| !l.trim().is_empty()) {
+                    archive.entries.push(Entry {
+                        kind: kind.clone(),
+                        archived,
+                        value: serde_json::from_str(line)?,
+                    });
+                }
+            }
+        }
+        if let Some(backend) = backend {
+            archive.memory = backend.dump().await?;
+        }
+        debug!(contents = ?archive.contents(), "collected soul");
+        Ok(archive)
+    }
+
+    /// Count the records of each type.
+    pub fn contents(&self) -> Contents {
+        Contents {
+            files: self.files.len(),
+            entries: self.entries.len(),
+            experiences: self.memory.experiences.len(),
+            edges: self.memory.edges.len(),
+            entities: self.memory.entities.len(),
+        }
+    }
+
+    /// Write the archive to `out`, header first.
+    pub fn write(&self, mut out: impl Write) -> anyhow::Result<()> {
+        let header = Header {
+            format: ARCHIVE_FORMAT.into(),
+            version: ARCHIVE_VERSION,
+            created: chrono::Utc::now(),
+            contents: self.contents(),
+        };
+        serde_json::to_writer(&mut out, &header)?;
+        out.write_all(b"\n")?;
+        let records = self
+            .files
+            .iter()
+            .cloned()
+            .map(Record::File)
+            .chain(self.entries.iter().cloned().map(Record::Entry))
+            .chain(
+                self.memory
+                    .experiences
+                    .iter()
+                    .cloned()
+                    .map(Record::Experience),
+            )
+            .chain(self.memory.edges.iter().cloned().map(Record::Edge))
+            .chain(self.memory.entities.iter().cloned().map(Record::Entity));
+        for record in records {
+            serde_json::to_writer(&mut out, &record)?;
+            out.write_all(b"\n")?;
+        }
+        out.flush()?;
+        Ok(())
+    }
+
+    /// Read an archive, checking its header and that nothing is missing.
+    pub fn read(input: impl BufRead) -> anyhow::Result<Self> {
+        let mut lines = input.lines();
+        let header: Header = match lines.next() {
+            Some(line) => serde_json::from_str(&line?)?,
+            None => anyhow::bail!("empty soul archive"),
+        };
+        if header.format != ARCHIVE_FORMAT {
+            anyhow::bail!("not a soul archive: format {}", header.format);
+        }
+        if header.version > ARCHIVE_VERSION {
+            anyhow::bail!(
+                "soul archive version {} is newer than {}",
+                header.version,
+                ARCHIVE_VERSION
+            );
+        }
+        let mut archive = Self::default();
+        for (n, line) in lines.enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record = serde_json::from_str(&line)
+                .map_err(|e| anyhow::anyhow!("archive line {}: {}", n + 2, e))?;
+            match record {
+                Record::File(file) => archive.files.push(file),
+                Record::Entry(entry) => archive.entries.push(entry),
+                Record::Experience(exp) => archive.memory.experiences.push(exp),
+                Record::Edge(edge) => archive.memory.edges.push(edge),
+                Record::Entity(entity) => archive.memory.entities.push(entity),
+            }
+        }
+        if archive.contents() != header.contents {
+            anyhow::bail!(
+                "soul archive is incomplete: header lists {:?}, found {:?}",
+                header.contents,
+                archive.contents()
+            );
+        }
+        Ok(archive)
+    }
+
+    /// Recreate the soul under `soul` and load the memory into `backend`.
+    /// Existing files are never overwritten and the backend must be empty,
+    /// so an import always starts from a fresh soul.
+    pub async fn restore<B: MemoryBackend + ?Sized>(
+        &self,
+        soul: &Path,
+        backend: Option<&B>,
+    ) -> anyhow::Result<()> {
+        let mut targets: Vec<String> = self.files.iter().map(|f| f.path.clone()).collect();
+        for entry in &self.entries {
+            targets.push(entry_path(entry)?);
+        }
+        targets.sort();
+        targets.dedup();
+        for path in &targets {
+            check_relative(path)?;
+            if soul.join(path).exists() {
+                anyhow::bail!("{} already exists in {}", path, soul.display());
+            }
+        }
+        if let Some(backend) = backend {
+            backend.restore(&self.memory).await?;
+        } else if !self.memory.is_empty() {
+            anyhow::bail!("archive holds memory but no backend was given");
+        }
+        for file in &self.files {
+            let path = soul.join(&file.path);
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            std::fs::write(path, &file.text)?;
+        }
+        let mut text: std::collections::BTreeMap<String, String> = Default::default();
+        for entry in &self.entries {
+            let lines = text.entry(entry_path(entry)?).or_default();
+            lines.push_str(&serde_json::to_string(&entry.value)?);
+            lines.push('\n');
+        }
+        for (path, lines) in text {
+            let path = soul.join(path);
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            std::fs::write(path, lines)?;
+        }
+        info!(soul = %soul.display(), contents = ?self.contents(), "restored soul");
+        Ok(())
+    }
+}
+
+/// Path of the JSONL file holding `entry`, relative to the soul.
+fn entry_path(entry: &Entry) -> anyhow::Result<String> {
+    if entry.kind.is_empty() || entry.kind.contains(['/', '\\']) {
+        anyhow::bail!("invalid entry kind {:?}", entry.kind);
+    }
+    Ok(if entry.archived {
+        format!("memory/archive/{}.jsonl", entry.kind)
+    } else {
+        format!("memory/{}.jsonl", entry.kind)
+    })
+}
+
+/// Refuse paths that would leave the soul directory.
+fn check_relative(path: &str) -> anyhow::Result<()> {
+    let inside = Path::new(path)
+        .components()
+        .all(|c| matches!(c, Component::Normal(_)));
+    if path.is_empty() || !inside {
+        anyhow::bail!("refusing to write outside the soul: {}", path);
+    }
+    Ok(())
+}
+
+fn read_optional(path: &Path) -> anyhow::Result<Option<String>> {
+    match std::fs::read_to_string(path) {
+        Ok(text) => Ok(Some(text)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Names of the regular files in `dir` with `extension`, or all of them,
+/// sorted. A missing directory has none.
+fn sorted_files(dir: &Path, extension: Option<&str>) -> anyhow::Result<Vec<String>> {
+    let read = match std::fs::read_dir(dir) {
+        Ok(read) => read,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut names = Vec::new();
+    for file in read {
+        let file = file?;
+        let path = file.path();
+        if !file.file_type()?.is_file()
+            || extension.is_some_and(|ext| path.extension().and_then(|e| e.to_str()) != Some(ext))
+        {
+            continue;
+        }
+        if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
+            names.push(name.to_string());
+        }
+    }
+    names.sort();
+    Ok(names)
+}
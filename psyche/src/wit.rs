@@ -1,10 +1,13 @@
-use crate::llm::{CanChat, LlmProfile};
+use crate::llm::prompt::{PromptContext, PromptHelper};
+use crate::llm::{CanChat, ChatMessage, GenerationOptions, LlmProfile};
+use crate::memory::Experience;
 use crate::models::{Instant, MemoryEntry, Sensation};
+use crate::schema;
 use crate::utils::{first_sentence, parse_json_or_string};
 use chrono::Utc;
 use serde_json::Value;
 use tokio_stream::StreamExt;
-use tracing::{debug, trace};
+use tracing::{debug, trace, warn};
 use uuid::Uuid;
 
 /// Distills a raw `Sensation` into an `Instant` when possible.
@@ -23,6 +26,16 @@ pub fn distill(sensation: &Sensation) -> Option<Instant> {
     }
 }
 
+/// Kind of the entry stored when a wit's reply never matched its
+/// [`WitConfig::output_schema`].
+pub const SCHEMA_ERROR_KIND: &str = "error/schema";
+
+/// Follow-up sent when a reply does not match the output schema. Sees the
+/// validation `errors` and the `schema` as pretty-printed JSON.
+pub const REPAIR_PROMPT: &str = "Your reply did not match the required JSON schema:\n\
+{% for e in errors %}- {{ e }}\n{% endfor %}\
+Reply again with only a JSON value matching this schema:\n{{ schema }}";
+
 /// Configuration for a [`Wit`].
 #[derive(Clone, Debug)]
 pub struct WitConfig {
@@ -32,12 +45,25 @@ pub struct WitConfig {
     pub input_kind: String,
     /// Kind of memory entry produced.
     pub output_kind: String,
-    /// Prompt template used when chatting with the LLM. The literal "{input}" is
-    /// replaced with the joined input contents.
+    /// Prompt template rendered by [`PromptHelper::render`]. `{{ input }}`
+    /// (or the legacy `{input}`) expands to the joined input contents.
     pub prompt_template: String,
     /// Optional post-processing hook applied to the LLM response.
     /// Receives the input entries and LLM response.
     pub post_process: Option<fn(&[MemoryEntry], &str) -> anyhow::Result<Value>>,
+    /// Sampling options passed with every LLM call.
+    pub options: GenerationOptions,
+    /// Scheduling priority of this wit's LLM calls, used unless
+    /// [`GenerationOptions::priority`] is set. Lower values are served first.
+    pub priority: usize,
+    /// Few-shot example turns sent ahead of the rendered prompt.
+    pub examples: Vec<ChatMessage>,
+    /// JSON schema the reply must match. Sent to the model as the `format`
+    /// constraint unless [`GenerationOptions::format`] is already set.
+    pub output_schema: Option<Value>,
+    /// How many repair prompts to send after a reply fails validation
+    /// before giving up with a [`SCHEMA_ERROR_KIND`] entry.
+    pub max_repairs: usize,
 }
 
 /// General-purpose wit powered by a language model.
@@ -48,11 +74,22 @@ pub struct Wit {
     pub llm: Box<dyn CanChat>,
     /// Profile for the bound LLM.
     pub profile: LlmProfile,
+    /// Renders the prompt template with identity and input context.
+    pub prompter: PromptHelper,
 }
 
 impl Wit {
     /// Distill a group of memory entries into a single output memory entry.
     pub async fn distill(&mut self, input: Vec<MemoryEntry>) -> anyhow::Result<Vec<MemoryEntry>> {
+        self.distill_with_memories(input, &[]).await
+    }
+
+    /// Like [`Wit::distill`], also exposing `memories` to the prompt template.
+    pub async fn distill_with_memories(
+        &mut self,
+        input: Vec<MemoryEntry>,
+        memories: &[Experience],
+    ) -> anyhow::Result<Vec<MemoryEntry>> {
         // Filter by input kind
         let entries: Vec<_> = input
             .into_iter()
@@ -66,35 +103,51 @@ impl Wit {
             return Ok(Vec::new());
         }
 
-        // Join inputs into a single prompt string
-        let joined = entries
-            .iter()
-            .map(|e| {
-                if !e.how.is_empty() {
-                    e.how.clone()
-                } else {
-                    e.what
-                        .as_str()
-                        .map(|s| s.to_string())
-                        .unwrap_or_else(|| e.what.to_string())
-                }
-            })
-            .collect::<Vec<_>>()
-            .join("\n");
-
-        let prompt = self.config.prompt_template.replace("{input}", &joined);
+        let ctx = PromptContext::new().input(&entries).memories(memories);
+        let prompt = self.prompter.render(&self.config.prompt_template, &ctx)?;
 
         trace!(target = "llm", prompt = %prompt, "wit prompt");
-        let mut stream = self.llm.chat_stream(&self.profile, "", &prompt).await?;
-        let mut resp = String::new();
-        while let Some(token) = stream.next().await {
-            resp.push_str(&token);
+        let mut options = self.config.options.clone();
+        options.wit.get_or_insert_with(|| self.config.name.clone());
+        options.priority.get_or_insert(self.config.priority);
+        if options.format.is_none() {
+            options.format = self.config.output_schema.clone();
         }
-        debug!(target = "llm", response = %resp, "wit response");
+        let mut messages = self.config.examples.clone();
+        messages.push(ChatMessage::user(prompt));
+        let mut attempts = 0;
+        let (resp, parsed) = loop {
+            attempts += 1;
+            let resp = self.complete(&messages, &options).await?;
+            let Some(schema) = &self.config.output_schema else {
+                break (resp, None);
+            };
+            let errors = match schema::parse_reply(&resp) {
+                Some(value) => match schema::validate(schema, &value) {
+                    Ok(()) => break (resp, Some(value)),
+                    Err(errors) => errors,
+                },
+                None => vec!["reply is not valid JSON".to_string()],
+            };
+            if attempts > self.config.max_repairs {
+                warn!(wit = %self.config.name, attempts, "wit output failed schema validation");
+                return Ok(vec![self.schema_error(&resp, &errors, attempts)]);
+            }
+            debug!(wit = %self.config.name, ?errors, "repairing wit output");
+            let ctx = PromptContext::new()
+                .var("errors", &errors)
+                .var("schema", serde_json::to_string_pretty(schema)?);
+            messages.push(ChatMessage::assistant(resp));
+            messages.push(ChatMessage::user(
+                self.prompter.render(REPAIR_PROMPT, &ctx)?,
+            ));
+        };
 
         // Optional post-processing
         let value = if let Some(pp) = self.config.post_process {
             pp(&entries, &resp)?
+        } else if let Some(value) = parsed {
+            value
         } else {
             parse_json_or_string(&resp)
         };
@@ -107,6 +160,44 @@ impl Wit {
             how: first_sentence(&resp),
         }])
     }
+
+    /// Run one chat turn and collect the reply.
+    async fn complete(
+        &self,
+        messages: &[ChatMessage],
+        options: &GenerationOptions,
+    ) -> anyhow::Result<String> {
+        let mut stream = self
+            .llm
+            .chat_messages_stream(&self.profile, messages, options)
+            .await?;
+        let mut resp = String::new();
+        while let Some(token) = stream.next().await {
+            resp.push_str(&token);
+        }
+        debug!(target = "llm", response = %resp, "wit response");
+        Ok(resp)
+    }
+
+    /// Entry recording a reply that never matched the output schema.
+    fn schema_error(&self, resp: &str, errors: &[String], attempts: usize) -> MemoryEntry {
+        MemoryEntry {
+            id: Uuid::new_v4(),
+            kind: SCHEMA_ERROR_KIND.to_string(),
+            when: Utc::now(),
+            what: serde_json::json!({
+                "wit": self.config.name,
+                "expected_kind": self.config.output_kind,
+                "attempts": attempts,
+                "errors": errors,
+                "response": resp,
+            }),
+            how: format!(
+                "{} gave no valid {} after {} attempts",
+                self.config.name, self.config.output_kind, attempts
+            ),
+        }
+    }
 }
 
 /// Simple post processor that stores the source entry ids as a JSON array.
@@ -116,3 +207,69 @@ pub fn link_sources(entries: &[MemoryEntry], _resp: &str) -> anyhow::Result<Valu
         .map(|e| e.id)
         .collect::<Vec<_>>()))
 }
+
+/// Kind of the entries written by an [`entity_extractor`] wit.
+pub const ENTITY_KIND: &str = "entity";
+
+/// Prompt of an [`entity_extractor`] wit.
+pub const ENTITY_PROMPT: &str = "List the people, places and objects named in these memories. \
+Give each the fullest name used as `name`, any other names used for it as `aliases`, and whether \
+it is a person, place or object as `kind`. Leave out anything that is not named.\n\n{{ input }}";
+
+/// JSON schema of the reply to [`ENTITY_PROMPT`].
+pub fn entity_schema() -> Value {
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "entities": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "name": {"type": "string", "minLength": 1},
+                        "kind": {"type": "string", "enum": ["person", "place", "object"]},
+                        "aliases": {"type": "array", "items": {"type": "string"}}
+                    },
+                    "required": ["name", "kind"]
+                }
+            }
+        },
+        "required": ["entities"]
+    })
+}
+
+/// Wit extracting the people, places and objects named in entries of
+/// `input_kind`. Its [`ENTITY_KIND`] entries are read by
+/// [`crate::memory::record_entities`].
+///
+/// ```
+/// use psyche::wit::{entity_extractor, ENTITY_KIND};
+/// let config = entity_extractor("situation");
+/// assert_eq!(config.output_kind, ENTITY_KIND);
+/// assert!(config.output_schema.is_some());
+/// ```
+pub fn entity_extractor(input_kind: &str) -> WitConfig {
+    WitConfig {
+        name: "entities".into(),
+        input_kind: input_kind.into(),
+        output_kind: ENTITY_KIND.into(),
+        prompt_template: ENTITY_PROMPT.into(),
+        post_process: Some(link_mentions),
+        options: GenerationOptions::default(),
+        priority: 0,
+        examples: Vec::new(),
+        output_schema: Some(entity_schema()),
+        max_repairs: 1,
+    }
+}
+
+/// Post processor pairing the extracted entities with the ids of the
+/// entries they were named in.
+pub fn link_mentions(entries: &[MemoryEntry], resp: &str) -> anyhow::Result<Value> {
+    let reply = schema::parse_reply(resp)
+        .ok_or_else(|| anyhow::anyhow!("entity reply is not JSON: {}", resp))?;
+    Ok(serde_json::json!({
+        "entities": reply.get("entities").cloned().unwrap_or_else(|| Value::Array(Vec::new())),
+        "mentions": entries.iter().map(|e| e.id.to_string()).collect::<Vec<_>>(),
+    }))
+}
@@ -1,6 +1,6 @@
 use chrono::Utc;
 use psyche::llm::mock_chat::MockChat;
-use psyche::llm::{LlmCapability, LlmProfile};
+use psyche::llm::{CanChat, ChatMessage, GenerationOptions, LlmCapability, LlmProfile};
 use psyche::models::MemoryEntry;
 use psyche::wit::{link_sources, Wit, WitConfig};
 use serde_json::json;
@@ -15,6 +15,11 @@ async fn combobulator_config_distills_chat() {
         output_kind: "instant".into(),
         prompt_template: "{input}".into(),
         post_process: Some(link_sources),
+        options: Default::default(),
+        priority: 0,
+        examples: Vec::new(),
+        output_schema: None,
+        max_repairs: 0,
     };
     let mut d = Wit {
         config: cfg,
@@ -24,6 +29,7 @@ async fn combobulator_config_distills_chat() {
             model: "mock".into(),
             capabilities: vec![LlmCapability::Chat],
         },
+        prompter: Default::default(),
     };
     let entry = MemoryEntry {
         id: entry_id,
@@ -47,6 +53,11 @@ async fn prefix_filter_matches_subkind() {
         output_kind: "instant".into(),
         prompt_template: "{input}".into(),
         post_process: Some(link_sources),
+        options: Default::default(),
+        priority: 0,
+        examples: Vec::new(),
+        output_schema: None,
+        max_repairs: 0,
     };
     let mut d = Wit {
         config: cfg,
@@ -56,6 +67,7 @@ async fn prefix_filter_matches_subkind() {
             model: "mock".into(),
             capabilities: vec![LlmCapability::Chat],
         },
+        prompter: Default::default(),
     };
     let entry = MemoryEntry {
         id: entry_id,
@@ -79,6 +91,11 @@ async fn memory_config_distills_instant() {
         output_kind: "situation".into(),
         prompt_template: "{input}".into(),
         post_process: Some(link_sources),
+        options: Default::default(),
+        priority: 0,
+        examples: Vec::new(),
+        output_schema: None,
+        max_repairs: 0,
     };
     let mut d = Wit {
         config: cfg,
@@ -88,6 +105,7 @@ async fn memory_config_distills_instant() {
             model: "mock".into(),
             capabilities: vec![LlmCapability::Chat],
         },
+        prompter: Default::default(),
     };
     let entry1 = MemoryEntry {
         id: id1,
@@ -108,3 +126,118 @@ async fn memory_config_distills_instant() {
     assert_eq!(out[0].how, "mock response");
     assert_eq!(out[0].what, json!([id1, id2]));
 }
+
+/// Remembers the messages and scheduling priority of the last request.
+#[derive(Default)]
+struct Recorded {
+    messages: Vec<ChatMessage>,
+    priority: Option<usize>,
+}
+
+struct RecordingChat(std::rc::Rc<std::cell::RefCell<Recorded>>);
+
+#[async_trait::async_trait(?Send)]
+impl CanChat for RecordingChat {
+    async fn chat_stream(
+        &self,
+        _profile: &LlmProfile,
+        _system: &str,
+        _user: &str,
+    ) -> anyhow::Result<Box<dyn tokio_stream::Stream<Item = String> + Unpin>> {
+        unreachable!("wits send message histories")
+    }
+
+    async fn chat_messages_stream(
+        &self,
+        _profile: &LlmProfile,
+        messages: &[ChatMessage],
+        options: &GenerationOptions,
+    ) -> anyhow::Result<Box<dyn tokio_stream::Stream<Item = String> + Unpin>> {
+        *self.0.borrow_mut() = Recorded {
+            messages: messages.to_vec(),
+            priority: options.priority,
+        };
+        Ok(Box::new(tokio_stream::iter(["ok".to_string()])))
+    }
+}
+
+#[tokio::test]
+async fn wit_sends_few_shot_examples_before_prompt() {
+    let seen = std::rc::Rc::new(std::cell::RefCell::new(Recorded::default()));
+    let cfg = WitConfig {
+        name: "combobulator".into(),
+        input_kind: "sensation/chat".into(),
+        output_kind: "instant".into(),
+        prompt_template: "Summarize: {input}".into(),
+        post_process: None,
+        options: Default::default(),
+        priority: 0,
+        examples: vec![
+            ChatMessage::user("Summarize: I am cold"),
+            ChatMessage::assistant("They feel cold."),
+        ],
+        output_schema: None,
+        max_repairs: 0,
+    };
+    let mut d = Wit {
+        config: cfg,
+        llm: Box::new(RecordingChat(seen.clone())),
+        profile: LlmProfile {
+            provider: "mock".into(),
+            model: "mock".into(),
+            capabilities: vec![LlmCapability::Chat],
+        },
+        prompter: Default::default(),
+    };
+    let entry = MemoryEntry {
+        id: Uuid::new_v4(),
+        kind: "sensation/chat".into(),
+        when: Utc::now(),
+        what: json!("I feel tired"),
+        how: String::new(),
+    };
+    d.distill(vec![entry]).await.unwrap();
+    assert_eq!(
+        seen.borrow().messages,
+        vec![
+            ChatMessage::user("Summarize: I am cold"),
+            ChatMessage::assistant("They feel cold."),
+            ChatMessage::user("Summarize: I feel tired"),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn wit_requests_carry_its_priority() {
+    let seen = std::rc::Rc::new(std::cell::RefCell::new(Recorded::default()));
+    let mut d = Wit {
+        config: WitConfig {
+            name: "narrator".into(),
+            input_kind: "instant".into(),
+            output_kind: "situation".into(),
+            prompt_template: "{input}".into(),
+            post_process: None,
+            options: Default::default(),
+            priority: 3,
+            examples: Vec::new(),
+            output_schema: None,
+            max_repairs: 0,
+        },
+        llm: Box::new(RecordingChat(seen.clone())),
+        profile: LlmProfile {
+            provider: "mock".into(),
+            model: "mock".into(),
+            capabilities: vec![LlmCapability::Chat],
+        },
+        prompter: Default::default(),
+    };
+    let entry = MemoryEntry {
+        id: Uuid::new_v4(),
+        kind: "instant".into(),
+        when: Utc::now(),
+        what: json!("the rain started"),
+        how: String::new(),
+    };
+    d.distill(vec![entry]).await.unwrap();
+    assert_eq!(seen.borrow().priority, Some(3));
+}
@@ -0,0 +1,162 @@
+use psyche::llm::cassette::{RecordingChat, RecordingEmbed, ReplayChat, ReplayEmbed};
+use psyche::llm::mock_chat::MockChat;
+use psyche::llm::mock_embed::MockEmbed;
+use psyche::llm::{
+    CanChat, CanEmbed, ChatEvent, ChatMessage, GenerationOptions, LlmCapability, LlmProfile,
+    ToolCall, ToolDefinition, ToolResponse,
+};
+use std::sync::Arc;
+use tempfile::tempdir;
+use tokio_stream::{Stream, StreamExt};
+
+/// Chat that picks a tool and breaks off its streamed replies.
+struct BrokenChat;
+
+#[async_trait::async_trait(?Send)]
+impl CanChat for BrokenChat {
+    async fn chat_stream(
+        &self,
+        _profile: &LlmProfile,
+        _system: &str,
+        _user: &str,
+    ) -> anyhow::Result<Box<dyn Stream<Item = String> + Unpin>> {
+        unreachable!("replies are streamed as events")
+    }
+
+    async fn chat_events(
+        &self,
+        _profile: &LlmProfile,
+        _messages: &[ChatMessage],
+        _options: &GenerationOptions,
+    ) -> anyhow::Result<Box<dyn Stream<Item = anyhow::Result<ChatEvent>> + Unpin>> {
+        Ok(Box::new(tokio_stream::iter(vec![
+            Ok(ChatEvent::Token("half a".into())),
+            Err(anyhow::anyhow!("connection reset")),
+        ])))
+    }
+
+    async fn chat_with_tools(
+        &self,
+        _profile: &LlmProfile,
+        messages: &[ChatMessage],
+        _tools: &[ToolDefinition],
+        _options: &GenerationOptions,
+    ) -> anyhow::Result<ToolResponse> {
+        Ok(ToolResponse {
+            content: String::new(),
+            tool_calls: vec![ToolCall {
+                name: "speak".into(),
+                arguments: serde_json::json!({"text": messages[0].content}),
+            }],
+        })
+    }
+}
+
+fn profile() -> LlmProfile {
+    LlmProfile {
+        provider: "mock".into(),
+        model: "mock".into(),
+        capabilities: vec![LlmCapability::Chat, LlmCapability::Embedding],
+    }
+}
+
+#[tokio::test]
+async fn recorded_chat_replays_by_prompt() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("cassette.jsonl");
+
+    let recorder = RecordingChat::new(Arc::new(MockChat), &path);
+    let live: Vec<String> = recorder
+        .chat_stream(&profile(), "be brief", "hello")
+        .await
+        .unwrap()
+        .collect()
+        .await;
+
+    let replay = ReplayChat::load(&path).unwrap();
+    let replayed: Vec<String> = replay
+        .chat_stream(&profile(), "be brief", "hello")
+        .await
+        .unwrap()
+        .collect()
+        .await;
+    assert_eq!(replayed, live);
+
+    let err = replay
+        .chat_stream(&profile(), "be brief", "goodbye")
+        .await
+        .err()
+        .expect("cache miss must fail");
+    assert!(err.to_string().contains("goodbye"));
+}
+
+#[tokio::test]
+async fn recorded_embeddings_replay_by_text() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("cassette.jsonl");
+
+    let recorder = RecordingEmbed::new(Box::new(MockEmbed), &path);
+    let live = recorder.embed(&profile(), "a cat").await.unwrap();
+
+    let replay = ReplayEmbed::load(&path).unwrap();
+    assert_eq!(replay.embed(&profile(), "a cat").await.unwrap(), live);
+    assert!(replay.embed(&profile(), "a dog").await.is_err());
+}
+
+#[test]
+fn replay_rejects_corrupt_cassette() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("cassette.jsonl");
+    std::fs::write(&path, "not json\n").unwrap();
+    assert!(ReplayChat::load(&path).is_err());
+    assert!(ReplayChat::load(&dir.path().join("missing.jsonl")).is_err());
+}
+
+#[tokio::test]
+async fn tool_calls_are_recorded_and_replayed() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("cassette.jsonl");
+    let tools = [ToolDefinition {
+        name: "speak".into(),
+        description: "Say something aloud".into(),
+        parameters: serde_json::json!({"type": "object"}),
+    }];
+    let messages = [ChatMessage::user("hello")];
+    let options = GenerationOptions::default();
+
+    let recorder = RecordingChat::new(Arc::new(BrokenChat), &path);
+    let live = recorder
+        .chat_with_tools(&profile(), &messages, &tools, &options)
+        .await
+        .unwrap();
+
+    let replay = ReplayChat::load(&path).unwrap();
+    let replayed = replay
+        .chat_with_tools(&profile(), &messages, &tools, &options)
+        .await
+        .unwrap();
+    assert_eq!(replayed, live);
+    assert!(replay
+        .chat_with_tools(&profile(), &[ChatMessage::user("bye")], &tools, &options)
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn broken_streams_are_not_recorded() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("cassette.jsonl");
+    let recorder = RecordingChat::new(Arc::new(BrokenChat), &path);
+    let events: Vec<_> = recorder
+        .chat_events(
+            &profile(),
+            &[ChatMessage::user("hello")],
+            &GenerationOptions::default(),
+        )
+        .await
+        .unwrap()
+        .collect()
+        .await;
+    assert!(events.last().unwrap().is_err());
+    assert!(!path.exists());
+}
@@ -0,0 +1,105 @@
+use chrono::{DateTime, Duration, Utc};
+use psyche::memory::{importance, plan, salience, Retention};
+use psyche::models::MemoryEntry;
+use serde_json::json;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+fn now() -> DateTime<Utc> {
+    DateTime::from_timestamp(1_700_000_000, 0).unwrap()
+}
+
+fn entry(kind: &str, how: &str, hours_ago: i64) -> MemoryEntry {
+    MemoryEntry {
+        id: Uuid::new_v4(),
+        kind: kind.into(),
+        when: now() - Duration::hours(hours_ago),
+        what: json!(how),
+        how: how.into(),
+    }
+}
+
+#[test]
+fn importance_grows_with_recall_salience_and_recency() {
+    let retention = Retention::default();
+    let plain = entry("instant", "A door opened.", 100);
+    let base = importance(&plain, 0, now(), &retention);
+    assert!(importance(&plain, 3, now(), &retention) > base);
+    assert!(importance(&entry("instant", "A door opened.", 1), 0, now(), &retention) > base);
+    let moving = entry("instant", "I felt lonely when the door opened.", 100);
+    assert!(salience(&moving) > 0.0);
+    assert!(importance(&moving, 0, now(), &retention) > base);
+    let mut rated = plain.clone();
+    rated.what = json!({ "salience": 2.5 });
+    assert_eq!(salience(&rated), 1.0);
+}
+
+#[test]
+fn merges_duplicates_and_summarizes_unimportant_runs() {
+    let retention = Retention {
+        run_length: 3,
+        ..Default::default()
+    };
+    let beep = entry("instant", "The rover beeped twice.", 60);
+    let beep_again = entry("instant", "The rover beeped twice!", 59);
+    let dull: Vec<_> = (0..4)
+        .map(|n| entry("instant", &format!("Dust number {n} drifted by."), 50 - n))
+        .collect();
+    let treasured = entry("instant", "Dust drifted by again.", 45);
+    let tail: Vec<_> = (0..2)
+        .map(|n| entry("instant", &format!("Rock {n} sat still."), 40 - n))
+        .collect();
+    let other_kind = entry("sensation/chat", "Rock 9 sat still.", 40);
+    let fresh = entry("instant", "Something new.", 1);
+
+    let mut entries = vec![beep.clone(), beep_again.clone(), treasured.clone()];
+    entries.extend(dull.iter().cloned());
+    entries.extend(tail.iter().cloned());
+    entries.push(other_kind.clone());
+    entries.push(fresh.clone());
+    let recalls = HashMap::from([(beep_again.id, 2), (treasured.id, 5)]);
+
+    let plan = plan(&entries, &recalls, &retention, now());
+    assert_eq!(plan.importance.len(), 9);
+    assert!(!plan.importance.contains_key(&fresh.id));
+    assert!(!plan.importance.contains_key(&other_kind.id));
+
+    assert_eq!(plan.merges.len(), 1);
+    assert_eq!(plan.merges[0].keep, beep_again.id);
+    assert_eq!(plan.merges[0].drop, vec![beep.id]);
+
+    // the recalled entry breaks the run; the two rocks are too few
+    assert_eq!(plan.summaries.len(), 1);
+    let summary = &plan.summaries[0];
+    assert_eq!(summary.kind, "instant");
+    assert_eq!(summary.when, dull[3].when);
+    assert_eq!(
+        summary.what,
+        json!(dull.iter().map(|e| e.id).collect::<Vec<_>>())
+    );
+    assert!(summary.how.contains("among 4 moments"), "{}", summary.how);
+
+    let removed = plan.removed();
+    assert_eq!(removed.len(), 5);
+    assert!(removed.contains(&beep.id) && !removed.contains(&treasured.id));
+}
+
+#[test]
+fn caps_summaries_at_max_run() {
+    let retention = Retention {
+        run_length: 2,
+        max_run: 3,
+        duplicate_similarity: 1.1,
+        ..Default::default()
+    };
+    let entries: Vec<_> = (0..7)
+        .map(|n| entry("situation", &format!("Quiet hour {n}."), 100 - n))
+        .collect();
+    let plan = plan(&entries, &HashMap::new(), &retention, now());
+    let sizes: Vec<_> = plan
+        .summaries
+        .iter()
+        .map(|s| s.what.as_array().unwrap().len())
+        .collect();
+    assert_eq!(sizes, [3, 3]);
+}
@@ -0,0 +1,213 @@
+use async_trait::async_trait;
+use psyche::llm::config::LlmProviderConfig;
+use psyche::llm::deadline::{DeadlineChat, Timeouts};
+use psyche::llm::limited::LimitedChat;
+use psyche::llm::pool::{LlmPool, RoutingPolicy};
+use psyche::llm::scheduler::PriorityScheduler;
+use psyche::llm::{
+    CanChat, ChatEvent, ChatMessage, GenerationOptions, LlmCapability, LlmInstance, LlmProfile,
+};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_stream::{Stream, StreamExt};
+
+fn profile() -> LlmProfile {
+    LlmProfile {
+        provider: "mock".into(),
+        model: "mock".into(),
+        capabilities: vec![LlmCapability::Chat],
+    }
+}
+
+/// How a [`HangingChat`] gets stuck.
+#[derive(Clone, Copy)]
+enum Hang {
+    /// Never returns a stream.
+    Connect,
+    /// Returns a stream that never yields.
+    FirstToken,
+    /// Yields one token and then stalls.
+    AfterToken,
+}
+
+struct HangingChat(Hang);
+
+#[async_trait(?Send)]
+impl CanChat for HangingChat {
+    async fn chat_stream(
+        &self,
+        _profile: &LlmProfile,
+        _system: &str,
+        _user: &str,
+    ) -> anyhow::Result<Box<dyn Stream<Item = String> + Unpin>> {
+        unreachable!()
+    }
+
+    async fn chat_events(
+        &self,
+        _profile: &LlmProfile,
+        _messages: &[ChatMessage],
+        _options: &GenerationOptions,
+    ) -> anyhow::Result<Box<dyn Stream<Item = anyhow::Result<ChatEvent>> + Unpin>> {
+        match self.0 {
+            Hang::Connect => std::future::pending().await,
+            Hang::FirstToken => Ok(Box::new(tokio_stream::pending())),
+            Hang::AfterToken => Ok(Box::new(
+                tokio_stream::iter([Ok(ChatEvent::Token("Hel".into()))])
+                    .chain(tokio_stream::pending()),
+            )),
+        }
+    }
+}
+
+fn deadline(hang: Hang, timeouts: Timeouts) -> DeadlineChat {
+    DeadlineChat::new(Arc::new(HangingChat(hang)), timeouts)
+}
+
+async fn events(chat: &dyn CanChat) -> anyhow::Result<Vec<anyhow::Result<ChatEvent>>> {
+    let stream = chat
+        .chat_events(
+            &profile(),
+            &[ChatMessage::user("hi")],
+            &GenerationOptions::default(),
+        )
+        .await?;
+    Ok(stream.collect().await)
+}
+
+#[tokio::test]
+async fn connect_timeout_fails_the_request() {
+    let chat = deadline(
+        Hang::Connect,
+        Timeouts {
+            connect: Some(Duration::from_millis(20)),
+            ..Default::default()
+        },
+    );
+    let err = events(&chat).await.err().unwrap();
+    assert!(err.to_string().contains("connect timeout"));
+}
+
+#[tokio::test]
+async fn first_token_timeout_ends_the_stream() {
+    let chat = deadline(
+        Hang::FirstToken,
+        Timeouts {
+            first_token: Some(Duration::from_millis(20)),
+            total: Some(Duration::from_secs(60)),
+            ..Default::default()
+        },
+    );
+    let items = events(&chat).await.unwrap();
+    assert_eq!(items.len(), 1);
+    let err = items[0].as_ref().unwrap_err();
+    assert!(err.to_string().contains("first token timeout"));
+}
+
+#[tokio::test]
+async fn total_timeout_keeps_partial_reply() {
+    let chat = deadline(
+        Hang::AfterToken,
+        Timeouts {
+            first_token: Some(Duration::from_secs(60)),
+            total: Some(Duration::from_millis(30)),
+            ..Default::default()
+        },
+    );
+    let items = events(&chat).await.unwrap();
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0].as_ref().unwrap(), &ChatEvent::Token("Hel".into()));
+    assert!(items[1]
+        .as_ref()
+        .unwrap_err()
+        .to_string()
+        .contains("total timeout"));
+}
+
+#[tokio::test]
+async fn cancel_aborts_inflight_generation_only() {
+    let chat = deadline(Hang::AfterToken, Timeouts::default());
+    let handle = chat.cancel_handle();
+    let canceller = async {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        handle.cancel();
+    };
+    let (items, _) = tokio::join!(events(&chat), canceller);
+    let items = items.unwrap();
+    assert_eq!(items.len(), 1);
+    assert!(items[0].is_ok());
+
+    let next = tokio::time::timeout(Duration::from_millis(50), events(&chat)).await;
+    assert!(next.is_err(), "later generations must not be cancelled");
+}
+
+#[tokio::test]
+#[allow(clippy::arc_with_non_send_sync)]
+async fn pool_cancel_handle_aborts_streams() {
+    let pool = LlmPool::new(
+        vec![LlmInstance {
+            name: "a".into(),
+            chat: Arc::new(HangingChat(Hang::AfterToken)),
+            profile: Arc::new(profile()),
+            scheduler: PriorityScheduler::new(1),
+        }],
+        RoutingPolicy::Priority,
+    );
+    let handle = pool.cancel_handle();
+    let canceller = async {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        handle.cancel();
+    };
+    let (items, _) = tokio::join!(events(&pool), canceller);
+    assert_eq!(items.unwrap().len(), 1);
+    assert!(pool.is_healthy("a").unwrap());
+}
+
+#[tokio::test]
+#[allow(clippy::arc_with_non_send_sync)]
+async fn limited_chat_releases_permit_on_timeout() {
+    let scheduler = PriorityScheduler::new(1);
+    let chat = LimitedChat::new(
+        Arc::new(deadline(
+            Hang::FirstToken,
+            Timeouts {
+                first_token: Some(Duration::from_millis(20)),
+                ..Default::default()
+            },
+        )),
+        scheduler.clone(),
+    );
+    let mut stream = chat
+        .chat_events(
+            &profile(),
+            &[ChatMessage::user("hi")],
+            &GenerationOptions::default(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(scheduler.available_permits(), 0);
+    assert!(stream.next().await.unwrap().is_err());
+    assert!(stream.next().await.is_none());
+    assert_eq!(scheduler.available_permits(), 1);
+}
+
+#[test]
+fn provider_timeouts_are_read_in_seconds() {
+    let prov: LlmProviderConfig = toml::from_str(
+        r#"
+        provider = "ollama"
+        models = ["llama3"]
+        connect_timeout_secs = 2
+        first_token_timeout_secs = 0.5
+        "#,
+    )
+    .unwrap();
+    assert_eq!(
+        prov.timeouts(),
+        Timeouts {
+            connect: Some(Duration::from_secs(2)),
+            first_token: Some(Duration::from_millis(500)),
+            total: None,
+        }
+    );
+}
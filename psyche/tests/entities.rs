@@ -0,0 +1,110 @@
+use chrono::{DateTime, Utc};
+use psyche::llm::scripted::ScriptedChat;
+use psyche::llm::{LlmCapability, LlmProfile};
+use psyche::memory::{
+    about, record_entities, Entity, EntityKind, Experience, HnswBackend, MemoryBackend,
+};
+use psyche::models::MemoryEntry;
+use psyche::wit::{entity_extractor, Wit, ENTITY_KIND};
+use serde_json::json;
+use uuid::Uuid;
+
+fn exp(how: &str, secs: i64) -> Experience {
+    Experience {
+        how: how.into(),
+        what: json!({ "said": how }),
+        when: DateTime::<Utc>::from_timestamp(1_700_000_000 + secs, 0).unwrap(),
+        tags: vec!["situation".into()],
+    }
+}
+
+#[tokio::test]
+async fn extractor_lists_entities_with_their_mentions() {
+    let chat = ScriptedChat::from_toml(
+        r#"
+[[rule]]
+contains = "harbor"
+response = '{"entities": [{"name": "Alice", "kind": "person", "aliases": ["Ally"]}, {"name": "the harbor", "kind": "place"}]}'
+"#,
+    )
+    .unwrap();
+    let mut wit = Wit {
+        config: entity_extractor("situation"),
+        llm: Box::new(chat),
+        profile: LlmProfile {
+            provider: "mock".into(),
+            model: "mock".into(),
+            capabilities: vec![LlmCapability::Chat],
+        },
+        prompter: Default::default(),
+    };
+    let entry = MemoryEntry {
+        id: Uuid::new_v4(),
+        kind: "situation".into(),
+        when: Utc::now(),
+        what: json!("Ally waited for me at the harbor."),
+        how: "Ally waited for me at the harbor.".into(),
+    };
+    let out = wit.distill(vec![entry.clone()]).await.unwrap();
+    assert_eq!(out[0].kind, ENTITY_KIND);
+    assert_eq!(out[0].what["mentions"], json!([entry.id.to_string()]));
+    assert_eq!(out[0].what["entities"][1]["kind"], "place");
+}
+
+#[tokio::test]
+async fn merges_aliases_and_recalls_everything_about_someone() {
+    let dir = tempfile::tempdir().unwrap();
+    let ids = {
+        let backend = HnswBackend::open(dir.path()).unwrap();
+        let waved = backend
+            .store(&exp("Alice waved.", 2), &[1.0])
+            .await
+            .unwrap();
+        let tea = backend
+            .store(&exp("Ally made tea.", 1), &[1.0])
+            .await
+            .unwrap();
+        backend.store(&exp("It rained.", 3), &[1.0]).await.unwrap();
+        record_entities(
+            &backend,
+            &json!({
+                "entities": [{"name": "Alice", "kind": "person"}, {"name": "Harbor", "kind": "place"}],
+                "mentions": [waved],
+            }),
+        )
+        .await
+        .unwrap();
+        let stored = record_entities(
+            &backend,
+            &json!({
+                "entities": [{"name": "Ally", "kind": "person", "aliases": ["alice"]}],
+                "mentions": [tea],
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(stored[0].name, "Alice");
+        assert_eq!(stored[0].aliases, ["Ally"]);
+        assert_eq!(backend.entities().len(), 2);
+        assert!(backend.link_mention(&tea, "missing").await.is_err());
+        (waved, tea)
+    };
+
+    let backend = HnswBackend::open(dir.path()).unwrap();
+    let found = about(&backend, " ALLY ").await.unwrap().unwrap();
+    assert_eq!(found.entity.kind, EntityKind::Person);
+    assert_eq!(found.entity.name, "Alice");
+    let hows: Vec<_> = found.experiences.iter().map(|e| e.how.as_str()).collect();
+    assert_eq!(hows, ["Ally made tea.", "Alice waved."]);
+    let place = about(&backend, "harbor").await.unwrap().unwrap();
+    assert_eq!(place.experiences, vec![exp("Alice waved.", 2)]);
+    assert!(about(&backend, "Bob").await.unwrap().is_none());
+
+    // an entity of another kind with the same name stays separate
+    let town = backend
+        .upsert_entity(&Entity::new(EntityKind::Place, "Alice"))
+        .await
+        .unwrap();
+    assert_ne!(town.id, found.entity.id);
+    assert!(backend.link_mention(&ids.0, &town.id).await.is_ok());
+}
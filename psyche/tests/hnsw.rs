@@ -0,0 +1,270 @@
+use chrono::{DateTime, Utc};
+use psyche::memory::{Edge, Experience, HnswBackend, MemoryBackend, SearchFilter};
+
+fn exp(n: usize) -> Experience {
+    Experience {
+        how: format!("experience {n}"),
+        what: serde_json::json!({ "n": n }),
+        when: DateTime::<Utc>::from_timestamp(1_700_000_000 + n as i64, 0).unwrap(),
+        tags: vec!["test".into()],
+    }
+}
+
+fn nofilter() -> SearchFilter {
+    SearchFilter::default()
+}
+
+/// Deterministic pseudo-random vectors.
+fn vectors(count: usize, dim: usize) -> Vec<Vec<f32>> {
+    let mut state = 0x2545f4914f6cdd1du64;
+    (0..count)
+        .map(|_| {
+            (0..dim)
+                .map(|_| {
+                    state ^= state << 13;
+                    state ^= state >> 7;
+                    state ^= state << 17;
+                    (state % 2000) as f32 / 1000.0 - 1.0
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn finds_nearest_neighbors() {
+    let dir = tempfile::tempdir().unwrap();
+    let backend = HnswBackend::open(dir.path()).unwrap();
+    let vs = vectors(500, 16);
+    for (n, v) in vs.iter().enumerate() {
+        backend.store(&exp(n), v).await.unwrap();
+    }
+    assert_eq!(backend.len(), 500);
+    let mut hits = 0;
+    for n in (0..500).step_by(25) {
+        let found = backend.search(&vs[n], 3, &nofilter()).await.unwrap();
+        assert_eq!(found.len(), 3);
+        if found[0].experience == exp(n) {
+            hits += 1;
+        }
+    }
+    assert!(hits >= 19, "only {hits} of 20 exact matches found");
+}
+
+/// Nodes of the snapshot in `dir` that sit above the bottom layer.
+fn upper_nodes(dir: &std::path::Path) -> usize {
+    let snapshot: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(dir.join("hnsw.json")).unwrap()).unwrap();
+    snapshot["nodes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|node| node["links"].as_array().unwrap().len() > 1)
+        .count()
+}
+
+#[tokio::test]
+async fn nodes_reach_upper_layers() {
+    let dir = tempfile::tempdir().unwrap();
+    let backend = HnswBackend::open(dir.path()).unwrap();
+    for (n, v) in vectors(200, 8).iter().enumerate() {
+        backend.store(&exp(n), v).await.unwrap();
+    }
+    backend.snapshot().unwrap();
+    let upper = upper_nodes(dir.path());
+    // one node in M = 16 is expected above the bottom layer
+    assert!(
+        (3..40).contains(&upper),
+        "{upper} of 200 nodes above layer 0"
+    );
+}
+
+#[tokio::test]
+async fn ids_that_are_not_uuids_spread_over_layers() {
+    let dir = tempfile::tempdir().unwrap();
+    let backend = HnswBackend::open(dir.path()).unwrap();
+    for (n, v) in vectors(200, 8).iter().enumerate() {
+        backend
+            .store_with_id(&format!("entry-{n}"), &exp(n), v)
+            .await
+            .unwrap();
+    }
+    backend.snapshot().unwrap();
+    let upper = upper_nodes(dir.path());
+    assert!(
+        (3..40).contains(&upper),
+        "{upper} of 200 nodes above layer 0"
+    );
+}
+
+#[tokio::test]
+async fn survives_reopen_with_and_without_snapshot() {
+    let dir = tempfile::tempdir().unwrap();
+    let vs = vectors(40, 8);
+    let mut ids = Vec::new();
+    {
+        let backend = HnswBackend::open(dir.path()).unwrap();
+        for (n, v) in vs.iter().take(30).enumerate() {
+            ids.push(backend.store(&exp(n), v).await.unwrap());
+        }
+        backend.snapshot().unwrap();
+        for (n, v) in vs.iter().enumerate().skip(30) {
+            ids.push(backend.store(&exp(n), v).await.unwrap());
+        }
+        backend.link_summary(&ids[35], &ids[3]).await.unwrap();
+        backend.link_called_to_mind(&ids[1], &ids[2]).await.unwrap();
+        // skip the snapshot written on drop, as a crash would
+        std::mem::forget(backend);
+    }
+    let backend = HnswBackend::open(dir.path()).unwrap();
+    assert_eq!(backend.len(), 40);
+    assert_eq!(backend.get(&ids[37]).await.unwrap(), Some(exp(37)));
+    let hits = backend.search(&vs[37], 1, &nofilter()).await.unwrap();
+    assert_eq!((&hits[0].id, &hits[0].experience), (&ids[37], &exp(37)));
+    let linked: Vec<Edge> = backend
+        .edges()
+        .into_iter()
+        .filter(|e| e.kind != "NEXT")
+        .collect();
+    assert_eq!(
+        linked,
+        vec![
+            Edge {
+                kind: "SUMMARIZES".into(),
+                from: ids[35].clone(),
+                to: ids[3].clone(),
+            },
+            Edge {
+                kind: "CALLED_TO_MIND".into(),
+                from: ids[1].clone(),
+                to: ids[2].clone(),
+            },
+        ]
+    );
+
+    // dropping writes the snapshot, so remove it afterwards to force a rebuild
+    drop(backend);
+    std::fs::remove_file(dir.path().join("hnsw.json")).unwrap();
+    let rebuilt = HnswBackend::open(dir.path()).unwrap();
+    let hits = rebuilt.search(&vs[12], 1, &nofilter()).await.unwrap();
+    assert_eq!(hits[0].experience, exp(12));
+}
+
+#[tokio::test]
+async fn torn_tail_is_cut_before_the_next_store() {
+    use std::io::Write;
+
+    let dir = tempfile::tempdir().unwrap();
+    let first = {
+        let backend = HnswBackend::open(dir.path()).unwrap();
+        backend.store(&exp(0), &[1.0, 0.0]).await.unwrap()
+    };
+    std::fs::OpenOptions::new()
+        .append(true)
+        .open(dir.path().join("experiences.jsonl"))
+        .unwrap()
+        .write_all(b"{\"id\":\"torn")
+        .unwrap();
+    let second = {
+        let backend = HnswBackend::open(dir.path()).unwrap();
+        assert_eq!(backend.len(), 1);
+        backend.store(&exp(1), &[0.0, 1.0]).await.unwrap()
+    };
+    let backend = HnswBackend::open(dir.path()).unwrap();
+    assert_eq!(backend.len(), 2);
+    assert_eq!(backend.get(&first).await.unwrap(), Some(exp(0)));
+    assert_eq!(backend.get(&second).await.unwrap(), Some(exp(1)));
+}
+
+#[tokio::test]
+async fn rejects_mismatched_vectors_and_unknown_links() {
+    let dir = tempfile::tempdir().unwrap();
+    let backend = HnswBackend::open(dir.path()).unwrap();
+    assert!(backend
+        .search(&[1.0, 0.0], 5, &nofilter())
+        .await
+        .unwrap()
+        .is_empty());
+    let id = backend.store(&exp(0), &[1.0, 0.0]).await.unwrap();
+    assert!(backend.store(&exp(1), &[1.0, 0.0, 0.0]).await.is_err());
+    // storing a known id again leaves it as it is
+    backend
+        .store_with_id(&id, &exp(1), &[0.0, 1.0])
+        .await
+        .unwrap();
+    assert_eq!(backend.get(&id).await.unwrap(), Some(exp(0)));
+    assert_eq!(backend.len(), 1);
+    assert!(backend.link_summary(&id, "missing").await.is_err());
+    assert_eq!(backend.get("missing").await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn selective_filters_still_fill_results() {
+    let dir = tempfile::tempdir().unwrap();
+    let backend = HnswBackend::open(dir.path()).unwrap();
+    let vs = vectors(300, 8);
+    for (n, v) in vs.iter().enumerate() {
+        let mut e = exp(n);
+        if n % 50 == 0 {
+            e.tags = vec!["rare".into()];
+        }
+        backend.store(&e, v).await.unwrap();
+    }
+    let filter = SearchFilter {
+        tags: vec!["rare".into()],
+        ..Default::default()
+    };
+    let hits = backend.search(&vs[1], 6, &filter).await.unwrap();
+    assert_eq!(hits.len(), 6);
+    assert!(hits.iter().all(|h| h.experience.tags == ["rare"]));
+
+    let filter = SearchFilter {
+        min_score: Some(0.999),
+        ..Default::default()
+    };
+    let hits = backend.search(&vs[7], 6, &filter).await.unwrap();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].experience, exp(7));
+}
+
+#[tokio::test]
+async fn archives_and_forgets_across_reopen() {
+    let dir = tempfile::tempdir().unwrap();
+    let vs = vectors(4, 8);
+    let mut ids = Vec::new();
+    {
+        let backend = HnswBackend::open(dir.path()).unwrap();
+        for (n, v) in vs.iter().enumerate() {
+            ids.push(backend.store(&exp(n), v).await.unwrap());
+        }
+        backend.link_summary(&ids[3], &ids[0]).await.unwrap();
+        backend.link_called_to_mind(&ids[2], &ids[1]).await.unwrap();
+        backend.link_called_to_mind(&ids[3], &ids[1]).await.unwrap();
+        assert_eq!(backend.recall_counts().await.unwrap()[&ids[1]], 2);
+
+        backend.archive(&ids[0]).await.unwrap();
+        backend.forget(&ids[2]).await.unwrap();
+        assert!(backend.archive("missing").await.is_err());
+        assert!(backend.forget(&ids[2]).await.is_err());
+        std::mem::forget(backend);
+    }
+    let backend = HnswBackend::open(dir.path()).unwrap();
+    assert_eq!(backend.len(), 3);
+    let hits = backend.search(&vs[0], 4, &nofilter()).await.unwrap();
+    let found: Vec<&str> = hits.iter().map(|h| h.id.as_str()).collect();
+    assert_eq!(found.len(), 2);
+    assert!(!found.contains(&ids[0].as_str()));
+    // archived experiences stay reachable from their summary
+    assert_eq!(backend.get(&ids[0]).await.unwrap(), Some(exp(0)));
+    assert!(backend.edges().contains(&Edge {
+        kind: "SUMMARIZES".into(),
+        from: ids[3].clone(),
+        to: ids[0].clone(),
+    }));
+    assert_eq!(backend.get(&ids[2]).await.unwrap(), None);
+    assert_eq!(backend.recall_counts().await.unwrap()[&ids[1]], 1);
+    assert!(backend
+        .edges()
+        .iter()
+        .all(|e| e.from != ids[2] && e.to != ids[2]));
+}
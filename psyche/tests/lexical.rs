@@ -0,0 +1,73 @@
+use psyche::llm::lexical::LexicalEmbed;
+use psyche::llm::prompt::PromptHelper;
+use psyche::llm::{CanEmbed, LlmCapability, LlmProfile};
+use psyche::memory::{InMemoryBackend, Memorizer, MemoryBackend};
+
+fn profile() -> LlmProfile {
+    LlmProfile {
+        provider: "lexical".into(),
+        model: "lexical".into(),
+        capabilities: vec![LlmCapability::Embedding],
+    }
+}
+
+#[tokio::test]
+async fn vectors_are_stable_and_normalized() {
+    let embed = LexicalEmbed::new(128);
+    let a = embed
+        .embed(&profile(), "Comet over the ridge")
+        .await
+        .unwrap();
+    let b = LexicalEmbed::new(128)
+        .embed(&profile(), "comet, over the RIDGE!")
+        .await
+        .unwrap();
+    assert_eq!(a.len(), 128);
+    assert_eq!(a, b);
+    let norm: f32 = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    assert!((norm - 1.0).abs() < 1e-5);
+    assert!(embed.vector("the and of").iter().all(|x| *x == 0.0));
+}
+
+#[tokio::test]
+async fn recall_finds_related_memory() {
+    let embed = LexicalEmbed::default();
+    let backend = InMemoryBackend::default();
+    let memorizer = Memorizer {
+        chat: None,
+        embed: &embed,
+        profile: &profile(),
+        backend: &backend,
+        prompter: PromptHelper::default(),
+    };
+    for text in [
+        "Alice brought a red umbrella because of the rain",
+        "The battery charge dropped below twenty percent",
+        "A comet crossed the night sky above the crater",
+    ] {
+        memorizer
+            .memorize(text, Some(text), false, vec![])
+            .await
+            .unwrap();
+    }
+
+    let query = embed.vector("how low is the battery charge?");
+    let hits = backend
+        .search(&query, 1, &Default::default())
+        .await
+        .unwrap();
+    assert_eq!(
+        hits[0].experience.how,
+        "The battery charge dropped below twenty percent"
+    );
+
+    let query = embed.vector("umbrellas in rainy weather");
+    let hits = backend
+        .search(&query, 1, &Default::default())
+        .await
+        .unwrap();
+    assert_eq!(
+        hits[0].experience.how,
+        "Alice brought a red umbrella because of the rain"
+    );
+}
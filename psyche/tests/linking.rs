@@ -0,0 +1,116 @@
+use chrono::{DateTime, Utc};
+use psyche::llm::prompt::PromptHelper;
+use psyche::llm::scripted::ScriptedChat;
+use psyche::llm::{LlmCapability, LlmProfile};
+use psyche::memory::{Edge, Experience, HnswBackend, Linker, MemoryBackend};
+
+fn exp(how: &str, secs: i64, tags: &[&str]) -> Experience {
+    Experience {
+        how: how.into(),
+        what: serde_json::json!({ "said": how }),
+        when: DateTime::<Utc>::from_timestamp(1_700_000_000 + secs, 0).unwrap(),
+        tags: tags.iter().map(|t| t.to_string()).collect(),
+    }
+}
+
+fn profile() -> LlmProfile {
+    LlmProfile {
+        provider: "mock".into(),
+        model: "mock".into(),
+        capabilities: vec![LlmCapability::Chat],
+    }
+}
+
+fn edges(backend: &HnswBackend, kind: &str) -> Vec<(String, String)> {
+    backend
+        .edges()
+        .into_iter()
+        .filter(|e| e.kind == kind)
+        .map(|Edge { from, to, .. }| (from, to))
+        .collect()
+}
+
+#[tokio::test]
+async fn chains_each_tag_in_time_order() {
+    let dir = tempfile::tempdir().unwrap();
+    let backend = HnswBackend::open(dir.path()).unwrap();
+    let a = backend.store(&exp("a", 1, &["ear"]), &[1.0]).await.unwrap();
+    let b = backend
+        .store(&exp("b", 2, &["eye", "ear"]), &[1.0])
+        .await
+        .unwrap();
+    let c = backend.store(&exp("c", 4, &["ear"]), &[1.0]).await.unwrap();
+    // arrives late but happened between b and c
+    let d = backend.store(&exp("d", 3, &["eye"]), &[1.0]).await.unwrap();
+    let e = backend.store(&exp("e", 5, &[]), &[1.0]).await.unwrap();
+
+    let next = edges(&backend, "NEXT");
+    assert_eq!(next, vec![(a, b.clone()), (b.clone(), c), (b, d.clone())]);
+    assert!(!next.iter().any(|(from, to)| *from == e || *to == e));
+    assert!(!next.iter().any(|(from, _)| *from == d));
+}
+
+#[tokio::test]
+async fn links_asserted_causes_and_explicit_topics() {
+    let dir = tempfile::tempdir().unwrap();
+    let backend = HnswBackend::open(dir.path()).unwrap();
+    let profile = profile();
+    let linker = Linker {
+        chat: None,
+        profile: &profile,
+        backend: &backend,
+        prompter: PromptHelper::default(),
+    };
+    let noise = backend
+        .store(&exp("A bang.", 1, &["ear"]), &[1.0])
+        .await
+        .unwrap();
+    let mut flinch = exp("I flinched at the #Noise.", 2, &["instant"]);
+    // an unknown cause is skipped without losing the others
+    flinch.what = serde_json::json!({ "caused_by": ["missing", noise], "topics": ["Startle"] });
+    let id = backend.store(&flinch, &[1.0]).await.unwrap();
+
+    let links = linker.link(&id, &flinch).await.unwrap();
+    assert_eq!(links.topics, ["startle", "noise"]);
+    assert_eq!(links.causes, std::slice::from_ref(&noise));
+    assert_eq!(edges(&backend, "CAUSES"), vec![(noise, id.clone())]);
+    assert_eq!(
+        edges(&backend, "REFERS_TO"),
+        vec![(id.clone(), "startle".into()), (id, "noise".into())]
+    );
+}
+
+#[tokio::test]
+async fn asks_the_model_for_topics() {
+    let dir = tempfile::tempdir().unwrap();
+    let backend = HnswBackend::open(dir.path()).unwrap();
+    let profile = profile();
+    let chat = ScriptedChat::from_toml(
+        r#"
+[[rule]]
+contains = "tomatoes"
+response = "Sure: [\"Garden\", \"tomatoes\", \"\"]"
+
+[[rule]]
+contains = "rain"
+error = "model overloaded"
+"#,
+    )
+    .unwrap();
+    let linker = Linker {
+        chat: Some(&chat),
+        profile: &profile,
+        backend: &backend,
+        prompter: PromptHelper::default(),
+    };
+    let picked = exp("I picked tomatoes.", 1, &["instant"]);
+    let id = backend.store(&picked, &[1.0]).await.unwrap();
+    let links = linker.link(&id, &picked).await.unwrap();
+    assert_eq!(links.topics, ["garden", "tomatoes"]);
+    assert!(links.causes.is_empty());
+
+    let wet = exp("The #rain came.", 2, &["instant"]);
+    let id = backend.store(&wet, &[1.0]).await.unwrap();
+    let links = linker.link(&id, &wet).await.unwrap();
+    assert_eq!(links.topics, ["rain"]);
+}
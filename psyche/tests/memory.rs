@@ -2,7 +2,7 @@ use psyche::llm::prompt::PromptHelper;
 use psyche::llm::{
     mock_chat::MockChat, mock_embed::MockEmbed, LlmCapability, LlmProfile, LlmRegistry,
 };
-use psyche::memory::{InMemoryBackend, Memorizer, MemoryBackend};
+use psyche::memory::{Experience, InMemoryBackend, Memorizer, MemoryBackend, SearchFilter};
 use tracing_test::traced_test;
 
 #[tokio::test]
@@ -165,8 +165,12 @@ async fn search_returns_neighbors() {
         .memorize("hello world", None, true, vec![])
         .await
         .unwrap();
-    let neighbors = backend.search(&stored.vector, 1).await.unwrap();
-    assert_eq!(neighbors[0], stored.experience);
+    let neighbors = backend
+        .search(&stored.vector, 1, &Default::default())
+        .await
+        .unwrap();
+    assert_eq!(neighbors[0].experience, stored.experience);
+    assert_eq!(neighbors[0].id, "0");
 }
 
 #[traced_test]
@@ -196,3 +200,46 @@ async fn memorize_emits_logs() {
     assert!(logs_contain("memorize called"));
     assert!(logs_contain("storing experience"));
 }
+
+#[tokio::test]
+async fn search_applies_filters() {
+    let backend = InMemoryBackend::default();
+    let at = |secs| chrono::DateTime::from_timestamp(secs, 0).unwrap();
+    for (how, secs, tag, vector) in [
+        ("old instant", 10, "instant", [1.0, 0.0]),
+        ("new instant", 20, "instant", [0.9, 0.1]),
+        ("new moment", 20, "moment", [1.0, 0.0]),
+        ("unrelated", 30, "instant", [0.0, 1.0]),
+    ] {
+        let exp = Experience {
+            how: how.into(),
+            what: how.into(),
+            when: at(secs),
+            tags: vec![tag.into()],
+        };
+        backend.store(&exp, &vector).await.unwrap();
+    }
+    let hows = |hits: Vec<psyche::memory::SearchHit>| {
+        hits.into_iter()
+            .map(|h| h.experience.how)
+            .collect::<Vec<_>>()
+    };
+
+    let filter = SearchFilter {
+        tags: vec!["instant".into()],
+        since: Some(at(15)),
+        ..Default::default()
+    };
+    let hits = backend.search(&[1.0, 0.0], 5, &filter).await.unwrap();
+    assert_eq!(hows(hits), ["new instant", "unrelated"]);
+
+    let filter = SearchFilter {
+        until: Some(at(20)),
+        min_score: Some(0.5),
+        ..Default::default()
+    };
+    let hits = backend.search(&[1.0, 0.0], 5, &filter).await.unwrap();
+    assert_eq!(hits.len(), 3);
+    assert!(hits.windows(2).all(|w| w[0].score >= w[1].score));
+    assert!(!hows(hits).contains(&"unrelated".to_string()));
+}
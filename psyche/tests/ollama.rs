@@ -0,0 +1,505 @@
+use httpmock::prelude::*;
+use psyche::llm::ollama::OllamaEmbed;
+use psyche::llm::{CanEmbed, LlmCapability, LlmProfile};
+
+#[tokio::test]
+async fn ollama_embed_returns_first_vector() {
+    let server = MockServer::start_async().await;
+    let mock = server
+        .mock_async(|when, then| {
+            when.method(POST)
+                .path("/api/embed")
+                .body_contains("\"model\":\"nomic-embed-text\"")
+                .body_contains("\"input\":\"hello\"");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body("{\"model\":\"nomic-embed-text\",\"embeddings\":[[0.1,0.2,0.3]]}");
+        })
+        .await;
+
+    let embed = OllamaEmbed {
+        base_url: server.base_url(),
+        model: "nomic-embed-text".into(),
+    };
+    let profile = LlmProfile {
+        provider: "ollama".into(),
+        model: "nomic-embed-text".into(),
+        capabilities: vec![LlmCapability::Embedding],
+    };
+    let vector = embed.embed(&profile, "hello").await.unwrap();
+    assert_eq!(vector, vec![0.1, 0.2, 0.3]);
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn ollama_chat_sends_generation_options() {
+    use psyche::llm::ollama::OllamaChat;
+    use psyche::llm::{CanChat, GenerationOptions};
+    use tokio_stream::StreamExt;
+
+    let server = MockServer::start_async().await;
+    let mock = server
+        .mock_async(|when, then| {
+            when.method(POST)
+                .path("/api/chat")
+                .body_contains("\"temperature\":0.0")
+                .body_contains("\"num_predict\":16")
+                .body_contains("\"stop\":[\"END\"]")
+                .body_contains("\"seed\":7")
+                .body_contains("\"format\":\"json\"");
+            then.status(200)
+                .header("content-type", "application/x-ndjson")
+                .body("{\"message\":{\"content\":\"{}\"},\"done\":true}\n");
+        })
+        .await;
+
+    let chat = OllamaChat {
+        base_url: server.base_url(),
+        model: "llama3".into(),
+    };
+    let profile = LlmProfile {
+        provider: "ollama".into(),
+        model: "llama3".into(),
+        capabilities: vec![LlmCapability::Chat],
+    };
+    let options = GenerationOptions {
+        temperature: Some(0.0),
+        max_tokens: Some(16),
+        stop: vec!["END".into()],
+        seed: Some(7),
+        format: Some(serde_json::json!("json")),
+        ..Default::default()
+    };
+    let stream = chat
+        .chat_stream_with_options(&profile, "", "hi", &options)
+        .await
+        .unwrap();
+    let tokens: Vec<String> = stream.collect().await;
+    assert_eq!(tokens.concat(), "{}");
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn ollama_chat_sends_message_history() {
+    use psyche::llm::ollama::OllamaChat;
+    use psyche::llm::{CanChat, ChatMessage, GenerationOptions};
+    use tokio_stream::StreamExt;
+
+    let server = MockServer::start_async().await;
+    let mock = server
+        .mock_async(|when, then| {
+            when.method(POST).path("/api/chat").json_body_partial(
+                r#"{"messages":[
+                    {"role":"system","content":"be brief"},
+                    {"role":"user","content":"2+2?"},
+                    {"role":"assistant","content":"4"},
+                    {"role":"user","content":"3+3?"}
+                ]}"#,
+            );
+            then.status(200)
+                .header("content-type", "application/x-ndjson")
+                .body("{\"message\":{\"content\":\"6\"},\"done\":true}\n");
+        })
+        .await;
+
+    let chat = OllamaChat {
+        base_url: server.base_url(),
+        model: "llama3".into(),
+    };
+    let profile = LlmProfile {
+        provider: "ollama".into(),
+        model: "llama3".into(),
+        capabilities: vec![LlmCapability::Chat],
+    };
+    let messages = [
+        ChatMessage::system("be brief"),
+        ChatMessage::user("2+2?"),
+        ChatMessage::assistant("4"),
+        ChatMessage::user("3+3?"),
+    ];
+    let stream = chat
+        .chat_messages_stream(&profile, &messages, &GenerationOptions::default())
+        .await
+        .unwrap();
+    let tokens: Vec<String> = stream.collect().await;
+    assert_eq!(tokens.concat(), "6");
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn ollama_chat_returns_tool_calls() {
+    use psyche::llm::ollama::OllamaChat;
+    use psyche::llm::{CanChat, ChatMessage, GenerationOptions, ToolCall, ToolDefinition};
+
+    let server = MockServer::start_async().await;
+    let mock = server
+        .mock_async(|when, then| {
+            when.method(POST).path("/api/chat").json_body_partial(
+                r#"{"stream":false,"tools":[{"type":"function","function":{"name":"speak"}}]}"#,
+            );
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(
+                    r#"{"message":{"role":"assistant","content":"","tool_calls":[
+                        {"function":{"name":"speak","arguments":{"text":"hello"}}}
+                    ]},"done":true}"#,
+                );
+        })
+        .await;
+
+    let chat = OllamaChat {
+        base_url: server.base_url(),
+        model: "llama3.1".into(),
+    };
+    let profile = LlmProfile {
+        provider: "ollama".into(),
+        model: "llama3.1".into(),
+        capabilities: vec![LlmCapability::Chat, LlmCapability::ToolUse],
+    };
+    let tools = [ToolDefinition {
+        name: "speak".into(),
+        description: "Say something aloud".into(),
+        parameters: serde_json::json!({
+            "type": "object",
+            "properties": {"text": {"type": "string"}}
+        }),
+    }];
+    let resp = chat
+        .chat_with_tools(
+            &profile,
+            &[ChatMessage::user("greet them")],
+            &tools,
+            &GenerationOptions::default(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        resp.tool_calls,
+        vec![ToolCall {
+            name: "speak".into(),
+            arguments: serde_json::json!({"text": "hello"}),
+        }]
+    );
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn ollama_vision_sends_encoded_image() {
+    use base64::{engine::general_purpose, Engine};
+    use psyche::llm::ollama::OllamaChat;
+    use psyche::llm::CanSee;
+    use tokio_stream::StreamExt;
+
+    let server = MockServer::start_async().await;
+    let expected = general_purpose::STANDARD.encode(b"JPEGDATA");
+    let mock = server
+        .mock_async(move |when, then| {
+            when.method(POST)
+                .path("/api/chat")
+                .body_contains("\"model\":\"gemma3n\"")
+                .body_contains("what do you see")
+                .body_contains(&expected);
+            then.status(200)
+                .header("content-type", "application/x-ndjson")
+                .body(
+                    "{\"message\":{\"content\":\"a \"},\"done\":false}\n\
+                     {\"message\":{\"content\":\"cat\"},\"done\":true}\n",
+                );
+        })
+        .await;
+
+    let vision = OllamaChat {
+        base_url: server.base_url(),
+        model: "gemma3n".into(),
+    };
+    let profile = LlmProfile {
+        provider: "ollama".into(),
+        model: "gemma3n".into(),
+        capabilities: vec![LlmCapability::Image],
+    };
+    let stream = vision
+        .see_stream(&profile, b"JPEGDATA", "what do you see")
+        .await
+        .unwrap();
+    let tokens: Vec<String> = stream.collect().await;
+    assert_eq!(tokens.concat(), "a cat");
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn ollama_try_stream_reports_truncated_response() {
+    use psyche::llm::ollama::OllamaChat;
+    use psyche::llm::{CanChat, ChatEvent, ChatMessage, GenerationOptions};
+    use tokio_stream::StreamExt;
+
+    let server = MockServer::start_async().await;
+    server
+        .mock_async(|when, then| {
+            when.method(POST).path("/api/chat");
+            then.status(200)
+                .header("content-type", "application/x-ndjson")
+                .body("{\"message\":{\"content\":\"partial\"},\"done\":false}\n");
+        })
+        .await;
+
+    let chat = OllamaChat {
+        base_url: server.base_url(),
+        model: "llama3".into(),
+    };
+    let profile = LlmProfile {
+        provider: "ollama".into(),
+        model: "llama3".into(),
+        capabilities: vec![LlmCapability::Chat],
+    };
+    let items: Vec<_> = chat
+        .chat_events(
+            &profile,
+            &[ChatMessage::user("hi")],
+            &GenerationOptions::default(),
+        )
+        .await
+        .unwrap()
+        .collect()
+        .await;
+    assert_eq!(items.len(), 2);
+    assert_eq!(
+        items[0].as_ref().unwrap(),
+        &ChatEvent::Token("partial".into())
+    );
+    assert!(items[1].is_err());
+}
+
+#[tokio::test]
+async fn ollama_chat_reports_token_usage() {
+    use psyche::llm::ollama::OllamaChat;
+    use psyche::llm::{CanChat, ChatEvent, ChatMessage, GenerationOptions, TokenUsage};
+    use tokio_stream::StreamExt;
+
+    let server = MockServer::start_async().await;
+    server
+        .mock_async(|when, then| {
+            when.method(POST).path("/api/chat");
+            then.status(200)
+                .header("content-type", "application/x-ndjson")
+                .body(concat!(
+                    "{\"model\":\"llama3\",\"message\":{\"content\":\"hi\"},\"done\":false}\n",
+                    "{\"model\":\"llama3\",\"message\":{\"content\":\"\"},\"done\":true,",
+                    "\"prompt_eval_count\":12,\"eval_count\":3}\n"
+                ));
+        })
+        .await;
+
+    let chat = OllamaChat {
+        base_url: server.base_url(),
+        model: "llama3".into(),
+    };
+    let profile = LlmProfile {
+        provider: "ollama".into(),
+        model: "llama3".into(),
+        capabilities: vec![LlmCapability::Chat],
+    };
+    let events: Vec<ChatEvent> = chat
+        .chat_events(
+            &profile,
+            &[ChatMessage::user("hi")],
+            &GenerationOptions::default(),
+        )
+        .await
+        .unwrap()
+        .map(Result::unwrap)
+        .collect()
+        .await;
+    assert_eq!(
+        events.last(),
+        Some(&ChatEvent::Usage(TokenUsage {
+            model: Some("llama3".into()),
+            host: None,
+            prompt_tokens: Some(12),
+            completion_tokens: Some(3),
+        }))
+    );
+}
+
+const TAGS: &str = r#"{"models":[{"name":"llama3:latest","size":42,"digest":"abc"},{"name":"phi4:14b","size":7,"digest":"def"}]}"#;
+
+#[tokio::test]
+async fn ollama_models_lists_and_matches_tags() {
+    use psyche::llm::ollama::OllamaModels;
+
+    let server = MockServer::start_async().await;
+    server
+        .mock_async(|when, then| {
+            when.method(GET).path("/api/tags");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(TAGS);
+        })
+        .await;
+
+    let admin = OllamaModels::new(server.base_url());
+    let models = admin.list().await.unwrap();
+    assert_eq!(models.len(), 2);
+    assert_eq!(models[0].size, 42);
+    assert!(admin.has("llama3").await.unwrap());
+    assert!(admin.has("phi4:14b").await.unwrap());
+    assert!(!admin.has("phi4").await.unwrap());
+}
+
+#[tokio::test]
+async fn ollama_models_pulls_missing_model_with_progress() {
+    use psyche::llm::ollama::OllamaModels;
+
+    let server = MockServer::start_async().await;
+    server
+        .mock_async(|when, then| {
+            when.method(GET).path("/api/tags");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(TAGS);
+        })
+        .await;
+    let pull = server
+        .mock_async(|when, then| {
+            when.method(POST)
+                .path("/api/pull")
+                .body_contains("\"model\":\"gemma3\"");
+            then.status(200).body(concat!(
+                "{\"status\":\"pulling manifest\"}\n",
+                "{\"status\":\"pulling abc\",\"total\":100,\"completed\":50}\n",
+                "{\"status\":\"pulling abc\",\"total\":100,\"completed\":100}\n",
+                "{\"status\":\"success\"}"
+            ));
+        })
+        .await;
+
+    let admin = OllamaModels::new(server.base_url());
+    admin.ensure("llama3", |_| panic!("present")).await.unwrap();
+
+    let mut seen = Vec::new();
+    admin
+        .ensure("gemma3", |p| seen.push((p.status.clone(), p.completed)))
+        .await
+        .unwrap();
+    assert_eq!(
+        seen,
+        vec![
+            ("pulling manifest".to_string(), None),
+            ("pulling abc".to_string(), Some(50)),
+            ("pulling abc".to_string(), Some(100)),
+            ("success".to_string(), None),
+        ]
+    );
+    pull.assert_async().await;
+}
+
+#[tokio::test]
+async fn ollama_models_reports_pull_errors() {
+    use psyche::llm::ollama::OllamaModels;
+
+    let server = MockServer::start_async().await;
+    server
+        .mock_async(|when, then| {
+            when.method(POST).path("/api/pull");
+            then.status(200)
+                .body("{\"status\":\"pulling manifest\"}\n{\"error\":\"file does not exist\"}\n");
+        })
+        .await;
+
+    let err = OllamaModels::new(server.base_url())
+        .pull("nope", |_| {})
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("file does not exist"));
+}
+
+#[tokio::test]
+async fn prepare_models_pulls_and_warms_up_configured_models() {
+    let server = MockServer::start_async().await;
+    server
+        .mock_async(|when, then| {
+            when.method(GET).path("/api/tags");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(TAGS);
+        })
+        .await;
+    let pull = server
+        .mock_async(|when, then| {
+            when.method(POST)
+                .path("/api/pull")
+                .body_contains("\"model\":\"nomic-embed-text\"");
+            then.status(200).body("{\"status\":\"success\"}\n");
+        })
+        .await;
+    let warmup = server
+        .mock_async(|when, then| {
+            when.method(POST)
+                .path("/api/generate")
+                .body_contains("\"model\":\"llama3\"")
+                .body_contains("\"keep_alive\":\"30m\"");
+            then.status(200).body("{\"done\":true}");
+        })
+        .await;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("llm.toml");
+    std::fs::write(
+        &path,
+        format!(
+            "[[llm]]\nprovider = \"ollama\"\nname = \"gpu\"\nbase_url = \"{}\"\nmodels = [\"llama3\"]\nkeep_alive = \"30m\"\n\n\
+             [embedding]\nllm = \"gpu\"\nmodel = \"nomic-embed-text\"\n",
+            server.base_url()
+        ),
+    )
+    .unwrap();
+
+    psyche::llm::config::prepare_models(&path).await.unwrap();
+    pull.assert_async().await;
+    warmup.assert_async().await;
+}
+
+#[tokio::test]
+async fn prepare_vision_model_ignores_other_models() {
+    let server = MockServer::start_async().await;
+    server
+        .mock_async(|when, then| {
+            when.method(GET).path("/api/tags");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(TAGS);
+        })
+        .await;
+    let pull = server
+        .mock_async(|when, then| {
+            when.method(POST).path("/api/pull");
+            then.status(200).body("{\"status\":\"success\"}\n");
+        })
+        .await;
+    let warmup = server
+        .mock_async(|when, then| {
+            when.method(POST)
+                .path("/api/generate")
+                .body_contains("\"model\":\"llama3\"");
+            then.status(200).body("{\"done\":true}");
+        })
+        .await;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("llm.toml");
+    std::fs::write(
+        &path,
+        format!(
+            "[[llm]]\nprovider = \"ollama\"\nbase_url = \"{0}\"\nmodels = [\"qwen3:32b\"]\nkeep_alive = \"30m\"\n\n\
+             [[llm]]\nprovider = \"ollama\"\nbase_url = \"{0}\"\nmodels = [\"llama3\"]\ncapabilities = [\"image\"]\nkeep_alive = \"30m\"\n\n\
+             [embedding]\nmodel = \"nomic-embed-text\"\n",
+            server.base_url()
+        ),
+    )
+    .unwrap();
+
+    psyche::llm::config::prepare_vision_model(&path)
+        .await
+        .unwrap();
+    assert_eq!(pull.hits_async().await, 0);
+    warmup.assert_async().await;
+}
@@ -0,0 +1,142 @@
+use httpmock::prelude::*;
+use psyche::llm::openai::{OpenAiChat, OpenAiEmbed};
+use psyche::llm::{CanChat, CanEmbed, LlmCapability, LlmProfile};
+use tokio_stream::StreamExt;
+
+fn profile() -> LlmProfile {
+    LlmProfile {
+        provider: "openai".into(),
+        model: "gpt-test".into(),
+        capabilities: vec![LlmCapability::Chat],
+    }
+}
+
+#[tokio::test]
+async fn openai_chat_streams_sse_tokens() {
+    let server = MockServer::start_async().await;
+    let body = concat!(
+        "data: {\"choices\":[{\"delta\":{\"role\":\"assistant\"}}]}\n\n",
+        "data: {\"choices\":[{\"delta\":{\"content\":\"Hel\"}}]}\n\n",
+        "data: {\"choices\":[{\"delta\":{\"content\":\"lo\"}}]}\n\n",
+        "data: [DONE]\n\n",
+        "data: {\"choices\":[{\"delta\":{\"content\":\"ignored\"}}]}\n\n"
+    );
+    let mock = server
+        .mock_async(|when, then| {
+            when.method(POST)
+                .path("/v1/chat/completions")
+                .header("authorization", "Bearer secret")
+                .body_contains("\"stream\":true")
+                .body_contains("\"model\":\"gpt-test\"")
+                .body_contains("hi there");
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .body(body);
+        })
+        .await;
+
+    let chat = OpenAiChat {
+        base_url: server.base_url(),
+        model: "gpt-test".into(),
+        api_key: Some("secret".into()),
+    };
+    let stream = chat
+        .chat_stream(&profile(), "be brief", "hi there")
+        .await
+        .unwrap();
+    let tokens: Vec<String> = stream.collect().await;
+    assert_eq!(tokens, vec!["Hel".to_string(), "lo".to_string()]);
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn openai_chat_reports_http_errors() {
+    let server = MockServer::start_async().await;
+    server
+        .mock_async(|when, then| {
+            when.method(POST).path("/v1/chat/completions");
+            then.status(401).body("{\"error\":\"bad key\"}");
+        })
+        .await;
+
+    let chat = OpenAiChat {
+        base_url: server.base_url(),
+        model: "gpt-test".into(),
+        api_key: None,
+    };
+    assert!(chat.chat_stream(&profile(), "", "hi").await.is_err());
+}
+
+#[tokio::test]
+async fn openai_embed_returns_vector() {
+    let server = MockServer::start_async().await;
+    let mock = server
+        .mock_async(|when, then| {
+            when.method(POST)
+                .path("/v1/embeddings")
+                .header("authorization", "Bearer secret")
+                .body_contains("\"input\":\"hello\"");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body("{\"data\":[{\"index\":0,\"embedding\":[1.0,0.5]}]}");
+        })
+        .await;
+
+    let embed = OpenAiEmbed {
+        base_url: server.base_url(),
+        model: "text-embedding-3-small".into(),
+        api_key: Some("secret".into()),
+    };
+    let vector = embed.embed(&profile(), "hello").await.unwrap();
+    assert_eq!(vector, vec![1.0, 0.5]);
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn openai_chat_reports_token_usage() {
+    use psyche::llm::{ChatEvent, ChatMessage, GenerationOptions};
+
+    let server = MockServer::start_async().await;
+    let body = concat!(
+        "data: {\"choices\":[{\"delta\":{\"content\":\"Hi\"}}]}\n\n",
+        "data: {\"model\":\"gpt-test\",\"choices\":[],",
+        "\"usage\":{\"prompt_tokens\":9,\"completion_tokens\":1}}\n\n",
+        "data: [DONE]\n\n"
+    );
+    server
+        .mock_async(|when, then| {
+            when.method(POST)
+                .path("/v1/chat/completions")
+                .body_contains("\"include_usage\":true");
+            then.status(200)
+                .header("content-type", "text/event-stream")
+                .body(body);
+        })
+        .await;
+
+    let chat = OpenAiChat {
+        base_url: server.base_url(),
+        model: "gpt-test".into(),
+        api_key: None,
+    };
+    let events: Vec<ChatEvent> = chat
+        .chat_events(
+            &profile(),
+            &[ChatMessage::user("hi")],
+            &GenerationOptions::default(),
+        )
+        .await
+        .unwrap()
+        .map(Result::unwrap)
+        .collect()
+        .await;
+    match events.as_slice() {
+        [ChatEvent::Token(t), ChatEvent::Usage(u)] => {
+            assert_eq!(t, "Hi");
+            assert_eq!(u.prompt_tokens, Some(9));
+            assert_eq!(u.completion_tokens, Some(1));
+            assert_eq!(u.model.as_deref(), Some("gpt-test"));
+        }
+        other => panic!("unexpected events: {other:?}"),
+    }
+}
@@ -0,0 +1,245 @@
+use async_trait::async_trait;
+use psyche::llm::mock_chat::NamedMockChat;
+use psyche::llm::pool::{LlmPool, RoutingPolicy};
+use psyche::llm::scheduler::PriorityScheduler;
+use psyche::llm::{
+    CanChat, ChatEvent, ChatMessage, GenerationOptions, LlmCapability, LlmInstance, LlmProfile,
+};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::Arc;
+use tokio_stream::{Stream, StreamExt};
+
+fn profile() -> LlmProfile {
+    LlmProfile {
+        provider: "mock".into(),
+        model: "mock".into(),
+        capabilities: vec![LlmCapability::Chat],
+    }
+}
+
+fn instance(name: &str, chat: Arc<dyn CanChat>) -> LlmInstance {
+    LlmInstance {
+        name: name.into(),
+        chat,
+        profile: Arc::new(profile()),
+        scheduler: PriorityScheduler::new(1),
+    }
+}
+
+fn named(name: &str) -> LlmInstance {
+    instance(name, Arc::new(NamedMockChat { name: name.into() }))
+}
+
+async fn ask(pool: &LlmPool) -> String {
+    pool.chat_stream(&profile(), "", "hi")
+        .await
+        .unwrap()
+        .collect::<Vec<_>>()
+        .await
+        .concat()
+}
+
+/// Refuses every connection and fails its health check.
+struct DownChat;
+
+#[async_trait(?Send)]
+impl CanChat for DownChat {
+    async fn chat_stream(
+        &self,
+        _profile: &LlmProfile,
+        _system: &str,
+        _user: &str,
+    ) -> anyhow::Result<Box<dyn Stream<Item = String> + Unpin>> {
+        anyhow::bail!("connection refused")
+    }
+
+    async fn health_check(&self) -> anyhow::Result<()> {
+        anyhow::bail!("connection refused")
+    }
+}
+
+/// Yields `token`, if any, and then fails: by dropping the connection when
+/// `transport` is set, else with an error reported by the model.
+struct BrokenChat {
+    token: Option<&'static str>,
+    transport: bool,
+}
+
+#[async_trait(?Send)]
+impl CanChat for BrokenChat {
+    async fn chat_stream(
+        &self,
+        _profile: &LlmProfile,
+        _system: &str,
+        _user: &str,
+    ) -> anyhow::Result<Box<dyn Stream<Item = String> + Unpin>> {
+        unreachable!()
+    }
+
+    async fn chat_events(
+        &self,
+        _profile: &LlmProfile,
+        _messages: &[ChatMessage],
+        _options: &GenerationOptions,
+    ) -> anyhow::Result<Box<dyn Stream<Item = anyhow::Result<ChatEvent>> + Unpin>> {
+        let error = if self.transport {
+            std::io::Error::from(std::io::ErrorKind::ConnectionReset).into()
+        } else {
+            anyhow::anyhow!("ollama: model is overloaded")
+        };
+        let token = self.token.map(|t| Ok(ChatEvent::Token(t.to_string())));
+        Ok(Box::new(tokio_stream::iter(
+            token.into_iter().chain([Err(error)]),
+        )))
+    }
+}
+
+/// Records the history it was given and finishes the reply.
+struct ResumeChat(Rc<RefCell<Vec<ChatMessage>>>);
+
+#[async_trait(?Send)]
+impl CanChat for ResumeChat {
+    async fn chat_stream(
+        &self,
+        _profile: &LlmProfile,
+        _system: &str,
+        _user: &str,
+    ) -> anyhow::Result<Box<dyn Stream<Item = String> + Unpin>> {
+        unreachable!()
+    }
+
+    async fn chat_messages_stream(
+        &self,
+        _profile: &LlmProfile,
+        messages: &[ChatMessage],
+        _options: &GenerationOptions,
+    ) -> anyhow::Result<Box<dyn Stream<Item = String> + Unpin>> {
+        *self.0.borrow_mut() = messages.to_vec();
+        Ok(Box::new(tokio_stream::iter(["lo".to_string()])))
+    }
+}
+
+#[tokio::test]
+async fn priority_prefers_first_instance() {
+    let pool = LlmPool::new(vec![named("a"), named("b")], RoutingPolicy::Priority);
+    assert_eq!(ask(&pool).await, "a");
+    assert_eq!(ask(&pool).await, "a");
+}
+
+#[tokio::test]
+async fn round_robin_rotates_instances() {
+    let pool = LlmPool::new(vec![named("a"), named("b")], RoutingPolicy::RoundRobin);
+    assert_eq!(ask(&pool).await, "a");
+    assert_eq!(ask(&pool).await, "b");
+    assert_eq!(ask(&pool).await, "a");
+}
+
+#[tokio::test]
+async fn least_loaded_skips_busy_instance() {
+    let a = named("a");
+    let busy = a.scheduler.acquire(0).await;
+    let pool = LlmPool::new(vec![a, named("b")], RoutingPolicy::LeastLoaded);
+    assert_eq!(ask(&pool).await, "b");
+    drop(busy);
+    assert_eq!(ask(&pool).await, "a");
+}
+
+#[tokio::test]
+async fn fails_over_when_connection_refused() {
+    let pool = LlmPool::new(
+        vec![instance("down", Arc::new(DownChat)), named("b")],
+        RoutingPolicy::Priority,
+    );
+    assert_eq!(ask(&pool).await, "b");
+    assert_eq!(pool.is_healthy("down"), Some(false));
+}
+
+#[tokio::test]
+async fn health_checks_route_around_down_hosts() {
+    let pool = LlmPool::new(
+        vec![instance("down", Arc::new(DownChat)), named("b")],
+        RoutingPolicy::Priority,
+    );
+    pool.check_health().await;
+    assert_eq!(pool.is_healthy("down"), Some(false));
+    assert_eq!(pool.is_healthy("b"), Some(true));
+    assert!(pool.health_check().await.is_ok());
+
+    let all_down = LlmPool::new(
+        vec![instance("down", Arc::new(DownChat))],
+        RoutingPolicy::Priority,
+    );
+    assert!(all_down.health_check().await.is_err());
+    assert!(all_down.chat_stream(&profile(), "", "hi").await.is_err());
+}
+
+fn broken_pool(broken: BrokenChat, seen: &Rc<RefCell<Vec<ChatMessage>>>) -> LlmPool {
+    #[allow(clippy::arc_with_non_send_sync)]
+    let spare = Arc::new(ResumeChat(seen.clone()));
+    LlmPool::new(
+        vec![
+            instance("broken", Arc::new(broken)),
+            instance("spare", spare),
+        ],
+        RoutingPolicy::Priority,
+    )
+}
+
+async fn events(pool: &LlmPool) -> Vec<Result<String, String>> {
+    let messages = [ChatMessage::system(""), ChatMessage::user("hi")];
+    pool.chat_events(&profile(), &messages, &GenerationOptions::default())
+        .await
+        .unwrap()
+        .filter_map(|event| match event {
+            Ok(ChatEvent::Token(token)) => Some(Ok(token)),
+            Ok(_) => None,
+            Err(e) => Some(Err(e.to_string())),
+        })
+        .collect()
+        .await
+}
+
+#[tokio::test]
+async fn retries_stream_failing_before_first_token() {
+    let seen = Rc::new(RefCell::new(Vec::new()));
+    let broken = BrokenChat {
+        token: None,
+        transport: true,
+    };
+    let pool = broken_pool(broken, &seen);
+    assert_eq!(events(&pool).await, [Ok("lo".to_string())]);
+    assert_eq!(pool.is_healthy("broken"), Some(false));
+    assert_eq!(
+        *seen.borrow(),
+        vec![ChatMessage::system(""), ChatMessage::user("hi")]
+    );
+}
+
+#[tokio::test]
+async fn ends_stream_failing_after_tokens_reached_the_caller() {
+    let seen = Rc::new(RefCell::new(Vec::new()));
+    let broken = BrokenChat {
+        token: Some("Hel"),
+        transport: true,
+    };
+    let pool = broken_pool(broken, &seen);
+    let got = events(&pool).await;
+    assert_eq!(got.len(), 2);
+    assert_eq!(got[0], Ok("Hel".to_string()));
+    assert!(got[1].is_err());
+    assert_eq!(pool.is_healthy("broken"), Some(false));
+    assert!(seen.borrow().is_empty());
+}
+
+#[tokio::test]
+async fn model_errors_leave_the_host_healthy() {
+    let seen = Rc::new(RefCell::new(Vec::new()));
+    let broken = BrokenChat {
+        token: None,
+        transport: false,
+    };
+    let pool = broken_pool(broken, &seen);
+    assert_eq!(events(&pool).await, [Ok("lo".to_string())]);
+    assert_eq!(pool.is_healthy("broken"), Some(true));
+}
@@ -0,0 +1,109 @@
+use chrono::{TimeZone, Utc};
+use psyche::llm::prompt::{PromptContext, PromptHelper};
+use psyche::memory::Experience;
+use psyche::models::MemoryEntry;
+use serde_json::json;
+use uuid::Uuid;
+
+fn soul() -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("identity.toml"),
+        concat!(
+            "name = \"Layka\"\n",
+            "role = \"Autonomous space probe\"\n",
+            "purpose = \"To explore\"\n",
+            "[wit.quick]\n",
+            "prompt = \"{input}\"\n",
+        ),
+    )
+    .unwrap();
+    dir
+}
+
+fn entry(text: &str) -> MemoryEntry {
+    MemoryEntry {
+        id: Uuid::new_v4(),
+        kind: "sensation/chat".into(),
+        when: Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap(),
+        what: json!(text),
+        how: String::new(),
+    }
+}
+
+#[test]
+fn renders_identity_fields() {
+    let dir = soul();
+    let helper = PromptHelper::from_config(&dir.path().join("identity.toml"));
+    let text = helper
+        .render(
+            "I am {{ name }}, a {{ role }}. {{ purpose }}.",
+            &PromptContext::new(),
+        )
+        .unwrap();
+    assert_eq!(text, "I am Layka, a Autonomous space probe. To explore.");
+}
+
+#[test]
+fn includes_partials_from_soul() {
+    let dir = soul();
+    std::fs::create_dir(dir.path().join("prompts")).unwrap();
+    std::fs::write(
+        dir.path().join("prompts/persona.txt"),
+        "You are {{ name }}.",
+    )
+    .unwrap();
+    let helper = PromptHelper::from_config(&dir.path().join("identity.toml"));
+    let text = helper
+        .render(
+            "{% include \"persona.txt\" %} Be brief.",
+            &PromptContext::new(),
+        )
+        .unwrap();
+    assert_eq!(text, "You are Layka. Be brief.");
+}
+
+#[test]
+fn exposes_entries_with_timestamps() {
+    let helper = PromptHelper::default();
+    let ctx = PromptContext::new().input(&[entry("hello"), entry("<bye>")]);
+    let text = helper
+        .render(
+            "{% for e in entries %}[{{ e.when }}] {{ e.text }}\n{% endfor %}",
+            &ctx,
+        )
+        .unwrap();
+    assert_eq!(
+        text,
+        "[2024-05-01T12:00:00+00:00] hello\n[2024-05-01T12:00:00+00:00] <bye>\n"
+    );
+}
+
+#[test]
+fn legacy_input_placeholder_still_works() {
+    let helper = PromptHelper::default();
+    let ctx = PromptContext::new().input(&[entry("a"), entry("b")]);
+    assert_eq!(helper.render("Saw: {input}", &ctx).unwrap(), "Saw: a\nb");
+    assert_eq!(helper.render("Saw: {{input}}", &ctx).unwrap(), "Saw: a\nb");
+}
+
+#[test]
+fn exposes_time_and_memories() {
+    let helper = PromptHelper::default();
+    let memory = Experience {
+        how: "I saw a comet".into(),
+        what: json!("comet"),
+        when: Utc.with_ymd_and_hms(2024, 4, 30, 8, 0, 0).unwrap(),
+        tags: vec!["sky".into()],
+    };
+    let ctx = PromptContext::new()
+        .at(Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap())
+        .memories(&[memory]);
+    let text = helper
+        .render(
+            "It is {{ now }}. {% for m in memories %}{{ m.how }} ({{ m.tags | join(sep=\",\") }}){% endfor %}",
+            &ctx,
+        )
+        .unwrap();
+    assert_eq!(text, "It is 2024-05-01T12:00:00+00:00. I saw a comet (sky)");
+}
@@ -38,3 +38,42 @@ async fn skips_creation_when_present() {
     ensure_collection(&client, 3).await.unwrap();
     assert!(!*client.created.lock().unwrap());
 }
+
+#[test]
+fn search_filter_becomes_payload_conditions() {
+    use psyche::memory::{payload_filter, SearchFilter};
+    assert_eq!(payload_filter(&SearchFilter::default()), None);
+    let filter = payload_filter(&SearchFilter {
+        tags: vec!["instant".into()],
+        since: chrono::DateTime::from_timestamp(100, 0),
+        min_score: Some(0.5),
+        ..Default::default()
+    })
+    .unwrap();
+    let debug = format!("{:?}", filter.must);
+    assert_eq!(filter.must.len(), 2);
+    assert!(debug.contains("\"tags\"") && debug.contains("instant"));
+    assert!(debug.contains("gte: Some(100.0)") && debug.contains("lte: None"));
+}
+
+#[test]
+fn legacy_points_without_payload_pass_the_filter() {
+    use psyche::memory::{payload_filter, SearchFilter};
+    use qdrant_client::qdrant::condition::ConditionOneOf;
+    let filter = payload_filter(&SearchFilter {
+        tags: vec!["instant".into()],
+        until: chrono::DateTime::from_timestamp(100, 0),
+        ..Default::default()
+    })
+    .unwrap();
+    for (cond, key) in filter.must.iter().zip(["tags", "when"]) {
+        let Some(ConditionOneOf::Filter(either)) = &cond.condition_one_of else {
+            panic!("{key} is not an alternative: {cond:?}");
+        };
+        assert_eq!(either.should.len(), 2);
+        let Some(ConditionOneOf::IsEmpty(empty)) = &either.should[1].condition_one_of else {
+            panic!("{key} does not admit missing payload: {either:?}");
+        };
+        assert_eq!(empty.key, key);
+    }
+}
@@ -0,0 +1,125 @@
+use psyche::llm::limited::LimitedChat;
+use psyche::llm::mock_chat::NamedMockChat;
+use psyche::llm::scheduler::PriorityScheduler;
+use psyche::llm::{CanChat, ChatMessage, GenerationOptions, LlmCapability, LlmProfile};
+use std::cell::RefCell;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::yield_now;
+use tokio_stream::StreamExt;
+
+#[tokio::test]
+async fn grants_waiting_requests_by_priority() {
+    let scheduler = PriorityScheduler::new(1).with_aging(Duration::from_secs(60));
+    let held = scheduler.acquire(0).await;
+    let order = RefCell::new(Vec::new());
+    let waiter = |priority: usize| {
+        let scheduler = scheduler.clone();
+        let order = &order;
+        async move {
+            let _permit = scheduler.acquire(priority).await;
+            order.borrow_mut().push(priority);
+            yield_now().await;
+        }
+    };
+    let release = async {
+        yield_now().await;
+        let stats = scheduler.stats();
+        assert_eq!(
+            [stats[&5].depth, stats[&1].depth, stats[&3].depth],
+            [1, 1, 1]
+        );
+        drop(held);
+    };
+    tokio::join!(waiter(5), waiter(1), waiter(3), release);
+    assert_eq!(*order.borrow(), vec![1, 3, 5]);
+    assert_eq!(scheduler.available_permits(), 1);
+    assert!(scheduler.stats().values().all(|s| s.depth == 0));
+}
+
+#[tokio::test(start_paused = true)]
+async fn aging_prevents_starvation() {
+    let scheduler = PriorityScheduler::new(1).with_aging(Duration::from_millis(10));
+    let held = scheduler.acquire(0).await;
+    let order = RefCell::new(Vec::new());
+    let waiter = |priority: usize, delay: u64| {
+        let scheduler = scheduler.clone();
+        let order = &order;
+        async move {
+            tokio::time::sleep(Duration::from_millis(delay)).await;
+            let _permit = scheduler.acquire(priority).await;
+            order.borrow_mut().push(priority);
+            yield_now().await;
+        }
+    };
+    let release = async {
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        drop(held);
+    };
+    // priority 9 ages past 1 after waiting 150 ms; priority 1 waits only 5 ms
+    tokio::join!(waiter(9, 0), waiter(1, 145), release);
+    assert_eq!(*order.borrow(), vec![9, 1]);
+}
+
+#[tokio::test]
+async fn records_wait_times_per_class() {
+    let scheduler = PriorityScheduler::new(1);
+    let held = scheduler.acquire(0).await;
+    let release = async {
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        drop(held);
+    };
+    let (_, _permit) = tokio::join!(release, scheduler.acquire(2));
+    let stats = scheduler.stats();
+    assert_eq!(stats[&0].granted, 1);
+    assert_eq!(stats[&0].max_wait, Duration::ZERO);
+    assert_eq!(stats[&2].granted, 1);
+    assert!(stats[&2].max_wait >= Duration::from_millis(25));
+    assert_eq!(stats[&2].mean_wait(), stats[&2].max_wait);
+}
+
+#[tokio::test]
+async fn abandoned_waiter_does_not_leak_permit() {
+    let scheduler = PriorityScheduler::new(1);
+    let held = scheduler.acquire(0).await;
+    let waited = tokio::time::timeout(Duration::from_millis(10), scheduler.acquire(1)).await;
+    assert!(waited.is_err());
+    drop(held);
+    assert_eq!(scheduler.available_permits(), 1);
+    assert_eq!(scheduler.stats().get(&1).map(|s| s.depth), None);
+}
+
+#[tokio::test]
+#[allow(clippy::arc_with_non_send_sync)]
+async fn limited_chat_admits_by_request_priority() {
+    let scheduler = PriorityScheduler::new(1).with_aging(Duration::from_secs(60));
+    let chat = LimitedChat::new(Arc::new(NamedMockChat::default()), scheduler.clone());
+    let profile = LlmProfile {
+        provider: "mock".into(),
+        model: "mock".into(),
+        capabilities: vec![LlmCapability::Chat],
+    };
+    let held = scheduler.acquire(0).await;
+    let order = RefCell::new(Vec::new());
+    let ask = |priority: usize| {
+        let (chat, profile, order) = (&chat, &profile, &order);
+        async move {
+            let options = GenerationOptions {
+                priority: Some(priority),
+                ..Default::default()
+            };
+            let stream = chat
+                .chat_messages_stream(profile, &[ChatMessage::user("hi")], &options)
+                .await
+                .unwrap();
+            order.borrow_mut().push(priority);
+            let _: Vec<String> = stream.collect().await;
+        }
+    };
+    let release = async {
+        yield_now().await;
+        drop(held);
+    };
+    tokio::join!(ask(7), ask(2), release);
+    assert_eq!(*order.borrow(), vec![2, 7]);
+}
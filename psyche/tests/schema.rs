@@ -0,0 +1,193 @@
+use chrono::Utc;
+use psyche::llm::{CanChat, ChatMessage, GenerationOptions, LlmCapability, LlmProfile};
+use psyche::models::MemoryEntry;
+use psyche::schema::validate;
+use psyche::wit::{Wit, WitConfig, SCHEMA_ERROR_KIND};
+use serde_json::{json, Value};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use uuid::Uuid;
+
+fn urge_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "urge": {"type": "string", "enum": ["say", "look", "wait"]},
+            "target": {"type": ["string", "null"]},
+            "strength": {"type": "number", "minimum": 0, "maximum": 1}
+        },
+        "required": ["urge", "target"],
+        "additionalProperties": false
+    })
+}
+
+#[test]
+fn accepts_matching_value() {
+    let value = json!({"urge": "say", "target": "Alice", "strength": 0.5});
+    assert!(validate(&urge_schema(), &value).is_ok());
+    assert!(validate(&urge_schema(), &json!({"urge": "wait", "target": null})).is_ok());
+}
+
+#[test]
+fn reports_every_violation_with_its_path() {
+    let value = json!({"urge": "dance", "strength": 2, "mood": "odd"});
+    let mut errors = validate(&urge_schema(), &value).unwrap_err();
+    errors.sort();
+    assert_eq!(
+        errors,
+        vec![
+            "/: missing property \"target\"".to_string(),
+            "/mood: unexpected property".to_string(),
+            "/strength: must be at most 1".to_string(),
+            "/urge: expected one of [\"say\",\"look\",\"wait\"]".to_string(),
+        ]
+    );
+}
+
+#[test]
+fn checks_array_items_and_lengths() {
+    let schema = json!({
+        "type": "array",
+        "items": {"type": "string", "minLength": 2},
+        "maxItems": 2
+    });
+    let errors = validate(&schema, &json!(["ok", "x", "fine"])).unwrap_err();
+    assert_eq!(
+        errors,
+        vec![
+            "/: allows at most 2 items".to_string(),
+            "/1: needs at least 2 characters".to_string(),
+        ]
+    );
+}
+
+type Request = (Vec<ChatMessage>, GenerationOptions);
+
+/// Replies with queued answers and records every request.
+#[derive(Clone, Default)]
+struct QueuedChat {
+    replies: Rc<RefCell<VecDeque<&'static str>>>,
+    requests: Rc<RefCell<Vec<Request>>>,
+}
+
+#[async_trait::async_trait(?Send)]
+impl CanChat for QueuedChat {
+    async fn chat_stream(
+        &self,
+        _profile: &LlmProfile,
+        _system: &str,
+        _user: &str,
+    ) -> anyhow::Result<Box<dyn tokio_stream::Stream<Item = String> + Unpin>> {
+        unreachable!("wits send message histories")
+    }
+
+    async fn chat_messages_stream(
+        &self,
+        _profile: &LlmProfile,
+        messages: &[ChatMessage],
+        options: &GenerationOptions,
+    ) -> anyhow::Result<Box<dyn tokio_stream::Stream<Item = String> + Unpin>> {
+        self.requests
+            .borrow_mut()
+            .push((messages.to_vec(), options.clone()));
+        let reply = self.replies.borrow_mut().pop_front().unwrap_or("");
+        Ok(Box::new(tokio_stream::iter([reply.to_string()])))
+    }
+}
+
+fn urge_wit(chat: QueuedChat, replies: &[&'static str], max_repairs: usize) -> Wit {
+    chat.replies.borrow_mut().extend(replies);
+    Wit {
+        config: WitConfig {
+            name: "will".into(),
+            input_kind: "sensation/chat".into(),
+            output_kind: "urge".into(),
+            prompt_template: "What now? {{ input }}".into(),
+            post_process: None,
+            options: Default::default(),
+            priority: 0,
+            examples: Vec::new(),
+            output_schema: Some(urge_schema()),
+            max_repairs,
+        },
+        llm: Box::new(chat),
+        profile: LlmProfile {
+            provider: "mock".into(),
+            model: "mock".into(),
+            capabilities: vec![LlmCapability::Chat],
+        },
+        prompter: Default::default(),
+    }
+}
+
+fn chat_entry() -> MemoryEntry {
+    MemoryEntry {
+        id: Uuid::new_v4(),
+        kind: "sensation/chat".into(),
+        when: Utc::now(),
+        what: json!("Alice waves"),
+        how: String::new(),
+    }
+}
+
+#[tokio::test]
+async fn wit_sends_schema_as_format_and_stores_json() {
+    let chat = QueuedChat::default();
+    let mut wit = urge_wit(
+        chat.clone(),
+        &["```json\n{\"urge\": \"say\", \"target\": \"Alice\"}\n```"],
+        0,
+    );
+    let out = wit.distill(vec![chat_entry()]).await.unwrap();
+    assert_eq!(out[0].kind, "urge");
+    assert_eq!(out[0].what, json!({"urge": "say", "target": "Alice"}));
+    let requests = chat.requests.borrow();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].1.format, Some(urge_schema()));
+}
+
+#[tokio::test]
+async fn wit_repairs_invalid_reply() {
+    let chat = QueuedChat::default();
+    let mut wit = urge_wit(
+        chat.clone(),
+        &[
+            "I should greet Alice.",
+            "{\"urge\": \"say\"}",
+            "{\"urge\": \"say\", \"target\": \"Alice\"}",
+        ],
+        2,
+    );
+    let out = wit.distill(vec![chat_entry()]).await.unwrap();
+    assert_eq!(out[0].kind, "urge");
+    assert_eq!(out[0].what, json!({"urge": "say", "target": "Alice"}));
+
+    let requests = chat.requests.borrow();
+    assert_eq!(requests.len(), 3);
+    let last = &requests[2].0;
+    assert_eq!(last.len(), 5);
+    assert_eq!(last[3].content, "{\"urge\": \"say\"}");
+    assert!(last[4].content.contains("- /: missing property \"target\""));
+    assert!(last[4].content.contains("\"required\""));
+}
+
+#[tokio::test]
+async fn wit_stores_error_entry_after_repairs_run_out() {
+    let chat = QueuedChat::default();
+    let mut wit = urge_wit(chat.clone(), &["nope", "{\"urge\": 1}"], 1);
+    let out = wit.distill(vec![chat_entry()]).await.unwrap();
+    assert_eq!(chat.requests.borrow().len(), 2);
+    assert_eq!(out[0].kind, SCHEMA_ERROR_KIND);
+    assert_eq!(out[0].what["wit"], "will");
+    assert_eq!(out[0].what["expected_kind"], "urge");
+    assert_eq!(out[0].what["attempts"], 2);
+    assert_eq!(out[0].what["response"], "{\"urge\": 1}");
+    assert_eq!(
+        out[0].what["errors"],
+        json!([
+            "/: missing property \"target\"",
+            "/urge: expected string, got number"
+        ])
+    );
+}
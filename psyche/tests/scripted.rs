@@ -0,0 +1,175 @@
+use psyche::llm::scripted::ScriptedChat;
+use psyche::llm::{CanChat, ChatEvent, ChatMessage, GenerationOptions, LlmCapability, LlmProfile};
+use psyche::models::MemoryEntry;
+use psyche::wit::{Wit, WitConfig};
+use serde_json::json;
+use std::time::Duration;
+use tokio_stream::StreamExt;
+
+const RULES: &str = r#"
+default = "..."
+
+[[rule]]
+wit = "quick"
+contains = "hello"
+response = "Hi there!"
+chunk_chars = 3
+
+[[rule]]
+regex = "(?i)weather in (\\w+)"
+response = "It is sunny in $1."
+
+[[rule]]
+contains = "slow"
+response = "abcd"
+chunk_chars = 1
+first_token_delay_ms = 20
+delay_ms = 10
+
+[[rule]]
+contains = "overload"
+error = "model overloaded"
+
+[[rule]]
+contains = "flaky"
+response = "partial reply"
+chunk_chars = 8
+error = "connection reset"
+error_after_chunks = 1
+"#;
+
+fn profile() -> LlmProfile {
+    LlmProfile {
+        provider: "scripted".into(),
+        model: "script".into(),
+        capabilities: vec![LlmCapability::Chat],
+    }
+}
+
+async fn events(chat: &ScriptedChat, prompt: &str) -> anyhow::Result<Vec<Result<String, String>>> {
+    let stream = chat
+        .chat_events(
+            &profile(),
+            &[ChatMessage::system("sys"), ChatMessage::user(prompt)],
+            &GenerationOptions::default(),
+        )
+        .await?;
+    Ok(stream
+        .map(|e| match e {
+            Ok(ChatEvent::Token(t)) => Ok(t),
+            Ok(other) => Err(format!("{:?}", other)),
+            Err(e) => Err(e.to_string()),
+        })
+        .collect()
+        .await)
+}
+
+#[tokio::test]
+async fn wit_rules_only_apply_to_bound_wit() {
+    let chat = ScriptedChat::from_toml(RULES).unwrap();
+    assert_eq!(
+        events(&chat, "hello").await.unwrap(),
+        vec![Ok("...".into())]
+    );
+    assert_eq!(
+        events(&chat.for_wit("quick"), "hello").await.unwrap(),
+        vec![Ok("Hi ".into()), Ok("the".into()), Ok("re!".into())]
+    );
+}
+
+#[tokio::test]
+async fn wit_rules_apply_to_requests_naming_the_wit() {
+    let chat = ScriptedChat::from_toml(RULES).unwrap();
+    let options = GenerationOptions {
+        wit: Some("quick".into()),
+        ..Default::default()
+    };
+    let reply: Vec<String> = chat
+        .chat_messages_stream(&profile(), &[ChatMessage::user("hello")], &options)
+        .await
+        .unwrap()
+        .collect()
+        .await;
+    assert_eq!(reply.concat(), "Hi there!");
+    let options = GenerationOptions {
+        wit: Some("will".into()),
+        ..Default::default()
+    };
+    let reply: Vec<String> = chat
+        .for_wit("quick")
+        .chat_messages_stream(&profile(), &[ChatMessage::user("hello")], &options)
+        .await
+        .unwrap()
+        .collect()
+        .await;
+    assert_eq!(reply.concat(), "...");
+}
+
+#[tokio::test]
+async fn regex_captures_expand_into_response() {
+    let chat = ScriptedChat::from_toml(RULES).unwrap();
+    assert_eq!(
+        events(&chat, "What's the Weather in Paris?").await.unwrap(),
+        vec![Ok("It is sunny in Paris.".into())]
+    );
+}
+
+#[tokio::test]
+async fn delays_between_chunks() {
+    let chat = ScriptedChat::from_toml(RULES).unwrap();
+    let started = std::time::Instant::now();
+    let items = events(&chat, "slow please").await.unwrap();
+    assert_eq!(items.len(), 4);
+    assert!(started.elapsed() >= Duration::from_millis(50));
+}
+
+#[tokio::test]
+async fn injects_errors() {
+    let chat = ScriptedChat::from_toml(RULES).unwrap();
+    let err = events(&chat, "overload").await.unwrap_err();
+    assert_eq!(err.to_string(), "model overloaded");
+    assert_eq!(
+        events(&chat, "flaky").await.unwrap(),
+        vec![Ok("partial ".into()), Err("connection reset".into())]
+    );
+}
+
+#[tokio::test]
+async fn unmatched_prompt_fails_without_default() {
+    let chat = ScriptedChat::from_toml("[[rule]]\ncontains = \"x\"\nresponse = \"y\"\n").unwrap();
+    let err = events(&chat, "nothing").await.unwrap_err();
+    assert!(err.to_string().contains("no scripted rule"));
+    assert!(ScriptedChat::from_toml("[[rule]]\nregex = \"(\"\n").is_err());
+}
+
+#[tokio::test]
+async fn drives_a_wit_deterministically() {
+    // unbound, as loaded from llm.toml: the wit names itself per request
+    let chat = ScriptedChat::from_toml(RULES).unwrap();
+    let mut wit = Wit {
+        config: WitConfig {
+            name: "quick".into(),
+            input_kind: "sensation/chat".into(),
+            output_kind: "instant".into(),
+            prompt_template: "{{ input }}".into(),
+            post_process: None,
+            options: Default::default(),
+            priority: 0,
+            examples: Vec::new(),
+            output_schema: None,
+            max_repairs: 0,
+        },
+        llm: Box::new(chat),
+        profile: profile(),
+        prompter: Default::default(),
+    };
+    let entry = MemoryEntry {
+        id: uuid::Uuid::new_v4(),
+        kind: "sensation/chat".into(),
+        when: chrono::Utc::now(),
+        what: json!("hello"),
+        how: String::new(),
+    };
+    let out = wit.distill(vec![entry]).await.unwrap();
+    assert_eq!(out[0].what, json!("Hi there!"));
+}
@@ -0,0 +1,199 @@
+#![cfg(feature = "sqlite")]
+
+use chrono::{DateTime, Utc};
+use psyche::memory::{
+    about, context_subgraph, record_entities, Experience, HnswBackend, MemoryBackend, SearchHit,
+    SqliteBackend,
+};
+use psyche::soul::SoulArchive;
+use serde_json::json;
+use std::path::Path;
+
+fn exp(how: &str, secs: i64, tag: &str) -> Experience {
+    Experience {
+        how: how.into(),
+        what: json!({ "said": how }),
+        when: DateTime::<Utc>::from_timestamp(1_700_000_000 + secs, 0).unwrap(),
+        tags: vec![tag.into()],
+    }
+}
+
+/// A soul with configuration, rememberd entries and a linked memory.
+async fn populate(soul: &Path) -> (HnswBackend, Vec<String>) {
+    std::fs::create_dir_all(soul.join("config")).unwrap();
+    std::fs::create_dir_all(soul.join("memory/archive")).unwrap();
+    std::fs::write(soul.join("identity.toml"), "name = \"Layka\"\n").unwrap();
+    std::fs::write(soul.join("self.txt"), "You are Layka.").unwrap();
+    std::fs::write(soul.join("config/llm.toml"), "[[chat]]\nmodel = \"m\"\n").unwrap();
+    std::fs::write(
+        soul.join("memory/instant.jsonl"),
+        "{\"id\":\"a\",\"how\":\"I see rain.\"}\n{\"id\":\"b\",\"how\":\"I hear thunder.\"}\n",
+    )
+    .unwrap();
+    std::fs::write(
+        soul.join("memory/archive/instant.jsonl"),
+        "{\"id\":\"c\",\"how\":\"I saw rain.\"}\n",
+    )
+    .unwrap();
+
+    let backend = HnswBackend::open(soul.join("memory/index")).unwrap();
+    let mut ids = Vec::new();
+    let moments = [
+        ("Clouds gathered.", "sight", [1.0, 0.0, 0.0]),
+        ("Rain began to fall.", "sight", [0.9, 0.1, 0.0]),
+        ("Thunder rolled.", "sound", [0.2, 0.9, 0.1]),
+        ("Alice opened an umbrella.", "sight", [0.1, 0.2, 0.9]),
+        ("The rain stopped.", "sound", [0.7, 0.0, 0.3]),
+    ];
+    for (i, (how, tag, vector)) in moments.iter().enumerate() {
+        ids.push(
+            backend
+                .store(&exp(how, i as i64, tag), vector)
+                .await
+                .unwrap(),
+        );
+    }
+    backend.link_causes(&ids[0], &ids[1]).await.unwrap();
+    backend.link_topic(&ids[1], "rain").await.unwrap();
+    backend.link_topic(&ids[4], "rain").await.unwrap();
+    backend.link_summary(&ids[4], &ids[1]).await.unwrap();
+    record_entities(
+        &backend,
+        &json!({
+            "entities": [{"name": "Alice", "kind": "person", "aliases": ["Ally"]}],
+            "mentions": [ids[3]],
+        }),
+    )
+    .await
+    .unwrap();
+    (backend, ids)
+}
+
+fn entries(path: &Path) -> Vec<serde_json::Value> {
+    std::fs::read_to_string(path)
+        .unwrap()
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect()
+}
+
+fn summary(hits: &[SearchHit]) -> Vec<(String, Experience)> {
+    hits.iter()
+        .map(|h| (h.id.clone(), h.experience.clone()))
+        .collect()
+}
+
+async fn assert_same_memory<A, B>(original: &A, restored: &B, ids: &[String])
+where
+    A: MemoryBackend + Sync,
+    B: MemoryBackend + Sync,
+{
+    for query in [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.3, 0.3, 0.9]] {
+        let want = original
+            .search(&query, 3, &Default::default())
+            .await
+            .unwrap();
+        let got = restored
+            .search(&query, 3, &Default::default())
+            .await
+            .unwrap();
+        assert_eq!(summary(&got), summary(&want));
+        for (g, w) in got.iter().zip(&want) {
+            assert!((g.score - w.score).abs() < 1e-5);
+        }
+    }
+    for id in ids {
+        assert_eq!(
+            context_subgraph(restored, id).await.unwrap(),
+            context_subgraph(original, id).await.unwrap()
+        );
+    }
+    assert_eq!(
+        about(restored, "ally").await.unwrap(),
+        about(original, "ally").await.unwrap()
+    );
+}
+
+#[tokio::test]
+async fn export_restores_into_fresh_embedded_backends() {
+    let soul = tempfile::tempdir().unwrap();
+    let (backend, ids) = populate(soul.path()).await;
+    let mut bytes = Vec::new();
+    SoulArchive::collect(soul.path(), Some(&backend))
+        .await
+        .unwrap()
+        .write(&mut bytes)
+        .unwrap();
+    let header: serde_json::Value =
+        serde_json::from_str(std::str::from_utf8(&bytes).unwrap().lines().next().unwrap()).unwrap();
+    assert_eq!(header["format"], "psyche-soul");
+    assert_eq!(header["contents"]["files"], 3);
+    assert_eq!(header["contents"]["entries"], 3);
+    assert_eq!(header["contents"]["experiences"], 5);
+
+    let hnsw_soul = tempfile::tempdir().unwrap();
+    let hnsw = HnswBackend::open(hnsw_soul.path().join("memory/index")).unwrap();
+    let archive = SoulArchive::read(&bytes[..]).unwrap();
+    archive
+        .restore(hnsw_soul.path(), Some(&hnsw))
+        .await
+        .unwrap();
+    assert_eq!(hnsw.edges(), backend.edges());
+    assert_same_memory(&backend, &hnsw, &ids).await;
+    for file in ["identity.toml", "self.txt", "config/llm.toml"] {
+        assert_eq!(
+            std::fs::read_to_string(hnsw_soul.path().join(file)).unwrap(),
+            std::fs::read_to_string(soul.path().join(file)).unwrap()
+        );
+    }
+    for file in ["memory/instant.jsonl", "memory/archive/instant.jsonl"] {
+        assert_eq!(
+            entries(&hnsw_soul.path().join(file)),
+            entries(&soul.path().join(file))
+        );
+    }
+
+    // a reopened copy answers the same
+    drop(hnsw);
+    let reopened = HnswBackend::open(hnsw_soul.path().join("memory/index")).unwrap();
+    assert_same_memory(&backend, &reopened, &ids).await;
+
+    let sqlite_soul = tempfile::tempdir().unwrap();
+    let sqlite = SqliteBackend::open_in_memory().unwrap();
+    archive
+        .restore(sqlite_soul.path(), Some(&sqlite))
+        .await
+        .unwrap();
+    assert_eq!(sqlite.edges().unwrap(), backend.edges());
+    assert_same_memory(&backend, &sqlite, &ids).await;
+}
+
+#[tokio::test]
+async fn import_refuses_to_overwrite_or_read_a_truncated_archive() {
+    let soul = tempfile::tempdir().unwrap();
+    let (backend, _) = populate(soul.path()).await;
+    let archive = SoulArchive::collect(soul.path(), Some(&backend))
+        .await
+        .unwrap();
+
+    // the source soul already has every file and a full memory
+    let err = archive
+        .restore(soul.path(), Some(&backend))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("already exists"), "{err}");
+    let fresh = tempfile::tempdir().unwrap();
+    let err = archive
+        .restore(fresh.path(), Some(&backend))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("not empty"), "{err}");
+    assert!(!fresh.path().join("identity.toml").exists());
+
+    let mut bytes = Vec::new();
+    archive.write(&mut bytes).unwrap();
+    let text = String::from_utf8(bytes).unwrap();
+    let truncated: Vec<&str> = text.lines().take(text.lines().count() - 1).collect();
+    let err = SoulArchive::read(truncated.join("\n").as_bytes()).unwrap_err();
+    assert!(err.to_string().contains("incomplete"), "{err}");
+}
@@ -0,0 +1,235 @@
+#![cfg(feature = "sqlite")]
+
+use chrono::{DateTime, Utc};
+use psyche::memory::{
+    about, context_subgraph, record_entities, summary_chain, tagged_since, Edge, Entity,
+    EntityKind, Experience, MemoryBackend, SearchFilter, SqliteBackend,
+};
+
+fn exp(how: &str, secs: i64, tag: &str) -> Experience {
+    Experience {
+        how: how.into(),
+        what: serde_json::json!({ "said": how }),
+        when: DateTime::<Utc>::from_timestamp(1_700_000_000 + secs, 0).unwrap(),
+        tags: vec![tag.into()],
+    }
+}
+
+#[tokio::test]
+async fn stores_and_reopens_from_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("memory.db");
+    let first = exp("Rain tapped the window.", 1, "sensation/ear");
+    let id = {
+        let db = SqliteBackend::open(&path).unwrap();
+        let id = db.store(&first, &[1.0, 0.0]).await.unwrap();
+        db.store(&exp("Sun came out.", 2, "sensation/eye"), &[0.0, 1.0])
+            .await
+            .unwrap();
+        id
+    };
+    let db = SqliteBackend::open(&path).unwrap();
+    assert_eq!(db.get(&id).await.unwrap(), Some(first.clone()));
+    let hits = db
+        .search(&[0.9, 0.2], 1, &Default::default())
+        .await
+        .unwrap();
+    assert_eq!(
+        (hits[0].id.as_str(), &hits[0].experience),
+        (id.as_str(), &first)
+    );
+    assert_eq!(db.get("missing").await.unwrap(), None);
+    db.store_with_id("chosen", &exp("Wind.", 3, "sensation/ear"), &[0.5, 0.5])
+        .await
+        .unwrap();
+    assert_eq!(db.get("chosen").await.unwrap().unwrap().how, "Wind.");
+    // storing a known id again leaves it as it is
+    db.store_with_id("chosen", &first, &[0.5, 0.5])
+        .await
+        .unwrap();
+    assert_eq!(db.get("chosen").await.unwrap().unwrap().how, "Wind.");
+
+    let filter = SearchFilter {
+        tags: vec!["sensation/eye".into(), "moment".into()],
+        ..Default::default()
+    };
+    let hits = db.search(&[0.9, 0.2], 5, &filter).await.unwrap();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].experience.how, "Sun came out.");
+    let filter = SearchFilter {
+        until: DateTime::from_timestamp(1_700_000_001, 0),
+        min_score: Some(0.9),
+        ..Default::default()
+    };
+    let hits = db.search(&[0.9, 0.2], 5, &filter).await.unwrap();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].id, id);
+}
+
+#[tokio::test]
+async fn keyword_search_covers_how_and_what() {
+    let db = SqliteBackend::open_in_memory().unwrap();
+    db.store(&exp("Alice waved hello.", 1, "instant"), &[1.0])
+        .await
+        .unwrap();
+    let mut other = exp("Something else.", 2, "instant");
+    other.what = serde_json::json!("a parcel for Alice arrived");
+    db.store(&other, &[1.0]).await.unwrap();
+
+    let hits = db.keyword_search("alice", 10).unwrap();
+    assert_eq!(hits.len(), 2);
+    let hits = db.keyword_search("parcel Alice", 10).unwrap();
+    assert_eq!(hits, vec![other]);
+    assert!(db.keyword_search("\"unbalanced OR", 10).unwrap().is_empty());
+    assert!(db.keyword_search("  ", 10).unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn traverses_edges() {
+    let db = SqliteBackend::open_in_memory().unwrap();
+    let a = db.store(&exp("a", 1, "t"), &[1.0]).await.unwrap();
+    let b = db.store(&exp("b", 2, "t"), &[1.0]).await.unwrap();
+    let c = db.store(&exp("c", 3, "t"), &[1.0]).await.unwrap();
+    let d = db.store(&exp("d", 4, "other"), &[1.0]).await.unwrap();
+    let s1 = db.store(&exp("s1", 5, "summary"), &[1.0]).await.unwrap();
+    let s2 = db.store(&exp("s2", 6, "summary"), &[1.0]).await.unwrap();
+    let next: Vec<_> = db
+        .edges()
+        .unwrap()
+        .into_iter()
+        .filter(|e| e.kind == "NEXT")
+        .map(|e| (e.from, e.to))
+        .collect();
+    assert_eq!(
+        next,
+        vec![
+            (a.clone(), b.clone()),
+            (b.clone(), c.clone()),
+            (s1.clone(), s2.clone())
+        ]
+    );
+    db.link("NEXT", &a, &b).unwrap();
+    db.link("REFERS_TO", &b, "garden").unwrap();
+    db.link("REFERS_TO", &d, "garden").unwrap();
+    db.link_summary(&s1, &b).await.unwrap();
+    db.link_summary(&s2, &s1).await.unwrap();
+    db.link_called_to_mind(&c, &a).await.unwrap();
+    assert!(db.link("LIKES", &a, &b).is_err());
+    assert!(db.link("NEXT", &a, "missing").is_err());
+
+    let hows = |exps: Vec<Experience>| exps.into_iter().map(|e| e.how).collect::<Vec<_>>();
+    assert_eq!(
+        hows(context_subgraph(&db, &b).await.unwrap()),
+        ["b", "a", "c", "d"]
+    );
+    assert_eq!(
+        hows(summary_chain(&db, &b, 5).await.unwrap()),
+        ["b", "s1", "s2"]
+    );
+    assert_eq!(hows(summary_chain(&db, &b, 1).await.unwrap()), ["b", "s1"]);
+    let since = DateTime::<Utc>::from_timestamp(1_700_000_001, 0).unwrap();
+    assert_eq!(
+        hows(tagged_since(&db, "t", since).await.unwrap()),
+        ["b", "c"]
+    );
+    assert_eq!(
+        db.edges().unwrap().last(),
+        Some(&Edge {
+            kind: "CALLED_TO_MIND".into(),
+            from: c,
+            to: a,
+        })
+    );
+}
+
+#[tokio::test]
+async fn entities_merge_by_name_and_collect_mentions() {
+    let db = SqliteBackend::open_in_memory().unwrap();
+    let waved = db
+        .store(&exp("Alice waved.", 2, "instant"), &[1.0])
+        .await
+        .unwrap();
+    let tea = db
+        .store(&exp("Ally made tea.", 1, "instant"), &[1.0])
+        .await
+        .unwrap();
+    record_entities(
+        &db,
+        &serde_json::json!({
+            "entities": [{"name": "Alice", "kind": "person"}],
+            "mentions": [waved],
+        }),
+    )
+    .await
+    .unwrap();
+    let stored = record_entities(
+        &db,
+        &serde_json::json!({
+            "entities": [{"name": "ALICE", "kind": "person", "aliases": ["Ally"]}],
+            "mentions": [tea],
+        }),
+    )
+    .await
+    .unwrap();
+    assert_eq!(stored[0].aliases, ["Ally"]);
+    let place = db
+        .upsert_entity(&Entity::new(EntityKind::Place, "Alice"))
+        .await
+        .unwrap();
+    assert_ne!(place.id, stored[0].id);
+    assert_eq!(db.entities().unwrap().len(), 2);
+
+    let found = about(&db, "ally").await.unwrap().unwrap();
+    assert_eq!(found.entity, stored[0]);
+    let hows: Vec<_> = found.experiences.iter().map(|e| e.how.as_str()).collect();
+    assert_eq!(hows, ["Ally made tea.", "Alice waved."]);
+    assert!(about(&db, "Bob").await.unwrap().is_none());
+    assert!(db.link_mention(&tea, "missing").await.is_err());
+}
+
+#[tokio::test]
+async fn archives_and_forgets() {
+    let db = SqliteBackend::open_in_memory().unwrap();
+    let old = db
+        .store(&exp("Wind hummed.", 1, "instant"), &[1.0, 0.0])
+        .await
+        .unwrap();
+    let query = db
+        .store(&exp("What was that?", 2, "query"), &[0.0, 1.0])
+        .await
+        .unwrap();
+    let summary = db
+        .store(&exp("It was windy.", 3, "instant"), &[1.0, 0.1])
+        .await
+        .unwrap();
+    db.link_summary(&summary, &old).await.unwrap();
+    db.link_called_to_mind(&query, &old).await.unwrap();
+    assert_eq!(db.recall_counts().await.unwrap()[&old], 1);
+
+    db.archive(&old).await.unwrap();
+    db.archive(&old).await.unwrap();
+    assert!(db.archive("missing").await.is_err());
+    let hits = db
+        .search(&[1.0, 0.0], 3, &SearchFilter::default())
+        .await
+        .unwrap();
+    assert!(hits.iter().all(|h| h.id != old));
+    assert!(db.keyword_search("hummed", 3).unwrap().is_empty());
+    assert_eq!(
+        summary_chain(&db, &old, 1).await.unwrap(),
+        vec![
+            exp("Wind hummed.", 1, "instant"),
+            exp("It was windy.", 3, "instant")
+        ]
+    );
+
+    db.forget(&old).await.unwrap();
+    assert!(db.forget(&old).await.is_err());
+    assert_eq!(db.get(&old).await.unwrap(), None);
+    assert!(db.recall_counts().await.unwrap().is_empty());
+    assert!(db
+        .edges()
+        .unwrap()
+        .iter()
+        .all(|e| e.from != old && e.to != old));
+}
@@ -1,27 +1,32 @@
-#[cfg(feature = "neo4j")]
 use async_trait::async_trait;
-#[cfg(feature = "neo4j")]
-use psyche::memory::{context_subgraph, Experience, MemoryBackend};
+use chrono::{TimeZone, Utc};
+use psyche::memory::{
+    context_subgraph, summary_chain, tagged_since, Experience, GraphParam, GraphQuery,
+    MemoryBackend, SearchFilter, SearchHit,
+};
 
-#[cfg(feature = "neo4j")]
 struct SpyBackend {
-    last: std::sync::Mutex<Option<String>>,
+    last: std::sync::Mutex<Option<GraphQuery>>,
 }
 
-#[cfg(feature = "neo4j")]
 #[async_trait(?Send)]
 impl MemoryBackend for SpyBackend {
     async fn store(&self, _exp: &Experience, _vector: &[f32]) -> anyhow::Result<String> {
         Ok("1".into())
     }
-    async fn search(&self, _vector: &[f32], _top_k: usize) -> anyhow::Result<Vec<Experience>> {
+    async fn search(
+        &self,
+        _vector: &[f32],
+        _top_k: usize,
+        _filter: &SearchFilter,
+    ) -> anyhow::Result<Vec<SearchHit>> {
         Ok(vec![])
     }
     async fn get(&self, _id: &str) -> anyhow::Result<Option<Experience>> {
         Ok(None)
     }
-    async fn cypher_query(&self, query: &str) -> anyhow::Result<Vec<Experience>> {
-        *self.last.lock().unwrap() = Some(query.to_string());
+    async fn graph_query(&self, query: &GraphQuery) -> anyhow::Result<Vec<Experience>> {
+        *self.last.lock().unwrap() = Some(query.clone());
         Ok(vec![])
     }
 
@@ -30,13 +35,59 @@ impl MemoryBackend for SpyBackend {
     }
 }
 
-#[cfg(feature = "neo4j")]
+fn spy() -> SpyBackend {
+    SpyBackend {
+        last: std::sync::Mutex::new(None),
+    }
+}
+
 #[tokio::test]
 async fn builds_context_query() {
-    let backend = SpyBackend {
-        last: std::sync::Mutex::new(None),
-    };
+    let backend = spy();
     let _ = context_subgraph(&backend, "abc").await.unwrap();
     let q = backend.last.lock().unwrap().clone().unwrap();
-    assert!(q.contains("MATCH (e:Experience {id: \"abc\"})"));
+    assert!(q.cypher.contains("MATCH (e:Experience {id: $id})"));
+    assert_eq!(q.params["id"], GraphParam::Text("abc".into()));
+}
+
+#[tokio::test]
+async fn hostile_values_stay_out_of_query_text() {
+    let backend = spy();
+    let tag = "sensation/chat' OR 1=1 //";
+    let since = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+    let _ = tagged_since(&backend, tag, since).await.unwrap();
+    let q = backend.last.lock().unwrap().clone().unwrap();
+    assert!(!q.cypher.contains("OR 1=1"));
+    assert!(q
+        .cypher
+        .contains("$tag IN node.tags AND node.when > $since"));
+    assert_eq!(q.params["tag"], GraphParam::Text(tag.into()));
+    assert_eq!(
+        q.params["since"],
+        GraphParam::Text("2024-05-01T12:00:00+00:00".into())
+    );
+}
+
+#[tokio::test]
+async fn builds_summary_chain_query() {
+    let backend = spy();
+    let _ = summary_chain(&backend, "x\"}) DETACH DELETE (n", 3)
+        .await
+        .unwrap();
+    let q = backend.last.lock().unwrap().clone().unwrap();
+    assert!(q.cypher.contains("[:SUMMARIZES*0..3]"));
+    assert!(!q.cypher.contains("DELETE"));
+    assert_eq!(q.params.len(), 1);
+}
+
+#[test]
+fn custom_queries_bind_parameters() {
+    let q = GraphQuery::new("MATCH (node:Experience) WHERE node.id IN $ids RETURN node LIMIT $n")
+        .param("ids", vec!["a".to_string(), "b".to_string()])
+        .param("n", 2i64);
+    assert_eq!(
+        q.params["ids"],
+        GraphParam::List(vec!["a".into(), "b".into()])
+    );
+    assert_eq!(q.params["n"], GraphParam::Int(2));
 }
@@ -0,0 +1,144 @@
+use async_trait::async_trait;
+use psyche::llm::mock_chat::MockChat;
+use psyche::llm::pool::{LlmPool, RoutingPolicy};
+use psyche::llm::scheduler::PriorityScheduler;
+use psyche::llm::usage::{MeteredChat, UsageReport, UsageStats};
+use psyche::llm::{
+    CanChat, ChatEvent, ChatMessage, GenerationOptions, LlmCapability, LlmInstance, LlmProfile,
+    TokenUsage,
+};
+use std::sync::Arc;
+use tokio_stream::{Stream, StreamExt};
+
+fn profile() -> LlmProfile {
+    LlmProfile {
+        provider: "mock".into(),
+        model: "mock-model".into(),
+        capabilities: vec![LlmCapability::Chat],
+    }
+}
+
+/// Replies with two tokens and reports usage.
+struct CountingChat;
+
+#[async_trait(?Send)]
+impl CanChat for CountingChat {
+    async fn chat_stream(
+        &self,
+        _profile: &LlmProfile,
+        _system: &str,
+        _user: &str,
+    ) -> anyhow::Result<Box<dyn Stream<Item = String> + Unpin>> {
+        unreachable!()
+    }
+
+    async fn chat_events(
+        &self,
+        _profile: &LlmProfile,
+        _messages: &[ChatMessage],
+        _options: &GenerationOptions,
+    ) -> anyhow::Result<Box<dyn Stream<Item = anyhow::Result<ChatEvent>> + Unpin>> {
+        Ok(Box::new(tokio_stream::iter([
+            Ok(ChatEvent::Token("Hel".into())),
+            Ok(ChatEvent::Token("lo".into())),
+            Ok(ChatEvent::Usage(TokenUsage {
+                model: Some("llama3".into()),
+                host: None,
+                prompt_tokens: Some(10),
+                completion_tokens: Some(2),
+            })),
+        ])))
+    }
+}
+
+#[tokio::test]
+async fn metered_chat_aggregates_per_wit() {
+    let stats = UsageStats::new();
+    let quick = MeteredChat::new(Arc::new(CountingChat), "quick", stats.clone());
+    let will = MeteredChat::new(Arc::new(CountingChat), "will", stats.clone());
+    for chat in [&quick, &quick, &will] {
+        let tokens: Vec<String> = chat
+            .chat_stream(&profile(), "", "hi")
+            .await
+            .unwrap()
+            .collect()
+            .await;
+        assert_eq!(tokens.concat(), "Hello");
+    }
+
+    let quick = stats.wit("quick").unwrap();
+    assert_eq!(quick.calls, 2);
+    assert_eq!(quick.prompt_tokens, 20);
+    assert_eq!(quick.completion_tokens, 4);
+    assert_eq!(quick.first_token_calls, 2);
+    assert_eq!(stats.wit("will").unwrap().calls, 1);
+    let hosts = stats.by_host();
+    assert_eq!(hosts.len(), 1);
+    assert_eq!(hosts["mock"].calls, 3);
+}
+
+#[tokio::test]
+async fn saved_totals_merge_across_processes() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("quick.json");
+    let stats = UsageStats::new().saving_to(&path);
+    let quick = MeteredChat::new(Arc::new(CountingChat), "quick", stats.clone());
+    let will = quick.for_wit("will");
+    for chat in [&quick, &will] {
+        let _: Vec<String> = chat
+            .chat_stream(&profile(), "", "hi")
+            .await
+            .unwrap()
+            .collect()
+            .await;
+    }
+
+    let saved = UsageReport::load(&path).unwrap();
+    assert_eq!(saved, stats.report());
+    let mut merged = saved.clone();
+    merged.merge(&saved);
+    assert_eq!(merged.by_wit["quick"].calls, 2);
+    assert_eq!(merged.by_wit["will"].prompt_tokens, 20);
+    assert_eq!(merged.by_host["mock"].calls, 4);
+}
+
+#[tokio::test]
+async fn metered_chat_records_backends_without_usage() {
+    let stats = UsageStats::new();
+    let chat = MeteredChat::new(Arc::new(MockChat), "quick", stats.clone());
+    let _: Vec<String> = chat
+        .chat_stream(&profile(), "", "hi")
+        .await
+        .unwrap()
+        .collect()
+        .await;
+    let totals = stats.wit("quick").unwrap();
+    assert_eq!(totals.calls, 1);
+    assert_eq!(totals.prompt_tokens, 0);
+    assert!(totals.mean_time_to_first_token().is_some());
+}
+
+#[tokio::test]
+#[allow(clippy::arc_with_non_send_sync)]
+async fn pool_tags_usage_with_instance_name() {
+    let pool = LlmPool::new(
+        vec![LlmInstance {
+            name: "gpu-1".into(),
+            chat: Arc::new(CountingChat),
+            profile: Arc::new(profile()),
+            scheduler: PriorityScheduler::new(1),
+        }],
+        RoutingPolicy::Priority,
+    );
+    let stats = UsageStats::new();
+    let chat = MeteredChat::new(Arc::new(pool), "quick", stats.clone());
+    let _: Vec<String> = chat
+        .chat_stream(&profile(), "", "hi")
+        .await
+        .unwrap()
+        .collect()
+        .await;
+    let host = stats.host("gpu-1").unwrap();
+    assert_eq!(host.calls, 1);
+    assert_eq!(host.completion_tokens, 2);
+}
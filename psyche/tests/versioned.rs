@@ -0,0 +1,67 @@
+use psyche::memory::Experience;
+use psyche::models::versioned::{
+    migrate_lines, parse_line, to_line, verify_lines, RecordError, RecordType, Versioned,
+};
+use psyche::models::{MemoryEntry, Sensation};
+
+const LEGACY_RECALL: &str = r#"{"id":"9a0c1f4e-8f53-4c4b-9d4e-7b7a4a3f1e2d","kind":"recall","when":"2024-05-01T12:00:00Z","how":"I remember rain.","what":["a"]}"#;
+
+fn value(line: &str) -> serde_json::Value {
+    serde_json::from_str(line).unwrap()
+}
+
+#[test]
+fn records_round_trip_with_their_version() {
+    let entry: MemoryEntry = parse_line(LEGACY_RECALL).unwrap();
+    let line = to_line(&entry).unwrap();
+    assert_eq!(value(&line)["version"], MemoryEntry::current_version());
+    assert_eq!(value(&line)["when"], 1_714_564_800);
+    assert_eq!(parse_line::<MemoryEntry>(&line).unwrap(), entry);
+
+    let exp: Experience =
+        parse_line(r#"{"how":"Rain.","what":"rain","when":"2024-05-01T12:00:00Z"}"#).unwrap();
+    assert!(exp.tags.is_empty());
+    assert_eq!(exp.when.timestamp(), 1_714_564_800);
+}
+
+#[test]
+fn newer_and_broken_records_are_errors() {
+    let newer = format!(
+        r#"{{"id":"a","path":"/chat","text":"hi","version":{}}}"#,
+        Sensation::current_version() + 1
+    );
+    assert!(matches!(
+        parse_line::<Sensation>(&newer),
+        Err(RecordError::Newer { .. })
+    ));
+    assert!(matches!(
+        parse_line::<MemoryEntry>("not json"),
+        Err(RecordError::Malformed(_))
+    ));
+    assert!(matches!(
+        parse_line::<MemoryEntry>(r#"{"id":"a","kind":"recall","when":"yesterday"}"#),
+        Err(RecordError::Invalid { version: 0, .. })
+    ));
+}
+
+#[test]
+fn migrating_lines_upgrades_legacy_and_keeps_the_rest() {
+    let current = to_line(&parse_line::<MemoryEntry>(LEGACY_RECALL).unwrap()).unwrap();
+    let text = format!("{LEGACY_RECALL}\n\n{current}\n{{broken\n");
+    let report = verify_lines(RecordType::MemoryEntry, &text);
+    assert_eq!((report.lines, report.current), (3, 1));
+    assert_eq!(report.legacy[0].line, 1);
+    assert_eq!(report.legacy[0].version, Some(0));
+    assert_eq!(report.unparseable[0].line, 4);
+    assert!(!report.is_clean());
+
+    let (migrated, before) = migrate_lines(RecordType::MemoryEntry, &text);
+    assert_eq!(before, report);
+    let lines: Vec<&str> = migrated.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(value(lines[0]), value(&current));
+    assert_eq!(lines[1], current);
+    assert_eq!(lines[2], "{broken");
+    let after = verify_lines(RecordType::MemoryEntry, &migrated);
+    assert_eq!((after.current, after.unparseable.len()), (2, 1));
+}
@@ -1,4 +1,5 @@
 use indexmap::IndexMap;
+use psyche::llm::{ChatMessage, GenerationOptions};
 use serde::Deserialize;
 use std::path::Path;
 
@@ -14,6 +15,12 @@ pub struct DistillerConfig {
     pub input: Option<String>,
     #[serde(default)]
     pub output: Option<String>,
+    /// Sampling options forwarded to `distilld`.
+    #[serde(default)]
+    pub options: GenerationOptions,
+    /// Few-shot example turns forwarded to `distilld`.
+    #[serde(default)]
+    pub examples: Vec<ChatMessage>,
 }
 
 fn default_sensor_enabled() -> bool {
@@ -78,6 +85,31 @@ pub struct SpokenConfig {
     pub log_level: String,
 }
 
+fn default_entities_input() -> Vec<String> {
+    vec!["instant".into(), "situation".into()]
+}
+
+fn default_entities_interval() -> u64 {
+    10
+}
+
+/// Extraction of the people, places and objects named in memories.
+///
+/// ```toml
+/// [entities]
+/// input = ["instant", "situation"]
+/// interval_secs = 10
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub struct EntitiesConfig {
+    /// Memory kinds scanned for entities. Subkinds match as well.
+    #[serde(default = "default_entities_input")]
+    pub input: Vec<String>,
+    /// Seconds between scans for entries not yet mined.
+    #[serde(default = "default_entities_interval")]
+    pub interval_secs: u64,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct PsycheConfig {
     #[serde(default)]
@@ -88,6 +120,8 @@ pub struct PsycheConfig {
     pub pipe: IndexMap<String, PipeConfig>,
     #[serde(default)]
     pub spoken: Option<SpokenConfig>,
+    #[serde(default)]
+    pub entities: Option<EntitiesConfig>,
 }
 
 impl Default for PsycheConfig {
@@ -97,6 +131,7 @@ impl Default for PsycheConfig {
             sensor: IndexMap::new(),
             pipe: IndexMap::new(),
             spoken: None,
+            entities: None,
         }
     }
 }
@@ -1,16 +1,17 @@
 use crate::file_memory::FileMemory;
 use anyhow::Result;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use psyche::llm::{CanEmbed, LlmProfile};
-use psyche::memory::{Experience, MemoryBackend, QdrantNeo4j};
-use psyche::models::{MemoryEntry, Sensation};
+use psyche::memory::{record_entities, tagged_since, Experience, Linker, MemoryBackend};
+use psyche::models::{versioned, MemoryEntry, Sensation};
 use psyche::utils::{first_sentence, parse_json_or_string};
+use psyche::wit::{Wit, ENTITY_KIND};
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::Mutex;
-use tracing::{debug, trace};
+use tracing::{debug, trace, warn};
 use uuid::Uuid;
 
 use crate::memory_client::MemoryClient;
@@ -27,7 +28,7 @@ pub trait QueryMemory {
 pub struct DbMemory<'a> {
     dir: PathBuf,
     inner: FileMemory,
-    backend: Option<Arc<QdrantNeo4j>>,
+    backend: Option<Arc<dyn MemoryBackend + Sync>>,
     embed: &'a dyn CanEmbed,
     profile: &'a LlmProfile,
     db_offsets: Arc<Mutex<HashMap<String, String>>>,
@@ -51,7 +52,7 @@ impl<'a> Clone for DbMemory<'a> {
 impl<'a> DbMemory<'a> {
     pub fn new(
         dir: PathBuf,
-        backend: Option<Arc<QdrantNeo4j>>,
+        backend: Option<Arc<dyn MemoryBackend + Sync>>,
         embed: &'a dyn CanEmbed,
         profile: &'a LlmProfile,
         socket: PathBuf,
@@ -78,15 +79,10 @@ impl<'a> DbMemory<'a> {
             let mut offsets = self.db_offsets.lock().await;
             let since = offsets
                 .get(kind)
-                .cloned()
-                .unwrap_or_else(|| "1970-01-01T00:00:00Z".to_string());
-            let query = format!(
-                "MATCH (e:Experience) WHERE '{tag}' IN e.tags AND e.when > '{since}' \
-RETURN e.how AS how, e.what AS what, e.when AS when, e.tags AS tags ORDER BY e.when",
-                tag = kind,
-                since = since
-            );
-            match backend.cypher_query(&query).await {
+                .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+                .map(|t| t.with_timezone(&Utc))
+                .unwrap_or(DateTime::UNIX_EPOCH);
+            match tagged_since(backend.as_ref(), kind, since).await {
                 Ok(mut exps) => {
                     if let Some(last) = exps.last() {
                         offsets.insert(kind.to_string(), last.when.to_rfc3339());
@@ -118,7 +114,6 @@ RETURN e.how AS how, e.what AS what, e.when AS when, e.tags AS tags ORDER BY e.w
         if let Some(backend) = &self.backend {
             #[cfg(feature = "neo4j")]
             {
-                use chrono::{DateTime, Utc};
                 use neo4rs::query;
                 let mut offsets = self.db_offsets.lock().await;
                 let since = offsets
@@ -187,8 +182,11 @@ RETURN e.how AS how, e.what AS what, e.when AS when, e.tags AS tags ORDER BY e.w
         let mut out = Vec::new();
         for entry in entries {
             self.client
-                .memorize(&entry.kind, serde_json::to_value(entry)?)
+                .memorize(&entry.kind, versioned::stamp(entry)?)
                 .await?;
+            if let Err(e) = self.persist(entry).await {
+                warn!(target = "psyched", id = %entry.id, error = %e, "failed to persist entry");
+            }
             out.push(entry.id.to_string());
         }
         Ok(out)
@@ -196,15 +194,16 @@ RETURN e.how AS how, e.what AS what, e.when AS when, e.tags AS tags ORDER BY e.w
 
     pub async fn store_sensation(&self, sens: &Sensation) -> Result<String> {
         self.client
-            .memorize(
-                &format!("sensation{}", sens.path),
-                serde_json::to_value(sens)?,
-            )
+            .memorize(&format!("sensation{}", sens.path), versioned::stamp(sens)?)
             .await?;
         Ok(sens.id.clone())
     }
 
-    async fn persist(&self, entry: &MemoryEntry) -> Result<String> {
+    /// Store `entry` in the memory backend under its own id, so the ids
+    /// other entries name in `caused_by`, `mentions` or a list of sources
+    /// resolve, and link it accordingly. Returns the stored id, or an empty
+    /// string without a backend.
+    pub async fn persist(&self, entry: &MemoryEntry) -> Result<String> {
         if let Some(backend) = &self.backend {
             let vector = self.embed.embed(self.profile, &entry.how).await?;
             let exp = Experience {
@@ -213,14 +212,31 @@ RETURN e.how AS how, e.what AS what, e.when AS when, e.tags AS tags ORDER BY e.w
                 when: entry.when,
                 tags: vec![entry.kind.clone()],
             };
-            let id = backend.store(&exp, &vector).await?;
+            let id = entry.id.to_string();
+            backend.store_with_id(&id, &exp, &vector).await?;
             if let Value::Array(arr) = &entry.what {
                 for v in arr {
                     if let Some(pid) = v.as_str() {
-                        let _ = backend.link_summary(&id, pid).await;
+                        if let Err(e) = backend.link_summary(&id, pid).await {
+                            warn!(target = "psyched", %id, source = pid, error = %e, "failed to link summary");
+                        }
                     }
                 }
             }
+            let linker = Linker {
+                chat: None,
+                profile: self.profile,
+                backend: backend.as_ref(),
+                prompter: Default::default(),
+            };
+            if let Err(e) = linker.link(&id, &exp).await {
+                warn!(target = "psyched", %id, error = %e, "failed to link entry");
+            }
+            if entry.kind == ENTITY_KIND {
+                if let Err(e) = record_entities(backend.as_ref(), &entry.what).await {
+                    warn!(target = "psyched", %id, error = %e, "failed to record entities");
+                }
+            }
             debug!(target = "psyched", ?entry.kind, id = %entry.id, "stored memory entry from wit");
             trace!(id = %entry.id, "persisted entry");
             return Ok(id);
@@ -229,6 +245,70 @@ RETURN e.how AS how, e.what AS what, e.when AS when, e.tags AS tags ORDER BY e.w
         trace!(id = %entry.id, "persisted entry");
         Ok(String::new())
     }
+
+    /// Run the entity extractor `wit` over the `entries` of its input kind
+    /// and memorize what it finds, linking every entity to the entries
+    /// mentioning it in the backend. Entries the backend lacks are persisted
+    /// first so the mentions resolve. Returns the entity entries.
+    pub async fn extract_entities(
+        &self,
+        wit: &mut Wit,
+        entries: Vec<MemoryEntry>,
+    ) -> Result<Vec<MemoryEntry>> {
+        let input = &wit.config.input_kind;
+        let entries: Vec<_> = entries
+            .into_iter()
+            .filter(|e| e.kind == *input || e.kind.starts_with(&format!("{input}/")))
+            .collect();
+        if let Some(backend) = &self.backend {
+            for entry in &entries {
+                if backend.get(&entry.id.to_string()).await?.is_none() {
+                    self.persist(entry).await?;
+                }
+            }
+        }
+        let found = wit.distill(entries).await?;
+        self.append_entries(&found).await?;
+        Ok(found)
+    }
+
+    /// Run each entity extractor of `wits` over the entries of its input
+    /// kind that no `entity` entry mentions yet, one entry at a time. Ids in
+    /// `tried` are skipped and every id tried is added, so an entry the
+    /// model fails on is not retried on each pass. Returns how many entries
+    /// were mined.
+    pub async fn mine_entities(
+        &self,
+        wits: &mut [Wit],
+        tried: &mut HashSet<Uuid>,
+    ) -> Result<usize> {
+        let mined: HashSet<Uuid> = self
+            .inner
+            .entries_by_kind(ENTITY_KIND)
+            .await?
+            .iter()
+            .filter_map(|e| e.what.get("mentions").and_then(Value::as_array))
+            .flatten()
+            .filter_map(|id| id.as_str()?.parse().ok())
+            .collect();
+        let mut count = 0;
+        for wit in wits.iter_mut() {
+            let entries = self.inner.entries_by_kind(&wit.config.input_kind).await?;
+            for entry in entries.into_iter().filter(|e| !mined.contains(&e.id)) {
+                let id = entry.id;
+                if !tried.insert(id) {
+                    continue;
+                }
+                match self.extract_entities(wit, vec![entry]).await {
+                    Ok(_) => count += 1,
+                    Err(e) => {
+                        warn!(target = "psyched", %id, error = %e, "entity extraction failed")
+                    }
+                }
+            }
+        }
+        Ok(count)
+    }
 }
 
 #[async_trait(?Send)]
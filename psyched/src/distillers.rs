@@ -9,6 +9,7 @@ pub struct Distiller {
     cfg: DistillerConfig,
     child: Option<Child>,
     socket: PathBuf,
+    identity: Option<PathBuf>,
 }
 
 impl Distiller {
@@ -18,9 +19,17 @@ impl Distiller {
             cfg,
             child: None,
             socket,
+            identity: None,
         }
     }
 
+    /// Render prompts with the identity, self description and partials of
+    /// the soul whose `identity.toml` is at `path`.
+    pub fn with_identity(mut self, path: PathBuf) -> Self {
+        self.identity = Some(path);
+        self
+    }
+
     /// Spawn the `distilld` process.
     pub async fn spawn(&mut self) -> anyhow::Result<()> {
         let exe = std::env::var("CARGO_BIN_EXE_distilld").unwrap_or_else(|_| {
@@ -31,9 +40,42 @@ impl Distiller {
             p.to_string_lossy().into_owned()
         });
         let mut cmd = Command::new(exe);
+        cmd.arg("--name").arg(&self.cfg.name);
+        cmd.arg("--usage-file")
+            .arg(std::path::Path::new(crate::USAGE_DIR).join(format!("{}.json", self.cfg.name)));
+        if let Some(ref identity) = self.identity {
+            cmd.arg("--identity").arg(identity);
+        }
         if let Some(ref t) = self.cfg.prompt {
             cmd.arg("--prompt").arg(t);
         }
+        let opts = &self.cfg.options;
+        if let Some(t) = opts.temperature {
+            cmd.arg("--temperature").arg(t.to_string());
+        }
+        if let Some(p) = opts.top_p {
+            cmd.arg("--top-p").arg(p.to_string());
+        }
+        if let Some(n) = opts.max_tokens {
+            cmd.arg("--max-tokens").arg(n.to_string());
+        }
+        for stop in &opts.stop {
+            cmd.arg("--stop").arg(stop);
+        }
+        if let Some(seed) = opts.seed {
+            cmd.arg("--seed").arg(seed.to_string());
+        }
+        if let Some(format) = &opts.format {
+            let format = format
+                .as_str()
+                .map(str::to_string)
+                .unwrap_or_else(|| format.to_string());
+            cmd.arg("--format").arg(format);
+        }
+        if !self.cfg.examples.is_empty() {
+            cmd.arg("--examples")
+                .arg(serde_json::to_string(&self.cfg.examples)?);
+        }
         cmd.arg("--daemon");
         let child = cmd.spawn()?;
         self.child = Some(child);
@@ -5,10 +5,12 @@ use tokio::sync::Mutex;
 use crate::db_memory::QueryMemory;
 use async_trait::async_trait;
 use chrono::Utc;
+use psyche::models::versioned::{parse_line, to_line, Versioned};
 use psyche::models::{MemoryEntry, Sensation};
 use psyche::utils::{first_sentence, parse_json_or_string};
 use serde_json::Value;
-use tracing::{debug, trace};
+use std::path::Path;
+use tracing::{debug, trace, warn};
 use uuid::Uuid;
 
 /// Simple JSONL-backed memory store for Wit scheduling.
@@ -47,6 +49,7 @@ impl FileMemory {
         let lines: Vec<_> = content.lines().collect();
         let mut offsets = self.offsets.lock().await;
         let start = offsets.entry(kind.to_string()).or_insert(0);
+        let first = *start;
         let slice = if *start < lines.len() {
             &lines[*start..]
         } else {
@@ -55,9 +58,11 @@ impl FileMemory {
         *start = lines.len();
         slice
             .iter()
-            .filter_map(|l| {
+            .enumerate()
+            .filter_map(|(n, l)| {
+                let line = first + n + 1;
                 if kind.starts_with("sensation") {
-                    serde_json::from_str::<Sensation>(l).ok().and_then(|s| {
+                    Self::read_record::<Sensation>(&path, line, l).and_then(|s| {
                         let entry_kind = format!("sensation{}", s.path);
                         if entry_kind.starts_with(kind) {
                             Some(s.text)
@@ -66,7 +71,7 @@ impl FileMemory {
                         }
                     })
                 } else {
-                    serde_json::from_str::<MemoryEntry>(l).ok().map(|e| {
+                    Self::read_record::<MemoryEntry>(&path, line, l).map(|e| {
                         if !e.how.is_empty() {
                             e.how
                         } else {
@@ -86,9 +91,9 @@ impl FileMemory {
             .join(format!("{}.jsonl", kind.split('/').next().unwrap_or(kind)));
         let content = tokio::fs::read_to_string(&path).await.unwrap_or_default();
         let mut out = Vec::new();
-        for line in content.lines() {
+        for (n, line) in content.lines().enumerate() {
             if kind.starts_with("sensation") {
-                if let Ok(s) = serde_json::from_str::<Sensation>(line) {
+                if let Some(s) = Self::read_record::<Sensation>(&path, n + 1, line) {
                     let entry_kind = format!("sensation{}", s.path);
                     if entry_kind.starts_with(kind) {
                         out.push(MemoryEntry {
@@ -100,7 +105,7 @@ impl FileMemory {
                         });
                     }
                 }
-            } else if let Ok(mut e) = serde_json::from_str::<MemoryEntry>(line) {
+            } else if let Some(mut e) = Self::read_record::<MemoryEntry>(&path, n + 1, line) {
                 e.kind = kind.to_string();
                 out.push(e);
             }
@@ -128,11 +133,26 @@ impl FileMemory {
             .create(true)
             .open(&path)
             .await?;
-        let line = serde_json::to_string(value)?;
+        let line = to_line(value)?;
         use tokio::io::AsyncWriteExt;
         file.write_all(line.as_bytes()).await?;
         file.write_all(b"\n").await?;
         trace!(kind, "appended entry");
         Ok(())
     }
+
+    /// Record on line `line` of `path`, upgraded from older versions. A line
+    /// that cannot be read is logged rather than silently dropped.
+    fn read_record<T: Versioned>(path: &Path, line: usize, text: &str) -> Option<T> {
+        if text.trim().is_empty() {
+            return None;
+        }
+        match parse_line(text) {
+            Ok(record) => Some(record),
+            Err(e) => {
+                warn!(path = %path.display(), line, error = %e, "skipping unreadable {}", T::NAME);
+                None
+            }
+        }
+    }
 }
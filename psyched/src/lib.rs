@@ -18,11 +18,12 @@ use uuid::Uuid;
 
 pub mod config;
 pub mod daemon;
-mod db_memory;
+pub mod db_memory;
 pub mod distillers;
 mod file_memory;
 pub mod llm_config;
 mod memory_client;
+mod registry_chat;
 pub mod router;
 pub mod sensor;
 mod socket_pipe;
@@ -109,6 +110,28 @@ async fn load_identity(path: &Path) -> Result<Identity> {
     }
 }
 
+/// Mine entities from new memories with `wits` every `every`. Never
+/// returns; without extractors it only waits.
+async fn mine_entities(
+    memory: &db_memory::DbMemory<'_>,
+    mut wits: Vec<psyche::wit::Wit>,
+    every: std::time::Duration,
+) {
+    if wits.is_empty() {
+        return std::future::pending().await;
+    }
+    let mut tried = std::collections::HashSet::new();
+    let mut ticks = tokio::time::interval(every);
+    loop {
+        ticks.tick().await;
+        match memory.mine_entities(&mut wits, &mut tried).await {
+            Ok(0) => {}
+            Ok(count) => debug!(count, "mined entities"),
+            Err(e) => tracing::warn!(error = %e, "entity mining failed"),
+        }
+    }
+}
+
 async fn notify_router(router: &router::Router, entry: &MemoryEntry) {
     if let Some(sock) = router.socket_for(&entry.kind) {
         if let Ok(mut stream) = UnixStream::connect(sock).await {
@@ -134,6 +157,102 @@ async fn fire_and_forget_recall(socket: &Path, query: &str) -> std::io::Result<(
     Ok(())
 }
 
+/// Memory backend chosen by the environment: Qdrant and Neo4j when
+/// `QDRANT_URL` and `NEO4J_URL` are set, SQLite when `MEMORY_DB` is, and the
+/// embedded index under `memory_dir/index` otherwise. `None` when the chosen
+/// backend cannot be opened.
+pub fn open_backend(
+    memory_dir: &Path,
+) -> Option<std::sync::Arc<dyn psyche::memory::MemoryBackend + Sync>> {
+    if let (Ok(qurl), Ok(nurl)) = (std::env::var("QDRANT_URL"), std::env::var("NEO4J_URL")) {
+        tracing::debug!(qdrant = %qurl, neo4j = %nurl, "connecting to backends");
+        match qdrant_client::Qdrant::from_url(&qurl).build() {
+            Ok(qdrant) => {
+                let user = std::env::var("NEO4J_USER").unwrap_or_else(|_| "neo4j".into());
+                let pass = std::env::var("NEO4J_PASS").unwrap_or_else(|_| "password".into());
+                match neo4rs::Graph::new(&nurl, user, pass) {
+                    Ok(graph) => Some(std::sync::Arc::new(psyche::memory::QdrantNeo4j {
+                        qdrant,
+                        graph,
+                    })),
+                    Err(e) => {
+                        tracing::warn!(error = %e, "neo4j connection failed");
+                        None
+                    }
+                }
+            }
+            Err(e) => {
+                tracing::warn!(error = %e, "qdrant connection failed");
+                None
+            }
+        }
+    } else if let Ok(db) = std::env::var("MEMORY_DB") {
+        let db = memory_dir.join(db);
+        tracing::debug!(db = %db.display(), "using sqlite memory");
+        match psyche::memory::SqliteBackend::open(&db) {
+            Ok(sqlite) => Some(std::sync::Arc::new(sqlite)),
+            Err(e) => {
+                tracing::warn!(error = %e, "sqlite memory failed");
+                None
+            }
+        }
+    } else {
+        let index_dir = memory_dir.join("index");
+        tracing::debug!(dir = %index_dir.display(), "using embedded memory index");
+        match psyche::memory::HnswBackend::open(&index_dir) {
+            Ok(hnsw) => Some(std::sync::Arc::new(hnsw)),
+            Err(e) => {
+                tracing::warn!(error = %e, "embedded memory index failed");
+                None
+            }
+        }
+    }
+}
+
+/// Write the soul at `soul` and everything its memory backend holds to a
+/// soul archive at `path`.
+pub async fn export_soul(soul: &Path, path: &Path) -> Result<psyche::soul::Contents> {
+    let backend = open_backend(&soul.join("memory"))
+        .ok_or_else(|| anyhow::anyhow!("memory backend unavailable"))?;
+    let archive = psyche::soul::SoulArchive::collect(soul, Some(&*backend)).await?;
+    archive.write(std::io::BufWriter::new(std::fs::File::create(path)?))?;
+    info!(path = %path.display(), contents = ?archive.contents(), "exported soul");
+    Ok(archive.contents())
+}
+
+/// Recreate a soul at `soul` from the archive at `path`, restoring its
+/// memory into the backend chosen by the environment, which must be empty.
+pub async fn import_soul(path: &Path, soul: &Path) -> Result<psyche::soul::Contents> {
+    let file = std::fs::File::open(path)?;
+    let archive = psyche::soul::SoulArchive::read(std::io::BufReader::new(file))?;
+    let memory_dir = soul.join("memory");
+    std::fs::create_dir_all(&memory_dir)?;
+    let backend =
+        open_backend(&memory_dir).ok_or_else(|| anyhow::anyhow!("memory backend unavailable"))?;
+    archive.restore(soul, Some(&*backend)).await?;
+    info!(path = %path.display(), contents = ?archive.contents(), "imported soul");
+    Ok(archive.contents())
+}
+
+/// Directory where psyched and every `distilld` save their LLM usage.
+pub const USAGE_DIR: &str = "/run/psyche/usage";
+
+/// LLM usage of every process that saved its totals to `dir`, merged.
+pub fn usage_report(dir: &Path) -> Result<psyche::llm::usage::UsageReport> {
+    let mut report = psyche::llm::usage::UsageReport::default();
+    for file in std::fs::read_dir(dir)? {
+        let path = file?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+        match psyche::llm::usage::UsageReport::load(&path) {
+            Ok(r) => report.merge(&r),
+            Err(e) => tracing::warn!(path = %path.display(), error = %e, "unreadable usage"),
+        }
+    }
+    Ok(report)
+}
+
 /// Runs the psyched daemon until `shutdown` is triggered.
 pub async fn run(
     socket: PathBuf,
@@ -205,7 +324,7 @@ pub async fn run(
     let mut distillers: Vec<distillers::Distiller> = wit_cfgs
         .iter()
         .cloned()
-        .map(distillers::Distiller::new)
+        .map(|cfg| distillers::Distiller::new(cfg).with_identity(psyche_cfg_path.clone()))
         .collect();
     for d in &mut distillers {
         let _ = d.spawn().await;
@@ -216,33 +335,7 @@ pub async fn run(
     let _identity = load_identity(&cfg_path).await?;
     debug!(identity = %cfg_path.display(), "loaded identity configuration");
 
-    let backend =
-        if let (Ok(qurl), Ok(nurl)) = (std::env::var("QDRANT_URL"), std::env::var("NEO4J_URL")) {
-            tracing::debug!(qdrant = %qurl, neo4j = %nurl, "connecting to backends");
-            match qdrant_client::prelude::QdrantClient::from_url(&qurl).build() {
-                Ok(qdrant) => {
-                    let user = std::env::var("NEO4J_USER").unwrap_or_else(|_| "neo4j".into());
-                    let pass = std::env::var("NEO4J_PASS").unwrap_or_else(|_| "password".into());
-                    match neo4rs::Graph::new(&nurl, user, pass) {
-                        Ok(graph) => Some(std::sync::Arc::new(psyche::memory::QdrantNeo4j {
-                            qdrant,
-                            graph,
-                        })),
-                        Err(e) => {
-                            tracing::warn!(error = %e, "neo4j connection failed");
-                            None
-                        }
-                    }
-                }
-                Err(e) => {
-                    tracing::warn!(error = %e, "qdrant connection failed");
-                    None
-                }
-            }
-        } else {
-            None
-        };
-
+    let backend = open_backend(&memory_dir);
     trace!("using memory backend");
 
     let memory_store = db_memory::DbMemory::new(
@@ -253,6 +346,22 @@ pub async fn run(
         memory_sock.clone(),
     );
 
+    let entity_wits: Vec<_> = psyche_cfg
+        .entities
+        .iter()
+        .flat_map(|cfg| &cfg.input)
+        .map(|kind| psyche::wit::Wit {
+            config: psyche::wit::entity_extractor(kind),
+            llm: Box::new(registry_chat::RegistryChat(registry.clone())),
+            profile: (*profile).clone(),
+            prompter: Default::default(),
+        })
+        .collect();
+    let entity_interval = psyche_cfg
+        .entities
+        .as_ref()
+        .map_or(10, |cfg| cfg.interval_secs.max(1));
+
     let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<Sensation>();
 
     let mut watchers = Vec::new();
@@ -297,9 +406,19 @@ pub async fn run(
     tokio::pin!(shutdown);
     let mut pending: VecDeque<Sensation> = VecDeque::new();
 
+    // entities are mined beside intake, so a slow model delays neither the
+    // sensations nor shutdown
+    let mining = mine_entities(
+        &memory_store,
+        entity_wits,
+        std::time::Duration::from_secs(entity_interval),
+    );
+    tokio::pin!(mining);
+
     loop {
         tokio::select! {
             _ = &mut shutdown => break,
+            _ = &mut mining => {}
             Some(sensation) = rx.recv() => {
                 debug!(path = %sensation.path, "received sensation");
                 pending.push_back(sensation);
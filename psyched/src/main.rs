@@ -1,10 +1,13 @@
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use daemon_common::{maybe_daemonize, LogLevel};
 use std::path::PathBuf;
 use tokio::fs;
 use toml;
 use tracing::{debug, trace};
 
+/// How often accumulated LLM usage is summarized in the log.
+const USAGE_SUMMARY_INTERVAL: std::time::Duration = std::time::Duration::from_secs(300);
+
 /// `psyched` — the orchestrator for psycheOS
 #[derive(Parser, Debug)]
 #[command(
@@ -37,9 +40,15 @@ pub struct Cli {
     #[arg(long, default_value = "info")]
     pub log_level: LogLevel,
 
-    /// Qdrant service URL
-    #[arg(long, default_value = "http://localhost:6334")]
-    pub qdrant_url: String,
+    /// Qdrant service URL. Without it memory is kept in an embedded index
+    /// under soul/memory/.
+    #[arg(long)]
+    pub qdrant_url: Option<String>,
+
+    /// SQLite database holding memory when Qdrant is not used. If relative,
+    /// resolved against soul/memory/.
+    #[arg(long)]
+    pub memory_db: Option<PathBuf>,
 
     /// Neo4j service URL
     #[arg(long, default_value = "bolt://localhost:7687")]
@@ -56,6 +65,31 @@ pub struct Cli {
     /// Run as a background daemon
     #[arg(short = 'd', long)]
     pub daemon: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// One-off commands run instead of the daemon.
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Write the soul and its whole memory to a soul archive
+    Export {
+        /// Archive file to write
+        path: PathBuf,
+    },
+    /// Recreate the soul and its memory from a soul archive. The soul must
+    /// not exist yet and the memory backend must be empty.
+    Import {
+        /// Archive file to read
+        path: PathBuf,
+    },
+    /// Print the LLM usage totals of psyched and every wit
+    Usage {
+        /// Directory the running processes save their usage to
+        #[arg(long, default_value = psyched::USAGE_DIR)]
+        dir: PathBuf,
+    },
 }
 
 #[tokio::main]
@@ -65,6 +99,35 @@ async fn main() -> anyhow::Result<()> {
         .with_max_level(tracing_subscriber::filter::LevelFilter::from(cli.log_level))
         .init();
 
+    if let Some(url) = &cli.qdrant_url {
+        std::env::set_var("QDRANT_URL", url);
+    }
+    if let Some(db) = &cli.memory_db {
+        std::env::set_var("MEMORY_DB", db);
+    }
+    std::env::set_var("NEO4J_URL", &cli.neo4j_url);
+    std::env::set_var("NEO4J_USER", &cli.neo4j_user);
+    std::env::set_var("NEO4J_PASS", &cli.neo4j_pass);
+
+    match &cli.command {
+        Some(Command::Export { path }) => {
+            let contents = psyched::export_soul(&cli.soul, path).await?;
+            println!("{}", serde_json::to_string(&contents)?);
+            return Ok(());
+        }
+        Some(Command::Import { path }) => {
+            let contents = psyched::import_soul(path, &cli.soul).await?;
+            println!("{}", serde_json::to_string(&contents)?);
+            return Ok(());
+        }
+        Some(Command::Usage { dir }) => {
+            let report = psyched::usage_report(dir)?;
+            println!("{}", serde_json::to_string_pretty(&report)?);
+            return Ok(());
+        }
+        None => {}
+    }
+
     maybe_daemonize(cli.daemon)?;
 
     // Canonicalize soul path
@@ -96,31 +159,44 @@ async fn main() -> anyhow::Result<()> {
         }
     }
 
+    // Make sure every configured model is installed before serving
+    if let Err(e) = psyched::llm_config::prepare_models(&llm_cfg).await {
+        tracing::warn!(error = %e, "could not prepare models");
+    }
+
     // Construct LLM registry from configuration
-    let llms = psyched::llm_config::load_llms(&llm_cfg).await?;
-    let first = llms
-        .first()
-        .cloned()
+    let (pool, pool_cfg) = psyched::llm_config::load_pool(&llm_cfg).await?;
+    let embed = psyched::llm_config::load_embedder(&llm_cfg).await?;
+    let profile = pool
+        .instances()
+        .next()
+        .map(|i| i.profile.clone())
         .ok_or_else(|| anyhow::anyhow!("no llm"))?;
+    let usage_dir = PathBuf::from(psyched::USAGE_DIR);
+    if let Err(e) = fs::create_dir_all(&usage_dir).await {
+        tracing::warn!(error = %e, dir = %usage_dir.display(), "cannot save usage");
+    }
+    let usage = psyche::llm::usage::UsageStats::new().saving_to(usage_dir.join("psyched.json"));
     let registry = std::sync::Arc::new(psyche::llm::LlmRegistry {
-        chat: Box::new(psyche::llm::limited::LimitedChat::new(
-            first.chat.clone(),
-            first.semaphore.clone(),
+        // wits run in their own distilld processes and meter themselves
+        chat: Box::new(psyche::llm::usage::MeteredChat::new(
+            std::sync::Arc::new(pool.clone()),
+            "psyched",
+            usage.clone(),
         )),
-        embed: Box::new(psyche::llm::mock_embed::MockEmbed::default()),
+        embed,
     });
-    let profile = first.profile.clone();
-    let llms: Vec<_> = llms.into_iter().map(std::sync::Arc::new).collect();
 
     debug!("\u{1F4C1}  Loading identity from {}", identity.display());
 
-    std::env::set_var("QDRANT_URL", &cli.qdrant_url);
-    std::env::set_var("NEO4J_URL", &cli.neo4j_url);
-    std::env::set_var("NEO4J_USER", &cli.neo4j_user);
-    std::env::set_var("NEO4J_PASS", &cli.neo4j_pass);
-
     // Kick off orchestrator
     let local = tokio::task::LocalSet::new();
+    let health_interval = std::time::Duration::from_secs(pool_cfg.health_interval_secs);
+    let cancel = pool.cancel_handle();
+    let queues = pool.clone();
+    local.spawn_local(async move { queues.run_queue_summaries(USAGE_SUMMARY_INTERVAL).await });
+    local.spawn_local(async move { pool.run_health_checks(health_interval).await });
+    local.spawn_local(async move { usage.run_summaries(USAGE_SUMMARY_INTERVAL).await });
     local
         .run_until(psyched::run(
             cli.socket,
@@ -129,7 +205,11 @@ async fn main() -> anyhow::Result<()> {
             registry,
             profile,
             cli.memory_sock,
-            shutdown_signal(),
+            async move {
+                shutdown_signal().await;
+                // abort generations still streaming so shutdown is not held up
+                cancel.cancel();
+            },
         ))
         .await
 }
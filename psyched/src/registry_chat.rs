@@ -0,0 +1,74 @@
+use async_trait::async_trait;
+use psyche::llm::{
+    CanChat, ChatEvent, ChatMessage, GenerationOptions, LlmProfile, LlmRegistry, ToolDefinition,
+    ToolResponse,
+};
+use std::sync::Arc;
+use tokio_stream::Stream;
+
+/// The chat model of a shared [`LlmRegistry`], for in-process wits that
+/// own their model.
+pub struct RegistryChat(pub Arc<LlmRegistry>);
+
+#[async_trait(?Send)]
+impl CanChat for RegistryChat {
+    async fn chat_stream(
+        &self,
+        profile: &LlmProfile,
+        system: &str,
+        user: &str,
+    ) -> anyhow::Result<Box<dyn Stream<Item = String> + Unpin>> {
+        self.0.chat.chat_stream(profile, system, user).await
+    }
+
+    async fn chat_stream_with_options(
+        &self,
+        profile: &LlmProfile,
+        system: &str,
+        user: &str,
+        options: &GenerationOptions,
+    ) -> anyhow::Result<Box<dyn Stream<Item = String> + Unpin>> {
+        self.0
+            .chat
+            .chat_stream_with_options(profile, system, user, options)
+            .await
+    }
+
+    async fn chat_messages_stream(
+        &self,
+        profile: &LlmProfile,
+        messages: &[ChatMessage],
+        options: &GenerationOptions,
+    ) -> anyhow::Result<Box<dyn Stream<Item = String> + Unpin>> {
+        self.0
+            .chat
+            .chat_messages_stream(profile, messages, options)
+            .await
+    }
+
+    async fn chat_events(
+        &self,
+        profile: &LlmProfile,
+        messages: &[ChatMessage],
+        options: &GenerationOptions,
+    ) -> anyhow::Result<Box<dyn Stream<Item = anyhow::Result<ChatEvent>> + Unpin>> {
+        self.0.chat.chat_events(profile, messages, options).await
+    }
+
+    async fn health_check(&self) -> anyhow::Result<()> {
+        self.0.chat.health_check().await
+    }
+
+    async fn chat_with_tools(
+        &self,
+        profile: &LlmProfile,
+        messages: &[ChatMessage],
+        tools: &[ToolDefinition],
+        options: &GenerationOptions,
+    ) -> anyhow::Result<ToolResponse> {
+        self.0
+            .chat
+            .chat_with_tools(profile, messages, tools, options)
+            .await
+    }
+}
@@ -25,12 +25,13 @@ async fn sensation_results_in_instant() {
     let (tx, rx) = tokio::sync::oneshot::channel();
     let local = LocalSet::new();
     let registry = std::sync::Arc::new(psyche::llm::LlmRegistry {
-        chat: Box::new(psyche::llm::mock_chat::MockChat::default()),
+        // no wit runs, so any request would be unexpected and fail
+        chat: Box::new(psyche::llm::scripted::ScriptedChat::new(Default::default())),
         embed: Box::new(psyche::llm::mock_embed::MockEmbed::default()),
     });
     let profile = std::sync::Arc::new(psyche::llm::LlmProfile {
-        provider: "mock".into(),
-        model: "mock".into(),
+        provider: "scripted".into(),
+        model: "script".into(),
         capabilities: vec![psyche::llm::LlmCapability::Chat],
     });
     let server = local.spawn_local(psyched::run(
@@ -0,0 +1,274 @@
+use chrono::Utc;
+use psyche::llm::mock_embed::MockEmbed;
+use psyche::llm::scripted::ScriptedChat;
+use psyche::llm::{LlmCapability, LlmProfile};
+use psyche::memory::{about, Edge, Experience, HnswBackend, MemoryBackend};
+use psyche::models::MemoryEntry;
+use psyche::wit::{entity_extractor, Wit, ENTITY_KIND};
+use psyched::db_memory::DbMemory;
+use serde_json::json;
+use std::collections::HashSet;
+use std::sync::Arc;
+use tempfile::tempdir;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::UnixListener;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+fn profile() -> LlmProfile {
+    LlmProfile {
+        provider: "mock".into(),
+        model: "mock".into(),
+        capabilities: vec![LlmCapability::Embedding],
+    }
+}
+
+/// Stand-in for `rememberd` on `sock`, acknowledging every request and
+/// passing its params on.
+fn fake_rememberd(sock: &std::path::Path) -> mpsc::UnboundedReceiver<serde_json::Value> {
+    let listener = UnixListener::bind(sock).unwrap();
+    let (tx, rx) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        loop {
+            let (mut s, _) = listener.accept().await.unwrap();
+            let mut buf = String::new();
+            s.read_to_string(&mut buf).await.unwrap();
+            let req: serde_json::Value = serde_json::from_str(&buf).unwrap();
+            let reply = json!({"jsonrpc": "2.0", "result": null, "id": req["id"]});
+            s.write_all(reply.to_string().as_bytes()).await.unwrap();
+            let _ = tx.send(req["params"].clone());
+        }
+    });
+    rx
+}
+
+fn entry(kind: &str, how: &str, what: serde_json::Value) -> MemoryEntry {
+    MemoryEntry {
+        id: Uuid::new_v4(),
+        kind: kind.into(),
+        when: Utc::now(),
+        what,
+        how: how.into(),
+    }
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn persist_links_causes_by_entry_id() {
+    let dir = tempdir().unwrap();
+    let backend = Arc::new(HnswBackend::open(dir.path().join("index")).unwrap());
+    let (embed, profile) = (MockEmbed, profile());
+    let memory = DbMemory::new(
+        dir.path().to_path_buf(),
+        Some(backend.clone()),
+        &embed,
+        &profile,
+        dir.path().join("memory.sock"),
+    );
+
+    let bang = entry("instant", "A bang.", json!("bang"));
+    let flinch = entry(
+        "instant",
+        "I flinched.",
+        json!({ "caused_by": [bang.id, Uuid::new_v4()] }),
+    );
+    assert_eq!(memory.persist(&bang).await.unwrap(), bang.id.to_string());
+    assert_eq!(
+        memory.persist(&flinch).await.unwrap(),
+        flinch.id.to_string()
+    );
+
+    assert!(backend.get(&bang.id.to_string()).await.unwrap().is_some());
+    let causes: Vec<Edge> = backend
+        .edges()
+        .into_iter()
+        .filter(|e| e.kind == "CAUSES")
+        .collect();
+    assert_eq!(
+        causes,
+        vec![Edge {
+            kind: "CAUSES".into(),
+            from: bang.id.to_string(),
+            to: flinch.id.to_string(),
+        }]
+    );
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn persist_links_mentions_by_entry_id() {
+    let dir = tempdir().unwrap();
+    let backend = Arc::new(HnswBackend::open(dir.path().join("index")).unwrap());
+    let (embed, profile) = (MockEmbed, profile());
+    let memory = DbMemory::new(
+        dir.path().to_path_buf(),
+        Some(backend.clone()),
+        &embed,
+        &profile,
+        dir.path().join("memory.sock"),
+    );
+
+    let waved = entry("situation", "Alice waved.", json!("Alice waved."));
+    let entities = entry(
+        ENTITY_KIND,
+        "",
+        json!({
+            "entities": [{ "name": "Alice", "kind": "person" }],
+            "mentions": [waved.id],
+        }),
+    );
+    memory.persist(&waved).await.unwrap();
+    memory.persist(&entities).await.unwrap();
+
+    let alice = about(backend.as_ref(), "Alice").await.unwrap().unwrap();
+    assert_eq!(alice.experiences.len(), 1);
+    assert_eq!(alice.experiences[0].how, "Alice waved.");
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn extracts_entities_from_sensations() {
+    let dir = tempdir().unwrap();
+    let mut rememberd = fake_rememberd(&dir.path().join("memory.sock"));
+    let backend = Arc::new(HnswBackend::open(dir.path().join("index")).unwrap());
+    let (embed, profile) = (MockEmbed, profile());
+    let memory = DbMemory::new(
+        dir.path().to_path_buf(),
+        Some(backend.clone()),
+        &embed,
+        &profile,
+        dir.path().join("memory.sock"),
+    );
+    let chat = ScriptedChat::from_toml(
+        r#"
+[[rule]]
+contains = "harbor"
+response = '{"entities": [{"name": "Alice", "kind": "person"}, {"name": "the harbor", "kind": "place"}]}'
+"#,
+    )
+    .unwrap();
+    let mut wit = Wit {
+        config: entity_extractor("sensation/chat"),
+        llm: Box::new(chat),
+        profile: profile.clone(),
+        prompter: Default::default(),
+    };
+
+    let said = entry(
+        "sensation/chat",
+        "Alice is at the harbor.",
+        json!("Alice is at the harbor."),
+    );
+    let heard = entry("sensation/ear", "A gull cried.", json!("A gull cried."));
+    let found = memory
+        .extract_entities(&mut wit, vec![said.clone(), heard.clone()])
+        .await
+        .unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].kind, ENTITY_KIND);
+    // memorized, so rememberd answers about queries with it
+    let memorized = rememberd.recv().await.unwrap();
+    assert_eq!(memorized["kind"], ENTITY_KIND);
+    assert_eq!(memorized["data"]["id"], json!(found[0].id));
+
+    let harbor = about(backend.as_ref(), "the harbor")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(harbor.experiences.len(), 1);
+    assert_eq!(harbor.experiences[0].how, "Alice is at the harbor.");
+    // sensations of other kinds are left alone
+    assert!(backend.get(&heard.id.to_string()).await.unwrap().is_none());
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn append_survives_backend_failures() {
+    let dir = tempdir().unwrap();
+    let sock = dir.path().join("memory.sock");
+    let mut rememberd = fake_rememberd(&sock);
+    let backend = Arc::new(HnswBackend::open(dir.path().join("index")).unwrap());
+    // two dimensions, where the mock embedder yields three
+    let old = Experience {
+        how: "Old.".into(),
+        what: json!("old"),
+        when: Utc::now(),
+        tags: vec!["instant".into()],
+    };
+    backend.store(&old, &[1.0, 0.0]).await.unwrap();
+    let (embed, profile) = (MockEmbed, profile());
+    let memory = DbMemory::new(
+        dir.path().to_path_buf(),
+        Some(backend.clone()),
+        &embed,
+        &profile,
+        sock,
+    );
+
+    let entries = [
+        entry("instant", "A bang.", json!("bang")),
+        entry("instant", "I flinched.", json!("flinch")),
+    ];
+    let ids = memory.append_entries(&entries).await.unwrap();
+    assert_eq!(ids, [entries[0].id.to_string(), entries[1].id.to_string()]);
+    for _ in 0..2 {
+        assert_eq!(rememberd.recv().await.unwrap()["kind"], "instant");
+    }
+    assert_eq!(backend.len(), 1);
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn mines_entries_not_yet_mentioned() {
+    let dir = tempdir().unwrap();
+    let mut rememberd = fake_rememberd(&dir.path().join("memory.sock"));
+    let mined = entry("instant", "Alice came home.", json!("Alice came home."));
+    let fresh = entry(
+        "instant",
+        "Bob is at the harbor.",
+        json!("Bob is at the harbor."),
+    );
+    let seen = entry(
+        ENTITY_KIND,
+        "",
+        json!({ "entities": [], "mentions": [mined.id] }),
+    );
+    let line = |e: &MemoryEntry| serde_json::to_string(e).unwrap() + "\n";
+    std::fs::write(
+        dir.path().join("instant.jsonl"),
+        line(&mined) + &line(&fresh),
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("entity.jsonl"), line(&seen)).unwrap();
+    let (embed, profile) = (MockEmbed, profile());
+    let memory = DbMemory::new(
+        dir.path().to_path_buf(),
+        None,
+        &embed,
+        &profile,
+        dir.path().join("memory.sock"),
+    );
+    let chat = ScriptedChat::from_toml(
+        r#"
+[[rule]]
+contains = "harbor"
+response = '{"entities": [{"name": "Bob", "kind": "person"}]}'
+"#,
+    )
+    .unwrap();
+    let mut wits = vec![Wit {
+        config: entity_extractor("instant"),
+        llm: Box::new(chat),
+        profile: profile.clone(),
+        prompter: Default::default(),
+    }];
+
+    let mut tried = HashSet::new();
+    assert_eq!(
+        memory.mine_entities(&mut wits, &mut tried).await.unwrap(),
+        1
+    );
+    let memorized = rememberd.recv().await.unwrap();
+    assert_eq!(memorized["kind"], ENTITY_KIND);
+    assert_eq!(memorized["data"]["what"]["mentions"], json!([fresh.id]));
+    // tried entries are left alone on the next pass
+    assert_eq!(
+        memory.mine_entities(&mut wits, &mut tried).await.unwrap(),
+        0
+    );
+}
@@ -14,3 +14,59 @@ async fn load_config_parses_distillers() {
     assert_eq!(cfg.wit.len(), 1);
     assert!(cfg.wit.contains_key("instant"));
 }
+
+#[tokio::test]
+async fn load_config_parses_generation_options() {
+    let toml = r#"
+        [wit.facts]
+        prompt = "Extract facts from {{current}}"
+        options = { temperature = 0.0, max_tokens = 64, stop = ["\n\n"], format = "json" }
+    "#;
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("config.toml");
+    tokio::fs::write(&path, toml).await.unwrap();
+    let cfg = config::load(&path).await.unwrap();
+    let opts = &cfg.wit["facts"].options;
+    assert_eq!(opts.temperature, Some(0.0));
+    assert_eq!(opts.max_tokens, Some(64));
+    assert_eq!(opts.stop, vec!["\n\n".to_string()]);
+    assert_eq!(opts.format, Some(serde_json::json!("json")));
+}
+
+#[tokio::test]
+async fn load_config_parses_examples() {
+    let toml = r#"
+        [wit.facts]
+        prompt = "Extract facts from {{current}}"
+        examples = [
+            { role = "user", content = "Alice waved." },
+            { role = "assistant", content = "[\"Alice waved\"]" },
+        ]
+    "#;
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("config.toml");
+    tokio::fs::write(&path, toml).await.unwrap();
+    let cfg = config::load(&path).await.unwrap();
+    let examples = &cfg.wit["facts"].examples;
+    assert_eq!(examples.len(), 2);
+    assert_eq!(
+        examples[1],
+        psyche::llm::ChatMessage::assistant("[\"Alice waved\"]")
+    );
+}
+
+#[tokio::test]
+async fn load_config_parses_entities() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("config.toml");
+    tokio::fs::write(&path, "[entities]\n").await.unwrap();
+    let cfg = config::load(&path).await.unwrap();
+    let entities = cfg.entities.unwrap();
+    assert_eq!(entities.input, ["instant", "situation"]);
+    assert_eq!(entities.interval_secs, 10);
+    tokio::fs::write(&path, "[entities]\ninput = [\"sensation/ear\"]\n")
+        .await
+        .unwrap();
+    let cfg = config::load(&path).await.unwrap();
+    assert_eq!(cfg.entities.unwrap().input, ["sensation/ear"]);
+}
@@ -4,6 +4,19 @@ use tokio::io::AsyncWriteExt;
 use tokio::net::UnixStream;
 use tokio::task::LocalSet;
 
+/// The quick wit notices the feeling, the combobulator makes it a situation.
+const RULES: &str = r#"
+[[rule]]
+wit = "quick"
+contains = "I feel lonely"
+response = "The interlocutor feels lonely."
+
+[[rule]]
+wit = "combobulator"
+contains = "feels lonely"
+response = "Someone reached out because they feel lonely."
+"#;
+
 #[tokio::test(flavor = "current_thread")]
 #[ignore]
 async fn quick_to_combobulator_generates_situation() {
@@ -24,12 +37,12 @@ async fn quick_to_combobulator_generates_situation() {
     .unwrap();
 
     let registry = std::sync::Arc::new(psyche::llm::LlmRegistry {
-        chat: Box::new(psyche::llm::mock_chat::MockChat::default()),
+        chat: Box::new(psyche::llm::scripted::ScriptedChat::from_toml(RULES).unwrap()),
         embed: Box::new(psyche::llm::mock_embed::MockEmbed::default()),
     });
     let profile = std::sync::Arc::new(psyche::llm::LlmProfile {
-        provider: "mock".into(),
-        model: "mock".into(),
+        provider: "scripted".into(),
+        model: "script".into(),
         capabilities: vec![psyche::llm::LlmCapability::Chat],
     });
 
@@ -68,11 +81,18 @@ async fn quick_to_combobulator_generates_situation() {
             let icontent = tokio::fs::read_to_string(&instant_path).await.unwrap();
             let ilines: Vec<_> = icontent.lines().collect();
             assert_eq!(ilines.len(), 1);
+            let instant: psyche::models::MemoryEntry = serde_json::from_str(ilines[0]).unwrap();
+            assert_eq!(instant.how, "The interlocutor feels lonely.");
 
             let situation_path = soul_dir.join("memory/situation.jsonl");
             let scontent = tokio::fs::read_to_string(&situation_path).await.unwrap();
             let slines: Vec<_> = scontent.lines().collect();
             assert_eq!(slines.len(), 1);
+            let situation: psyche::models::MemoryEntry = serde_json::from_str(slines[0]).unwrap();
+            assert_eq!(
+                situation.how,
+                "Someone reached out because they feel lonely."
+            );
         })
         .await;
 }
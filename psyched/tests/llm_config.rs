@@ -15,3 +15,275 @@ async fn load_first_llm_picks_first_provider() {
     assert_eq!(llms.first().unwrap().profile.provider, "ollama");
     assert_eq!(llms.first().unwrap().profile.model, "x");
 }
+
+#[tokio::test]
+async fn load_llms_supports_openai_provider() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("llm.toml");
+    tokio::fs::write(
+        &path,
+        "[[llm]]\nprovider = \"openai\"\nname = \"local\"\nbase_url = \"http://localhost:8080\"\napi_key = \"sk-test\"\nmodels = [\"qwen\"]\n",
+    )
+    .await
+    .unwrap();
+
+    let llms = psyched::llm_config::load_llms(&path).await.unwrap();
+    assert_eq!(llms[0].name, "local");
+    assert_eq!(llms[0].profile.provider, "openai");
+    assert_eq!(llms[0].profile.model, "qwen");
+
+    let (_registry, profile) = psyched::llm_config::load_first_llm(&path).await.unwrap();
+    assert_eq!(profile.provider, "openai");
+}
+
+#[tokio::test]
+async fn embedding_section_selects_named_provider() {
+    use httpmock::prelude::*;
+
+    let server = httpmock::MockServer::start_async().await;
+    let mock = server
+        .mock_async(|when, then| {
+            when.method(POST)
+                .path("/api/embed")
+                .body_contains("\"model\":\"nomic-embed-text\"");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body("{\"embeddings\":[[0.5,0.25]]}");
+        })
+        .await;
+
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("llm.toml");
+    let config = format!(
+        "[[llm]]\nprovider = \"mock\"\nmodels = [\"chat\"]\n\n\
+         [[llm]]\nprovider = \"ollama\"\nname = \"embedder\"\nbase_url = \"{}\"\nmodels = [\"llama3\"]\n\n\
+         [embedding]\nllm = \"embedder\"\nmodel = \"nomic-embed-text\"\n",
+        server.base_url()
+    );
+    tokio::fs::write(&path, config).await.unwrap();
+
+    let embed = psyched::llm_config::load_embedder(&path).await.unwrap();
+    let (_, profile) = psyched::llm_config::load_first_llm(&path).await.unwrap();
+    let vector = embed.embed(&profile, "hello").await.unwrap();
+    assert_eq!(vector, vec![0.5, 0.25]);
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn embedding_falls_back_to_mock_without_provider() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("llm.toml");
+    tokio::fs::write(&path, "[[llm]]\nprovider = \"ollama\"\nmodels = [\"x\"]\n")
+        .await
+        .unwrap();
+
+    let embed = psyched::llm_config::load_embedder(&path).await.unwrap();
+    let (_, profile) = psyched::llm_config::load_first_llm(&path).await.unwrap();
+    assert_eq!(
+        embed.embed(&profile, "hi").await.unwrap(),
+        vec![0.0, 0.0, 0.0]
+    );
+}
+
+#[tokio::test]
+async fn embedding_rejects_unknown_provider_name() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("llm.toml");
+    tokio::fs::write(
+        &path,
+        "[[llm]]\nprovider = \"ollama\"\nmodels = [\"x\"]\n\n[embedding]\nllm = \"missing\"\n",
+    )
+    .await
+    .unwrap();
+
+    assert!(psyched::llm_config::load_embedder(&path).await.is_err());
+}
+
+#[tokio::test]
+async fn load_llms_parses_tool_use_capability() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("llm.toml");
+    tokio::fs::write(
+        &path,
+        "[[llm]]\nprovider = \"mock\"\nmodels = [\"m\"]\ncapabilities = [\"chat\", \"tool_use\"]\n",
+    )
+    .await
+    .unwrap();
+
+    let llms = psyched::llm_config::load_llms(&path).await.unwrap();
+    assert_eq!(
+        llms[0].profile.capabilities,
+        vec![
+            psyche::llm::LlmCapability::Chat,
+            psyche::llm::LlmCapability::ToolUse
+        ]
+    );
+}
+
+#[tokio::test]
+async fn vision_uses_first_image_capable_provider() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("llm.toml");
+    tokio::fs::write(
+        &path,
+        "[[llm]]\nprovider = \"ollama\"\nmodels = [\"phi4\"]\n\n\
+         [[llm]]\nprovider = \"mock\"\nmodels = [\"eyes\"]\ncapabilities = [\"image\"]\n",
+    )
+    .await
+    .unwrap();
+
+    let (vision, profile) = psyched::llm_config::load_vision(&path).await.unwrap();
+    assert_eq!(profile.provider, "mock");
+    assert_eq!(profile.model, "eyes");
+    use tokio_stream::StreamExt;
+    let caption: Vec<String> = vision
+        .see_stream(&profile, b"img", "describe")
+        .await
+        .unwrap()
+        .collect()
+        .await;
+    assert_eq!(caption, vec!["mock caption".to_string()]);
+}
+
+#[tokio::test]
+async fn vision_section_overrides_model() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("llm.toml");
+    tokio::fs::write(
+        &path,
+        "[[llm]]\nprovider = \"ollama\"\nname = \"gpu\"\nmodels = [\"phi4\", \"gemma3n\"]\n\n\
+         [vision]\nllm = \"gpu\"\nmodel = \"gemma3n\"\n",
+    )
+    .await
+    .unwrap();
+
+    let (_, profile) = psyched::llm_config::load_vision(&path).await.unwrap();
+    assert_eq!(profile.provider, "ollama");
+    assert_eq!(profile.model, "gemma3n");
+
+    tokio::fs::write(
+        &path,
+        "[[llm]]\nprovider = \"ollama\"\nmodels = [\"phi4\"]\n",
+    )
+    .await
+    .unwrap();
+    assert!(psyched::llm_config::load_vision(&path).await.is_err());
+}
+
+#[tokio::test]
+async fn load_pool_reads_policy_and_skips_non_chat_providers() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("llm.toml");
+    tokio::fs::write(
+        &path,
+        "[[llm]]\nprovider = \"mock\"\nname = \"a\"\nmodels = [\"m\"]\n\n\
+         [[llm]]\nprovider = \"mock\"\nname = \"embedder\"\nmodels = [\"e\"]\ncapabilities = [\"embedding\"]\n\n\
+         [[llm]]\nprovider = \"mock\"\nname = \"b\"\nmodels = [\"m\"]\n\n\
+         [pool]\npolicy = \"round_robin\"\nhealth_interval_secs = 5\n",
+    )
+    .await
+    .unwrap();
+
+    let (pool, cfg) = psyched::llm_config::load_pool(&path).await.unwrap();
+    assert_eq!(cfg.policy, psyche::llm::pool::RoutingPolicy::RoundRobin);
+    assert_eq!(cfg.health_interval_secs, 5);
+    let names: Vec<_> = pool.instances().map(|i| i.name.clone()).collect();
+    assert_eq!(names, vec!["a".to_string(), "b".to_string()]);
+}
+
+#[tokio::test]
+async fn replay_provider_serves_recorded_cassette() {
+    use tokio_stream::StreamExt;
+
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("llm.toml");
+    tokio::fs::write(
+        &path,
+        "[[llm]]\nprovider = \"mock\"\nmodels = [\"m\"]\ncapabilities = [\"chat\", \"embedding\"]\ncassette = \"tape.jsonl\"\n",
+    )
+    .await
+    .unwrap();
+    let (registry, profile) = psyched::llm_config::load_first_llm(&path).await.unwrap();
+    let live: Vec<String> = registry
+        .chat
+        .chat_stream(&profile, "", "hi")
+        .await
+        .unwrap()
+        .collect()
+        .await;
+    let vector = registry.embed.embed(&profile, "hi").await.unwrap();
+    assert!(dir.path().join("tape.jsonl").exists());
+
+    tokio::fs::write(
+        &path,
+        "[[llm]]\nprovider = \"replay\"\nmodels = [\"m\"]\ncapabilities = [\"chat\", \"embedding\"]\ncassette = \"tape.jsonl\"\n",
+    )
+    .await
+    .unwrap();
+    let (registry, profile) = psyched::llm_config::load_first_llm(&path).await.unwrap();
+    let replayed: Vec<String> = registry
+        .chat
+        .chat_stream(&profile, "", "hi")
+        .await
+        .unwrap()
+        .collect()
+        .await;
+    assert_eq!(replayed, live);
+    assert_eq!(registry.embed.embed(&profile, "hi").await.unwrap(), vector);
+    assert!(registry
+        .chat
+        .chat_stream(&profile, "", "bye")
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn scripted_provider_loads_rules_next_to_config() {
+    use tokio_stream::StreamExt;
+
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("llm.toml");
+    tokio::fs::write(
+        dir.path().join("rules.toml"),
+        "[[rule]]\ncontains = \"ping\"\nresponse = \"pong\"\nchunk_chars = 2\n",
+    )
+    .await
+    .unwrap();
+    tokio::fs::write(
+        &path,
+        "[[llm]]\nprovider = \"scripted\"\nrules = \"rules.toml\"\nmodels = [\"script\"]\n",
+    )
+    .await
+    .unwrap();
+
+    let (registry, profile) = psyched::llm_config::load_first_llm(&path).await.unwrap();
+    let reply: Vec<String> = registry
+        .chat
+        .chat_stream(&profile, "", "ping")
+        .await
+        .unwrap()
+        .collect()
+        .await;
+    assert_eq!(reply, vec!["po".to_string(), "ng".to_string()]);
+    assert!(registry.chat.chat_stream(&profile, "", "hi").await.is_err());
+}
+
+#[tokio::test]
+async fn lexical_embedding_provider_works_offline() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("llm.toml");
+    tokio::fs::write(
+        &path,
+        "[[llm]]\nprovider = \"lexical\"\ncapabilities = [\"embedding\"]\ndimensions = 32\n\n\
+         [[llm]]\nprovider = \"mock\"\nmodels = [\"chat\"]\n",
+    )
+    .await
+    .unwrap();
+
+    let embed = psyched::llm_config::load_embedder(&path).await.unwrap();
+    let (_, profile) = psyched::llm_config::load_first_llm(&path).await.unwrap();
+    assert_eq!(profile.provider, "mock");
+    assert_eq!(embed.embed(&profile, "hello").await.unwrap().len(), 32);
+    let (pool, _) = psyched::llm_config::load_pool(&path).await.unwrap();
+    assert_eq!(pool.instances().count(), 1);
+}
@@ -35,7 +35,7 @@ async fn wit_recall_postprocess_sends_query() {
         name: "mock".into(),
         chat: std::sync::Arc::new(psyche::llm::mock_chat::MockChat::default()),
         profile: profile.clone(),
-        semaphore: std::sync::Arc::new(tokio::sync::Semaphore::new(1)),
+        scheduler: psyche::llm::scheduler::PriorityScheduler::new(1),
     });
 
     let listener = UnixListener::bind(&memory_sock).unwrap();
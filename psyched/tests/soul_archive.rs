@@ -0,0 +1,48 @@
+use psyche::memory::{context_subgraph, Experience, HnswBackend, MemoryBackend};
+use tempfile::tempdir;
+
+#[tokio::test(flavor = "current_thread")]
+async fn export_then_import_into_a_fresh_soul() {
+    let dir = tempdir().unwrap();
+    let soul = dir.path().join("soul");
+    std::fs::create_dir_all(soul.join("memory")).unwrap();
+    std::fs::write(soul.join("identity.toml"), "name = \"Layka\"").unwrap();
+    std::fs::write(soul.join("memory/instant.jsonl"), "{\"how\":\"I see.\"}\n").unwrap();
+    let (first, second) = {
+        let backend = HnswBackend::open(soul.join("memory/index")).unwrap();
+        let exp = |how: &str, secs: i64| Experience {
+            how: how.into(),
+            what: how.into(),
+            when: chrono::DateTime::from_timestamp(1_700_000_000 + secs, 0).unwrap(),
+            tags: vec!["instant".into()],
+        };
+        let first = backend.store(&exp("Dawn.", 0), &[1.0, 0.0]).await.unwrap();
+        let second = backend.store(&exp("Noon.", 60), &[0.0, 1.0]).await.unwrap();
+        (first, second)
+    };
+
+    let archive = dir.path().join("layka.soul");
+    let exported = psyched::export_soul(&soul, &archive).await.unwrap();
+    assert_eq!((exported.files, exported.entries), (1, 1));
+    assert_eq!((exported.experiences, exported.edges), (2, 1));
+
+    let copy = dir.path().join("copy");
+    let imported = psyched::import_soul(&archive, &copy).await.unwrap();
+    assert_eq!(imported, exported);
+    assert!(copy.join("identity.toml").exists());
+    let restored = HnswBackend::open(copy.join("memory/index")).unwrap();
+    let hits = restored
+        .search(&[0.0, 1.0], 1, &Default::default())
+        .await
+        .unwrap();
+    assert_eq!(hits[0].id, second);
+    let context = context_subgraph(&restored, &second).await.unwrap();
+    assert_eq!(context.len(), 2);
+    assert_eq!(
+        restored.get(&first).await.unwrap(),
+        Some(context[1].clone())
+    );
+
+    // importing again would overwrite the copy
+    assert!(psyched::import_soul(&archive, &copy).await.is_err());
+}
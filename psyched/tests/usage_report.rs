@@ -0,0 +1,30 @@
+use psyche::llm::usage::{UsageRecord, UsageStats};
+use std::time::Duration;
+
+fn record(wit: &str) -> UsageRecord {
+    UsageRecord {
+        wit: wit.into(),
+        model: "llama3".into(),
+        host: "local".into(),
+        prompt_tokens: Some(10),
+        completion_tokens: Some(5),
+        time_to_first_token: Some(Duration::from_millis(100)),
+        latency: Duration::from_millis(500),
+    }
+}
+
+#[test]
+fn usage_report_merges_every_process() {
+    let dir = tempfile::tempdir().unwrap();
+    let quick = UsageStats::new().saving_to(dir.path().join("quick.json"));
+    quick.record(record("quick"));
+    quick.record(record("quick"));
+    let psyched = UsageStats::new().saving_to(dir.path().join("psyched.json"));
+    psyched.record(record("psyched"));
+    std::fs::write(dir.path().join("notes.txt"), "not usage").unwrap();
+
+    let report = psyched::usage_report(dir.path()).unwrap();
+    assert_eq!(report.by_wit["quick"].calls, 2);
+    assert_eq!(report.by_wit["psyched"].completion_tokens, 5);
+    assert_eq!(report.by_host["local"].calls, 3);
+}
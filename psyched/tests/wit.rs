@@ -1,6 +1,24 @@
 use tempfile::tempdir;
 use tokio::task::LocalSet;
 
+/// Echo greets back; in the feedback chain the second wit answers the first.
+const RULES: &str = r#"
+[[rule]]
+wit = "echo"
+contains = "hello"
+response = "Hello back."
+
+[[rule]]
+wit = "first"
+contains = "hi"
+response = "Pass it on."
+
+[[rule]]
+wit = "second"
+contains = "Pass it on."
+response = "Passed on."
+"#;
+
 #[tokio::test(flavor = "current_thread")]
 #[ignore]
 async fn wit_produces_output() {
@@ -21,12 +39,12 @@ async fn wit_produces_output() {
     .unwrap();
 
     let registry = std::sync::Arc::new(psyche::llm::LlmRegistry {
-        chat: Box::new(psyche::llm::mock_chat::MockChat::default()),
+        chat: Box::new(psyche::llm::scripted::ScriptedChat::from_toml(RULES).unwrap()),
         embed: Box::new(psyche::llm::mock_embed::MockEmbed::default()),
     });
     let profile = std::sync::Arc::new(psyche::llm::LlmProfile {
-        provider: "mock".into(),
-        model: "mock".into(),
+        provider: "scripted".into(),
+        model: "script".into(),
         capabilities: vec![psyche::llm::LlmCapability::Chat],
     });
 
@@ -65,7 +83,7 @@ async fn wit_produces_output() {
             let lines: Vec<_> = content.lines().collect();
             assert_eq!(lines.len(), 1);
             let entry: psyche::models::MemoryEntry = serde_json::from_str(lines[0]).unwrap();
-            assert_eq!(entry.how, "mock response");
+            assert_eq!(entry.how, "Hello back.");
         })
         .await;
 }
@@ -87,12 +105,12 @@ async fn feedback_forwards_output() {
     tokio::fs::write(&config_path, config).await.unwrap();
 
     let registry = std::sync::Arc::new(psyche::llm::LlmRegistry {
-        chat: Box::new(psyche::llm::mock_chat::MockChat::default()),
+        chat: Box::new(psyche::llm::scripted::ScriptedChat::from_toml(RULES).unwrap()),
         embed: Box::new(psyche::llm::mock_embed::MockEmbed::default()),
     });
     let profile = std::sync::Arc::new(psyche::llm::LlmProfile {
-        provider: "mock".into(),
-        model: "mock".into(),
+        provider: "scripted".into(),
+        model: "script".into(),
         capabilities: vec![psyche::llm::LlmCapability::Chat],
     });
 
@@ -129,6 +147,8 @@ async fn feedback_forwards_output() {
             let content = tokio::fs::read_to_string(&path).await.unwrap();
             let lines: Vec<_> = content.lines().collect();
             assert_eq!(lines.len(), 1);
+            let entry: psyche::models::MemoryEntry = serde_json::from_str(lines[0]).unwrap();
+            assert_eq!(entry.how, "Passed on.");
         })
         .await;
 }
@@ -7,7 +7,7 @@ mod policy;
 mod rpc;
 mod store;
 
-pub use store::FileStore;
+pub use store::{Backend, FileStore, Verification};
 
 async fn handle_connection(stream: UnixStream, store: FileStore) -> anyhow::Result<()> {
     let mut reader = BufReader::new(stream);
@@ -25,17 +25,21 @@ async fn handle_connection(stream: UnixStream, store: FileStore) -> anyhow::Resu
     Ok(())
 }
 
-/// Run the `rememberd` JSON-RPC server.
+/// Run the `rememberd` JSON-RPC server. Connections are served on the
+/// current [`LocalSet`](tokio::task::LocalSet), as memory backends are not
+/// `Send`.
 pub async fn run(socket: PathBuf, store: FileStore) -> anyhow::Result<()> {
     if socket.exists() {
         tokio::fs::remove_file(&socket).await.ok();
     }
     let listener = UnixListener::bind(&socket)?;
     info!(?socket, "rememberd listening");
+    let sleeper = store.clone();
+    tokio::task::spawn_local(async move { sleeper.consolidate_periodically().await });
     loop {
         let (stream, _) = listener.accept().await?;
         let st = store.clone();
-        tokio::spawn(async move {
+        tokio::task::spawn_local(async move {
             if let Err(e) = handle_connection(stream, st).await {
                 error!(error = %e, "connection failed");
             }
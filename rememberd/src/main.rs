@@ -1,8 +1,10 @@
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use daemon_common::{maybe_daemonize, LogLevel};
-use qdrant_client::prelude::*;
-use rememberd::{run, FileStore};
-use std::path::PathBuf;
+use qdrant_client::Qdrant;
+use rememberd::{run, Backend, FileStore};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::warn;
 
 #[derive(Parser, Debug)]
 #[command(name = "rememberd", about = "Memory JSON-RPC daemon")]
@@ -19,6 +21,12 @@ struct Cli {
     #[arg(long)]
     qdrant_url: Option<String>,
 
+    /// LLM configuration whose embedder vectors consolidation summaries.
+    /// With it, the memory backend named by `QDRANT_URL` and `NEO4J_URL`
+    /// or by `MEMORY_DB` is consolidated along with the files
+    #[arg(long)]
+    llm: Option<PathBuf>,
+
     /// Logging verbosity
     #[arg(long, default_value = "info")]
     log_level: LogLevel,
@@ -26,6 +34,19 @@ struct Cli {
     /// Run as a background daemon
     #[arg(short = 'd', long)]
     daemon: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+/// One-off commands run instead of the daemon.
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Report memory lines that are unparseable or at an old schema version
+    Verify,
+    /// Upgrade legacy memory lines to the current schema version. Refused
+    /// while a daemon answers on the socket, as it writes the same files
+    Migrate,
 }
 
 #[tokio::main]
@@ -35,13 +56,68 @@ async fn main() -> anyhow::Result<()> {
         .with_max_level(tracing_subscriber::filter::LevelFilter::from(cli.log_level))
         .init();
 
+    match cli.command {
+        Some(Command::Verify) => {
+            let report = FileStore::new(cli.memory_dir).verify().await?;
+            println!("{}", serde_json::to_string_pretty(&report)?);
+            if !report.is_clean() {
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Some(Command::Migrate) => {
+            if std::os::unix::net::UnixStream::connect(&cli.socket).is_ok() {
+                anyhow::bail!(
+                    "rememberd is running on {}; stop it before migrating",
+                    cli.socket.display()
+                );
+            }
+            let report = FileStore::new(cli.memory_dir).migrate().await?;
+            println!("{}", serde_json::to_string_pretty(&report)?);
+            return Ok(());
+        }
+        None => {}
+    }
+
     maybe_daemonize(cli.daemon)?;
 
-    let store = if let Some(url) = cli.qdrant_url {
-        let client = QdrantClient::from_url(&url).build()?;
+    let backend = match &cli.llm {
+        Some(llm) => shared_backend(&cli.memory_dir, llm).await?,
+        None => None,
+    };
+    let mut store = if let Some(url) = cli.qdrant_url {
+        let client = Qdrant::from_url(&url).build()?;
         FileStore::with_qdrant(cli.memory_dir, client)
     } else {
         FileStore::new(cli.memory_dir)
     };
-    run(cli.socket, store).await
+    if let Err(e) = store.index_faces().await {
+        warn!(error = %e, "could not index faces");
+    }
+    if let Some(backend) = backend {
+        store = store.with_backend(backend);
+    }
+    tokio::task::LocalSet::new()
+        .run_until(run(cli.socket, store))
+        .await
+}
+
+/// The memory backend psyched writes to, when the environment names one
+/// that several processes can open at once. The embedded index under
+/// `memory_dir/index` is held open by psyched and left alone.
+async fn shared_backend(memory_dir: &Path, llm: &Path) -> anyhow::Result<Option<Backend>> {
+    let services =
+        std::env::var_os("QDRANT_URL").is_some() && std::env::var_os("NEO4J_URL").is_some();
+    if !services && std::env::var_os("MEMORY_DB").is_none() {
+        warn!("no shared memory backend configured; consolidating the files only");
+        return Ok(None);
+    }
+    let memory = psyched::open_backend(memory_dir)
+        .ok_or_else(|| anyhow::anyhow!("memory backend unavailable"))?;
+    let (registry, profile) = psyche::llm::config::load_first_llm(llm).await?;
+    Ok(Some(Backend {
+        memory,
+        embed: Arc::from(registry.embed),
+        profile,
+    }))
 }
@@ -1,3 +1,4 @@
+use psyche::memory::Retention;
 use serde::Deserialize;
 use std::collections::HashSet;
 use std::path::Path;
@@ -6,6 +7,8 @@ use std::path::Path;
 struct RawPolicy {
     #[serde(default)]
     recall: RecallSection,
+    #[serde(default)]
+    retention: Retention,
 }
 
 #[derive(Deserialize, Default)]
@@ -18,6 +21,7 @@ struct RecallSection {
 #[derive(Clone, Default)]
 pub struct Policy {
     recall_kinds: HashSet<String>,
+    retention: Retention,
 }
 
 impl Policy {
@@ -28,6 +32,7 @@ impl Policy {
             if let Ok(raw) = toml::from_str::<RawPolicy>(&text) {
                 return Self {
                     recall_kinds: raw.recall.kinds.into_iter().collect(),
+                    retention: raw.retention,
                 };
             }
         }
@@ -38,4 +43,9 @@ impl Policy {
     pub fn recall_for(&self, kind: &str) -> bool {
         self.recall_kinds.contains(kind)
     }
+
+    /// How old entries are consolidated, from the `[retention]` table.
+    pub fn retention(&self) -> &Retention {
+        &self.retention
+    }
 }
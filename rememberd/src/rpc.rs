@@ -39,6 +39,11 @@ struct QueryVectorParams {
     top_k: usize,
 }
 
+#[derive(Deserialize)]
+struct AboutParams {
+    name: String,
+}
+
 /// Dispatch a single JSON-RPC request.
 pub async fn dispatch(req: RpcRequest, store: &FileStore) -> anyhow::Result<RpcResponse> {
     match req.method.as_str() {
@@ -80,6 +85,25 @@ pub async fn dispatch(req: RpcRequest, store: &FileStore) -> anyhow::Result<RpcR
                 id: req.id,
             })
         }
+        "about" => {
+            let params: AboutParams = serde_json::from_value(req.params)?;
+            let about = store.about(&params.name).await?;
+            Ok(RpcResponse {
+                jsonrpc: "2.0",
+                result: Some(about.unwrap_or(Value::Null)),
+                error: None,
+                id: req.id,
+            })
+        }
+        "sleep" => {
+            let report = store.consolidate(chrono::Utc::now()).await?;
+            Ok(RpcResponse {
+                jsonrpc: "2.0",
+                result: Some(serde_json::to_value(report)?),
+                error: None,
+                id: req.id,
+            })
+        }
         "query_graph" | "episode" => Ok(RpcResponse {
             jsonrpc: "2.0",
             result: Some(Value::Null),
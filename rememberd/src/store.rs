@@ -1,12 +1,27 @@
-use qdrant_client::prelude::*;
+use chrono::{DateTime, Utc};
+use psyche::llm::{CanEmbed, LlmProfile};
+use psyche::memory::{
+    mentioned_entities, plan, Consolidation, Entity, Experience, MemoryBackend, Plan,
+    RetentionAction,
+};
+use psyche::models::versioned::{self, migrate_lines, verify_lines, LinesReport, RecordType};
+use psyche::models::MemoryEntry;
+use psyche::wit::ENTITY_KIND;
 use qdrant_client::qdrant::{
-    point_id, CreateCollectionBuilder, Distance, PointStruct, SearchPoints, VectorParamsBuilder,
+    point_id, CreateCollectionBuilder, Distance, PointStruct, SearchPointsBuilder,
+    UpsertPointsBuilder, VectorParamsBuilder,
 };
+use qdrant_client::Qdrant;
+use serde::Serialize;
 use serde_json::Value;
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 use tokio::io::AsyncWriteExt;
-use tracing::trace;
+use tokio::sync::Mutex;
+use tracing::{error, info, trace, warn};
+use uuid::Uuid;
 
 use crate::policy::Policy;
 
@@ -15,7 +30,64 @@ use crate::policy::Policy;
 pub struct FileStore {
     pub dir: PathBuf,
     policy: Policy,
-    qdrant: Option<std::sync::Arc<QdrantClient>>,
+    qdrant: Option<std::sync::Arc<Qdrant>>,
+    /// Memory backend kept in step with consolidation.
+    backend: Option<Backend>,
+    /// Held while a file is written or rewritten.
+    files: Arc<Mutex<()>>,
+}
+
+/// Memory backend holding the same entries as the files, with the embedder
+/// for the summaries consolidation stores in it.
+#[derive(Clone)]
+pub struct Backend {
+    pub memory: Arc<dyn MemoryBackend>,
+    pub embed: Arc<dyn CanEmbed>,
+    pub profile: LlmProfile,
+}
+
+impl Backend {
+    /// Store the summaries of `plan`, each linked to the originals it
+    /// summarizes, then archive or forget the entries it removes. The files
+    /// are rewritten by then, so failures are logged and skipped.
+    async fn apply(&self, plan: &Plan, action: RetentionAction) {
+        for summary in &plan.summaries {
+            let id = summary.id.to_string();
+            if let Err(e) = self.store(summary).await {
+                warn!(%id, error = %e, "cannot store summary");
+                continue;
+            }
+            let originals = summary.what.as_array().into_iter().flatten();
+            for original in originals.filter_map(Value::as_str) {
+                if let Err(e) = self.memory.link_summary(&id, original).await {
+                    warn!(%id, original, error = %e, "cannot link summary");
+                }
+            }
+        }
+        for id in plan.removed() {
+            let id = id.to_string();
+            let retired = match action {
+                RetentionAction::Archive => self.memory.archive(&id).await,
+                RetentionAction::Prune => self.memory.forget(&id).await,
+            };
+            if let Err(e) = retired {
+                warn!(%id, ?action, error = %e, "cannot retire experience");
+            }
+        }
+    }
+
+    async fn store(&self, entry: &MemoryEntry) -> anyhow::Result<()> {
+        let vector = self.embed.embed(&self.profile, &entry.how).await?;
+        let exp = Experience {
+            how: entry.how.clone(),
+            what: entry.what.clone(),
+            when: entry.when,
+            tags: vec![entry.kind.clone()],
+        };
+        self.memory
+            .store_with_id(&entry.id.to_string(), &exp, &vector)
+            .await
+    }
 }
 
 impl FileStore {
@@ -26,53 +98,48 @@ impl FileStore {
             dir,
             policy,
             qdrant: None,
+            backend: None,
+            files: Arc::default(),
         }
     }
 
-    pub fn with_qdrant(dir: PathBuf, qdrant: QdrantClient) -> Self {
+    pub fn with_qdrant(dir: PathBuf, qdrant: Qdrant) -> Self {
         let policy = Policy::load(&dir);
         Self {
             dir,
             policy,
             qdrant: Some(std::sync::Arc::new(qdrant)),
+            backend: None,
+            files: Arc::default(),
         }
     }
 
+    /// Keep `backend` in step with consolidation, and take how often each
+    /// entry was recalled from its `CALLED_TO_MIND` links.
+    pub fn with_backend(mut self, backend: Backend) -> Self {
+        self.backend = Some(backend);
+        self
+    }
+
     /// Append a serialized value under the provided memory `kind`.
     pub async fn append(&self, kind: &str, value: &Value) -> anyhow::Result<()> {
         if kind == "face" {
-            if let Some(client) = &self.qdrant {
-                if let (Some(idv), Some(embv)) = (value.get("id"), value.get("embedding")) {
-                    if let (Some(id), Some(arr)) = (idv.as_str(), embv.as_array()) {
-                        let vector: Vec<f32> = arr
-                            .iter()
-                            .filter_map(|v| v.as_f64().map(|f| f as f32))
-                            .collect();
-                        ensure_faces_collection(&**client, vector.len() as u64).await?;
-                        let points = vec![PointStruct::new(
-                            id.to_string(),
-                            vector,
-                            HashMap::<String, qdrant_client::qdrant::Value>::new(),
-                        )];
-                        client
-                            .upsert_points_blocking("faces", None, points, None)
-                            .await?;
-                    }
-                }
+            if let (Some(client), Some(face)) = (&self.qdrant, face_vector(value)) {
+                upsert_faces(client, vec![face]).await?;
             }
         }
         self.write(kind, value).await?;
         if self.policy.recall_for(kind) {
             if let Some(how) = value.get("how").and_then(|v| v.as_str()) {
                 if let Some(id) = value.get("id") {
-                    let recall = serde_json::json!({
-                        "id": uuid::Uuid::new_v4(),
-                        "kind": "recall",
-                        "when": chrono::Utc::now(),
-                        "how": how,
-                        "what": [id.clone()],
-                    });
-                    self.write("recall", &recall).await?;
+                    let recall = MemoryEntry {
+                        id: Uuid::new_v4(),
+                        kind: "recall".into(),
+                        when: Utc::now(),
+                        what: Value::Array(vec![id.clone()]),
+                        how: how.to_string(),
+                    };
+                    self.write("recall", &versioned::stamp(&recall)?).await?;
                 }
             }
         }
@@ -82,6 +149,7 @@ impl FileStore {
     async fn write(&self, kind: &str, value: &Value) -> anyhow::Result<()> {
         let base = kind.split('/').next().unwrap_or(kind);
         let path = self.dir.join(format!("{}.jsonl", base));
+        let _files = self.files.lock().await;
         let mut file = tokio::fs::OpenOptions::new()
             .append(true)
             .create(true)
@@ -111,6 +179,220 @@ impl FileStore {
         Ok(out)
     }
 
+    /// Everything remembered about the person, place or object answering
+    /// to `name`: the entity merged from every `entity` entry naming it and
+    /// the entries those mention, oldest first. `None` when nothing answers.
+    pub async fn about(&self, name: &str) -> anyhow::Result<Option<Value>> {
+        let mut known: Vec<(Entity, HashSet<String>)> = Vec::new();
+        for entry in self.list(ENTITY_KIND).await? {
+            let (entities, mentions) =
+                mentioned_entities(entry.get("what").unwrap_or(&Value::Null));
+            for entity in entities {
+                match known.iter_mut().find(|(k, _)| k.same_as(&entity)) {
+                    Some((k, ids)) => {
+                        k.absorb(&entity);
+                        ids.extend(mentions.iter().cloned());
+                    }
+                    None => known.push((entity, mentions.iter().cloned().collect())),
+                }
+            }
+        }
+        let Some((entity, ids)) = known.into_iter().find(|(k, _)| k.answers_to(name)) else {
+            return Ok(None);
+        };
+        let mut memories = Vec::new();
+        let mut files = tokio::fs::read_dir(&self.dir).await?;
+        while let Some(file) = files.next_entry().await? {
+            let path = file.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("jsonl") {
+                continue;
+            }
+            let Some(kind) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            memories.extend(self.list(kind).await?.into_iter().filter(|v| {
+                v.get("id")
+                    .and_then(Value::as_str)
+                    .is_some_and(|id| ids.contains(id))
+            }));
+        }
+        memories.sort_by_key(|v| v.get("when").and_then(entry_time));
+        trace!(name, entity = %entity.name, memories = memories.len(), "about");
+        Ok(Some(
+            serde_json::json!({ "entity": entity, "memories": memories }),
+        ))
+    }
+
+    /// Consolidate old entries under the `[retention]` policy: merge
+    /// near-duplicates, fold runs of unimportant entries into summaries and
+    /// archive the originals to `archive/<kind>.jsonl` or prune them. The
+    /// backend, if any, gets the summaries and archives or forgets the
+    /// originals alike.
+    pub async fn consolidate(&self, now: DateTime<Utc>) -> anyhow::Result<Consolidation> {
+        let retention = self.policy.retention();
+        let _files = self.files.lock().await;
+        let mut recalls: HashMap<Uuid, usize> = HashMap::new();
+        if let Some(backend) = &self.backend {
+            for (id, count) in backend.memory.recall_counts().await? {
+                if let Ok(id) = id.parse() {
+                    recalls.insert(id, count);
+                }
+            }
+        }
+        let mut bases: Vec<&str> = retention
+            .kinds
+            .iter()
+            .map(|k| k.split('/').next().unwrap_or(k))
+            .collect();
+        bases.sort();
+        bases.dedup();
+        let mut files = Vec::new();
+        let mut entries = Vec::new();
+        for base in bases {
+            let values = self.list(base).await?;
+            entries.extend(values.iter().filter_map(|v| {
+                versioned::upgrade::<MemoryEntry>(v.clone())
+                    .inspect_err(|e| warn!(kind = base, error = %e, "skipping unreadable entry"))
+                    .ok()
+            }));
+            files.push((base, values));
+        }
+        let plan = plan(&entries, &recalls, retention, now);
+        let removed = plan.removed();
+        for (base, values) in files {
+            let (gone, mut kept): (Vec<Value>, Vec<Value>) = values.into_iter().partition(|v| {
+                v.get("id")
+                    .and_then(entry_id)
+                    .is_some_and(|id| removed.contains(&id))
+            });
+            let summaries = plan
+                .summaries
+                .iter()
+                .filter(|s| s.kind.split('/').next() == Some(base));
+            for summary in summaries {
+                kept.push(versioned::stamp(summary)?);
+            }
+            if gone.is_empty() {
+                continue;
+            }
+            if retention.action == RetentionAction::Archive {
+                let archive = self.dir.join("archive");
+                tokio::fs::create_dir_all(&archive).await?;
+                append_lines(&archive.join(format!("{base}.jsonl")), &gone).await?;
+            }
+            rewrite_lines(&self.dir.join(format!("{base}.jsonl")), &kept).await?;
+        }
+        if let Some(backend) = &self.backend {
+            backend.apply(&plan, retention.action).await;
+        }
+        let report = Consolidation::of(&plan, retention);
+        info!(
+            considered = report.considered,
+            merged = report.merged,
+            summarized = report.summarized,
+            "consolidated memory"
+        );
+        Ok(report)
+    }
+
+    /// Check every line of the memory files, archives included, against
+    /// the record schema of its kind. `face` files hold recognizer output
+    /// rather than memory records and are left out.
+    pub async fn verify(&self) -> anyhow::Result<Verification> {
+        let _files = self.files.lock().await;
+        let mut report = Verification::default();
+        for (name, path, record_type) in self.record_files().await? {
+            let text = tokio::fs::read_to_string(&path).await?;
+            report.files.insert(name, verify_lines(record_type, &text));
+        }
+        Ok(report)
+    }
+
+    /// Upgrade legacy lines of every memory file to the current schema,
+    /// rewriting only files that hold some. Unparseable lines are kept as
+    /// they are. Returns what was found before migrating.
+    pub async fn migrate(&self) -> anyhow::Result<Verification> {
+        let _files = self.files.lock().await;
+        let mut report = Verification::default();
+        for (name, path, record_type) in self.record_files().await? {
+            let text = tokio::fs::read_to_string(&path).await?;
+            let (migrated, lines) = migrate_lines(record_type, &text);
+            if !lines.legacy.is_empty() {
+                rewrite_text(&path, &migrated).await?;
+                info!(file = %name, upgraded = lines.legacy.len(), "migrated memory file");
+            }
+            report.files.insert(name, lines);
+        }
+        Ok(report)
+    }
+
+    /// Memory files with versioned records, named relative to the store.
+    async fn record_files(&self) -> anyhow::Result<Vec<(String, PathBuf, RecordType)>> {
+        let mut out = Vec::new();
+        for sub in ["", "archive"] {
+            let dir = self.dir.join(sub);
+            let Ok(mut files) = tokio::fs::read_dir(&dir).await else {
+                continue;
+            };
+            while let Some(file) = files.next_entry().await? {
+                let path = file.path();
+                if path.extension().and_then(|e| e.to_str()) != Some("jsonl") {
+                    continue;
+                }
+                let Some(kind) = path.file_stem().and_then(|s| s.to_str()) else {
+                    continue;
+                };
+                if kind == "face" {
+                    continue;
+                }
+                let name = Path::new(sub).join(format!("{kind}.jsonl"));
+                out.push((
+                    name.to_string_lossy().into_owned(),
+                    path.clone(),
+                    RecordType::for_kind(kind),
+                ));
+            }
+        }
+        Ok(out)
+    }
+
+    /// Consolidate every `[retention] interval_secs` until the task is
+    /// dropped. Returns at once when no interval is configured.
+    pub async fn consolidate_periodically(&self) {
+        let Some(secs) = self.policy.retention().interval_secs.filter(|s| *s > 0) else {
+            return;
+        };
+        let period = Duration::from_secs(secs);
+        let mut ticks = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+        loop {
+            ticks.tick().await;
+            if let Err(e) = self.consolidate(Utc::now()).await {
+                error!(error = %e, "consolidation failed");
+            }
+        }
+    }
+
+    /// Upsert every `face` entry into the Qdrant `faces` collection, so
+    /// faces restored from a soul archive or written while Qdrant was away
+    /// can be found. Returns how many were indexed; none without Qdrant.
+    pub async fn index_faces(&self) -> anyhow::Result<usize> {
+        let Some(client) = &self.qdrant else {
+            return Ok(0);
+        };
+        let faces: Vec<_> = self
+            .list("face")
+            .await?
+            .iter()
+            .filter_map(face_vector)
+            .collect();
+        let count = faces.len();
+        for chunk in faces.chunks(256) {
+            upsert_faces(client, chunk.to_vec()).await?;
+        }
+        info!(faces = count, "indexed faces");
+        Ok(count)
+    }
+
     pub async fn query_vector(
         &self,
         kind: &str,
@@ -125,23 +407,13 @@ impl FileStore {
             None => return Ok(Vec::new()),
         };
         ensure_faces_collection(client, vector.len() as u64).await?;
-        let req = SearchPoints {
-            collection_name: "faces".into(),
-            vector: vector.to_vec(),
-            filter: None,
-            limit: top_k as u64,
-            with_payload: None,
-            params: None,
-            score_threshold: None,
-            offset: None,
-            vector_name: None,
-            with_vectors: None,
-            read_consistency: None,
-            timeout: None,
-            shard_key_selector: None,
-            sparse_indices: None,
-        };
-        let res = client.search_points(&req).await?;
+        let res = client
+            .search_points(SearchPointsBuilder::new(
+                "faces",
+                vector.to_vec(),
+                top_k as u64,
+            ))
+            .await?;
         let mut out = Vec::new();
         for pt in res.result {
             if let Some(id) = pt.id.as_ref() {
@@ -158,12 +430,110 @@ impl FileStore {
     }
 }
 
-async fn ensure_faces_collection(client: &QdrantClient, dim: u64) -> anyhow::Result<()> {
+/// Schema check of the memory files, keyed by path relative to the store.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Verification {
+    pub files: BTreeMap<String, LinesReport>,
+}
+
+impl Verification {
+    /// Whether every line of every file is at the current version.
+    pub fn is_clean(&self) -> bool {
+        self.files.values().all(LinesReport::is_clean)
+    }
+}
+
+/// Entry id given as a UUID string.
+fn entry_id(id: &Value) -> Option<Uuid> {
+    id.as_str()?.parse().ok()
+}
+
+async fn append_lines(path: &Path, values: &[Value]) -> anyhow::Result<()> {
+    let mut file = tokio::fs::OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(path)
+        .await?;
+    for value in values {
+        file.write_all(serde_json::to_string(value)?.as_bytes())
+            .await?;
+        file.write_all(b"\n").await?;
+    }
+    Ok(())
+}
+
+/// Replace the contents of `path` with `values`, one per line, atomically.
+async fn rewrite_lines(path: &Path, values: &[Value]) -> anyhow::Result<()> {
+    let mut text = String::new();
+    for value in values {
+        text.push_str(&serde_json::to_string(value)?);
+        text.push('\n');
+    }
+    rewrite_text(path, &text).await
+}
+
+/// Replace the contents of `path` with `text` atomically.
+async fn rewrite_text(path: &Path, text: &str) -> anyhow::Result<()> {
+    let tmp = path.with_extension("jsonl.tmp");
+    tokio::fs::write(&tmp, text).await?;
+    tokio::fs::rename(&tmp, path).await?;
+    Ok(())
+}
+
+/// Time of an entry, written either as Unix seconds or as RFC 3339.
+fn entry_time(when: &Value) -> Option<chrono::DateTime<chrono::Utc>> {
+    match when {
+        Value::Number(n) => chrono::DateTime::from_timestamp(n.as_i64()?, 0),
+        Value::String(s) => chrono::DateTime::parse_from_rfc3339(s)
+            .ok()
+            .map(|t| t.to_utc()),
+        _ => None,
+    }
+}
+
+/// Id and embedding of a `face` entry.
+fn face_vector(value: &Value) -> Option<(String, Vec<f32>)> {
+    let id = value.get("id")?.as_str()?;
+    let vector = value
+        .get("embedding")?
+        .as_array()?
+        .iter()
+        .filter_map(|v| v.as_f64().map(|f| f as f32))
+        .collect();
+    Some((id.to_string(), vector))
+}
+
+/// Upsert `faces` into the `faces` collection, creating it for their
+/// dimension when missing.
+async fn upsert_faces(client: &Qdrant, faces: Vec<(String, Vec<f32>)>) -> anyhow::Result<()> {
+    let Some((_, first)) = faces.first() else {
+        return Ok(());
+    };
+    ensure_faces_collection(client, first.len() as u64).await?;
+    let points: Vec<PointStruct> = faces
+        .into_iter()
+        .map(|(id, vector)| {
+            PointStruct::new(
+                id,
+                vector,
+                HashMap::<String, qdrant_client::qdrant::Value>::new(),
+            )
+        })
+        .collect();
+    client
+        .upsert_points(UpsertPointsBuilder::new("faces", points).wait(true))
+        .await?;
+    Ok(())
+}
+
+async fn ensure_faces_collection(client: &Qdrant, dim: u64) -> anyhow::Result<()> {
     if !client.collection_exists("faces").await? {
-        let req = CreateCollectionBuilder::new("faces")
-            .vectors_config(VectorParamsBuilder::new(dim, Distance::Cosine))
-            .build();
-        client.create_collection(&req).await?;
+        client
+            .create_collection(
+                CreateCollectionBuilder::new("faces")
+                    .vectors_config(VectorParamsBuilder::new(dim, Distance::Cosine)),
+            )
+            .await?;
     }
     Ok(())
 }
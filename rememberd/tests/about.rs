@@ -0,0 +1,86 @@
+use rememberd::{run, FileStore};
+use serde_json::{json, Value};
+use std::path::Path;
+use tempfile::tempdir;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::UnixStream;
+use tokio::task::LocalSet;
+
+async fn call(sock: &Path, method: &str, params: Value) -> Value {
+    let mut client = UnixStream::connect(sock).await.unwrap();
+    let req = json!({"jsonrpc": "2.0", "method": method, "params": params, "id": 1});
+    client
+        .write_all(&serde_json::to_vec(&req).unwrap())
+        .await
+        .unwrap();
+    client.shutdown().await.unwrap();
+    let mut buf = Vec::new();
+    client.read_to_end(&mut buf).await.unwrap();
+    serde_json::from_slice::<Value>(&buf).unwrap()["result"].clone()
+}
+
+#[tokio::test]
+async fn about_gathers_entries_mentioning_an_entity() {
+    let dir = tempdir().unwrap();
+    let sock = dir.path().join("memory.sock");
+    let mem_dir = dir.path().join("mem");
+    tokio::fs::create_dir_all(&mem_dir).await.unwrap();
+    let store = FileStore::new(mem_dir.clone());
+    let rt = LocalSet::new();
+    let handle = rt.spawn_local(run(sock.clone(), store.clone()));
+    rt.run_until(async {
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        let entries = [
+            ("instant", "a", json!(1_700_000_002), "Alice waved."),
+            (
+                "situation",
+                "b",
+                json!("2023-11-14T22:13:21Z"),
+                "Ally made tea.",
+            ),
+            ("instant", "c", json!(1_700_000_003), "It rained."),
+        ];
+        for (kind, id, when, how) in entries {
+            let data = json!({"id": id, "kind": kind, "when": when, "how": how, "what": how});
+            call(&sock, "memorize", json!({"kind": kind, "data": data})).await;
+        }
+        for (id, entities, mentions) in [
+            (
+                "e1",
+                json!([{"name": "Alice", "kind": "person"}]),
+                json!(["a"]),
+            ),
+            (
+                "e2",
+                json!([{"name": "Ally", "kind": "person", "aliases": ["alice"]}]),
+                json!(["b"]),
+            ),
+        ] {
+            let data = json!({
+                "id": id,
+                "kind": "entity",
+                "when": "2024-01-01T10:00:04Z",
+                "how": "",
+                "what": {"entities": entities, "mentions": mentions},
+            });
+            call(&sock, "memorize", json!({"kind": "entity", "data": data})).await;
+        }
+
+        let result = call(&sock, "about", json!({"name": "ally"})).await;
+        assert_eq!(result["entity"]["name"], "Alice");
+        assert_eq!(result["entity"]["aliases"], json!(["Ally"]));
+        let hows: Vec<_> = result["memories"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|m| m["how"].as_str().unwrap())
+            .collect();
+        assert_eq!(hows, ["Ally made tea.", "Alice waved."]);
+        assert_eq!(
+            call(&sock, "about", json!({"name": "Bob"})).await,
+            Value::Null
+        );
+    })
+    .await;
+    handle.abort();
+}
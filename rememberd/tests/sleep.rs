@@ -0,0 +1,211 @@
+use chrono::{Duration, Utc};
+use psyche::llm::mock_embed::MockEmbed;
+use psyche::llm::{LlmCapability, LlmProfile};
+use psyche::memory::{Experience, HnswBackend, MemoryBackend, SearchFilter};
+use rememberd::{run, Backend, FileStore};
+use serde_json::{json, Value};
+use std::path::Path;
+use std::sync::Arc;
+use tempfile::tempdir;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::UnixStream;
+use tokio::task::LocalSet;
+use uuid::Uuid;
+
+async fn call(sock: &Path, method: &str, params: Value) -> Value {
+    let mut client = UnixStream::connect(sock).await.unwrap();
+    let req = json!({"jsonrpc": "2.0", "method": method, "params": params, "id": 1});
+    client
+        .write_all(&serde_json::to_vec(&req).unwrap())
+        .await
+        .unwrap();
+    client.shutdown().await.unwrap();
+    let mut buf = Vec::new();
+    client.read_to_end(&mut buf).await.unwrap();
+    serde_json::from_slice::<Value>(&buf).unwrap()["result"].clone()
+}
+
+fn lines(path: &Path) -> Vec<Value> {
+    std::fs::read_to_string(path)
+        .unwrap_or_default()
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn sleep_consolidates_old_entries_and_archives_originals() {
+    let dir = tempdir().unwrap();
+    let sock = dir.path().join("memory.sock");
+    let mem_dir = dir.path().join("mem");
+    tokio::fs::create_dir_all(&mem_dir).await.unwrap();
+    tokio::fs::write(
+        mem_dir.join("policy.toml"),
+        "[retention]\nkinds = [\"instant\"]\nrun_length = 3\n",
+    )
+    .await
+    .unwrap();
+    let store = FileStore::new(mem_dir.clone());
+    let rt = LocalSet::new();
+    let handle = rt.spawn_local(run(sock.clone(), store.clone()));
+    rt.run_until(async {
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        let old = chrono::Utc::now().timestamp() - 30 * 24 * 3600;
+        let mut ids = Vec::new();
+        for (n, how) in [
+            "A pebble rolled.",
+            "Wind hummed softly.",
+            "Wind hummed softly!",
+            "Sand shifted.",
+            "The antenna creaked.",
+        ]
+        .iter()
+        .enumerate()
+        {
+            let id = Uuid::new_v4();
+            ids.push(id);
+            let data = json!({"id": id, "kind": "instant", "when": old + n as i64, "what": how, "how": how});
+            call(&sock, "memorize", json!({"kind": "instant", "data": data})).await;
+        }
+        let fresh = json!({"id": Uuid::new_v4(), "kind": "instant", "when": chrono::Utc::now().timestamp(), "what": "Now.", "how": "Now."});
+        call(&sock, "memorize", json!({"kind": "instant", "data": fresh})).await;
+        call(&sock, "memorize", json!({"kind": "sensation/chat", "data": {"id": "s", "text": "hi"}})).await;
+
+        let report = call(&sock, "sleep", Value::Null).await;
+        assert_eq!(report["considered"], 5);
+        assert_eq!(report["merged"], 1);
+        assert_eq!(report["summarized"], 4);
+        assert_eq!(report["archived"], true);
+
+        let live = lines(&mem_dir.join("instant.jsonl"));
+        assert_eq!(live.len(), 2);
+        assert_eq!(live[0], fresh);
+        assert_eq!(live[1]["id"], report["summaries"][0]);
+        assert_eq!(live[1]["what"].as_array().unwrap().len(), 4);
+        assert_eq!(lines(&mem_dir.join("archive/instant.jsonl")).len(), 5);
+        assert_eq!(lines(&mem_dir.join("sensation.jsonl")).len(), 1);
+
+        // a second night finds nothing new to fold
+        let report = call(&sock, "sleep", Value::Null).await;
+        assert_eq!(report["summarized"], 0);
+        assert_eq!(lines(&mem_dir.join("instant.jsonl")).len(), 2);
+    })
+    .await;
+    handle.abort();
+}
+
+/// Store with an HNSW backend holding four dull old instants followed by
+/// one that was called to mind three times.
+async fn backed_store(dir: &Path, action: &str) -> (FileStore, Arc<HnswBackend>, Vec<Uuid>) {
+    let mem_dir = dir.join("mem");
+    std::fs::create_dir_all(&mem_dir).unwrap();
+    std::fs::write(
+        mem_dir.join("policy.toml"),
+        format!("[retention]\nkinds = [\"instant\"]\naction = \"{action}\"\n"),
+    )
+    .unwrap();
+    let memory = Arc::new(HnswBackend::open(dir.join("index")).unwrap());
+    let store = FileStore::new(mem_dir).with_backend(Backend {
+        memory: memory.clone(),
+        embed: Arc::new(MockEmbed),
+        profile: LlmProfile {
+            provider: "mock".into(),
+            model: "mock".into(),
+            capabilities: vec![LlmCapability::Embedding],
+        },
+    });
+    let old = Utc::now() - Duration::days(30);
+    let mut ids = Vec::new();
+    for (n, how) in [
+        "A pebble rolled.",
+        "Sand shifted.",
+        "The antenna creaked.",
+        "Dust settled.",
+        "A gull cried.",
+    ]
+    .iter()
+    .enumerate()
+    {
+        let id = Uuid::new_v4();
+        let when = old + Duration::seconds(n as i64);
+        let entry = json!({"id": id, "kind": "instant", "when": when, "what": how, "how": how});
+        store.append("instant", &entry).await.unwrap();
+        let exp = Experience {
+            how: how.to_string(),
+            what: json!(how),
+            when,
+            tags: vec!["instant".into()],
+        };
+        memory
+            .store_with_id(&id.to_string(), &exp, &[1.0, 0.0, 0.0])
+            .await
+            .unwrap();
+        ids.push(id);
+    }
+    let gull = ids[4].to_string();
+    for _ in 0..3 {
+        let query = Experience {
+            how: "What cried?".into(),
+            what: json!("What cried?"),
+            when: Utc::now(),
+            tags: vec!["query".into()],
+        };
+        let q = memory.store(&query, &[0.0, 1.0, 0.0]).await.unwrap();
+        memory.link_called_to_mind(&q, &gull).await.unwrap();
+    }
+    (store, memory, ids)
+}
+
+fn summarized(memory: &HnswBackend, summary: &str) -> Vec<String> {
+    let mut originals: Vec<String> = memory
+        .edges()
+        .into_iter()
+        .filter(|e| e.kind == "SUMMARIZES" && e.from == summary)
+        .map(|e| e.to)
+        .collect();
+    originals.sort();
+    originals
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn consolidation_archives_backend_originals_under_linked_summary() {
+    let dir = tempdir().unwrap();
+    let (store, memory, ids) = backed_store(dir.path(), "archive").await;
+
+    let report = store.consolidate(Utc::now()).await.unwrap();
+    // the recalled gull is kept out of the run
+    assert_eq!(report.summarized, 4);
+    let summary = report.summaries[0].to_string();
+    assert!(memory.get(&summary).await.unwrap().is_some());
+    let mut dull: Vec<String> = ids[..4].iter().map(Uuid::to_string).collect();
+    dull.sort();
+    assert_eq!(summarized(&memory, &summary), dull);
+
+    let hits = memory
+        .search(&[1.0, 0.0, 0.0], 20, &SearchFilter::default())
+        .await
+        .unwrap();
+    let found: Vec<&str> = hits.iter().map(|h| h.id.as_str()).collect();
+    assert!(found.contains(&summary.as_str()));
+    assert!(found.contains(&ids[4].to_string().as_str()));
+    for id in &dull {
+        assert!(!found.contains(&id.as_str()));
+        assert!(memory.get(id).await.unwrap().is_some());
+    }
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn consolidation_prunes_backend_originals() {
+    let dir = tempdir().unwrap();
+    let (store, memory, ids) = backed_store(dir.path(), "prune").await;
+
+    let report = store.consolidate(Utc::now()).await.unwrap();
+    assert_eq!(report.summarized, 4);
+    let summary = report.summaries[0].to_string();
+    assert!(memory.get(&summary).await.unwrap().is_some());
+    assert!(summarized(&memory, &summary).is_empty());
+    for id in &ids[..4] {
+        assert!(memory.get(&id.to_string()).await.unwrap().is_none());
+    }
+    assert!(memory.get(&ids[4].to_string()).await.unwrap().is_some());
+}
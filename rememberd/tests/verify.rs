@@ -0,0 +1,40 @@
+use rememberd::FileStore;
+use tempfile::tempdir;
+
+const LEGACY: &str = r#"{"id":"9a0c1f4e-8f53-4c4b-9d4e-7b7a4a3f1e2d","kind":"recall","when":"2024-05-01T12:00:00Z","how":"I remember.","what":["a"]}"#;
+
+#[tokio::test]
+async fn verify_reports_legacy_and_unparseable_lines_and_migrate_upgrades() {
+    let dir = tempdir().unwrap();
+    let mem = dir.path().join("mem");
+    std::fs::create_dir_all(mem.join("archive")).unwrap();
+    std::fs::write(mem.join("recall.jsonl"), format!("{LEGACY}\nnot json\n")).unwrap();
+    std::fs::write(
+        mem.join("archive/sensation.jsonl"),
+        "{\"id\":\"s\",\"path\":\"/chat\",\"text\":\"hi\",\"version\":1}\n",
+    )
+    .unwrap();
+    std::fs::write(mem.join("face.jsonl"), "{\"embedding\":[0.1]}\n").unwrap();
+    let store = FileStore::new(mem.clone());
+
+    let report = store.verify().await.unwrap();
+    assert!(!report.is_clean());
+    assert_eq!(
+        report.files.keys().collect::<Vec<_>>(),
+        ["archive/sensation.jsonl", "recall.jsonl"]
+    );
+    let recall = &report.files["recall.jsonl"];
+    assert_eq!(recall.legacy[0].line, 1);
+    assert_eq!(recall.unparseable[0].line, 2);
+    assert!(report.files["archive/sensation.jsonl"].is_clean());
+
+    let migrated = store.migrate().await.unwrap();
+    assert_eq!(migrated, report);
+    let after = store.verify().await.unwrap();
+    let recall = &after.files["recall.jsonl"];
+    assert_eq!((recall.current, recall.legacy.len()), (1, 0));
+    assert_eq!(recall.unparseable.len(), 1);
+    let entry = &store.list("recall").await.unwrap()[0];
+    assert_eq!(entry["when"], 1_714_564_800);
+    assert_eq!(entry["version"], 1);
+}
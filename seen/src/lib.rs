@@ -2,19 +2,15 @@ use std::collections::VecDeque;
 use std::path::PathBuf;
 use std::sync::Arc;
 
-use base64::{engine::general_purpose, Engine};
-
-use ollama_rs::generation::completion::request::GenerationRequest;
-use ollama_rs::generation::completion::GenerationResponseStream;
-use ollama_rs::generation::images::Image;
-use ollama_rs::Ollama;
+use psyche::llm::{CanSee, LlmProfile};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::{UnixListener, UnixStream};
 use tokio::sync::{broadcast, Mutex, Notify};
 use tokio_stream::StreamExt;
 use tracing::{debug, error, info, trace};
 
-const PROMPT: &str = "This is what you are currently seeing. It is from your perspective, so whomever you see isn't you, unless you're looking at a mirror or something. Narrate to yourself what you are seeing in one and only one sentence.";
+/// Default instruction given to the vision model with every frame.
+pub const PROMPT: &str = "This is what you are currently seeing. It is from your perspective, so whomever you see isn't you, unless you're looking at a mirror or something. Narrate to yourself what you are seeing in one and only one sentence.";
 
 #[derive(Clone)]
 struct ImageQueue {
@@ -52,27 +48,18 @@ fn find_jpeg_eoi(buf: &[u8]) -> Option<usize> {
         .map(|pos| pos + 2)
 }
 
-async fn describe_image(base_url: &str, model: &str, img: &[u8]) -> anyhow::Result<String> {
-    let ollama = Ollama::try_new(base_url)?;
-    let b64 = general_purpose::STANDARD.encode(img);
-    let req = GenerationRequest::new(model.to_string(), PROMPT.to_string())
-        .images(vec![Image::from_base64(b64)]);
-    trace!(prompt = %PROMPT, "llm prompt");
-    let mut stream: GenerationResponseStream = ollama.generate_stream(req).await?;
+async fn describe_image(
+    vision: &dyn CanSee,
+    profile: &LlmProfile,
+    prompt: &str,
+    img: &[u8],
+) -> anyhow::Result<String> {
+    trace!(prompt = %prompt, "llm prompt");
+    let mut stream = vision.see_stream(profile, img, prompt).await?;
     let mut out = String::new();
-    while let Some(chunk) = stream.next().await {
-        match chunk {
-            Ok(responses) => {
-                for resp in responses {
-                    trace!(token = %resp.response, "stream token");
-                    out.push_str(&resp.response);
-                }
-            }
-            Err(e) => {
-                error!(?e, "stream error");
-                break;
-            }
-        }
+    while let Some(token) = stream.next().await {
+        trace!(token = %token, "stream token");
+        out.push_str(&token);
     }
     let trimmed = out.trim().to_string();
     debug!(response = %trimmed, "llm response");
@@ -80,8 +67,9 @@ async fn describe_image(base_url: &str, model: &str, img: &[u8]) -> anyhow::Resu
 }
 
 async fn caption_loop(
-    base_url: String,
-    model: String,
+    vision: Arc<dyn CanSee>,
+    profile: LlmProfile,
+    prompt: String,
     queue: ImageQueue,
     tx: broadcast::Sender<String>,
 ) {
@@ -92,7 +80,7 @@ async fn caption_loop(
             }
             queue.wait().await;
         };
-        match describe_image(&base_url, &model, &img).await {
+        match describe_image(vision.as_ref(), &profile, &prompt, &img).await {
             Ok(desc) => {
                 debug!(%desc, "caption ready");
                 let _ = tx.send(desc);
@@ -153,8 +141,15 @@ async fn handle_connection(
     Ok(())
 }
 
-/// Run the seen daemon.
-pub async fn run(socket: PathBuf, base_url: String, model: String) -> anyhow::Result<()> {
+/// Run the seen daemon, captioning frames with `vision`.
+///
+/// Must be called from within a [`tokio::task::LocalSet`].
+pub async fn run(
+    socket: PathBuf,
+    vision: Arc<dyn CanSee>,
+    profile: LlmProfile,
+    prompt: String,
+) -> anyhow::Result<()> {
     if socket.exists() {
         tokio::fs::remove_file(&socket).await.ok();
     }
@@ -164,9 +159,10 @@ pub async fn run(socket: PathBuf, base_url: String, model: String) -> anyhow::Re
     let queue = ImageQueue::new();
     let (tx, _) = broadcast::channel(8);
 
-    tokio::spawn(caption_loop(
-        base_url.clone(),
-        model.clone(),
+    tokio::task::spawn_local(caption_loop(
+        vision,
+        profile,
+        prompt,
         queue.clone(),
         tx.clone(),
     ));
@@ -186,54 +182,41 @@ pub async fn run(socket: PathBuf, base_url: String, model: String) -> anyhow::Re
 #[cfg(test)]
 mod tests {
     use super::*;
-    use httpmock::prelude::*;
+    use psyche::llm::mock_vision::MockVision;
+    use psyche::llm::LlmCapability;
     use tempfile::tempdir;
     use tokio::io::AsyncBufReadExt;
     use tokio::net::UnixStream;
     use tokio::task::LocalSet;
 
+    fn profile() -> LlmProfile {
+        LlmProfile {
+            provider: "mock".into(),
+            model: "mock".into(),
+            capabilities: vec![LlmCapability::Image],
+        }
+    }
+
+    fn cat() -> Arc<dyn CanSee> {
+        Arc::new(MockVision {
+            caption: " a cat\n".into(),
+        })
+    }
+
     #[tokio::test]
-    async fn describe_image_sends_request() {
-        let server = MockServer::start_async().await;
-        let body =
-            "{\"model\":\"gemma3n\",\"created_at\":\"now\",\"response\":\"desc\",\"done\":true}\n";
-        let expected = base64::engine::general_purpose::STANDARD.encode(b"data");
-        let mock = server
-            .mock_async(move |when, then| {
-                when.method(POST)
-                    .path("/api/generate")
-                    .body_contains("\"images\"")
-                    .body_contains(&expected);
-                then.status(200)
-                    .header("content-type", "application/json")
-                    .body(body);
-            })
-            .await;
-        let out = describe_image(&server.base_url(), "gemma3n", b"data")
+    async fn describe_image_trims_caption() {
+        let out = describe_image(cat().as_ref(), &profile(), PROMPT, b"data")
             .await
             .unwrap();
-        assert_eq!(out, "desc");
-        mock.assert_async().await;
+        assert_eq!(out, "a cat");
     }
 
     #[tokio::test]
     async fn run_broadcasts_caption() {
-        let server = MockServer::start_async().await;
-        let body =
-            "{\"model\":\"gemma3n\",\"created_at\":\"now\",\"response\":\"a cat\",\"done\":true}\n";
-        server
-            .mock_async(|when, then| {
-                when.method(POST).path("/api/generate");
-                then.status(200)
-                    .header("content-type", "application/json")
-                    .body(body);
-            })
-            .await;
         let dir = tempdir().unwrap();
         let sock = dir.path().join("eye.sock");
-        let url = server.base_url();
         let local = LocalSet::new();
-        let run_fut = local.spawn_local(run(sock.clone(), url, "gemma3n".into()));
+        let run_fut = local.spawn_local(run(sock.clone(), cat(), profile(), PROMPT.into()));
         local
             .run_until(async {
                 tokio::time::sleep(std::time::Duration::from_millis(50)).await;
@@ -261,22 +244,10 @@ mod tests {
 
     #[tokio::test]
     async fn processes_on_jpeg_eoi() {
-        let server = MockServer::start_async().await;
-        let body =
-            "{\"model\":\"gemma3n\",\"created_at\":\"now\",\"response\":\"a cat\",\"done\":true}\n";
-        server
-            .mock_async(|when, then| {
-                when.method(POST).path("/api/generate");
-                then.status(200)
-                    .header("content-type", "application/json")
-                    .body(body);
-            })
-            .await;
         let dir = tempdir().unwrap();
         let sock = dir.path().join("eye.sock");
-        let url = server.base_url();
         let local = LocalSet::new();
-        let run_fut = local.spawn_local(run(sock.clone(), url, "gemma3n".into()));
+        let run_fut = local.spawn_local(run(sock.clone(), cat(), profile(), PROMPT.into()));
         local
             .run_until(async {
                 tokio::time::sleep(std::time::Duration::from_millis(50)).await;
@@ -302,22 +273,10 @@ mod tests {
 
     #[tokio::test]
     async fn handles_timestamp_prefix() {
-        let server = MockServer::start_async().await;
-        let body =
-            "{\"model\":\"gemma3n\",\"created_at\":\"now\",\"response\":\"a cat\",\"done\":true}\n";
-        server
-            .mock_async(|when, then| {
-                when.method(POST).path("/api/generate");
-                then.status(200)
-                    .header("content-type", "application/json")
-                    .body(body);
-            })
-            .await;
         let dir = tempdir().unwrap();
         let sock = dir.path().join("eye.sock");
-        let url = server.base_url();
         let local = LocalSet::new();
-        let run_fut = local.spawn_local(run(sock.clone(), url, "gemma3n".into()));
+        let run_fut = local.spawn_local(run(sock.clone(), cat(), profile(), PROMPT.into()));
         local
             .run_until(async {
                 tokio::time::sleep(std::time::Duration::from_millis(50)).await;
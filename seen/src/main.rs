@@ -1,6 +1,9 @@
 use clap::Parser;
 use daemon_common::{maybe_daemonize, LogLevel};
+use psyche::llm::ollama::{log_progress, OllamaChat, OllamaModels};
+use psyche::llm::{CanSee, LlmCapability, LlmProfile};
 use std::path::PathBuf;
+use std::sync::Arc;
 
 #[derive(Parser, Debug)]
 #[command(
@@ -13,6 +16,11 @@ struct Cli {
     #[arg(long, default_value = "/run/psyched/eye.sock")]
     socket: PathBuf,
 
+    /// Path to an `llm.toml` selecting the vision model. Overrides
+    /// `--llm-url` and `--model` when given.
+    #[arg(long)]
+    llm_config: Option<PathBuf>,
+
     /// Base URL for Ollama
     #[arg(long, default_value = "http://localhost:11434")]
     llm_url: String,
@@ -21,6 +29,10 @@ struct Cli {
     #[arg(long, default_value = "gemma3:27b")]
     model: String,
 
+    /// Instruction sent to the vision model with each frame
+    #[arg(long, default_value = seen::PROMPT)]
+    prompt: String,
+
     /// Logging verbosity level
     #[arg(long, default_value = "info")]
     log_level: LogLevel,
@@ -37,5 +49,33 @@ async fn main() -> anyhow::Result<()> {
         .with_max_level(tracing_subscriber::filter::LevelFilter::from(cli.log_level))
         .init();
     maybe_daemonize(cli.daemon)?;
-    seen::run(cli.socket, cli.llm_url, cli.model).await
+    let prepared = match &cli.llm_config {
+        Some(path) => psyche::llm::config::prepare_vision_model(path).await,
+        None => {
+            OllamaModels::new(cli.llm_url.clone())
+                .ensure(&cli.model, log_progress(&cli.model))
+                .await
+        }
+    };
+    if let Err(e) = prepared {
+        tracing::warn!(error = %e, "could not prepare vision model");
+    }
+    let (vision, profile): (Arc<dyn CanSee>, LlmProfile) = match &cli.llm_config {
+        Some(path) => psyche::llm::config::load_vision(path).await?,
+        None => (
+            Arc::new(OllamaChat {
+                base_url: cli.llm_url.clone(),
+                model: cli.model.clone(),
+            }),
+            LlmProfile {
+                provider: "ollama".into(),
+                model: cli.model.clone(),
+                capabilities: vec![LlmCapability::Image],
+            },
+        ),
+    };
+    let local = tokio::task::LocalSet::new();
+    local
+        .run_until(seen::run(cli.socket, vision, profile, cli.prompt))
+        .await
 }
@@ -2,7 +2,9 @@ use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::process::Stdio;
 
-use psyche::llm::{CanChat, LlmProfile};
+use psyche::llm::{
+    CanChat, ChatMessage, GenerationOptions, LlmCapability, LlmProfile, ToolCall, ToolDefinition,
+};
 use roxmltree::Document;
 use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
 use tokio::net::{UnixListener, UnixStream};
@@ -54,11 +56,84 @@ fn parse_function(xml: &str) -> anyhow::Result<FnCall> {
     })
 }
 
+impl From<ToolCall> for FnCall {
+    fn from(call: ToolCall) -> Self {
+        let mut args = HashMap::new();
+        let mut body = String::new();
+        if let serde_json::Value::Object(map) = call.arguments {
+            for (k, v) in map {
+                let v = match v {
+                    serde_json::Value::String(s) => s,
+                    other => other.to_string(),
+                };
+                if k == "body" {
+                    body = v;
+                } else {
+                    args.insert(k, v);
+                }
+            }
+        }
+        FnCall {
+            name: call.name,
+            args,
+            body,
+        }
+    }
+}
+
+/// Describe each configured motor as a tool the model can call.
+///
+/// Every argument except `body` becomes a `--name value` flag; `body` is
+/// written to the motor's stdin.
+fn motor_tools(cfg: &WouldConfig) -> Vec<ToolDefinition> {
+    let mut names: Vec<_> = cfg.motors.keys().collect();
+    names.sort();
+    names
+        .into_iter()
+        .map(|name| ToolDefinition {
+            name: name.clone(),
+            description: format!("Run the {name} motor."),
+            parameters: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "body": {
+                        "type": "string",
+                        "description": "Text passed to the motor on stdin."
+                    }
+                },
+                "additionalProperties": {"type": "string"}
+            }),
+        })
+        .collect()
+}
+
 async fn interpret_urge(
     llm: &(dyn CanChat + Sync),
     profile: &LlmProfile,
+    cfg: &WouldConfig,
     urge: &str,
 ) -> anyhow::Result<FnCall> {
+    if profile.capabilities.contains(&LlmCapability::ToolUse) {
+        const TOOL_SYSTEM: &str = "Choose the single motor that best carries out the urge.";
+        let messages = [ChatMessage::system(TOOL_SYSTEM), ChatMessage::user(urge)];
+        match llm
+            .chat_with_tools(
+                profile,
+                &messages,
+                &motor_tools(cfg),
+                &GenerationOptions::default(),
+            )
+            .await
+        {
+            Ok(resp) => match resp.tool_calls.into_iter().next() {
+                Some(call) => return Ok(call.into()),
+                None => {
+                    debug!(target = "llm", content = %resp.content, "no tool call; falling back to XML")
+                }
+            },
+            Err(e) => debug!(target = "llm", error = %e, "tool call failed; falling back to XML"),
+        }
+    }
     const SYSTEM: &str = "Convert the urge into a single <function> call.";
     let mut stream = llm.chat_stream(profile, SYSTEM, urge).await?;
     let mut resp = String::new();
@@ -115,7 +190,7 @@ async fn handle_connection(
         return Ok(());
     }
     info!(urge = %buf, "urge received");
-    let call = interpret_urge(llm.as_ref(), &profile, &buf).await?;
+    let call = interpret_urge(llm.as_ref(), &profile, &cfg, &buf).await?;
     if let Some(path) = cfg.motors.get(&call.name) {
         run_motor(path, call, output_sock).await?;
     } else {
@@ -203,4 +278,81 @@ mod tests {
             .await;
         handle.abort();
     }
+
+    struct ToolMock;
+
+    #[async_trait::async_trait(?Send)]
+    impl CanChat for ToolMock {
+        async fn chat_stream(
+            &self,
+            _profile: &LlmProfile,
+            _system: &str,
+            _user: &str,
+        ) -> anyhow::Result<Box<dyn tokio_stream::Stream<Item = String> + Unpin>> {
+            Ok(Box::new(tokio_stream::iter(["<xml/>".to_string()])))
+        }
+
+        async fn chat_with_tools(
+            &self,
+            _profile: &LlmProfile,
+            _messages: &[ChatMessage],
+            tools: &[ToolDefinition],
+            _options: &GenerationOptions,
+        ) -> anyhow::Result<psyche::llm::ToolResponse> {
+            Ok(psyche::llm::ToolResponse {
+                content: String::new(),
+                tool_calls: vec![ToolCall {
+                    name: tools[0].name.clone(),
+                    arguments: serde_json::json!({"body": "hi", "volume": 3}),
+                }],
+            })
+        }
+    }
+
+    fn motors() -> WouldConfig {
+        WouldConfig {
+            motors: HashMap::from([("speak".to_string(), "/bin/echo".to_string())]),
+        }
+    }
+
+    #[tokio::test]
+    async fn uses_tool_calls_when_supported() {
+        let profile = LlmProfile {
+            provider: "mock".into(),
+            model: "mock".into(),
+            capabilities: vec![LlmCapability::Chat, LlmCapability::ToolUse],
+        };
+        let call = interpret_urge(&ToolMock, &profile, &motors(), "say hi")
+            .await
+            .unwrap();
+        assert_eq!(call.name, "speak");
+        assert_eq!(call.body, "hi");
+        assert_eq!(call.args.get("volume").map(String::as_str), Some("3"));
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_xml_without_tool_support() {
+        let profile = LlmProfile {
+            provider: "mock".into(),
+            model: "mock".into(),
+            capabilities: vec![LlmCapability::Chat],
+        };
+        let call = interpret_urge(&ToolMock, &profile, &motors(), "say hi")
+            .await
+            .unwrap();
+        assert_eq!(call.name, "xml");
+
+        let llm = NamedMockChat {
+            name: "<speak>hi</speak>".into(),
+        };
+        let profile = LlmProfile {
+            capabilities: vec![LlmCapability::ToolUse],
+            ..profile
+        };
+        let call = interpret_urge(&llm, &profile, &motors(), "say hi")
+            .await
+            .unwrap();
+        assert_eq!(call.name, "speak");
+        assert_eq!(call.body, "hi");
+    }
 }